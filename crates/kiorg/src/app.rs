@@ -15,12 +15,13 @@ use crate::input;
 use crate::models::preview_content::PreviewContent;
 use crate::models::tab::{TabManager, TabManagerState};
 use crate::open_wrap::{open_that, open_with};
+use crate::ui::editor_rename;
 use crate::ui::egui_notify::Toasts;
 use crate::ui::popup::delete::DeleteConfirmResult;
 use crate::ui::popup::{
     PopupType, about, action_history, add_entry, bookmark, delete, exit, file_drop,
-    generic_message, open_with as open_with_popup, plugin, preview as popup_preview, rename,
-    sort_toggle, teleport, theme,
+    generic_message, job_progress, open_with as open_with_popup, paste_conflict, plugin,
+    preview as popup_preview, rename, sort_toggle, teleport, theme, trash as trash_popup,
 };
 use crate::ui::search_bar::{self, SearchBar};
 use crate::ui::separator;
@@ -29,6 +30,7 @@ use crate::ui::terminal;
 use crate::ui::top_banner;
 use crate::ui::update;
 use crate::ui::{center_panel, help_window, left_panel, notification, preview, right_panel};
+use crate::vfs;
 use crate::visit_history::{self, VisitHistoryEntry};
 
 /// Error type for Kiorg application
@@ -89,7 +91,7 @@ const STATE_FILE_NAME: &str = "state.json";
 const PANEL_SPACING: f32 = 5.0; // Space between panels
 
 fn create_fs_watcher(
-    watch_dir: &Path,
+    watch_dir: Option<&Path>,
 ) -> Result<(notify::RecommendedWatcher, Arc<AtomicBool>), std::io::Error> {
     let notify_fs_change = Arc::new(AtomicBool::new(false));
     let (tx, rx) = std::sync::mpsc::channel::<notify::Result<notify::Event>>();
@@ -99,7 +101,10 @@ fn create_fs_watcher(
         Err(e) => return Err(std::io::Error::other(e.to_string())),
     };
 
-    if let Err(e) = fs_watcher.watch(watch_dir, RecursiveMode::NonRecursive) {
+    // Filesystems without a local path have nothing to watch
+    if let Some(watch_dir) = watch_dir
+        && let Err(e) = fs_watcher.watch(watch_dir, RecursiveMode::NonRecursive)
+    {
         return Err(std::io::Error::other(format!("Failed to watch path: {e}")));
     }
 
@@ -161,6 +166,8 @@ pub struct Kiorg {
     pub prev_path: Option<PathBuf>, // Previous path for selection preservation
     pub cached_preview_path: Option<PathBuf>,
     pub preview_content: Option<PreviewContent>,
+    // Line to scroll the text preview to once the file is previewed
+    pub preview_scroll_line: Option<(PathBuf, usize)>,
     // fields that get reset after changing directories
     // TODO: will it crash the app if large amount of entries are deleted in the same dir?
     pub scroll_range: Option<std::ops::Range<usize>>,
@@ -169,6 +176,8 @@ pub struct Kiorg {
     pub clipboard: Option<Clipboard>,
     pub search_bar: SearchBar,
     pub terminal_ctx: Option<terminal::TerminalContext>,
+    // Bulk rename waiting for the external editor to exit
+    pub editor_rename: Option<editor_rename::EditorRename>,
     pub notify_fs_change: Arc<AtomicBool>,
     pub fs_watcher: notify::RecommendedWatcher,
     // Track files that are currently being opened
@@ -188,6 +197,8 @@ pub struct Kiorg {
     pub dragged_file: Option<PathBuf>,
     // Plugin manager for external functionality
     pub plugin_manager: crate::plugins::PluginManager,
+    // Values of the file list columns contributed by plugins
+    pub column_cache: crate::plugins::columns::ColumnCache,
 }
 
 impl Kiorg {
@@ -231,14 +242,14 @@ impl Kiorg {
         cc.egui_ctx.set_visuals(colors.to_visuals());
 
         // Determine the initial path and tab manager
-        let (tab_manager, initial_path) = match initial_dir {
+        let (mut tab_manager, initial_path) = match initial_dir {
             // If initial directory is provided, use it
             Some(path) => {
                 // For explicitly provided paths, validate and return error if invalid
-                if !path.exists() {
+                if !vfs::exists(&path) {
                     return Err(KiorgError::DirectoryNotFound(path.clone()));
                 }
-                if !path.is_dir() {
+                if !vfs::is_dir(&path) {
                     return Err(KiorgError::NotADirectory(path.clone()));
                 }
 
@@ -252,7 +263,7 @@ impl Kiorg {
                     let path = tab_manager.current_tab_ref().current_path.clone();
 
                     // Verify that the saved path still exists
-                    if !path.exists() || !path.is_dir() {
+                    if !vfs::is_dir(&path) {
                         // If saved path doesn't exist, fall back to home directory
                         tracing::error!(
                             "Saved path in state '{}' is invalid, falling back to home directory",
@@ -274,10 +285,25 @@ impl Kiorg {
             }
         };
 
-        let (fs_watcher, notify_fs_change) = match create_fs_watcher(initial_path.as_path()) {
-            Ok(watcher) => watcher,
-            Err(e) => return Err(KiorgError::WatcherError(e.to_string())),
-        };
+        let (fs_watcher, notify_fs_change) =
+            match create_fs_watcher(vfs::watch_path(&initial_path).as_deref()) {
+                Ok(watcher) => watcher,
+                Err(e) => return Err(KiorgError::WatcherError(e.to_string())),
+            };
+
+        match crate::models::action_history::load_journal(config_dir_override.as_ref()) {
+            Ok(journal) => tab_manager.restore_action_histories(journal),
+            Err(e) => tracing::error!(err =? e, "Failed to load action journal"),
+        }
+        // Restoring is not a change that needs saving
+        tab_manager.take_action_history_changes();
+        // Invalid rules were already reported when loading the config
+        tab_manager.filter_rules =
+            crate::models::filter::FilterRules::from_config(&config).unwrap_or_default();
+        // Entries stashed by earlier sessions may have expired meanwhile
+        crate::utils::stash::enforce_retention_in_background(
+            crate::utils::stash::Retention::from_config(&config),
+        );
 
         let bookmarks = bookmark::load_bookmarks(config_dir_override.as_ref());
 
@@ -295,7 +321,13 @@ impl Kiorg {
         let history_saver = visit_history::HistorySaver::new();
 
         // Initialize plugin system
-        let mut plugin_manager = crate::plugins::PluginManager::new(config_dir_override.as_ref());
+        let disabled_plugins = config
+            .plugins
+            .as_ref()
+            .map(|plugins| plugins.disabled.as_slice())
+            .unwrap_or_default();
+        let mut plugin_manager =
+            crate::plugins::PluginManager::new(config_dir_override.as_ref(), disabled_plugins);
         match plugin_manager.load_plugins() {
             Ok(()) => {
                 let loaded_plugins = plugin_manager.list_loaded();
@@ -306,6 +338,11 @@ impl Kiorg {
                 tracing::error!("Failed to load plugins: {}", e);
             }
         }
+        crate::ui::popup::command_palette::bind_shortcuts(
+            &mut merged_shortcuts,
+            &plugin_manager,
+            config.shortcuts.as_ref(),
+        );
 
         let mut app = Self {
             tab_manager,
@@ -320,6 +357,7 @@ impl Kiorg {
             prev_path: None,
             cached_preview_path: None,
             preview_content: None,
+            preview_scroll_line: None,
             scroll_range: None,
             show_popup: None,
             clipboard: None,
@@ -328,6 +366,7 @@ impl Kiorg {
             notification_system,
             key_buffer: Vec::new(),
             terminal_ctx: None,
+            editor_rename: None,
             shutdown_requested: false,
             notify_fs_change,
             scroll_left_panel: false,
@@ -336,6 +375,7 @@ impl Kiorg {
             history_saver,
             dragged_file: None,
             plugin_manager,
+            column_cache: crate::plugins::columns::ColumnCache::default(),
         };
 
         app.refresh_entries();
@@ -357,11 +397,35 @@ impl Kiorg {
         notification::notify_success(&mut self.toasts, message);
     }
 
+    /// Whether entries of the current directory can be changed
+    /// Notifies the user when browsing a read-only archive
+    pub fn ensure_current_dir_writable(&mut self) -> bool {
+        let current_path = &self.tab_manager.current_tab_ref().current_path;
+        if vfs::is_read_only_dir(current_path) {
+            self.notify_error("Archives are read-only");
+            return false;
+        }
+        true
+    }
+
     /// Check and process notification messages from background operations
     pub fn check_notifications(&mut self) {
         notification::check_notifications(self);
     }
 
+    /// Add the plugins enabled from the plugins list once they started
+    fn poll_started_plugins(&mut self) {
+        let first_command = self.plugin_manager.commands().len();
+        if self.plugin_manager.finish_starting() {
+            crate::ui::popup::command_palette::bind_shortcuts_from(
+                &mut self.merged_shortcuts,
+                &self.plugin_manager,
+                self.config.shortcuts.as_ref(),
+                first_command,
+            );
+        }
+    }
+
     pub fn poll_preview_content(&mut self, ctx: &egui::Context) {
         // Handle preview content loading
         let receiver = match &self.preview_content {
@@ -471,13 +535,9 @@ impl Kiorg {
         })
     }
 
-    pub fn refresh_entries(&mut self) {
-        self.tab_manager.refresh_entries();
-        // tab_manager.refresh_entries() will refresh both parent and current directory entries
-        // so always refocus left panel after refresh
-        self.scroll_left_panel = true;
-
-        // Restore search filter if it was active before refresh
+    /// Filter the entries of the current tab again by the active search,
+    /// after they were reloaded or sorted
+    pub fn reapply_search_filter(&mut self) {
         if self.search_bar.query.is_some() {
             let case_insensitive = self.search_bar.case_insensitive;
             let tab = self.tab_manager.current_tab_mut();
@@ -485,8 +545,40 @@ impl Kiorg {
                 &self.search_bar.query,
                 case_insensitive,
                 self.search_bar.fuzzy,
+                self.search_bar.keep_order,
             );
         }
+    }
+
+    pub fn refresh_entries(&mut self) {
+        self.tab_manager.refresh_entries();
+        // Remote directories are listed on a worker thread, see poll_listing
+        if !self.tab_manager.current_tab_ref().is_listing() {
+            self.entries_refreshed();
+        }
+    }
+
+    /// Show the entries of a remote directory once they were listed
+    fn poll_listing(&mut self, ctx: &egui::Context) {
+        if let Some(result) = self.tab_manager.poll_listing() {
+            if let Err(e) = result {
+                let path = self.tab_manager.current_tab_ref().current_path.clone();
+                self.notify_error(format!("Cannot list '{}': {e}", path.display()));
+            }
+            self.entries_refreshed();
+        }
+        if self.tab_manager.current_tab_ref().is_listing() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(50));
+        }
+    }
+
+    fn entries_refreshed(&mut self) {
+        // tab_manager.refresh_entries() will refresh both parent and current directory entries
+        // so always refocus left panel after refresh
+        self.scroll_left_panel = true;
+
+        // Restore search filter if it was active before refresh
+        self.reapply_search_filter();
 
         // --- Start: Restore Selection Preservation (Post-Sort) ---
         if let Some(prev_path) = &self.prev_path {
@@ -512,6 +604,17 @@ impl Kiorg {
     }
 
     pub fn delete_selected_entry(&mut self) {
+        self.show_delete_popup(crate::ui::popup::delete::DeleteMode::default_mode());
+    }
+
+    pub fn permanently_delete_selected_entry(&mut self) {
+        self.show_delete_popup(crate::ui::popup::delete::DeleteMode::Permanent);
+    }
+
+    fn show_delete_popup(&mut self, mode: crate::ui::popup::delete::DeleteMode) {
+        if !self.ensure_current_dir_writable() {
+            return;
+        }
         let tab = self.tab_manager.current_tab_mut();
 
         if tab.is_range_selection_active() {
@@ -533,10 +636,14 @@ impl Kiorg {
         self.show_popup = Some(PopupType::Delete(
             crate::ui::popup::delete::DeleteConfirmState::Initial,
             entries_to_delete,
+            mode,
         ));
     }
 
     pub fn rename_selected_entry(&mut self) {
+        if !self.ensure_current_dir_writable() {
+            return;
+        }
         let tab = self.tab_manager.current_tab_mut();
         if let Some(entry) = tab.selected_entry() {
             self.show_popup = Some(PopupType::Rename(entry.name.clone()));
@@ -584,6 +691,9 @@ impl Kiorg {
     }
 
     pub fn cut_selected_entries(&mut self) {
+        if !self.ensure_current_dir_writable() {
+            return;
+        }
         let paths = self.prepare_clipboard_operation();
         if !paths.is_empty() {
             self.clipboard = Some(Clipboard::Cut(paths));
@@ -642,17 +752,18 @@ impl Kiorg {
             return;
         }
 
-        // Use the existing cut/move functionality
-        self.clipboard = Some(Clipboard::Cut(vec![dragged_item]));
-        let tab = self.tab_manager.current_tab_mut();
-        if crate::ui::center_panel::handle_clipboard_operations(
-            &mut self.clipboard,
-            &target_folder,
-            &mut tab.action_history,
-            &mut self.toasts,
-        ) {
-            self.refresh_entries();
+        if vfs::for_path(&dragged_item).is_read_only() || vfs::is_read_only_dir(&target_folder) {
+            self.notify_error("Archives are read-only");
+            return;
         }
+
+        // Use the existing cut/move functionality without touching the clipboard
+        paste_conflict::start(
+            self,
+            Clipboard::Cut(vec![dragged_item]),
+            &target_folder,
+            None,
+        );
     }
 
     pub fn move_selection(&mut self, delta: isize) {
@@ -743,12 +854,13 @@ impl Kiorg {
         tab.range_selection_start = None;
         self.search_bar.close();
         // Reset filter when closing search bar
-        tab.update_filtered_cache(&None, false, false);
+        tab.update_filtered_cache(&None, false, false, false);
 
-        // Watch the new directory
-        if let Err(e) = self
-            .fs_watcher
-            .watch(tab.current_path.as_path(), RecursiveMode::NonRecursive)
+        // Watch the new directory, or the archive file when browsing one
+        if let Some(watch_path) = vfs::watch_path(&tab.current_path)
+            && let Err(e) = self
+                .fs_watcher
+                .watch(&watch_path, RecursiveMode::NonRecursive)
         {
             self.notify_error(format!("Failed to watch directory: {e}"));
         }
@@ -757,17 +869,29 @@ impl Kiorg {
     }
 
     pub fn navigate_to_dir(&mut self, path: PathBuf) {
-        if !path.exists() || !path.is_dir() {
-            if self.visit_history.remove(&path).is_some() {
-                // Save updated visit history asynchronously
-                self.history_saver
-                    .save_async(&self.visit_history, self.config_dir_override.as_ref());
+        // Remote directories are checked when they are listed on a worker
+        // thread, so an unreachable host does not block the UI. They may only
+        // be unreachable for now, so they stay in the visit history.
+        if !vfs::is_slow_to_list(&path) {
+            let fs = vfs::for_dir(&path);
+            if fs.local_path(&path).is_none()
+                && let Err(e) = fs.stat(&path)
+            {
+                self.notify_error(format!("Cannot navigate to '{}': {e}", path.display()));
+                return;
+            }
+            if !vfs::is_dir(&path) {
+                if self.visit_history.remove(&path).is_some() {
+                    // Save updated visit history asynchronously
+                    self.history_saver
+                        .save_async(&self.visit_history, self.config_dir_override.as_ref());
+                }
+                self.notify_error(format!(
+                    "Cannot navigate to '{}': Path is not a directory or doesn't exist",
+                    path.display()
+                ));
+                return;
             }
-            self.notify_error(format!(
-                "Cannot navigate to '{}': Path is not a directory or doesn't exist",
-                path.display()
-            ));
-            return;
         }
         self.navigate_to_dir_without_history(path.clone());
 
@@ -780,6 +904,25 @@ impl Kiorg {
         self.tab_manager.current_tab_mut().add_to_history(path);
     }
 
+    /// Navigate to the directory containing `path` and select it
+    pub fn reveal_path(&mut self, path: &Path) {
+        let Some(parent) = vfs::parent(path) else {
+            return;
+        };
+        if self.tab_manager.current_tab_ref().current_path == parent {
+            // A search filter could hide the entry
+            self.search_bar.close();
+            let tab = self.tab_manager.current_tab_mut();
+            tab.update_filtered_cache(&None, false, false, false);
+        } else {
+            self.navigate_to_dir(parent);
+        }
+        if self.tab_manager.select_child(&path.to_path_buf()) {
+            self.selection_changed = true;
+            self.ensure_selected_visible = true;
+        }
+    }
+
     pub fn navigate_history_back(&mut self) {
         let tab = self.tab_manager.current_tab_mut();
         if let Some(path) = tab.history_back() {
@@ -823,10 +966,12 @@ impl Kiorg {
     }
 
     /// Open a file with the default application
+    /// Files of other filesystems are opened from a local copy
     pub fn open_file(&mut self, path: PathBuf) {
         let path_clone = path.clone();
         self.open_file_internal(path, move || {
-            open_that(&path_clone).map_err(|e| format!("Failed to open file: {e}"))
+            let local = vfs::local_copy(&path_clone)?;
+            open_that(&local).map_err(|e| format!("Failed to open file: {e}"))
         });
     }
 
@@ -835,7 +980,8 @@ impl Kiorg {
         let path_clone = path.clone();
         let command_clone = command.clone();
         self.open_file_internal(path, move || {
-            open_with(&path_clone, &command_clone)
+            let local = vfs::local_copy(&path_clone)?;
+            open_with(&local, &command_clone)
                 .map_err(|e| format!("Failed to open file with '{command_clone}': {e}"))
         });
     }
@@ -882,7 +1028,8 @@ impl Kiorg {
     }
 
     fn handle_delete_confirmation(&mut self, ctx: &egui::Context) {
-        if let Some(PopupType::Delete(ref mut state, ref entries_to_delete)) = self.show_popup {
+        if let Some(PopupType::Delete(ref mut state, ref entries_to_delete, mode)) = self.show_popup
+        {
             if entries_to_delete.is_empty() {
                 return;
             }
@@ -895,6 +1042,7 @@ impl Kiorg {
                 entries_to_delete,
                 &self.colors,
                 state,
+                mode,
             );
 
             if !show_delete_confirm {
@@ -951,9 +1099,29 @@ impl Kiorg {
         let state_json = serde_json::to_string_pretty(&app_state)?;
         std::fs::write(&state_path, state_json)?;
 
+        crate::models::action_history::save_journal(
+            self.tab_manager.action_histories(),
+            self.tab_manager.closed_action_histories(),
+            self.config_dir_override.as_ref(),
+        )?;
+
         Ok(())
     }
 
+    /// Persist the action journal whenever a tab's undo history changed
+    fn save_action_journal_if_changed(&mut self) {
+        if !self.tab_manager.take_action_history_changes() {
+            return;
+        }
+        if let Err(e) = crate::models::action_history::save_journal(
+            self.tab_manager.action_histories(),
+            self.tab_manager.closed_action_histories(),
+            self.config_dir_override.as_ref(),
+        ) {
+            tracing::error!(err =? e, "Failed to save action journal");
+        }
+    }
+
     fn load_app_state(config_dir_override: Option<&PathBuf>) -> Option<TabManager> {
         let config_dir = config::get_kiorg_config_dir(config_dir_override);
         let state_path = config_dir.join(STATE_FILE_NAME);
@@ -1000,8 +1168,10 @@ impl eframe::App for Kiorg {
         #[cfg(feature = "debug")]
         ctx.set_debug_on_hover(true);
 
+        self.poll_listing(ctx);
         self.poll_preview_content(ctx);
         self.poll_popup_viewers(ctx);
+        self.poll_started_plugins();
         self.check_notifications();
 
         if self
@@ -1027,6 +1197,7 @@ impl eframe::App for Kiorg {
         }
 
         terminal::draw(ctx, self);
+        editor_rename::poll(self);
 
         self.process_input(ctx);
 
@@ -1052,11 +1223,11 @@ impl eframe::App for Kiorg {
             Some(PopupType::Exit) => {
                 exit::draw(ctx, self);
             }
-            Some(PopupType::Delete(_, _)) => {
+            Some(PopupType::Delete(..)) => {
                 self.handle_delete_confirmation(ctx);
             }
-            Some(PopupType::DeleteProgress(_)) => {
-                delete::handle_delete_progress(ctx, self);
+            Some(PopupType::JobProgress(_)) => {
+                job_progress::handle_job_progress(ctx, self);
             }
             Some(PopupType::Rename(_)) => {
                 rename::draw(ctx, self);
@@ -1161,9 +1332,35 @@ impl eframe::App for Kiorg {
             Some(PopupType::ActionHistory) => {
                 action_history::draw(ctx, self);
             }
+            Some(PopupType::Trash(_)) => {
+                trash_popup::draw(ctx, self);
+            }
+            Some(PopupType::PasteConflict(_)) => {
+                paste_conflict::draw(ctx, self);
+            }
+            Some(PopupType::BulkRename(_)) => {
+                crate::ui::popup::bulk_rename::draw(ctx, self);
+            }
+            Some(PopupType::ExtractPassword(_)) => {
+                crate::ui::popup::extract::draw(ctx, self);
+            }
+            Some(PopupType::Compress(_)) => {
+                crate::ui::popup::compress::draw(ctx, self);
+            }
+            Some(PopupType::ContentSearch(_)) => {
+                crate::ui::popup::content_search::draw(ctx, self);
+            }
+            Some(PopupType::Find(_)) => {
+                crate::ui::popup::find::draw(ctx, self);
+            }
+            Some(PopupType::CommandPalette(_)) => {
+                crate::ui::popup::command_palette::draw(ctx, self);
+            }
             None => {}
         }
 
+        self.save_action_journal_if_changed();
+
         egui::CentralPanel::default().show(ctx, |ui| {
             let total_available_height = ui.available_height();
 
@@ -46,6 +46,55 @@ pub struct Layout {
     pub preview: Option<f32>,
 }
 
+/// Retention of the undo stash that keeps deleted and overwritten entries
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct UndoStash {
+    /// Total size in MiB, the oldest entries are purged above it
+    pub max_size_mb: Option<u64>,
+    /// Entries stashed longer ago than this are purged
+    pub max_age_days: Option<u64>,
+}
+
+/// Kind of entries a filter rule lists
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Dirs,
+    Files,
+    Images,
+    Videos,
+}
+
+/// Entries to hide from directory listings, see [`crate::models::filter`]
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FilterRule {
+    /// Only applies in this directory and below it, everywhere when unset
+    pub path: Option<PathBuf>,
+    /// Glob patterns of file names to list, other files are hidden
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Glob patterns of names to hide, a trailing `/` only matches directories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    pub kind: Option<EntryKind>,
+    /// Files smaller than this are hidden
+    pub min_size_kb: Option<u64>,
+    /// Files larger than this are hidden
+    pub max_size_kb: Option<u64>,
+    /// Files modified less than this many days ago are hidden
+    pub min_age_days: Option<u64>,
+    /// Files modified more than this many days ago are hidden
+    pub max_age_days: Option<u64>,
+}
+
+/// Settings of the plugins found in the plugins directory
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PluginsConfig {
+    /// Executable names of the plugins not to run, e.g. `kiorg_plugin_git`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled: Vec<String>,
+}
+
 #[derive(Deserialize, Serialize, Default, Debug)]
 pub struct Config {
     pub theme: Option<String>,
@@ -53,6 +102,9 @@ pub struct Config {
     pub shortcuts: Option<shortcuts::Shortcuts>,
     pub custom_themes: Option<Vec<Theme>>,
     pub layout: Option<Layout>,
+    pub undo_stash: Option<UndoStash>,
+    pub filters: Option<Vec<FilterRule>>,
+    pub plugins: Option<PluginsConfig>,
 }
 
 impl Config {
@@ -63,6 +115,9 @@ impl Config {
             shortcuts: None,
             custom_themes: None,
             layout: None,
+            undo_stash: None,
+            filters: None,
+            plugins: None,
         }
     }
 }
@@ -167,6 +222,19 @@ pub fn load_config_with_override(
         ));
     }
 
+    if let Some(undo_stash) = &user_config.undo_stash
+        && (undo_stash.max_size_mb == Some(0) || undo_stash.max_age_days == Some(0))
+    {
+        return Err(ConfigError::ValueError(
+            "Undo stash limits must be greater than zero".to_string(),
+            config_path,
+        ));
+    }
+
+    if let Err(e) = crate::models::filter::FilterRules::from_config(&user_config) {
+        return Err(ConfigError::ValueError(e, config_path));
+    }
+
     Ok(user_config)
 }
 
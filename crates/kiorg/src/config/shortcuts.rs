@@ -106,6 +106,28 @@ impl KeyboardShortcut {
         self
     }
 
+    /// Parse a shortcut written like "ctrl+shift+g" or "gs", as plugins declare them
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key = parts.pop().filter(|key| !key.is_empty());
+        let Some(key) = key else {
+            return Err(format!("Missing key in shortcut '{text}'"));
+        };
+        let mut shortcut = Self::new(&key.to_lowercase());
+        for modifier in parts {
+            match modifier.to_lowercase().as_str() {
+                "shift" => shortcut.shift = true,
+                "ctrl" => shortcut.ctrl = true,
+                "alt" => shortcut.alt = true,
+                #[cfg(target_os = "macos")]
+                "cmd" => shortcut.command = true,
+                other => return Err(format!("Unknown modifier '{other}' in shortcut '{text}'")),
+            }
+        }
+        shortcut.to_shortcut_keys()?;
+        Ok(shortcut)
+    }
+
     // Convert the key sequence to a vector of ShortcutKey structs
     pub fn to_shortcut_keys(&self) -> Result<Vec<ShortcutKey>, String> {
         let mut keys = Vec::new();
@@ -253,7 +275,12 @@ pub enum ShortcutAction {
 
     // File operations
     DeleteEntry,
+    PermanentlyDeleteEntry,
     RenameEntry,
+    RenameInEditor,
+    BulkRename,
+    ExtractArchive,
+    CompressEntries,
     AddEntry,
     SelectEntry,
     SelectAllEntries,
@@ -287,19 +314,30 @@ pub enum ShortcutAction {
 
     // UI interaction
     ActivateSearch,
+    ShowContentSearch,
+    ShowFind,
     ShowHelp,
     OpenTerminal,
     ShowFilePreview,
     ShowTeleport,
     ShowSortToggle,
     ShowActionHistory,
+    ShowTrash,
     Undo,
     Redo,
     Exit,
     ToggleRangeSelection,
     ToggleHiddenFiles,
+    ToggleFilters,
     CopyPath,
     CopyName,
+
+    // Plugins
+    ShowCommandPalette,
+    // Command at this index of the plugin manager's commands, bound at
+    // startup from the `PluginCommand` table
+    #[serde(skip)]
+    PluginCommand(usize),
 }
 
 // Define a struct for the shortcuts map using a prefix tree
@@ -308,6 +346,13 @@ pub struct Shortcuts {
     // Main mapping from action to list of shortcuts (for serialization and display)
     #[serde(flatten)]
     action_to_shortcuts: HashMap<ShortcutAction, Vec<KeyboardShortcut>>,
+    // Shortcuts of plugin commands keyed by "<plugin>:<command>"
+    #[serde(
+        default,
+        rename = "PluginCommand",
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    plugin_commands: HashMap<String, Vec<KeyboardShortcut>>,
     // Prefix tree for efficient multi-character shortcut matching
     #[serde(skip)]
     shortcut_tree: ShortcutTreeNode,
@@ -318,6 +363,7 @@ impl Shortcuts {
     pub fn new() -> Self {
         Self {
             action_to_shortcuts: HashMap::new(),
+            plugin_commands: HashMap::new(),
             shortcut_tree: ShortcutTreeNode::new(),
         }
     }
@@ -327,6 +373,12 @@ impl Shortcuts {
         self.action_to_shortcuts.get(action)
     }
 
+    // Shortcuts the user bound to a plugin command, see `CommandRef::id`
+    #[must_use]
+    pub fn get_plugin_command(&self, id: &str) -> Option<&Vec<KeyboardShortcut>> {
+        self.plugin_commands.get(id)
+    }
+
     // Add a shortcut for an action, updating both the action map and tree
     pub fn add_shortcut(
         &mut self,
@@ -603,7 +655,28 @@ pub fn default_shortcuts() -> Shortcuts {
     // File operations
     add_shortcut(KeyboardShortcut::new("d"), ShortcutAction::DeleteEntry);
 
+    add_shortcut(
+        KeyboardShortcut::new("d").with_shift(),
+        ShortcutAction::PermanentlyDeleteEntry,
+    );
+
     add_shortcut(KeyboardShortcut::new("r"), ShortcutAction::RenameEntry);
+    add_shortcut(
+        KeyboardShortcut::new("r").with_shift(),
+        ShortcutAction::RenameInEditor,
+    );
+    add_shortcut(
+        KeyboardShortcut::new("r").with_ctrl().with_shift(),
+        ShortcutAction::BulkRename,
+    );
+    add_shortcut(
+        KeyboardShortcut::new("x").with_shift(),
+        ShortcutAction::ExtractArchive,
+    );
+    add_shortcut(
+        KeyboardShortcut::new("c").with_shift(),
+        ShortcutAction::CompressEntries,
+    );
 
     add_shortcut(KeyboardShortcut::new("a"), ShortcutAction::AddEntry);
 
@@ -762,6 +835,14 @@ pub fn default_shortcuts() -> Shortcuts {
         KeyboardShortcut::new("f").with_ctrl(),
         ShortcutAction::ActivateSearch,
     );
+    add_shortcut(
+        KeyboardShortcut::new("f").with_ctrl().with_shift(),
+        ShortcutAction::ShowContentSearch,
+    );
+    add_shortcut(
+        KeyboardShortcut::new("f").with_shift(),
+        ShortcutAction::ShowFind,
+    );
     add_shortcut(
         KeyboardShortcut::new("p").with_ctrl(),
         ShortcutAction::ShowTeleport,
@@ -773,6 +854,10 @@ pub fn default_shortcuts() -> Shortcuts {
         ShortcutAction::ShowActionHistory,
     );
     add_shortcut(KeyboardShortcut::new("u"), ShortcutAction::Undo);
+    add_shortcut(
+        KeyboardShortcut::new("t").with_ctrl().with_shift(),
+        ShortcutAction::ShowTrash,
+    );
     add_shortcut(KeyboardShortcut::new("r").with_ctrl(), ShortcutAction::Redo);
 
     // Add new shortcuts for switching to preview tab and next/previous tab
@@ -808,10 +893,15 @@ pub fn default_shortcuts() -> Shortcuts {
         KeyboardShortcut::new("h").with_ctrl(),
         ShortcutAction::ToggleHiddenFiles,
     );
+    add_shortcut(KeyboardShortcut::new("zf"), ShortcutAction::ToggleFilters);
 
     // Copy operations to system clipboard
     add_shortcut(KeyboardShortcut::new("cp"), ShortcutAction::CopyPath);
     add_shortcut(KeyboardShortcut::new("cn"), ShortcutAction::CopyName);
+    add_shortcut(
+        KeyboardShortcut::new("p").with_ctrl().with_shift(),
+        ShortcutAction::ShowCommandPalette,
+    );
 
     shortcuts
 }
@@ -972,6 +1062,38 @@ mod tests {
 
         assert_eq!(no_match_command_only, TraverseResult::NoMatch);
     }
+
+    #[test]
+    fn test_parse_plugin_shortcut() {
+        assert_eq!(
+            KeyboardShortcut::parse("ctrl+shift+G").unwrap(),
+            KeyboardShortcut::new("g").with_ctrl().with_shift()
+        );
+        assert_eq!(
+            KeyboardShortcut::parse("gs").unwrap(),
+            KeyboardShortcut::new("gs")
+        );
+        assert!(KeyboardShortcut::parse("hyper+g").is_err());
+        assert!(KeyboardShortcut::parse("ctrl+").is_err());
+    }
+
+    #[test]
+    fn test_plugin_command_table() {
+        let shortcuts: Shortcuts = toml::from_str(
+            r#"
+            ShowHelp = [{ key = "?" }]
+
+            [PluginCommand]
+            "git:Stage" = [{ key = "s", ctrl = true }]
+            "#,
+        )
+        .unwrap();
+        assert!(shortcuts.get(&ShortcutAction::ShowHelp).is_some());
+        assert_eq!(
+            shortcuts.get_plugin_command("git:Stage"),
+            Some(&vec![KeyboardShortcut::new("s").with_ctrl()])
+        );
+    }
 }
 
 // Helper functions for the Shortcuts type
@@ -1,8 +1,12 @@
 use crate::config::shortcuts::ShortcutKey;
 use crate::config::shortcuts::{ShortcutAction, TraverseResult};
 use crate::ui::center_panel;
-use crate::ui::popup::{add_entry, bookmark, file_drop, preview as popup_preview, sort_toggle};
+use crate::ui::editor_rename;
+use crate::ui::popup::{
+    add_entry, bookmark, file_drop, paste_conflict, preview as popup_preview, sort_toggle,
+};
 use crate::ui::terminal;
+use crate::vfs;
 use egui::{Key, Modifiers};
 
 use super::app::Kiorg;
@@ -21,12 +25,7 @@ fn handle_shortcut_action(app: &mut Kiorg, ctx: &egui::Context, action: &Shortcu
         ShortcutAction::MoveDown => app.move_selection(1),
         ShortcutAction::MoveUp => app.move_selection(-1),
         ShortcutAction::GoToParentDirectory => {
-            let parent_path = app
-                .tab_manager
-                .current_tab_ref()
-                .current_path
-                .parent()
-                .map(std::path::Path::to_path_buf);
+            let parent_path = vfs::parent(&app.tab_manager.current_tab_ref().current_path);
             if let Some(parent) = parent_path {
                 app.navigate_to_dir(parent);
             }
@@ -35,7 +34,7 @@ fn handle_shortcut_action(app: &mut Kiorg, ctx: &egui::Context, action: &Shortcu
             let tab = app.tab_manager.current_tab_ref();
             if let Some(selected_entry) = tab.entries.get(tab.selected_index) {
                 let path = selected_entry.meta.path.clone();
-                if path.is_dir() {
+                if vfs::is_dir(&path) {
                     app.navigate_to_dir(path);
                 }
             }
@@ -44,9 +43,9 @@ fn handle_shortcut_action(app: &mut Kiorg, ctx: &egui::Context, action: &Shortcu
             let tab = app.tab_manager.current_tab_ref();
             if let Some(selected_entry) = tab.entries.get(tab.selected_index) {
                 let path = selected_entry.meta.path.clone();
-                if path.is_dir() {
+                if vfs::is_dir(&path) {
                     app.navigate_to_dir(path);
-                } else if path.is_file() {
+                } else if !selected_entry.is_dir {
                     // TODO: write a test for this
                     // only open file on enter
                     app.open_file(path);
@@ -74,8 +73,17 @@ fn handle_shortcut_action(app: &mut Kiorg, ctx: &egui::Context, action: &Shortcu
             }
         }
         ShortcutAction::DeleteEntry => app.delete_selected_entry(),
+        ShortcutAction::PermanentlyDeleteEntry => app.permanently_delete_selected_entry(),
         ShortcutAction::RenameEntry => app.rename_selected_entry(),
-        ShortcutAction::AddEntry => app.show_popup = Some(PopupType::AddEntry(String::new())),
+        ShortcutAction::RenameInEditor => editor_rename::start(ctx, app),
+        ShortcutAction::BulkRename => crate::ui::popup::bulk_rename::open(app),
+        ShortcutAction::ExtractArchive => crate::ui::popup::extract::extract_selected_entry(app),
+        ShortcutAction::CompressEntries => crate::ui::popup::compress::open(app),
+        ShortcutAction::AddEntry => {
+            if app.ensure_current_dir_writable() {
+                app.show_popup = Some(PopupType::AddEntry(String::new()));
+            }
+        }
         ShortcutAction::SelectEntry => {
             let tab = app.tab_manager.current_tab_mut();
 
@@ -113,17 +121,8 @@ fn handle_shortcut_action(app: &mut Kiorg, ctx: &egui::Context, action: &Shortcu
         ShortcutAction::CopyEntry => app.copy_selected_entries(),
         ShortcutAction::CutEntry => app.cut_selected_entries(),
         ShortcutAction::PasteEntry => {
-            let tab = app.tab_manager.current_tab_mut();
-            // Clear marked entries after paste operation
-            tab.marked_entries.clear();
-            if center_panel::handle_clipboard_operations(
-                &mut app.clipboard,
-                &tab.current_path,
-                &mut tab.action_history,
-                &mut app.toasts,
-            ) {
-                app.refresh_entries();
-            }
+            let current_path = app.tab_manager.current_tab_ref().current_path.clone();
+            center_panel::handle_clipboard_operations(app, &current_path);
         }
         ShortcutAction::CreateTab => {
             let current_path = app.tab_manager.current_tab_ref().current_path.clone();
@@ -200,6 +199,12 @@ fn handle_shortcut_action(app: &mut Kiorg, ctx: &egui::Context, action: &Shortcu
         }
         ShortcutAction::Exit => app.show_popup = Some(PopupType::Exit),
         ShortcutAction::ActivateSearch => app.search_bar.activate(),
+        ShortcutAction::ShowContentSearch => crate::ui::popup::content_search::open(app),
+        ShortcutAction::ShowFind => crate::ui::popup::find::open(ctx, app),
+        ShortcutAction::ShowCommandPalette => crate::ui::popup::command_palette::open(app),
+        ShortcutAction::PluginCommand(index) => {
+            crate::ui::popup::command_palette::run_command(ctx, app, *index);
+        }
         ShortcutAction::ShowTeleport => {
             app.show_popup = Some(PopupType::Teleport(
                 crate::ui::popup::teleport::TeleportState::default(),
@@ -211,6 +216,9 @@ fn handle_shortcut_action(app: &mut Kiorg, ctx: &egui::Context, action: &Shortcu
         ShortcutAction::ShowActionHistory => {
             app.show_popup = Some(PopupType::ActionHistory);
         }
+        ShortcutAction::ShowTrash => {
+            app.show_popup = Some(PopupType::Trash(crate::ui::popup::trash::TrashState::load()));
+        }
         ShortcutAction::Undo => {
             crate::ui::popup::action_history::undo_last_action(app);
         }
@@ -261,6 +269,18 @@ fn handle_shortcut_action(app: &mut Kiorg, ctx: &egui::Context, action: &Shortcu
         ShortcutAction::ToggleHiddenFiles => {
             app.tab_manager.toggle_show_hidden();
             app.refresh_entries();
+            let mode = if app.tab_manager.show_hidden {
+                "Showing hidden files"
+            } else if app.tab_manager.hide_ignored {
+                "Hiding hidden and git ignored files"
+            } else {
+                "Hiding hidden files"
+            };
+            app.toasts.info(mode);
+        }
+        ShortcutAction::ToggleFilters => {
+            app.tab_manager.toggle_filters();
+            app.refresh_entries();
         }
         ShortcutAction::CopyPath => {
             let tab = app.tab_manager.current_tab_ref();
@@ -316,7 +336,7 @@ fn process_key(
             }
             return;
         }
-        Some(PopupType::Delete(_, _)) => {
+        Some(PopupType::Delete(..)) => {
             if key == Key::Enter {
                 crate::ui::popup::delete::confirm_delete(app);
             } else if is_cancel_keys(key) {
@@ -332,6 +352,22 @@ fn process_key(
             }
             return;
         }
+        Some(PopupType::ExtractPassword(_)) => {
+            if key == Key::Enter {
+                crate::ui::popup::extract::confirm_password(app);
+            } else if key == Key::Escape {
+                crate::ui::popup::extract::cancel_password(app);
+            }
+            return;
+        }
+        Some(PopupType::Compress(_)) => {
+            if key == Key::Enter {
+                crate::ui::popup::compress::confirm(app);
+            } else if key == Key::Escape {
+                crate::ui::popup::compress::cancel(app);
+            }
+            return;
+        }
         Some(PopupType::OpenWith) => {
             if key == Key::Enter {
                 crate::ui::popup::open_with::handle_confirm(app, ctx);
@@ -384,14 +420,44 @@ fn process_key(
             // Volumes popup input is handled in show_volumes_popup
             return;
         }
-        Some(PopupType::DeleteProgress(_)) => {
-            // Delete progress popup doesn't handle input - just return
+        Some(PopupType::JobProgress(_)) => {
+            if is_cancel_keys(key) {
+                crate::ui::popup::job_progress::cancel_job(app);
+            }
             return;
         }
         Some(PopupType::Teleport(_)) => {
             // Teleport popup handles its own input - just return
             return;
         }
+        Some(PopupType::Trash(_)) => {
+            // Trash popup handles its own input - just return
+            return;
+        }
+        Some(PopupType::BulkRename(_)) => {
+            // Bulk rename popup handles its own input - just return
+            return;
+        }
+        Some(PopupType::ContentSearch(_)) => {
+            // Content search popup handles its own input - just return
+            return;
+        }
+        Some(PopupType::Find(_)) => {
+            // Find popup handles its own input - just return
+            return;
+        }
+        Some(PopupType::CommandPalette(_)) => {
+            // Command palette handles its own input - just return
+            return;
+        }
+        Some(PopupType::PasteConflict(_)) => {
+            if is_cancel_keys(key) {
+                paste_conflict::cancel(app);
+            } else {
+                paste_conflict::handle_key_press(app, key);
+            }
+            return;
+        }
         Some(PopupType::SortToggle) => {
             if is_cancel_keys(key) {
                 app.show_popup = None;
@@ -431,7 +497,7 @@ fn process_key(
         app.search_bar.close();
         // Reset filter when closing search bar
         let tab = app.tab_manager.current_tab_mut();
-        tab.update_filtered_cache(&None, false, false);
+        tab.update_filtered_cache(&None, false, false, false);
         return;
     }
 
@@ -9,6 +9,7 @@ pub mod startup_error;
 pub mod theme;
 pub mod ui;
 pub mod utils;
+pub mod vfs;
 pub mod visit_history;
 
 pub use app::Kiorg;
@@ -57,41 +57,44 @@ fn main() -> Result<(), eframe::Error> {
         kiorg::utils::preview_cache::purge_cache_dir();
     }
 
-    // If a directory is provided, validate and canonicalize it
-    let initial_dir = if let Some(dir) = args.directory {
-        // Validate the provided directory
-        if !dir.exists() {
-            return kiorg::startup_error::StartupErrorApp::show_error_dialog(
-                format!("Directory '{}' does not exist", dir.display()),
-                "Filesystem Error".to_string(),
-                Some(format!("Requested directory: {}", dir.display())),
-            );
-        }
-
-        if !dir.is_dir() {
-            return kiorg::startup_error::StartupErrorApp::show_error_dialog(
-                format!("'{}' is not a directory", dir.display()),
-                "Filesystem Error".to_string(),
-                Some(format!("Path provided: {}", dir.display())),
-            );
-        }
+    // If a local directory is provided, validate and canonicalize it
+    let initial_dir = match args.directory {
+        // Remote and plugin directories are checked once kiorg lists them
+        Some(dir) if kiorg::vfs::url_root(&dir).is_some() => Some(dir),
+        Some(dir) => {
+            // Validate the provided directory
+            if !dir.exists() {
+                return kiorg::startup_error::StartupErrorApp::show_error_dialog(
+                    format!("Directory '{}' does not exist", dir.display()),
+                    "Filesystem Error".to_string(),
+                    Some(format!("Requested directory: {}", dir.display())),
+                );
+            }
 
-        // Canonicalize the path to get absolute path
-        let canonical_dir = match fs::canonicalize(&dir) {
-            Ok(path) => path,
-            Err(e) => {
+            if !dir.is_dir() {
                 return kiorg::startup_error::StartupErrorApp::show_error_dialog(
-                    format!("Failed to canonicalize path '{}': {}", dir.display(), e),
-                    "Permission Error".to_string(),
+                    format!("'{}' is not a directory", dir.display()),
+                    "Filesystem Error".to_string(),
                     Some(format!("Path provided: {}", dir.display())),
                 );
             }
-        };
 
-        Some(canonical_dir)
-    } else {
+            // Canonicalize the path to get absolute path
+            let canonical_dir = match fs::canonicalize(&dir) {
+                Ok(path) => path,
+                Err(e) => {
+                    return kiorg::startup_error::StartupErrorApp::show_error_dialog(
+                        format!("Failed to canonicalize path '{}': {}", dir.display(), e),
+                        "Permission Error".to_string(),
+                        Some(format!("Path provided: {}", dir.display())),
+                    );
+                }
+            };
+
+            Some(canonical_dir)
+        }
         // No directory provided, use None to load from saved state
-        None
+        None => None,
     };
 
     // Load the app icon from embedded data
@@ -1,33 +1,107 @@
 use chrono::{DateTime, Local};
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config;
+use crate::vfs;
+
+/// Journal file in the config directory that keeps action history across restarts
+const JOURNAL_FILE_NAME: &str = "action_journal.json";
+const DEFAULT_MAX_HISTORY_SIZE: usize = 256;
 
 /// Individual operation data structures
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CreateOperation {
     pub path: PathBuf,
     pub is_dir: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RenameOperation {
     pub old_path: PathBuf,
     pub new_path: PathBuf,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CopyOperation {
     pub source_path: PathBuf,
     pub target_path: PathBuf,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MoveOperation {
     pub source_path: PathBuf,
     pub target_path: PathBuf,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashOperation {
+    pub original_path: PathBuf,
+    /// Location of the item inside the trash `files` directory
+    pub trashed_path: PathBuf,
+    /// The `.trashinfo` file describing the trashed item
+    pub info_path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StashOperation {
+    pub original_path: PathBuf,
+    /// Location of the entry inside the undo stash
+    pub stashed_path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverwriteOperation {
+    pub source_path: PathBuf,
+    pub target_path: PathBuf,
+    /// True when the source was moved onto the target instead of copied
+    pub is_move: bool,
+    /// Where the replaced target was put aside so it can be restored
+    pub backup: OverwriteBackup,
+}
+
+/// Where an overwritten entry was put aside
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OverwriteBackup {
+    /// In the undo stash, for entries on its device
+    Stash(StashOperation),
+    /// In the trash of the entry's own mount, for entries on other devices
+    Trash(TrashOperation),
+}
+
+impl OverwriteBackup {
+    /// Location of the replaced entry while it is put aside
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Stash(op) => &op.stashed_path,
+            Self::Trash(op) => &op.trashed_path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractOperation {
+    /// The archive, or the entry inside it that was copied out
+    pub archive_path: PathBuf,
+    pub target_path: PathBuf,
+    /// Undo puts the extracted entries aside here, redo brings them back
+    /// without extracting again
+    pub backup: StashOperation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressOperation {
+    pub sources: Vec<PathBuf>,
+    pub archive_path: PathBuf,
+    /// Undo puts the archive aside here, redo brings it back without
+    /// compressing again
+    pub backup: StashOperation,
+}
+
 /// Represents different types of mutation actions that can be performed on files/directories
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ActionType {
     /// File or directory creation operations
     Create { operations: Vec<CreateOperation> },
@@ -37,33 +111,169 @@ pub enum ActionType {
     Copy { operations: Vec<CopyOperation> },
     /// File move operations (different from rename as it can cross directory boundaries)
     Move { operations: Vec<MoveOperation> },
+    /// Move to trash operations
+    Trash { operations: Vec<TrashOperation> },
+    /// Delete operations, the entries are kept in the undo stash
+    Delete { operations: Vec<StashOperation> },
+    /// Paste operations that replaced an existing entry
+    Overwrite { operations: Vec<OverwriteOperation> },
+    /// Entries extracted from archives
+    Extract { operations: Vec<ExtractOperation> },
+    /// Archives created from existing entries
+    Compress { operations: Vec<CompressOperation> },
+}
+
+impl ActionType {
+    /// Paths left behind by the action, these are what undoing it touches
+    pub fn result_paths(&self) -> Vec<&Path> {
+        match self {
+            Self::Create { operations } => operations.iter().map(|op| op.path.as_path()).collect(),
+            Self::Rename { operations } => {
+                operations.iter().map(|op| op.new_path.as_path()).collect()
+            }
+            Self::Copy { operations } => operations
+                .iter()
+                .map(|op| op.target_path.as_path())
+                .collect(),
+            Self::Move { operations } => operations
+                .iter()
+                .map(|op| op.target_path.as_path())
+                .collect(),
+            Self::Trash { operations } => operations
+                .iter()
+                .map(|op| op.trashed_path.as_path())
+                .collect(),
+            Self::Delete { operations } => operations
+                .iter()
+                .map(|op| op.stashed_path.as_path())
+                .collect(),
+            Self::Overwrite { operations } => operations
+                .iter()
+                .flat_map(|op| [op.target_path.as_path(), op.backup.path()])
+                .collect(),
+            Self::Extract { operations } => operations
+                .iter()
+                .map(|op| op.target_path.as_path())
+                .collect(),
+            Self::Compress { operations } => operations
+                .iter()
+                .map(|op| op.archive_path.as_path())
+                .collect(),
+        }
+    }
+}
+
+/// State of a path right after an action, used to tell whether it changed since
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathFingerprint {
+    pub path: PathBuf,
+    /// None when the path did not exist
+    pub state: Option<PathState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathState {
+    /// Directories only record their existence, their mtime changes whenever
+    /// a later action touches their content
+    Dir,
+    File {
+        size: u64,
+        modified: Option<SystemTime>,
+    },
+}
+
+impl PathFingerprint {
+    pub fn capture(path: &Path) -> Self {
+        let state = vfs::for_path(path).stat(path).ok().map(|meta| {
+            // Links are fingerprinted themselves, not what they point to
+            if meta.is_dir && !meta.is_symlink {
+                PathState::Dir
+            } else {
+                PathState::File {
+                    size: meta.size,
+                    modified: Some(meta.modified),
+                }
+            }
+        });
+        Self {
+            path: path.to_path_buf(),
+            state,
+        }
+    }
+
+    /// Whether the path no longer looks like it did when captured
+    pub fn is_stale(&self) -> bool {
+        Self::capture(&self.path).state != self.state
+    }
 }
 
 /// Represents a single action in the history with metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryAction {
     /// Type of action performed
     pub action_type: ActionType,
     /// Timestamp when the action was performed
     pub timestamp: DateTime<Local>,
+    /// State of the resulting paths when the action was last performed
+    #[serde(default)]
+    pub fingerprints: Vec<PathFingerprint>,
 }
 
 impl HistoryAction {
+    /// Record an action that was just performed
+    pub fn new(action_type: ActionType) -> Self {
+        let mut action = Self {
+            action_type,
+            timestamp: Local::now(),
+            fingerprints: Vec::new(),
+        };
+        action.refresh_fingerprints();
+        action
+    }
+
     /// Get the description for this action
     pub fn get_description(&self) -> String {
         TabActionHistory::generate_description(&self.action_type)
     }
+
+    /// Capture the current state of the resulting paths, e.g. after a redo
+    pub fn refresh_fingerprints(&mut self) {
+        self.fingerprints = self
+            .action_type
+            .result_paths()
+            .into_iter()
+            .map(PathFingerprint::capture)
+            .collect();
+    }
+
+    /// Paths that were modified, replaced or removed since the action was performed
+    /// Undoing a stale action could destroy changes made afterwards
+    pub fn stale_paths(&self) -> Vec<&Path> {
+        self.fingerprints
+            .iter()
+            .filter(|fingerprint| fingerprint.is_stale())
+            .map(|fingerprint| fingerprint.path.as_path())
+            .collect()
+    }
 }
 
 /// History manager for a single tab
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TabActionHistory {
     /// Actions that have not been rolled back
     active_actions: Vec<HistoryAction>,
     /// Actions that have been rolled back
     rolled_back_actions: Vec<HistoryAction>,
     /// Maximum number of actions to keep in history
+    #[serde(skip, default = "default_max_history_size")]
     max_history_size: usize,
+    /// Set whenever the history changes so the journal knows to save it
+    #[serde(skip)]
+    changed: bool,
+}
+
+const fn default_max_history_size() -> usize {
+    DEFAULT_MAX_HISTORY_SIZE
 }
 
 impl Default for TabActionHistory {
@@ -75,7 +285,7 @@ impl Default for TabActionHistory {
 impl TabActionHistory {
     /// Create a new empty action history
     pub fn new() -> Self {
-        Self::with_max_size(256)
+        Self::with_max_size(DEFAULT_MAX_HISTORY_SIZE)
     }
 
     /// Create a new action history with custom max size
@@ -84,18 +294,15 @@ impl TabActionHistory {
             active_actions: Vec::new(),
             rolled_back_actions: Vec::new(),
             max_history_size: max_size,
+            changed: false,
         }
     }
 
     /// Add a new action to the history
     pub fn add_action(&mut self, action_type: ActionType) {
-        let action = HistoryAction {
-            action_type,
-            timestamp: Local::now(),
-        };
-
-        self.active_actions.push(action);
+        self.active_actions.push(HistoryAction::new(action_type));
         self.rolled_back_actions.truncate(0);
+        self.changed = true;
 
         // Maintain max history size
         let overflow = self.active_actions.len() as i64 - self.max_history_size as i64;
@@ -117,6 +324,7 @@ impl TabActionHistory {
     /// Undo the last action (linear undo)
     pub fn undo_last_action(&mut self) -> Option<HistoryAction> {
         if let Some(action) = self.active_actions.pop() {
+            self.changed = true;
             self.rolled_back_actions.push(action.clone());
             Some(action)
         } else {
@@ -127,6 +335,7 @@ impl TabActionHistory {
     /// Redo the most recently rolled back action
     pub fn redo_last_action(&mut self) -> Option<HistoryAction> {
         if let Some(action) = self.rolled_back_actions.pop() {
+            self.changed = true;
             self.active_actions.push(action.clone());
             Some(action)
         } else {
@@ -134,6 +343,14 @@ impl TabActionHistory {
         }
     }
 
+    /// Capture the state of the last active action again once it was redone
+    pub fn refresh_last_action(&mut self) {
+        if let Some(action) = self.active_actions.last_mut() {
+            action.refresh_fingerprints();
+            self.changed = true;
+        }
+    }
+
     /// Get the most recent rolled back action that can be redone
     pub fn get_last_redoable_action(&self) -> Option<&HistoryAction> {
         self.rolled_back_actions.last()
@@ -153,6 +370,12 @@ impl TabActionHistory {
     pub fn clear(&mut self) {
         self.active_actions.clear();
         self.rolled_back_actions.clear();
+        self.changed = true;
+    }
+
+    /// Returns whether the history changed since the last call
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
     }
 
     /// Get the number of actions in history (active + rolled back)
@@ -208,10 +431,107 @@ impl TabActionHistory {
                     format!("Moved {} items", operations.len())
                 }
             }
+            ActionType::Trash { operations } => {
+                if operations.len() == 1 {
+                    format!("Moved '{}' to trash", operations[0].original_path.display())
+                } else {
+                    format!("Moved {} items to trash", operations.len())
+                }
+            }
+            ActionType::Delete { operations } => {
+                if operations.len() == 1 {
+                    format!("Deleted '{}'", operations[0].original_path.display())
+                } else {
+                    format!("Deleted {} items", operations.len())
+                }
+            }
+            ActionType::Overwrite { operations } => {
+                if operations.len() == 1 {
+                    format!(
+                        "Overwrote '{}' with '{}'",
+                        operations[0].target_path.display(),
+                        operations[0].source_path.display()
+                    )
+                } else {
+                    format!("Overwrote {} items", operations.len())
+                }
+            }
+            ActionType::Extract { operations } => {
+                if operations.len() == 1 {
+                    format!(
+                        "Extracted '{}' to '{}'",
+                        operations[0].archive_path.display(),
+                        operations[0].target_path.display()
+                    )
+                } else {
+                    format!("Extracted {} items", operations.len())
+                }
+            }
+            ActionType::Compress { operations } => {
+                if operations.len() == 1 {
+                    format!("Compressed '{}'", operations[0].archive_path.display())
+                } else {
+                    format!("Compressed {} archives", operations.len())
+                }
+            }
         }
     }
 }
 
+/// Action histories of all tabs as persisted in the journal file
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ActionJournal {
+    /// Histories of the open tabs in tab order
+    pub tabs: Vec<TabActionHistory>,
+    /// Histories of closed tabs, oldest first
+    #[serde(default)]
+    pub closed_tabs: Vec<TabActionHistory>,
+}
+
+/// Load the per tab action histories saved by [`save_journal`]
+pub fn load_journal(
+    config_dir_override: Option<&PathBuf>,
+) -> Result<ActionJournal, Box<dyn std::error::Error>> {
+    let config_dir = config::get_kiorg_config_dir(config_dir_override);
+    let journal_path = config_dir.join(JOURNAL_FILE_NAME);
+
+    if !journal_path.exists() {
+        return Ok(ActionJournal::default());
+    }
+
+    let content = std::fs::read_to_string(&journal_path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Save the action histories of all open and closed tabs to the journal file
+pub fn save_journal<'a>(
+    histories: impl IntoIterator<Item = &'a TabActionHistory>,
+    closed_histories: impl IntoIterator<Item = &'a TabActionHistory>,
+    config_dir_override: Option<&PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config_dir = config::get_kiorg_config_dir(config_dir_override);
+    if !config_dir.exists() {
+        std::fs::create_dir_all(&config_dir)?;
+    }
+
+    #[derive(Serialize)]
+    struct JournalRef<'h> {
+        tabs: Vec<&'h TabActionHistory>,
+        closed_tabs: Vec<&'h TabActionHistory>,
+    }
+    let journal = JournalRef {
+        tabs: histories.into_iter().collect(),
+        closed_tabs: closed_histories.into_iter().collect(),
+    };
+
+    // Write to a temporary file first so a crash never leaves a truncated journal
+    let journal_path = config_dir.join(JOURNAL_FILE_NAME);
+    let temp_path = journal_path.with_extension("json.tmp");
+    std::fs::write(&temp_path, serde_json::to_string(&journal)?)?;
+    std::fs::rename(&temp_path, &journal_path)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,6 +621,68 @@ mod tests {
         assert_eq!(history.len(), 3);
     }
 
+    #[test]
+    fn test_journal_roundtrip() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let config_dir_override = Some(config_dir.path().to_path_buf());
+        let file = config_dir.path().join("created.txt");
+        std::fs::write(&file, "content").unwrap();
+
+        let mut history = TabActionHistory::new();
+        history.add_action(ActionType::Create {
+            operations: vec![CreateOperation {
+                path: file.clone(),
+                is_dir: false,
+            }],
+        });
+        history.add_action(ActionType::Rename {
+            operations: vec![RenameOperation {
+                old_path: PathBuf::from("old.txt"),
+                new_path: PathBuf::from("new.txt"),
+            }],
+        });
+        history.undo_last_action();
+        save_journal(
+            [&history, &TabActionHistory::new()],
+            [&history],
+            config_dir_override.as_ref(),
+        )
+        .unwrap();
+
+        let journal = load_journal(config_dir_override.as_ref()).unwrap();
+        assert_eq!(journal.closed_tabs.len(), 1);
+        let loaded = journal.tabs;
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].get_active_actions().len(), 1);
+        assert_eq!(
+            loaded[0].get_rolled_back_actions()[0].action_type,
+            history.get_rolled_back_actions()[0].action_type
+        );
+        assert!(loaded[1].is_empty());
+        assert!(loaded[0].get_active_actions()[0].stale_paths().is_empty());
+    }
+
+    #[test]
+    fn test_stale_action_detection() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("created.txt");
+        std::fs::write(&file, "content").unwrap();
+
+        let action = HistoryAction::new(ActionType::Create {
+            operations: vec![CreateOperation {
+                path: file.clone(),
+                is_dir: false,
+            }],
+        });
+        assert!(action.stale_paths().is_empty());
+
+        std::fs::write(&file, "changed content").unwrap();
+        assert_eq!(action.stale_paths(), vec![file.as_path()]);
+
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(action.stale_paths(), vec![file.as_path()]);
+    }
+
     #[test]
     fn test_undo_redo_functionality() {
         let mut history = TabActionHistory::new();
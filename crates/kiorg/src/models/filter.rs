@@ -0,0 +1,274 @@
+//! Persistent filter rules from the `[[filters]]` config sections
+//!
+//! Unlike the search bar, these filters stay on until they are toggled off.
+//! A rule without a `path` applies everywhere, one with a `path` applies in
+//! that directory and below it. An entry is listed only if every rule that
+//! applies lets it through.
+//!
+//! Exclude patterns hide files and directories alike. Include patterns, the
+//! size and the age limits only hide files, and so do the image and video
+//! kinds, so directories stay navigable.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::config::{Config, EntryKind, FilterRule};
+use crate::models::dir_entry::DirEntry;
+use crate::ui::preview::{image_extensions, path_to_ext_info, video_extensions};
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    dir: Option<PathBuf>,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Patterns that ended with `/`
+    exclude_dirs: GlobSet,
+    kind: Option<EntryKind>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    min_age: Option<Duration>,
+    max_age: Option<Duration>,
+}
+
+/// The filter rules of the config, ready to match entries
+#[derive(Debug, Clone, Default)]
+pub struct FilterRules {
+    rules: Vec<CompiledRule>,
+}
+
+impl FilterRules {
+    /// Compile the rules of the `[[filters]]` config sections
+    ///
+    /// # Errors
+    ///
+    /// Returns an error string naming the first invalid glob pattern.
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let rules = config
+            .filters
+            .iter()
+            .flatten()
+            .map(compile_rule)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether any rule applies to the entries of `dir`
+    #[must_use]
+    pub fn applies_to(&self, dir: &Path) -> bool {
+        self.rules.iter().any(|rule| rule_applies(rule, dir))
+    }
+
+    /// Whether `entry`, listed in `dir`, is hidden by a rule
+    #[must_use]
+    pub fn hides(&self, dir: &Path, entry: &DirEntry) -> bool {
+        let now = SystemTime::now();
+        self.rules
+            .iter()
+            .filter(|rule| rule_applies(rule, dir))
+            .any(|rule| !rule_keeps(rule, entry, now))
+    }
+}
+
+fn compile_rule(rule: &FilterRule) -> Result<CompiledRule, String> {
+    let mut exclude = GlobSetBuilder::new();
+    let mut exclude_dirs = GlobSetBuilder::new();
+    for pattern in &rule.exclude {
+        match pattern.strip_suffix('/') {
+            Some(dir_pattern) => exclude_dirs.add(glob(dir_pattern)?),
+            None => exclude.add(glob(pattern)?),
+        };
+    }
+    let include = if rule.include.is_empty() {
+        None
+    } else {
+        let mut include = GlobSetBuilder::new();
+        for pattern in &rule.include {
+            include.add(glob(pattern)?);
+        }
+        Some(build(include)?)
+    };
+
+    Ok(CompiledRule {
+        dir: rule.path.as_deref().map(expand_home),
+        include,
+        exclude: build(exclude)?,
+        exclude_dirs: build(exclude_dirs)?,
+        kind: rule.kind,
+        min_size: rule.min_size_kb.map(|kb| kb.saturating_mul(1024)),
+        max_size: rule.max_size_kb.map(|kb| kb.saturating_mul(1024)),
+        min_age: rule.min_age_days.map(days),
+        max_age: rule.max_age_days.map(days),
+    })
+}
+
+const fn days(days: u64) -> Duration {
+    Duration::from_secs(days.saturating_mul(24 * 60 * 60))
+}
+
+fn glob(pattern: &str) -> Result<Glob, String> {
+    Glob::new(pattern).map_err(|e| format!("Invalid filter pattern '{pattern}': {e}"))
+}
+
+fn build(builder: GlobSetBuilder) -> Result<GlobSet, String> {
+    builder
+        .build()
+        .map_err(|e| format!("Invalid filter pattern: {e}"))
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~")
+        && let Some(home) = dirs::home_dir()
+    {
+        return home.join(rest);
+    }
+    path.to_path_buf()
+}
+
+fn rule_applies(rule: &CompiledRule, dir: &Path) -> bool {
+    rule.dir
+        .as_ref()
+        .is_none_or(|rule_dir| dir.starts_with(rule_dir))
+}
+
+fn rule_keeps(rule: &CompiledRule, entry: &DirEntry, now: SystemTime) -> bool {
+    if rule.exclude.is_match(&entry.name) {
+        return false;
+    }
+    if entry.is_dir {
+        return !rule.exclude_dirs.is_match(&entry.name) && rule.kind != Some(EntryKind::Files);
+    }
+
+    if rule
+        .include
+        .as_ref()
+        .is_some_and(|include| !include.is_match(&entry.name))
+    {
+        return false;
+    }
+    let kind_matches = match rule.kind {
+        None | Some(EntryKind::Files) => true,
+        Some(EntryKind::Dirs) => false,
+        Some(EntryKind::Images) => {
+            matches!(
+                path_to_ext_info(&entry.meta.path).as_str(),
+                image_extensions!()
+            )
+        }
+        Some(EntryKind::Videos) => {
+            matches!(
+                path_to_ext_info(&entry.meta.path).as_str(),
+                video_extensions!()
+            )
+        }
+    };
+    if !kind_matches {
+        return false;
+    }
+
+    if rule.min_size.is_some_and(|min| entry.size < min)
+        || rule.max_size.is_some_and(|max| entry.size > max)
+    {
+        return false;
+    }
+    // Entries from the future count as brand new
+    let age = now.duration_since(entry.meta.modified).unwrap_or_default();
+    !(rule.min_age.is_some_and(|min| age < min) || rule.max_age.is_some_and(|max| age > max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::dir_entry::DirEntryMeta;
+
+    fn entry(name: &str, is_dir: bool, size: u64, age_days: u64) -> DirEntry {
+        DirEntry::new(
+            name.to_string(),
+            DirEntryMeta {
+                path: PathBuf::from("/project").join(name),
+                modified: SystemTime::now() - days(age_days),
+            },
+            is_dir,
+            false,
+            size,
+        )
+    }
+
+    fn rules(filters: Vec<FilterRule>) -> FilterRules {
+        FilterRules::from_config(&Config {
+            filters: Some(filters),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_exclude_patterns() {
+        let rules = rules(vec![FilterRule {
+            exclude: vec!["*.o".to_string(), "target/".to_string()],
+            ..Default::default()
+        }]);
+        let dir = Path::new("/project");
+        assert!(rules.hides(dir, &entry("main.o", false, 1, 0)));
+        assert!(rules.hides(dir, &entry("target", true, 0, 0)));
+        // The trailing slash only matches directories
+        assert!(!rules.hides(dir, &entry("target", false, 1, 0)));
+        assert!(!rules.hides(dir, &entry("main.c", false, 1, 0)));
+    }
+
+    #[test]
+    fn test_rules_per_directory() {
+        let rules = rules(vec![FilterRule {
+            path: Some(PathBuf::from("/project")),
+            include: vec!["*.rs".to_string()],
+            ..Default::default()
+        }]);
+        assert!(rules.applies_to(Path::new("/project/src")));
+        assert!(!rules.applies_to(Path::new("/other")));
+        assert!(rules.hides(Path::new("/project/src"), &entry("notes.txt", false, 1, 0)));
+        assert!(!rules.hides(Path::new("/other"), &entry("notes.txt", false, 1, 0)));
+        // Directories stay navigable
+        assert!(!rules.hides(Path::new("/project"), &entry("src", true, 0, 0)));
+    }
+
+    #[test]
+    fn test_kind_size_and_age() {
+        let dir = Path::new("/photos");
+        let images = rules(vec![FilterRule {
+            kind: Some(EntryKind::Images),
+            min_size_kb: Some(1),
+            max_age_days: Some(30),
+            ..Default::default()
+        }]);
+        assert!(!images.hides(dir, &entry("cat.JPG", false, 4096, 1)));
+        assert!(!images.hides(dir, &entry("2024", true, 0, 400)));
+        assert!(images.hides(dir, &entry("cat.txt", false, 4096, 1)));
+        assert!(images.hides(dir, &entry("tiny.png", false, 10, 1)));
+        assert!(images.hides(dir, &entry("old.png", false, 4096, 31)));
+
+        let dirs = rules(vec![FilterRule {
+            kind: Some(EntryKind::Dirs),
+            ..Default::default()
+        }]);
+        assert!(dirs.hides(dir, &entry("cat.png", false, 1, 0)));
+        assert!(!dirs.hides(dir, &entry("2024", true, 0, 0)));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let config = Config {
+            filters: Some(vec![FilterRule {
+                exclude: vec!["[".to_string()],
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        assert!(FilterRules::from_config(&config).is_err());
+    }
+}
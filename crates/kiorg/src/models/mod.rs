@@ -1,4 +1,5 @@
 pub mod action_history;
 pub mod dir_entry;
+pub mod filter;
 pub mod preview_content;
 pub mod tab;
@@ -1,8 +1,22 @@
 use crate::config::Config as AppConfig;
-use crate::models::action_history::TabActionHistory;
+use crate::models::action_history::{ActionJournal, TabActionHistory};
 use crate::models::dir_entry::DirEntry;
+use crate::models::filter::FilterRules;
 use nucleo::{Config as NucleoConfig, Matcher, Utf32Str};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+
+/// Closed tabs whose action history is kept, older ones are forgotten
+const MAX_CLOSED_ACTION_HISTORIES: usize = 10;
+
+/// Entries of a directory listed on a worker thread
+pub struct Listing {
+    pub path: PathBuf,
+    pub parent_entries: Option<(PathBuf, Vec<DirEntry>)>,
+    pub entries: Result<Vec<DirEntry>, String>,
+}
+
+pub type ListingReceiver = Arc<Mutex<mpsc::Receiver<Listing>>>;
 
 #[derive(Clone, PartialEq, Debug, Hash, Eq, serde::Serialize, serde::Deserialize, Copy)]
 pub enum SortColumn {
@@ -10,6 +24,21 @@ pub enum SortColumn {
     Modified,
     Size,
     None,
+    /// Column contributed by a plugin, by index into the plugin manager's
+    /// columns. Not persisted since plugins may change between runs.
+    #[serde(skip)]
+    Plugin(usize),
+}
+
+impl SortColumn {
+    /// The column to persist, plugin columns fall back to no sorting
+    #[must_use]
+    pub const fn persisted(self) -> Self {
+        match self {
+            Self::Plugin(_) => Self::None,
+            column => column,
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize, Copy)]
@@ -44,6 +73,12 @@ pub struct Tab {
     path_to_index: std::collections::HashMap<PathBuf, usize>,
     // Cached filtered entries to avoid re-filtering on every draw
     cached_filtered_entries: Vec<usize>,
+    // Matched character indices in the names of the filtered entries, in the same order
+    cached_match_indices: Vec<Vec<u32>>,
+    // Number of entries of the current directory hidden by the filter rules
+    pub hidden_by_filters: usize,
+    // Set while the current directory is listed on a worker thread
+    listing: Option<ListingReceiver>,
 }
 
 // Private helper function for sorting DirEntry slices
@@ -52,10 +87,49 @@ fn sort_entries_by(entries: &mut [DirEntry], sort_column: SortColumn, sort_order
         SortColumn::Name => |a: &DirEntry, b: &DirEntry| a.name.cmp(&b.name),
         SortColumn::Modified => |a: &DirEntry, b: &DirEntry| a.meta.modified.cmp(&b.meta.modified),
         SortColumn::Size => |a: &DirEntry, b: &DirEntry| a.size.cmp(&b.size),
-        SortColumn::None => {
+        // Plugin columns need the values, see sort_entries_with_keys
+        SortColumn::None | SortColumn::Plugin(_) => {
             return;
         }
     };
+    sort_dirs_first(entries, sort_order, primary_order_fn);
+}
+
+// Sort by plugin column values, entries without a value compare lowest
+fn sort_entries_with_keys(
+    entries: &mut [DirEntry],
+    sort_column: SortColumn,
+    sort_order: SortOrder,
+    plugin_sort_keys: &std::collections::HashMap<PathBuf, String>,
+) {
+    if !matches!(sort_column, SortColumn::Plugin(_)) {
+        sort_entries_by(entries, sort_column, sort_order);
+        return;
+    }
+    sort_dirs_first(entries, sort_order, |a, b| {
+        match (
+            plugin_sort_keys.get(&a.meta.path),
+            plugin_sort_keys.get(&b.meta.path),
+        ) {
+            (Some(a), Some(b)) => compare_column_values(a, b),
+            (a, b) => a.cmp(&b),
+        }
+    });
+}
+
+// Numbers compare by value, anything else as text
+fn compare_column_values(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+fn sort_dirs_first(
+    entries: &mut [DirEntry],
+    sort_order: SortOrder,
+    primary_order_fn: impl Fn(&DirEntry, &DirEntry) -> std::cmp::Ordering,
+) {
     match sort_order {
         SortOrder::Ascending => entries.sort_by(|a, b| {
             // Always keep folders first regardless of sort column
@@ -116,12 +190,21 @@ impl Tab {
             action_history,
             path_to_index: std::collections::HashMap::new(),
             cached_filtered_entries: Vec::new(),
+            cached_match_indices: Vec::new(),
+            hidden_by_filters: 0,
+            listing: None,
         };
         // Add the initial path to history
         tab.add_to_history(path);
         tab
     }
 
+    // Whether the current directory is still being listed on a worker thread
+    #[must_use]
+    pub const fn is_listing(&self) -> bool {
+        self.listing.is_some()
+    }
+
     // Convert Tab to TabState for serialization
     #[must_use]
     pub fn to_state(&self) -> TabState {
@@ -151,6 +234,9 @@ impl Tab {
             action_history,
             path_to_index: std::collections::HashMap::new(),
             cached_filtered_entries: Vec::new(),
+            cached_match_indices: Vec::new(),
+            hidden_by_filters: 0,
+            listing: None,
         };
         // Add the initial path to history
         tab.add_to_history(path);
@@ -299,12 +385,17 @@ impl Tab {
     // Returns a filtered list of entries based on the search query with case sensitivity option
 
     // Update cached filtered entries with new filter parameters
+    //
+    // Fuzzy matches are ranked by score unless `keep_order` is set, then they
+    // stay in the current sort order.
     pub fn update_filtered_cache(
         &mut self,
         query: &Option<String>,
         case_insensitive: bool,
         fuzzy: bool,
+        keep_order: bool,
     ) {
+        self.cached_match_indices.clear();
         // Inline the filtering logic instead of calling get_filtered_entries_with_indices_and_case
         let filtered_indices = match query.as_ref() {
             Some(q) if fuzzy => {
@@ -325,13 +416,26 @@ impl Tab {
                     let mut haystack_buf = Vec::new();
                     let haystack_utf32 = Utf32Str::new(&entry.name, &mut haystack_buf);
 
-                    // TODO: rank result by score
-                    if let Some(_score) = matcher.fuzzy_match(haystack_utf32, needle_utf32) {
-                        matches.push(index);
+                    let mut indices = Vec::new();
+                    if let Some(score) =
+                        matcher.fuzzy_indices(haystack_utf32, needle_utf32, &mut indices)
+                    {
+                        indices.sort_unstable();
+                        indices.dedup();
+                        matches.push((index, score, indices));
                     }
                 }
 
-                matches
+                if !keep_order {
+                    // Stable, so equally good matches keep the sort order
+                    matches.sort_by(|a, b| b.1.cmp(&a.1));
+                }
+                let (filtered, match_indices): (Vec<usize>, Vec<Vec<u32>>) = matches
+                    .into_iter()
+                    .map(|(index, _, indices)| (index, indices))
+                    .unzip();
+                self.cached_match_indices = match_indices;
+                filtered
             }
             Some(q) if case_insensitive => {
                 let lower_query = q.to_lowercase();
@@ -360,73 +464,54 @@ impl Tab {
     pub fn get_cached_filtered_entries(&self) -> &Vec<usize> {
         &self.cached_filtered_entries
     }
-}
-
-fn read_dir_entries(path: &PathBuf, show_hidden: bool) -> Vec<DirEntry> {
-    if let Ok(read_dir) = std::fs::read_dir(path) {
-        read_dir
-            .filter_map(|entry| {
-                let entry = entry.ok()?;
-                let path = entry.path();
-                let name = entry.file_name().to_string_lossy().into_owned();
-
-                let file_type = entry.file_type().ok()?;
-                let is_symlink = file_type.is_symlink();
-
-                // Filter out hidden files if not requested
-                if !show_hidden {
-                    // For Windows, check the "hidden" file attribute.
-                    #[cfg(windows)]
-                    {
-                        use std::os::windows::fs::MetadataExt;
-                        if let Ok(metadata) = entry.metadata() {
-                            const HIDDEN_ATTRIBUTE: u32 = 0x2;
-                            if (metadata.file_attributes() & HIDDEN_ATTRIBUTE) != 0 {
-                                return None;
-                            }
-                        }
-                    }
 
-                    // For Unix-like systems, check for a leading dot.
-                    #[cfg(not(windows))]
-                    {
-                        if name.starts_with('.') {
-                            return None;
-                        }
-                    }
-                }
-
-                // For non-symlinks, we can determine is_dir without additional syscalls
-                let is_dir = if is_symlink {
-                    // For symlinks, we need to follow the link to determine if target is a directory
-                    // This is the only case where we need the additional syscall
-                    path.is_dir()
-                } else {
-                    // For regular files/directories, use the file_type directly
-                    file_type.is_dir()
-                };
+    // Matched character indices in the name of the entry at `filtered_index`
+    // of the filtered list, empty when there is nothing to highlight
+    #[must_use]
+    pub fn get_cached_match_indices(&self, filtered_index: usize) -> &[u32] {
+        self.cached_match_indices
+            .get(filtered_index)
+            .map_or(&[], Vec::as_slice)
+    }
 
-                // Get metadata for size and modification time
-                let metadata = entry.metadata().ok()?;
-                let modified = metadata
-                    .modified()
-                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-                let size = if is_dir { 0 } else { metadata.len() };
-
-                Some(DirEntry::new(
-                    name,
-                    crate::models::dir_entry::DirEntryMeta { path, modified },
-                    is_dir,
-                    is_symlink,
-                    size,
-                ))
+    /// Marked entries in listing order, or every listed entry when nothing is marked
+    /// Entries hidden by the search filter are left out
+    #[must_use]
+    pub fn marked_or_listed_entries(&self) -> Vec<&DirEntry> {
+        self.cached_filtered_entries
+            .iter()
+            .filter_map(|&index| self.entries.get(index))
+            .filter(|entry| {
+                self.marked_entries.is_empty() || self.marked_entries.contains(&entry.meta.path)
             })
             .collect()
-    } else {
-        Vec::new()
     }
 }
 
+fn read_dir_entries(path: &PathBuf, show_hidden: bool, hide_ignored: bool) -> Vec<DirEntry> {
+    crate::vfs::read_dir_entries(path, show_hidden, hide_ignored)
+}
+
+// List the directory and its parent on a worker thread, so connecting to a
+// remote host does not block the UI
+fn spawn_listing(path: PathBuf, show_hidden: bool, hide_ignored: bool) -> ListingReceiver {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let entries = crate::vfs::try_read_dir_entries(&path, show_hidden, hide_ignored)
+            .map_err(|e| e.to_string());
+        let parent_entries = crate::vfs::parent(&path).map(|parent| {
+            let entries = read_dir_entries(&parent, show_hidden, hide_ignored);
+            (parent, entries)
+        });
+        let _ = sender.send(Listing {
+            path,
+            parent_entries,
+            entries,
+        });
+    });
+    Arc::new(Mutex::new(receiver))
+}
+
 // TabManagerState is the minimal state that gets serialized/deserialized
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct TabManagerState {
@@ -436,6 +521,8 @@ pub struct TabManagerState {
     pub sort_order: SortOrder,
     #[serde(default)]
     pub show_hidden: bool,
+    #[serde(default)]
+    pub hide_ignored: bool,
 }
 
 #[derive(Clone)]
@@ -445,6 +532,17 @@ pub struct TabManager {
     pub sort_column: SortColumn,
     pub sort_order: SortOrder,
     pub show_hidden: bool,
+    // Also hide what git ignores, only when hidden files are hidden
+    pub hide_ignored: bool,
+    // Rules from the `[[filters]]` config sections
+    pub filter_rules: FilterRules,
+    pub filters_enabled: bool,
+    // Values of the plugin column being sorted by, filled as plugins return them
+    pub plugin_sort_keys: std::collections::HashMap<PathBuf, String>,
+    // Histories of closed tabs, oldest first, kept so their actions stay journaled
+    closed_action_histories: Vec<TabActionHistory>,
+    // Set when a tab with action history is closed so the journal gets saved
+    action_histories_changed: bool,
 }
 
 impl TabManager {
@@ -470,6 +568,12 @@ impl TabManager {
             sort_column,
             sort_order,
             show_hidden: false,
+            hide_ignored: false,
+            filter_rules: FilterRules::default(),
+            filters_enabled: true,
+            plugin_sort_keys: std::collections::HashMap::new(),
+            closed_action_histories: Vec::new(),
+            action_histories_changed: false,
         }
     }
 
@@ -479,9 +583,10 @@ impl TabManager {
         TabManagerState {
             tab_states: self.tabs.iter().map(Tab::to_state).collect(),
             current_tab_index: self.current_tab_index,
-            sort_column: self.sort_column,
+            sort_column: self.sort_column.persisted(),
             sort_order: self.sort_order,
             show_hidden: self.show_hidden,
+            hide_ignored: self.hide_ignored,
         }
     }
 
@@ -493,6 +598,12 @@ impl TabManager {
             sort_column: state.sort_column,
             sort_order: state.sort_order,
             show_hidden: state.show_hidden,
+            hide_ignored: state.hide_ignored && !state.show_hidden,
+            filter_rules: FilterRules::default(),
+            filters_enabled: true,
+            plugin_sort_keys: std::collections::HashMap::new(),
+            closed_action_histories: Vec::new(),
+            action_histories_changed: false,
         }
     }
 
@@ -516,7 +627,9 @@ impl TabManager {
 
     pub fn close_current_tab(&mut self) -> bool {
         if self.tabs.len() > 1 {
-            self.tabs.remove(self.current_tab_index);
+            let closed = self.tabs.remove(self.current_tab_index);
+            self.keep_closed_history(closed.action_history);
+            self.action_histories_changed = true;
             // Adjust the current tab index if necessary
             if self.current_tab_index >= self.tabs.len() {
                 self.current_tab_index = self.tabs.len() - 1;
@@ -527,6 +640,31 @@ impl TabManager {
         false
     }
 
+    // The tab at `index` if it still shows `path`, otherwise the first tab showing
+    // `path`. Falls back to `index`, or the current tab once `index` is gone.
+    #[must_use]
+    pub fn find_tab_showing(&self, index: usize, path: &Path) -> usize {
+        if self
+            .tabs
+            .get(index)
+            .is_some_and(|tab| tab.current_path == path)
+        {
+            return index;
+        }
+        if let Some(showing) = self.tabs.iter().position(|tab| tab.current_path == path) {
+            return showing;
+        }
+        if index < self.tabs.len() {
+            index
+        } else {
+            self.current_tab_index
+        }
+    }
+
+    pub fn tab_mut(&mut self, index: usize) -> Option<&mut Tab> {
+        self.tabs.get_mut(index)
+    }
+
     pub fn current_tab_mut(&mut self) -> &mut Tab {
         &mut self.tabs[self.current_tab_index]
     }
@@ -548,6 +686,52 @@ impl TabManager {
         self.tabs.len()
     }
 
+    // Action histories of all tabs in tab order
+    pub fn action_histories(&self) -> impl Iterator<Item = &TabActionHistory> {
+        self.tabs.iter().map(|tab| &tab.action_history)
+    }
+
+    // Action histories of closed tabs, oldest first
+    #[must_use]
+    pub fn closed_action_histories(&self) -> &[TabActionHistory] {
+        &self.closed_action_histories
+    }
+
+    // Attach histories loaded from the journal to tabs by position, histories of
+    // tabs that are no longer open are kept as closed tab histories
+    pub fn restore_action_histories(&mut self, journal: ActionJournal) {
+        for history in journal.closed_tabs {
+            self.keep_closed_history(history);
+        }
+        let tab_count = self.tabs.len();
+        for (index, history) in journal.tabs.into_iter().enumerate() {
+            if index < tab_count {
+                self.tabs[index].action_history = history;
+            } else {
+                self.keep_closed_history(history);
+            }
+        }
+    }
+
+    fn keep_closed_history(&mut self, history: TabActionHistory) {
+        if history.is_empty() {
+            return;
+        }
+        self.closed_action_histories.push(history);
+        if self.closed_action_histories.len() > MAX_CLOSED_ACTION_HISTORIES {
+            self.closed_action_histories.remove(0);
+        }
+    }
+
+    // Returns whether any action history changed since the last call
+    pub fn take_action_history_changes(&mut self) -> bool {
+        let mut changed = std::mem::take(&mut self.action_histories_changed);
+        for tab in &mut self.tabs {
+            changed |= tab.action_history.take_changed();
+        }
+        changed
+    }
+
     // Get the index of an entry by its path in the current tab
     #[must_use]
     pub fn get_entry_index_by_path(&self, path: &PathBuf) -> Option<usize> {
@@ -586,37 +770,145 @@ impl TabManager {
         }
 
         let (column, order) = (self.sort_column, self.sort_order);
-        let tab = self.current_tab_mut();
-        sort_entries_by(&mut tab.entries, column, order);
-        sort_entries_by(&mut tab.parent_entries, column, order);
+        let tab = &mut self.tabs[self.current_tab_index];
+        sort_entries_with_keys(&mut tab.entries, column, order, &self.plugin_sort_keys);
+        sort_entries_with_keys(
+            &mut tab.parent_entries,
+            column,
+            order,
+            &self.plugin_sort_keys,
+        );
         refresh_path_to_index(tab);
 
         // Reset filter cache to show all entries when sort order changes
-        tab.update_filtered_cache(&None, false, false);
+        tab.update_filtered_cache(&None, false, false, false);
+    }
+
+    /// Sort the current tab again by the plugin column being sorted by, once
+    /// more of its values are known, keeping the selected entry selected
+    pub fn resort_by_plugin_keys(&mut self, keys: std::collections::HashMap<PathBuf, String>) {
+        self.plugin_sort_keys = keys;
+        let (column, order) = (self.sort_column, self.sort_order);
+        if !matches!(column, SortColumn::Plugin(_)) {
+            return;
+        }
+        let tab = &mut self.tabs[self.current_tab_index];
+        let selected = tab.selected_entry().map(|entry| entry.meta.path.clone());
+        sort_entries_with_keys(&mut tab.entries, column, order, &self.plugin_sort_keys);
+        refresh_path_to_index(tab);
+        if let Some(index) = selected.and_then(|path| tab.get_index_by_path(&path)) {
+            tab.selected_index = index;
+        }
+        tab.update_filtered_cache(&None, false, false, false);
     }
 
+    /// Cycle through hiding hidden files, showing them, and hiding hidden
+    /// files along with what git ignores
     pub fn toggle_show_hidden(&mut self) {
-        self.show_hidden = !self.show_hidden;
+        (self.show_hidden, self.hide_ignored) = match (self.show_hidden, self.hide_ignored) {
+            (false, false) => (true, false),
+            (true, _) => (false, true),
+            (false, true) => (false, false),
+        };
+    }
+
+    pub fn toggle_filters(&mut self) {
+        self.filters_enabled = !self.filters_enabled;
     }
 
     pub fn refresh_entries(&mut self) {
+        let show_hidden = self.show_hidden;
+        let hide_ignored = self.hide_ignored;
+        let tab = &mut self.tabs[self.current_tab_index];
+        let current_path = tab.current_path.clone(); // Get current path from the tab
+
+        if crate::vfs::is_slow_to_list(&current_path) {
+            // Entries of another directory must not be acted on meanwhile, those
+            // of the same directory stay until the new ones arrive
+            let same_dir = tab
+                .entries
+                .first()
+                .is_some_and(|entry| entry.meta.path.parent() == Some(current_path.as_path()));
+            if !same_dir {
+                tab.entries.clear();
+                tab.parent_entries.clear();
+                refresh_path_to_index(tab);
+                tab.update_filtered_cache(&None, false, false, false);
+            }
+            tab.listing = Some(spawn_listing(current_path, show_hidden, hide_ignored));
+            return;
+        }
+        tab.listing = None;
+
+        let parent_entries = crate::vfs::parent(&current_path).map(|parent| {
+            let entries = read_dir_entries(&parent, show_hidden, hide_ignored);
+            (parent, entries)
+        });
+        let entries = read_dir_entries(&current_path, show_hidden, hide_ignored); // Read entries for the current path
+        self.apply_entries(parent_entries, entries);
+    }
+
+    /// Take the entries of the current tab once they were listed on a worker
+    /// thread, returns the listing error if there was one
+    pub fn poll_listing(&mut self) -> Option<Result<(), String>> {
+        let tab = &mut self.tabs[self.current_tab_index];
+        let receiver = tab.listing.clone()?;
+        let listing = match receiver.lock().ok()?.try_recv() {
+            Ok(listing) => listing,
+            Err(mpsc::TryRecvError::Empty) => return None,
+            Err(mpsc::TryRecvError::Disconnected) => Listing {
+                path: tab.current_path.clone(),
+                parent_entries: None,
+                entries: Err("Listing stopped unexpectedly".to_string()),
+            },
+        };
+        tab.listing = None;
+        if listing.path != tab.current_path {
+            return None;
+        }
+        let (entries, result) = match listing.entries {
+            Ok(entries) => (entries, Ok(())),
+            Err(e) => (Vec::new(), Err(e)),
+        };
+        self.apply_entries(listing.parent_entries, entries);
+        Some(result)
+    }
+
+    // Filter and sort freshly read entries of the current tab
+    fn apply_entries(
+        &mut self,
+        parent_entries: Option<(PathBuf, Vec<DirEntry>)>,
+        entries: Vec<DirEntry>,
+    ) {
         // Store sort settings before borrowing self mutably
         let sort_column = self.sort_column;
         let sort_order = self.sort_order;
-        let show_hidden = self.show_hidden;
+        let filters =
+            (self.filters_enabled && !self.filter_rules.is_empty()).then_some(&self.filter_rules);
 
-        let tab = self.current_tab_mut();
-        let current_path = tab.current_path.clone(); // Get current path from the tab
+        let tab = &mut self.tabs[self.current_tab_index];
+        let current_path = tab.current_path.clone();
 
         // Path changed or first load, perform full refresh
         // --- Start: Parent Directory Logic ---
         tab.parent_entries.clear();
         tab.parent_selected_index = 0; // Default selection
 
-        if let Some(parent) = current_path.parent() {
-            tab.parent_entries = read_dir_entries(&parent.to_path_buf(), show_hidden);
+        if let Some((parent, parent_entries)) = parent_entries {
+            tab.parent_entries = parent_entries;
+            if let Some(filters) = filters {
+                // The current directory stays listed even if a rule hides it
+                tab.parent_entries.retain(|entry| {
+                    entry.meta.path == current_path || !filters.hides(&parent, entry)
+                });
+            }
             // Sort parent entries using the global sort settings
-            sort_entries_by(&mut tab.parent_entries, sort_column, sort_order);
+            sort_entries_with_keys(
+                &mut tab.parent_entries,
+                sort_column,
+                sort_order,
+                &self.plugin_sort_keys,
+            );
 
             // Find current directory in parent entries after sorting
             if let Some(pos) = tab
@@ -630,13 +922,25 @@ impl TabManager {
         // --- End: Parent Directory Logic ---
 
         // --- Start: Current Directory Logic ---
-        tab.entries = read_dir_entries(&current_path, show_hidden); // Read entries for the current path
+        tab.entries = entries;
+        tab.hidden_by_filters = 0;
+        if let Some(filters) = filters {
+            let listed = tab.entries.len();
+            tab.entries
+                .retain(|entry| !filters.hides(&current_path, entry));
+            tab.hidden_by_filters = listed - tab.entries.len();
+        }
         // Sort entries using the global sort settings
-        sort_entries_by(&mut tab.entries, sort_column, sort_order);
+        sort_entries_with_keys(
+            &mut tab.entries,
+            sort_column,
+            sort_order,
+            &self.plugin_sort_keys,
+        );
         refresh_path_to_index(tab);
 
         // Reset filter cache to show all entries when entries change
-        tab.update_filtered_cache(&None, false, false);
+        tab.update_filtered_cache(&None, false, false, false);
 
         // Reset selection index if it's out of bounds (can happen after rehydrating from TabState)
         if tab.selected_index >= tab.entries.len() && !tab.entries.is_empty() {
@@ -797,6 +1101,37 @@ mod tests {
         assert_eq!(&names[2..], &["large_file", "medium_file", "small_file"]);
     }
 
+    #[test]
+    fn test_sort_plugin_column() {
+        let mut entries = vec![
+            create_entry("ten", false, 10, 0),
+            create_entry("nine", false, 10, 0),
+            create_entry("unknown", false, 10, 0),
+            create_entry("dir", true, 10, 0),
+        ];
+        let keys = [("ten", "10"), ("nine", "9"), ("dir", "1")]
+            .into_iter()
+            .map(|(name, value)| (PathBuf::from(name), value.to_string()))
+            .collect();
+        sort_entries_with_keys(
+            &mut entries,
+            SortColumn::Plugin(0),
+            SortOrder::Ascending,
+            &keys,
+        );
+        // Numbers compare by value, entries without a value come first
+        assert_eq!(get_names(&entries), vec!["dir", "unknown", "nine", "ten"]);
+
+        sort_entries_with_keys(
+            &mut entries,
+            SortColumn::Plugin(0),
+            SortOrder::Descending,
+            &keys,
+        );
+        assert_eq!(get_names(&entries), vec!["dir", "ten", "nine", "unknown"]);
+        assert_eq!(SortColumn::Plugin(0).persisted(), SortColumn::None);
+    }
+
     #[test]
     fn test_sort_only_dirs() {
         let mut entries = vec![
@@ -932,6 +1267,65 @@ mod tests {
         assert_eq!(new_tab.parent_selected_index, 0);
     }
 
+    #[test]
+    fn test_closed_tab_history_is_kept_apart() {
+        use crate::models::action_history::{ActionType, CreateOperation};
+
+        let created = |name: &str| ActionType::Create {
+            operations: vec![CreateOperation {
+                path: PathBuf::from(name),
+                is_dir: false,
+            }],
+        };
+        let mut tab_manager = TabManager::new(PathBuf::from("/first"));
+        tab_manager
+            .current_tab_mut()
+            .action_history
+            .add_action(created("first.txt"));
+        tab_manager.add_tab(PathBuf::from("/second"));
+        tab_manager
+            .current_tab_mut()
+            .action_history
+            .add_action(created("second.txt"));
+
+        assert!(tab_manager.close_current_tab());
+        let closed = tab_manager.closed_action_histories();
+        assert_eq!(closed.len(), 1);
+        assert_eq!(
+            closed[0].get_active_actions()[0].action_type,
+            created("second.txt")
+        );
+        assert_eq!(
+            tab_manager
+                .current_tab_ref()
+                .action_history
+                .get_active_actions()
+                .len(),
+            1
+        );
+
+        // Journaled histories of tabs that are gone stay closed on restore
+        let journal = ActionJournal {
+            tabs: tab_manager
+                .action_histories()
+                .cloned()
+                .chain(closed.iter().cloned())
+                .collect(),
+            closed_tabs: Vec::new(),
+        };
+        let mut restored = TabManager::new(PathBuf::from("/first"));
+        restored.restore_action_histories(journal);
+        assert_eq!(
+            restored
+                .current_tab_ref()
+                .action_history
+                .get_active_actions()
+                .len(),
+            1
+        );
+        assert_eq!(restored.closed_action_histories().len(), 1);
+    }
+
     #[test]
     fn test_fuzzy_search_functionality() {
         // Create a tab with sample file entries
@@ -947,7 +1341,7 @@ mod tests {
         ];
 
         // Test fuzzy search with "rs" - should match "main.rs" and "rust-project.json"
-        tab.update_filtered_cache(&Some("rs".to_string()), true, true);
+        tab.update_filtered_cache(&Some("rs".to_string()), true, true, false);
         let fuzzy_rs_results: Vec<String> = tab
             .get_cached_filtered_entries()
             .iter()
@@ -958,7 +1352,7 @@ mod tests {
         assert!(fuzzy_rs_results.contains(&"rust-project.json".to_string()));
 
         // Test exact search with "rt" - should not match anything because "rt" doesn't appear consecutively
-        tab.update_filtered_cache(&Some("rt".to_string()), true, false);
+        tab.update_filtered_cache(&Some("rt".to_string()), true, false, false);
         let exact_rt_results: Vec<String> = tab
             .get_cached_filtered_entries()
             .iter()
@@ -967,7 +1361,7 @@ mod tests {
         assert_eq!(exact_rt_results.len(), 0);
 
         // Test fuzzy search with "cfg" - should match "config.toml"
-        tab.update_filtered_cache(&Some("cfg".to_string()), true, true);
+        tab.update_filtered_cache(&Some("cfg".to_string()), true, true, false);
         let fuzzy_cfg_results: Vec<String> = tab
             .get_cached_filtered_entries()
             .iter()
@@ -977,7 +1371,7 @@ mod tests {
         assert!(fuzzy_cfg_results.contains(&"config.toml".to_string()));
 
         // Test fuzzy search with "rt" - should match "readme.txt" and "rust-project.json"
-        tab.update_filtered_cache(&Some("rt".to_string()), true, true);
+        tab.update_filtered_cache(&Some("rt".to_string()), true, true, false);
         let fuzzy_rt_results: Vec<String> = tab
             .get_cached_filtered_entries()
             .iter()
@@ -989,7 +1383,7 @@ mod tests {
 
         // Test case sensitivity in fuzzy search - use a pattern that exists in different cases
         // First test case sensitive search with lowercase "config" - should match "config.toml"
-        tab.update_filtered_cache(&Some("config".to_string()), false, true); // case sensitive
+        tab.update_filtered_cache(&Some("config".to_string()), false, true, false); // case sensitive
         let case_sensitive_results: Vec<String> = tab
             .get_cached_filtered_entries()
             .iter()
@@ -999,7 +1393,7 @@ mod tests {
         assert!(case_sensitive_results.contains(&"config.toml".to_string()));
 
         // Test case sensitive search with uppercase "CONFIG" - should not match anything
-        tab.update_filtered_cache(&Some("CONFIG".to_string()), false, true); // case sensitive
+        tab.update_filtered_cache(&Some("CONFIG".to_string()), false, true, false); // case sensitive
         let case_sensitive_upper_results: Vec<String> = tab
             .get_cached_filtered_entries()
             .iter()
@@ -1008,7 +1402,7 @@ mod tests {
         assert_eq!(case_sensitive_upper_results.len(), 0); // Should not match "config.toml"
 
         // Test case insensitive search with uppercase "CONFIG" - should match "config.toml"
-        tab.update_filtered_cache(&Some("CONFIG".to_string()), true, true); // case insensitive
+        tab.update_filtered_cache(&Some("CONFIG".to_string()), true, true, false); // case insensitive
         let case_insensitive_results: Vec<String> = tab
             .get_cached_filtered_entries()
             .iter()
@@ -1018,7 +1412,7 @@ mod tests {
         assert!(case_insensitive_results.contains(&"config.toml".to_string()));
 
         // Test that original indices are preserved correctly
-        tab.update_filtered_cache(&Some("rs".to_string()), true, true);
+        tab.update_filtered_cache(&Some("rs".to_string()), true, true, false);
         let results_with_indices = tab.get_cached_filtered_entries();
 
         // Find the original indices for our matched files
@@ -1033,8 +1427,33 @@ mod tests {
         }
 
         // Test empty query shows all entries
-        tab.update_filtered_cache(&None, true, true);
+        tab.update_filtered_cache(&None, true, true, false);
         let all_results = tab.get_cached_filtered_entries();
         assert_eq!(all_results.len(), 5);
     }
+
+    #[test]
+    fn test_fuzzy_search_ranking() {
+        let mut tab = Tab::new(PathBuf::from("/demo"));
+        tab.entries = vec![
+            create_entry("main_backup.txt", false, 10, 100),
+            create_entry("Cargo.lock", false, 20, 100),
+            create_entry("main.rs", false, 30, 100),
+        ];
+
+        // The consecutive match ranks first, ahead of the earlier entry
+        tab.update_filtered_cache(&Some("main.".to_string()), true, true, false);
+        assert_eq!(tab.get_cached_filtered_entries(), &vec![2, 0]);
+        assert_eq!(tab.get_cached_match_indices(0), &[0, 1, 2, 3, 4]);
+        assert_eq!(tab.get_cached_match_indices(1).len(), 5);
+
+        // Keeping the sort order only filters
+        tab.update_filtered_cache(&Some("main.".to_string()), true, true, true);
+        assert_eq!(tab.get_cached_filtered_entries(), &vec![0, 2]);
+        assert_eq!(tab.get_cached_match_indices(1), &[0, 1, 2, 3, 4]);
+
+        // Nothing is highlighted without a fuzzy query
+        tab.update_filtered_cache(&Some("main".to_string()), true, false, false);
+        assert!(tab.get_cached_match_indices(0).is_empty());
+    }
 }
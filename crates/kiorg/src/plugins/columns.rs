@@ -0,0 +1,181 @@
+//! Values of the file list columns contributed by plugins
+//!
+//! Values are requested lazily in batches, only for the rows on screen, and
+//! cached until the modification time of the entry changes.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::SystemTime;
+
+use tracing::warn;
+
+use super::manager::ColumnRef;
+use crate::models::dir_entry::DirEntry;
+
+/// Paths sent to a plugin in a single call
+const BATCH_SIZE: usize = 200;
+
+/// Cached rows of a plugin kept before dropping the ones not in flight
+const MAX_CACHED_ROWS: usize = 20_000;
+
+/// Values a plugin returned for a path
+struct CachedRow {
+    modified: SystemTime,
+    /// `None` while the request is in flight
+    values: Option<Vec<String>>,
+}
+
+/// Values fetched in the background for the plugin whose columns start at
+/// `first_column`
+struct FetchedRows {
+    first_column: usize,
+    rows: Vec<(PathBuf, SystemTime, Vec<String>)>,
+}
+
+pub struct ColumnCache {
+    /// Rows per plugin, keyed by the index of its first column in
+    /// [`super::PluginManager::columns`]
+    rows: HashMap<usize, HashMap<PathBuf, CachedRow>>,
+    sender: mpsc::Sender<FetchedRows>,
+    receiver: mpsc::Receiver<FetchedRows>,
+}
+
+impl Default for ColumnCache {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            rows: HashMap::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl ColumnCache {
+    /// Value of the column at `column` for `entry`, if fetched and still fresh
+    pub fn value<'a>(
+        &'a self,
+        columns: &[ColumnRef],
+        column: usize,
+        entry: &DirEntry,
+    ) -> Option<&'a str> {
+        let column_ref = columns.get(column)?;
+        let row = self
+            .rows
+            .get(&(column - column_ref.index))?
+            .get(&entry.meta.path)?;
+        if row.modified != entry.meta.modified {
+            return None;
+        }
+        row.values
+            .as_ref()?
+            .get(column_ref.index)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Values of the column at `column` for `entries`, to sort by
+    pub fn sort_keys(
+        &self,
+        columns: &[ColumnRef],
+        column: usize,
+        entries: &[DirEntry],
+    ) -> HashMap<PathBuf, String> {
+        entries
+            .iter()
+            .filter_map(|entry| {
+                self.value(columns, column, entry)
+                    .map(|value| (entry.meta.path.clone(), value.to_string()))
+            })
+            .collect()
+    }
+
+    /// Store the values fetched in the background, returns whether any arrived
+    pub fn poll(&mut self) -> bool {
+        let mut received = false;
+        while let Ok(fetched) = self.receiver.try_recv() {
+            let rows = self.rows.entry(fetched.first_column).or_default();
+            for (path, modified, values) in fetched.rows {
+                rows.insert(
+                    path,
+                    CachedRow {
+                        modified,
+                        values: Some(values),
+                    },
+                );
+            }
+            received = true;
+        }
+        received
+    }
+
+    /// Fetch the values missing for `entries` on background threads, one per
+    /// plugin
+    pub fn request(&mut self, ctx: &egui::Context, columns: &[ColumnRef], entries: &[&DirEntry]) {
+        // Columns of a plugin are contiguous, each plugin starts at index 0
+        for (first_column, column_ref) in columns.iter().enumerate() {
+            if column_ref.index != 0 || column_ref.plugin.is_disabled() {
+                continue;
+            }
+            let plugin = column_ref.plugin.clone();
+            let rows = self.rows.entry(first_column).or_default();
+            let missing: Vec<(PathBuf, SystemTime)> = entries
+                .iter()
+                .filter(|entry| {
+                    rows.get(&entry.meta.path)
+                        .is_none_or(|row| row.modified != entry.meta.modified)
+                })
+                .filter(|entry| wants_values(&plugin.columns, &entry.meta.path))
+                .map(|entry| (entry.meta.path.clone(), entry.meta.modified))
+                .collect();
+            if missing.is_empty() {
+                continue;
+            }
+
+            if rows.len() > MAX_CACHED_ROWS {
+                rows.retain(|_, row| row.values.is_none());
+            }
+            for (path, modified) in &missing {
+                rows.insert(
+                    path.clone(),
+                    CachedRow {
+                        modified: *modified,
+                        values: None,
+                    },
+                );
+            }
+
+            let sender = self.sender.clone();
+            let ctx = ctx.clone();
+            std::thread::spawn(move || {
+                for batch in missing.chunks(BATCH_SIZE) {
+                    let paths: Vec<PathBuf> = batch.iter().map(|(path, _)| path.clone()).collect();
+                    let values = plugin.column_values(&paths).unwrap_or_else(|e| {
+                        warn!(
+                            "Failed to fetch columns from plugin '{}': {}",
+                            plugin.metadata.name, e
+                        );
+                        // Leave the cells blank rather than asking again every frame
+                        vec![Vec::new(); batch.len()]
+                    });
+                    let rows = batch
+                        .iter()
+                        .cloned()
+                        .zip(values)
+                        .map(|((path, modified), values)| (path, modified, values))
+                        .collect();
+                    if sender.send(FetchedRows { first_column, rows }).is_err() {
+                        return;
+                    }
+                    ctx.request_repaint();
+                }
+            });
+        }
+    }
+}
+
+/// Whether any of the columns of a plugin has a value for `path`
+fn wants_values(columns: &[super::manager::PluginColumn], path: &Path) -> bool {
+    crate::vfs::is_local(path) && columns.iter().any(|column| column.applies_to(path))
+}
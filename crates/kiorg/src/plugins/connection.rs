@@ -0,0 +1,577 @@
+//! Calls to a running plugin process
+//!
+//! Several calls can be in flight at once. A reader thread per plugin hands
+//! each response to the call with the same id, responses to calls that were
+//! cancelled or timed out are dropped.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use kiorg_plugin::{CallId, EngineCommand, EngineMessage, PluginMessage, PluginResponse};
+use tracing::{debug, warn};
+
+use super::health::{MAX_TIMEOUTS_IN_A_ROW, PluginLog, PluginStats};
+use super::manager::PluginError;
+
+/// Lines of the log quoted when a plugin goes away
+const STDERR_TAIL_LINES: usize = 5;
+
+/// A spawned plugin process, what it writes to stderr goes to its log
+#[derive(Debug)]
+pub struct Process {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: Option<ChildStdout>,
+    stderr_reader: Option<JoinHandle<()>>,
+    log: Arc<Mutex<PluginLog>>,
+}
+
+impl Process {
+    pub fn spawn(path: &Path, log: Arc<Mutex<PluginLog>>) -> Result<Self, PluginError> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| PluginError::ExecutionError {
+                message: format!("Failed to spawn plugin process: {}", e),
+            })?;
+
+        let stderr_reader = child.stderr.take().map(|stderr| {
+            let log = log.clone();
+            std::thread::spawn(move || {
+                for line in BufReader::new(stderr).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    log.lock().expect("Failed to lock plugin log").push(line);
+                }
+            })
+        });
+
+        Ok(Self {
+            stdin: child.stdin.take(),
+            stdout: child.stdout.take(),
+            child,
+            stderr_reader,
+            log,
+        })
+    }
+
+    /// Send the hello message and wait for the answer, before responses are
+    /// read by a [`Connection`]
+    pub fn hello(&mut self, timeout: Duration) -> Result<PluginResponse, String> {
+        let (Some(mut stdin), Some(mut stdout)) = (self.stdin.take(), self.stdout.take()) else {
+            return Err("Plugin stdio not available".to_string());
+        };
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let hello_message = EngineMessage {
+                id: CallId::new(),
+                command: EngineCommand::Hello {
+                    protocol_version: kiorg_plugin::PROTOCOL_VERSION.to_string(),
+                },
+            };
+            if let Err(e) = kiorg_plugin::send_message_to_writer(&mut stdin, &hello_message) {
+                let _ = tx.send(Err(format!("Failed to send message: {}", e)));
+                return;
+            }
+
+            let frame = match kiorg_plugin::read_frame_from_reader(&mut stdout) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    let _ = tx.send(Err(format!("Failed to read response: {}", e)));
+                    return;
+                }
+            };
+            // Plugins built for protocol 0.x answer without the envelope,
+            // decode the bare response to tell they are incompatible
+            let response = rmp_serde::from_slice::<PluginMessage>(&frame)
+                .map(|message| message.response)
+                .or_else(|_| rmp_serde::from_slice::<PluginResponse>(&frame));
+            let _ = tx.send(match response {
+                Ok(response) => Ok((response, stdin, stdout)),
+                Err(e) => Err(format!("Failed to read response: {}", e)),
+            });
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(Ok((response, stdin, stdout))) => {
+                self.stdin = Some(stdin);
+                self.stdout = Some(stdout);
+                Ok(response)
+            }
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err("Timed out waiting for response".to_string()),
+        }
+    }
+
+    /// Stop the process if it still runs and describe how it ended, with the
+    /// last lines it wrote to stderr
+    pub fn stop(&mut self) -> String {
+        let status = self.child.try_wait().ok().flatten();
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        // Stderr closes with the process, wait for its last lines
+        if let Some(reader) = self.stderr_reader.take() {
+            let _ = reader.join();
+        }
+
+        let stderr_output = self
+            .log
+            .lock()
+            .expect("Failed to lock plugin log")
+            .tail(STDERR_TAIL_LINES);
+        match status {
+            Some(status) => format!(
+                "Plugin process exited unexpectedly: {}. Stderr: `{}`",
+                status, stderr_output
+            ),
+            None => format!("Plugin process was stopped. Stderr: `{}`", stderr_output),
+        }
+    }
+}
+
+type CallResult = Result<PluginResponse, PluginError>;
+
+#[derive(Debug)]
+struct Waiting {
+    sender: mpsc::Sender<CallResult>,
+    /// Order in which the call was sent
+    seq: u64,
+}
+
+#[derive(Debug, Default)]
+struct Calls {
+    /// Calls waiting for their response
+    waiting: HashMap<CallId, Waiting>,
+    next_seq: u64,
+    /// When the plugin last answered, including calls given up on. Plugins
+    /// handle calls one at a time, the next one starts from there.
+    last_answer: Option<Instant>,
+    /// Why the plugin stopped responding, once it did
+    closed: Option<String>,
+}
+
+/// Why a connection is being closed from this side
+#[derive(Debug)]
+struct Closing {
+    reason: String,
+    /// Whether the plugin should be restarted, as after a crash
+    restart: bool,
+}
+
+#[derive(Debug)]
+pub struct Connection {
+    plugin_name: String,
+    process: Mutex<Process>,
+    stdin: Mutex<ChildStdin>,
+    calls: Mutex<Calls>,
+    stats: Arc<Mutex<PluginStats>>,
+    closing: Mutex<Option<Closing>>,
+}
+
+impl Connection {
+    /// Start reading the responses of a plugin that answered the hello
+    /// message. Once its stdout closes, `on_close` gets why the plugin went
+    /// away and whether it crashed.
+    pub fn start<F>(
+        plugin_name: String,
+        mut process: Process,
+        stats: Arc<Mutex<PluginStats>>,
+        on_close: F,
+    ) -> Result<Arc<Self>, String>
+    where
+        F: FnOnce(String, bool) + Send + 'static,
+    {
+        let (Some(stdin), Some(mut stdout)) = (process.stdin.take(), process.stdout.take()) else {
+            process.stop();
+            return Err("Plugin stdio not available".to_string());
+        };
+        let connection = Arc::new(Self {
+            plugin_name,
+            process: Mutex::new(process),
+            stdin: Mutex::new(stdin),
+            calls: Mutex::new(Calls::default()),
+            stats,
+            closing: Mutex::new(None),
+        });
+
+        let reader = connection.clone();
+        std::thread::spawn(move || {
+            loop {
+                let frame = match kiorg_plugin::read_frame_from_reader(&mut stdout) {
+                    Ok(frame) => frame,
+                    Err(_) => break,
+                };
+                match rmp_serde::from_slice::<PluginMessage>(&frame) {
+                    Ok(message) => reader.respond(message.id, Ok(message.response)),
+                    Err(e) => warn!(
+                        "Invalid message from plugin '{}': {}",
+                        reader.plugin_name, e
+                    ),
+                }
+            }
+            let closing = reader
+                .closing
+                .lock()
+                .expect("Failed to lock plugin state")
+                .take();
+            let exit = reader
+                .process
+                .lock()
+                .expect("Failed to lock plugin process")
+                .stop();
+            let (reason, crashed) = match closing {
+                Some(closing) => (closing.reason, closing.restart),
+                None => (exit, true),
+            };
+            reader.close(reason.clone());
+            on_close(reason, crashed);
+        });
+
+        Ok(connection)
+    }
+
+    /// Stop the plugin process, `restart` tells whether to treat it as a
+    /// crash
+    pub fn kill(&self, reason: String, restart: bool) {
+        *self.closing.lock().expect("Failed to lock plugin state") =
+            Some(Closing { reason, restart });
+        let _ = self
+            .process
+            .lock()
+            .expect("Failed to lock plugin process")
+            .child
+            .kill();
+    }
+
+    /// Send a command, the response is delivered to the returned call
+    pub fn send(self: &Arc<Self>, command: EngineCommand) -> Result<PendingCall, PluginError> {
+        let id = CallId::new();
+        let (sender, receiver) = mpsc::channel();
+        {
+            let mut calls = self.calls.lock().expect("Failed to lock plugin calls");
+            if let Some(reason) = &calls.closed {
+                return Err(PluginError::ExecutionError {
+                    message: reason.clone(),
+                });
+            }
+            let seq = calls.next_seq;
+            calls.next_seq += 1;
+            calls.waiting.insert(id, Waiting { sender, seq });
+        }
+
+        let message = EngineMessage { id, command };
+        debug!(
+            "Sending message to plugin '{}': {:?}",
+            self.plugin_name, message
+        );
+        if let Err(e) = self.write(&message) {
+            self.calls
+                .lock()
+                .expect("Failed to lock plugin calls")
+                .waiting
+                .remove(&id);
+            return Err(PluginError::ProtocolError {
+                message: format!("Failed to send message: {}", e),
+            });
+        }
+
+        Ok(PendingCall {
+            id,
+            receiver,
+            connection: self.clone(),
+            sent: Instant::now(),
+        })
+    }
+
+    /// Give up on call `id`, the plugin skips it if it has not started on it
+    fn cancel(&self, id: CallId) {
+        let waiting = self
+            .calls
+            .lock()
+            .expect("Failed to lock plugin calls")
+            .waiting
+            .remove(&id);
+        let Some(waiting) = waiting else {
+            // Already answered
+            return;
+        };
+        let _ = waiting.sender.send(Err(PluginError::Cancelled));
+
+        debug!("Cancelling call to plugin '{}'", self.plugin_name);
+        let message = EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::Cancel { id },
+        };
+        if let Err(e) = self.write(&message) {
+            debug!(
+                "Failed to cancel call to plugin '{}': {}",
+                self.plugin_name, e
+            );
+        }
+    }
+
+    fn write(&self, message: &EngineMessage) -> Result<(), Box<dyn std::error::Error>> {
+        let mut stdin = self.stdin.lock().expect("Failed to lock plugin stdin");
+        kiorg_plugin::send_message_to_writer(&mut *stdin, message)
+    }
+
+    fn respond(&self, id: CallId, result: CallResult) {
+        let waiting = {
+            let mut calls = self.calls.lock().expect("Failed to lock plugin calls");
+            calls.last_answer = Some(Instant::now());
+            calls.waiting.remove(&id)
+        };
+        match waiting {
+            Some(waiting) => {
+                let _ = waiting.sender.send(result);
+            }
+            None => debug!(
+                "Dropping response from plugin '{}' to a cancelled call",
+                self.plugin_name
+            ),
+        }
+    }
+
+    /// How long the plugin has been working on call `id`, `None` while calls
+    /// sent before it are still waiting for theirs
+    fn handling_time(&self, id: CallId, sent: Instant) -> Option<Duration> {
+        let calls = self.calls.lock().expect("Failed to lock plugin calls");
+        let seq = calls.waiting.get(&id)?.seq;
+        if calls.waiting.values().any(|call| call.seq < seq) {
+            return None;
+        }
+        let started = calls.last_answer.map_or(sent, |answer| answer.max(sent));
+        Some(started.elapsed())
+    }
+
+    /// Fail the calls in flight and the ones made from now on
+    fn close(&self, reason: String) {
+        let mut calls = self.calls.lock().expect("Failed to lock plugin calls");
+        for (_, waiting) in calls.waiting.drain() {
+            let _ = waiting.sender.send(Err(PluginError::ExecutionError {
+                message: reason.clone(),
+            }));
+        }
+        calls.closed = Some(reason);
+    }
+}
+
+/// A call waiting for its response, dropping it cancels the call
+pub struct PendingCall {
+    id: CallId,
+    receiver: mpsc::Receiver<CallResult>,
+    connection: Arc<Connection>,
+    sent: Instant,
+}
+
+impl PendingCall {
+    /// Wait for the response, the call is cancelled when it takes longer
+    /// than `timeout`
+    pub fn wait(self, timeout: Duration) -> CallResult {
+        let connection = &self.connection;
+        match self.receiver.recv_timeout(timeout) {
+            Ok(result) => {
+                if let Ok(response) = &result {
+                    debug!(
+                        "Received response from plugin '{}': {:?}",
+                        connection.plugin_name, response
+                    );
+                    connection
+                        .stats
+                        .lock()
+                        .expect("Failed to lock plugin stats")
+                        .record_response(
+                            self.sent.elapsed(),
+                            matches!(response, PluginResponse::Error { .. }),
+                        );
+                }
+                result
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // A call queued behind a slow one timing out does not mean
+                // the plugin hangs
+                let while_handling = connection
+                    .handling_time(self.id, self.sent)
+                    .is_some_and(|handling| handling >= timeout / 2);
+                let timeouts_in_a_row = {
+                    let mut stats = connection
+                        .stats
+                        .lock()
+                        .expect("Failed to lock plugin stats");
+                    stats.record_timeout(while_handling);
+                    stats.timeouts_in_a_row
+                };
+                if while_handling && timeouts_in_a_row >= MAX_TIMEOUTS_IN_A_ROW {
+                    warn!(
+                        "Plugin '{}' stopped responding, restarting it",
+                        connection.plugin_name
+                    );
+                    connection.kill(
+                        format!(
+                            "Plugin stopped responding after {} timeouts",
+                            timeouts_in_a_row
+                        ),
+                        true,
+                    );
+                }
+                Err(PluginError::ExecutionError {
+                    message: format!(
+                        "Timed out waiting for response from plugin '{}'",
+                        connection.plugin_name
+                    ),
+                })
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(PluginError::Cancelled),
+        }
+    }
+
+    /// Cancel the call once `signal` fires or its sender is dropped, e.g.
+    /// when the user moves on from the file being previewed
+    pub fn cancel_on(&self, signal: mpsc::Receiver<()>) {
+        let connection = self.connection.clone();
+        let id = self.id;
+        std::thread::spawn(move || {
+            let _ = signal.recv();
+            connection.cancel(id);
+        });
+    }
+}
+
+impl Drop for PendingCall {
+    fn drop(&mut self) {
+        self.connection.cancel(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A connection to `cat`, which echoes the messages sent to it
+    fn echo_connection() -> (Arc<Connection>, mpsc::Receiver<(String, bool)>) {
+        let log = Arc::new(Mutex::new(PluginLog::default()));
+        let process = Process::spawn(Path::new("cat"), log).unwrap();
+        let (sender, closed) = mpsc::channel();
+        let connection = Connection::start(
+            "echo".to_string(),
+            process,
+            Arc::default(),
+            move |reason, crashed| {
+                let _ = sender.send((reason, crashed));
+            },
+        )
+        .unwrap();
+        (connection, closed)
+    }
+
+    fn remove(path: &str) -> EngineCommand {
+        EngineCommand::VfsRemove {
+            path: path.to_string(),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_responses_go_to_their_call() {
+        let (connection, _closed) = echo_connection();
+        let first = connection.send(remove("a")).unwrap();
+        let second = connection.send(remove("b")).unwrap();
+
+        // The echoed engine messages are not responses, answer out of order
+        connection.respond(second.id, Ok(PluginResponse::VfsDone));
+        connection.respond(
+            first.id,
+            Ok(PluginResponse::Error {
+                message: "a".to_string(),
+            }),
+        );
+        assert!(matches!(
+            second.wait(Duration::from_secs(1)),
+            Ok(PluginResponse::VfsDone)
+        ));
+        assert!(matches!(
+            first.wait(Duration::from_secs(1)),
+            Ok(PluginResponse::Error { message }) if message == "a"
+        ));
+        {
+            let stats = connection.stats.lock().unwrap();
+            assert_eq!(stats.responses, 2);
+            assert_eq!(stats.errors, 1);
+        }
+
+        // A cancelled call fails right away and its late response is dropped
+        let (signal, cancel) = mpsc::channel();
+        let third = connection.send(remove("c")).unwrap();
+        third.cancel_on(cancel);
+        signal.send(()).unwrap();
+        let id = third.id;
+        assert!(matches!(
+            third.wait(Duration::from_secs(1)),
+            Err(PluginError::Cancelled)
+        ));
+        connection.respond(id, Ok(PluginResponse::VfsDone));
+
+        connection.kill("Test finished".to_string(), false);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_closed_connection_fails_calls() {
+        let (connection, closed) = echo_connection();
+        let call = connection.send(remove("a")).unwrap();
+        connection.kill("Disabled".to_string(), false);
+
+        assert!(matches!(
+            call.wait(Duration::from_secs(5)),
+            Err(PluginError::ExecutionError { message }) if message == "Disabled"
+        ));
+        assert!(connection.send(remove("b")).is_err());
+        assert_eq!(
+            closed.recv_timeout(Duration::from_secs(5)).unwrap(),
+            ("Disabled".to_string(), false)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hung_plugin_is_killed() {
+        let (connection, closed) = echo_connection();
+        for _ in 0..MAX_TIMEOUTS_IN_A_ROW {
+            let call = connection.send(remove("a")).unwrap();
+            assert!(call.wait(Duration::from_millis(10)).is_err());
+        }
+        let (reason, crashed) = closed.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(reason.contains("stopped responding"));
+        assert!(crashed);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_calls_queued_behind_a_slow_one_do_not_count_as_hung() {
+        let (connection, closed) = echo_connection();
+        let slow = connection.send(remove("slow")).unwrap();
+        for _ in 0..MAX_TIMEOUTS_IN_A_ROW {
+            let call = connection.send(remove("a")).unwrap();
+            assert!(call.wait(Duration::from_millis(10)).is_err());
+        }
+        {
+            let stats = connection.stats.lock().unwrap();
+            assert_eq!(stats.timeouts, u64::from(MAX_TIMEOUTS_IN_A_ROW));
+            assert_eq!(stats.timeouts_in_a_row, 0);
+        }
+        assert!(closed.try_recv().is_err());
+
+        connection.respond(slow.id, Ok(PluginResponse::VfsDone));
+        assert!(slow.wait(Duration::from_secs(1)).is_ok());
+        connection.kill("Test finished".to_string(), false);
+    }
+}
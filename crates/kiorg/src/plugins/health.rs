@@ -0,0 +1,167 @@
+//! Health of plugin processes
+//!
+//! What plugins write to stderr is kept in a log per plugin, calls are
+//! counted, and plugins that crash are restarted after a growing delay.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Lines of stderr kept per plugin
+const LOG_LINES: usize = 500;
+
+/// Delay before the first restart of a crashed plugin, doubled with every
+/// crash in a row
+const RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Crashes in a row after which a plugin is left stopped until the user
+/// reloads it
+pub const MAX_CRASHES_IN_A_ROW: u32 = 5;
+
+/// Calls in a row that time out before a plugin is considered hung and
+/// restarted
+pub const MAX_TIMEOUTS_IN_A_ROW: u32 = 3;
+
+/// The last lines a plugin wrote to stderr, across restarts
+#[derive(Debug, Default)]
+pub struct PluginLog {
+    lines: VecDeque<String>,
+}
+
+impl PluginLog {
+    pub fn push(&mut self, line: String) {
+        if self.lines.len() == LOG_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// The last `count` lines, to explain why a plugin went away
+    pub fn tail(&self, count: usize) -> String {
+        let skip = self.lines.len().saturating_sub(count);
+        self.lines
+            .iter()
+            .skip(skip)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Counters of the calls made to a plugin since kiorg started
+#[derive(Debug, Default, Clone)]
+pub struct PluginStats {
+    /// Calls the plugin answered
+    pub responses: u64,
+    /// Answers that were errors
+    pub errors: u64,
+    pub timeouts: u64,
+    pub crashes: u64,
+    pub restarts: u64,
+    /// Reset by any answer
+    pub timeouts_in_a_row: u32,
+    /// Reset by any answer
+    pub crashes_in_a_row: u32,
+    pub max_latency: Duration,
+    total_latency: Duration,
+}
+
+impl PluginStats {
+    pub fn record_response(&mut self, latency: Duration, is_error: bool) {
+        self.responses += 1;
+        if is_error {
+            self.errors += 1;
+        }
+        self.timeouts_in_a_row = 0;
+        self.crashes_in_a_row = 0;
+        self.total_latency += latency;
+        self.max_latency = self.max_latency.max(latency);
+    }
+
+    /// Only calls the plugin was working on when they timed out count
+    /// towards `timeouts_in_a_row`, calls still queued behind a slow one say
+    /// nothing about the plugin being hung
+    pub fn record_timeout(&mut self, while_handling: bool) {
+        self.timeouts += 1;
+        if while_handling {
+            self.timeouts_in_a_row += 1;
+        }
+    }
+
+    pub fn record_crash(&mut self) {
+        self.crashes += 1;
+        self.crashes_in_a_row += 1;
+    }
+
+    /// Errors and timeouts
+    pub fn failures(&self) -> u64 {
+        self.errors + self.timeouts
+    }
+
+    pub fn average_latency(&self) -> Option<Duration> {
+        u32::try_from(self.responses)
+            .ok()
+            .filter(|&responses| responses > 0)
+            .map(|responses| self.total_latency / responses)
+    }
+}
+
+/// Delay before restarting a plugin after its `crashes`th crash in a row
+pub fn restart_delay(crashes: u32) -> Duration {
+    let doublings = crashes.saturating_sub(1).min(6);
+    (RESTART_BACKOFF * 2u32.pow(doublings)).min(MAX_RESTART_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_delay_grows_and_caps() {
+        assert_eq!(restart_delay(1), Duration::from_secs(1));
+        assert_eq!(restart_delay(2), Duration::from_secs(2));
+        assert_eq!(restart_delay(4), Duration::from_secs(8));
+        assert_eq!(restart_delay(7), MAX_RESTART_BACKOFF);
+        assert_eq!(restart_delay(100), MAX_RESTART_BACKOFF);
+    }
+
+    #[test]
+    fn test_log_keeps_last_lines() {
+        let mut log = PluginLog::default();
+        for i in 0..LOG_LINES + 2 {
+            log.push(format!("line {i}"));
+        }
+        assert_eq!(log.lines().count(), LOG_LINES);
+        assert_eq!(log.lines().next(), Some("line 2"));
+        assert_eq!(
+            log.tail(2),
+            format!("line {}\nline {}", LOG_LINES, LOG_LINES + 1)
+        );
+    }
+
+    #[test]
+    fn test_stats_reset_streaks_on_response() {
+        let mut stats = PluginStats::default();
+        assert_eq!(stats.average_latency(), None);
+        stats.record_timeout(true);
+        stats.record_timeout(false);
+        assert_eq!(stats.timeouts_in_a_row, 1);
+        stats.record_crash();
+        stats.record_response(Duration::from_millis(10), false);
+        stats.record_response(Duration::from_millis(30), true);
+        assert_eq!(stats.timeouts_in_a_row, 0);
+        assert_eq!(stats.crashes_in_a_row, 0);
+        assert_eq!(stats.failures(), 3);
+        assert_eq!(stats.average_latency(), Some(Duration::from_millis(20)));
+        assert_eq!(stats.max_latency, Duration::from_millis(30));
+    }
+}
@@ -5,17 +5,37 @@
 //! - Managing basic plugin metadata
 //! - Simple plugin operations without complex async execution
 
-use kiorg_plugin::{CallId, EngineCommand, EngineMessage, PluginMetadata};
+use kiorg_plugin::{EngineCommand, PluginMetadata, PluginResponse};
 use snafu::Snafu;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread::JoinHandle;
+use std::time::Instant;
 use tracing::{debug, error, info, warn};
 
+use super::connection::{Connection, PendingCall, Process};
+use super::health::{self, PluginLog, PluginStats};
+use super::vfs::PluginFs;
+
 /// Plugin executable prefix
 const PLUGIN_PREFIX: &str = "kiorg_plugin_";
 
+/// Time a plugin has to answer the hello message
+const HELLO_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Previews are shown as the user moves through files
+const PREVIEW_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Commands may take a while, unlike previews
+const EXECUTE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Column values are requested in batches, checksums can take a bit
+const COLUMNS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Width of plugin columns that don't declare one
+const DEFAULT_COLUMN_WIDTH: f32 = 80.0;
+
 /// Error types for plugin management
 #[derive(Debug, Snafu)]
 pub enum PluginError {
@@ -32,6 +52,8 @@ pub enum PluginError {
     },
     #[snafu(display("IO error: {}", source))]
     IoError { source: std::io::Error },
+    #[snafu(display("Plugin call cancelled"))]
+    Cancelled,
 }
 
 /// A failed plugin load attempt
@@ -50,33 +72,304 @@ pub struct LoadedPlugin {
     pub metadata: PluginMetadata,
     /// Plugin executable path
     pub path: PathBuf,
-    /// Plugin state (process and error)
-    pub state: Mutex<PluginState>,
+    /// Plugin process, restarted when it crashes
+    runtime: Arc<Runtime>,
     /// Time taken to load the plugin
     pub load_time: std::time::Duration,
     /// Compiled regex for preview file pattern matching
     pub preview_regex: Option<regex::Regex>,
+    /// Commands declared in the capabilities
+    pub commands: Vec<PluginCommand>,
+    /// File list columns declared in the capabilities
+    pub columns: Vec<PluginColumn>,
 }
 
-/// State of the running plugin
+/// A command declared by a plugin
+#[derive(Debug)]
+pub struct PluginCommand {
+    /// Command name, as sent back to the plugin
+    pub name: String,
+    /// Default shortcut declared by the plugin
+    pub shortcut: Option<String>,
+    /// Whether the command accepts several paths at once
+    pub multiple: bool,
+    /// Compiled regex every file name has to match
+    pub file_regex: regex::Regex,
+}
+
+impl PluginCommand {
+    /// Whether the command can run on `paths`
+    pub fn accepts(&self, paths: &[PathBuf]) -> bool {
+        !paths.is_empty()
+            && (self.multiple || paths.len() == 1)
+            && paths.iter().all(|path| {
+                path.file_name()
+                    .is_some_and(|name| self.file_regex.is_match(&name.to_string_lossy()))
+            })
+    }
+}
+
+/// A file list column declared by a plugin
 #[derive(Debug)]
-pub struct PluginState {
-    /// Running plugin process
-    pub process: Child,
+pub struct PluginColumn {
+    /// Column title
+    pub name: String,
+    /// Column width in points
+    pub width: f32,
+    /// Compiled regex file names have to match to get a value
+    pub file_regex: regex::Regex,
+}
+
+impl PluginColumn {
+    /// Whether the column has a value for `path`
+    pub fn applies_to(&self, path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|name| self.file_regex.is_match(&name.to_string_lossy()))
+    }
+}
+
+/// What the plugin asked for after running a command
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteResult {
+    pub refresh: bool,
+    pub notification: Option<String>,
+    pub navigate: Option<PathBuf>,
+}
+
+/// A command of a loaded plugin, addressed by its position in
+/// [`PluginManager::commands`]
+#[derive(Debug, Clone)]
+pub struct CommandRef {
+    pub plugin: Arc<LoadedPlugin>,
+    /// Index into the commands of the plugin
+    pub index: usize,
+}
+
+impl CommandRef {
+    pub fn command(&self) -> &PluginCommand {
+        &self.plugin.commands[self.index]
+    }
+
+    /// Identifier used to bind the command in the config, `<plugin>:<command>`
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.plugin.metadata.name, self.command().name)
+    }
+}
+
+/// A column of a loaded plugin, addressed by its position in
+/// [`PluginManager::columns`]
+#[derive(Debug, Clone)]
+pub struct ColumnRef {
+    pub plugin: Arc<LoadedPlugin>,
+    /// Index into the columns of the plugin
+    pub index: usize,
+}
+
+impl ColumnRef {
+    pub fn column(&self) -> &PluginColumn {
+        &self.plugin.columns[self.index]
+    }
+}
+
+/// State of the running plugin
+#[derive(Debug, Default)]
+struct PluginState {
+    /// Calls to the running process, `None` while stopped
+    connection: Option<Arc<Connection>>,
     /// Error state if plugin has crashed or failed
-    pub error: Option<String>,
+    error: Option<String>,
+    /// Whether the user disabled the plugin
+    disabled: bool,
+    /// Whether a process is being started in the background
+    starting: bool,
+    /// When the plugin is restarted after a crash
+    restart_at: Option<Instant>,
+    /// Bumped whenever a process is started or stopped, restarts scheduled
+    /// for an older process are skipped
+    generation: u64,
 }
 
-impl Drop for LoadedPlugin {
-    fn drop(&mut self) {
-        let result = self.state.lock();
-        let mut state = match result {
-            Ok(guard) => guard,
-            Err(poisoned) => poisoned.into_inner(),
+/// The process of a plugin, shared with the threads watching it
+#[derive(Debug)]
+struct Runtime {
+    name: String,
+    path: PathBuf,
+    state: Mutex<PluginState>,
+    /// Counters across restarts
+    stats: Arc<Mutex<PluginStats>>,
+    /// Stderr across restarts
+    log: Arc<Mutex<PluginLog>>,
+}
+
+impl Runtime {
+    fn state(&self) -> MutexGuard<'_, PluginState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Start a new process in place of the current one
+    fn start(self: &Arc<Self>) -> Result<(), String> {
+        self.stop("Plugin restarted");
+        let mut process =
+            Process::spawn(&self.path, self.log.clone()).map_err(|e| e.to_string())?;
+        let error = match process.hello(HELLO_TIMEOUT) {
+            Ok(PluginResponse::Hello(_)) => return self.attach(process),
+            Ok(PluginResponse::VersionIncompatible {
+                protocol_version, ..
+            }) => incompatible_error(&protocol_version),
+            Ok(PluginResponse::Error { message }) => message,
+            Ok(_) => "Expected Hello response from plugin".to_string(),
+            Err(msg) => format!("Plugin communication error: {}", msg),
+        };
+        Err(format!("{}. {}", error, process.stop()))
+    }
+
+    /// Start a new process on another thread, answering the hello message
+    /// can take up to [`HELLO_TIMEOUT`]
+    fn start_in_background(self: &Arc<Self>) {
+        self.state().starting = true;
+        let runtime = self.clone();
+        std::thread::spawn(move || {
+            let result = runtime.start();
+            let mut state = runtime.state();
+            state.starting = false;
+            if let Err(message) = result
+                && !state.disabled
+            {
+                state.error = Some(message);
+            }
+        });
+    }
+
+    /// Make calls to a process that answered the hello message
+    fn attach(self: &Arc<Self>, mut process: Process) -> Result<(), String> {
+        let mut state = self.state();
+        // Disabled while the process was starting
+        if state.disabled {
+            drop(state);
+            process.stop();
+            return Err(format!("Plugin '{}' is disabled", self.name));
+        }
+        state.generation += 1;
+        let generation = state.generation;
+        let runtime = Arc::downgrade(self);
+        let connection = Connection::start(
+            self.name.clone(),
+            process,
+            self.stats.clone(),
+            move |reason, crashed| {
+                if let Some(runtime) = runtime.upgrade() {
+                    runtime.fail(generation, reason, crashed);
+                }
+            },
+        )?;
+        let previous = state.connection.replace(connection);
+        state.error = None;
+        state.restart_at = None;
+        drop(state);
+        // Started twice at once, only the last process is kept
+        if let Some(previous) = previous {
+            previous.kill("Plugin restarted".to_string(), false);
+        }
+        Ok(())
+    }
+
+    /// Stop the process without restarting it
+    fn stop(&self, reason: &str) {
+        let connection = {
+            let mut state = self.state();
+            state.generation += 1;
+            state.restart_at = None;
+            state.connection.take()
+        };
+        if let Some(connection) = connection {
+            connection.kill(reason.to_string(), false);
+        }
+    }
+
+    /// Record why the process of `generation` went away, a crashed plugin is
+    /// restarted after a delay growing with each crash in a row
+    fn fail(self: &Arc<Self>, generation: u64, reason: String, crashed: bool) {
+        let mut state = self.state();
+        if state.generation != generation {
+            // The process was replaced or stopped meanwhile
+            return;
+        }
+        warn!("Plugin '{}' stopped: {}", self.name, reason);
+        state.connection = None;
+        state.error = Some(reason);
+        if !crashed || state.disabled {
+            return;
+        }
+
+        let crashes = {
+            let mut stats = self.stats.lock().expect("Failed to lock plugin stats");
+            stats.record_crash();
+            stats.crashes_in_a_row
         };
+        if crashes > health::MAX_CRASHES_IN_A_ROW {
+            error!(
+                "Plugin '{}' crashed {} times in a row, not restarting it",
+                self.name, crashes
+            );
+            return;
+        }
+        let delay = health::restart_delay(crashes);
+        state.restart_at = Some(Instant::now() + delay);
+        let runtime = Arc::downgrade(self);
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            if let Some(runtime) = Weak::upgrade(&runtime) {
+                runtime.restart(generation);
+            }
+        });
+    }
+
+    /// Restart the process of `generation` after a crash
+    fn restart(self: &Arc<Self>, generation: u64) {
+        {
+            let state = self.state();
+            if state.generation != generation || state.disabled {
+                return;
+            }
+        }
+        info!("Restarting plugin '{}'", self.name);
+        self.stats
+            .lock()
+            .expect("Failed to lock plugin stats")
+            .restarts += 1;
+        if let Err(reason) = self.start() {
+            // Counts as another crash, the next attempt waits longer
+            let generation = self.state().generation;
+            self.fail(generation, reason, true);
+        }
+    }
+}
+
+/// Disabled plugins are identified by the name of their executable, so they
+/// are known without running them
+fn executable_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn incompatible_error(protocol_version: &str) -> String {
+    let major_version = protocol_version.split('.').next().unwrap_or("0");
+    let my_major = kiorg_plugin::PROTOCOL_VERSION
+        .split('.')
+        .next()
+        .unwrap_or("0");
+    format!(
+        "Incompatible protocol version. Plugin built for protocol major version: {}, rebuild it against kiorg_plugin {}.x",
+        major_version, my_major
+    )
+}
 
-        let _ = state.process.kill();
-        let _ = state.process.wait();
+impl Drop for LoadedPlugin {
+    fn drop(&mut self) {
+        self.runtime.stop("Plugin unloaded");
     }
 }
 
@@ -86,14 +379,12 @@ impl LoadedPlugin {
         &self,
         file_path: &str,
         available_width: f32,
-    ) -> Result<Vec<kiorg_plugin::Component>, PluginError> {
-        self.call_preview_internal(
-            EngineCommand::Preview {
-                path: file_path.to_string(),
-                available_width,
-            },
-            file_path,
-        )
+    ) -> Result<PreviewCall, PluginError> {
+        self.send(EngineCommand::Preview {
+            path: file_path.to_string(),
+            available_width,
+        })
+        .map(PreviewCall)
     }
 
     /// Execute preview popup command on the plugin for the given file path
@@ -101,163 +392,213 @@ impl LoadedPlugin {
         &self,
         file_path: &str,
         available_width: f32,
-    ) -> Result<Vec<kiorg_plugin::Component>, PluginError> {
-        self.call_preview_internal(
-            EngineCommand::PreviewPopup {
-                path: file_path.to_string(),
-                available_width,
-            },
-            file_path,
-        )
+    ) -> Result<PreviewCall, PluginError> {
+        self.send(EngineCommand::PreviewPopup {
+            path: file_path.to_string(),
+            available_width,
+        })
+        .map(PreviewCall)
     }
 
-    fn call_preview_internal(
+    /// Execute a command declared by the plugin on the given paths
+    pub fn execute(
         &self,
-        command: EngineCommand,
-        file_path: &str,
-    ) -> Result<Vec<kiorg_plugin::Component>, PluginError> {
-        let mut state = self.state.lock().expect("Failed to lock plugin state");
+        command: &str,
+        paths: &[PathBuf],
+        cwd: &Path,
+    ) -> Result<ExecuteResult, PluginError> {
+        let command = EngineCommand::Execute {
+            command: command.to_string(),
+            paths: paths
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect(),
+            cwd: cwd.to_string_lossy().into_owned(),
+        };
+
+        match self.call(command, EXECUTE_TIMEOUT)? {
+            PluginResponse::Execute {
+                refresh,
+                notification,
+                navigate,
+            } => Ok(ExecuteResult {
+                refresh,
+                notification,
+                navigate: navigate.map(PathBuf::from),
+            }),
+            PluginResponse::Error { message } => Err(PluginError::ExecutionError { message }),
+            _ => Err(PluginError::ProtocolError {
+                message: "Expected Execute response from plugin".to_string(),
+            }),
+        }
+    }
 
-        if let Some(error) = &state.error {
+    /// Send a command without waiting for the response, other calls can be
+    /// made while it is in flight
+    pub(crate) fn send(&self, command: EngineCommand) -> Result<PendingCall, PluginError> {
+        let connection = {
+            let state = self.runtime.state();
+            if state.disabled {
+                return Err(PluginError::ExecutionError {
+                    message: format!("Plugin '{}' is disabled", self.metadata.name),
+                });
+            }
+            match (&state.connection, &state.error) {
+                (Some(connection), _) => connection.clone(),
+                (None, Some(error)) => {
+                    return Err(PluginError::ExecutionError {
+                        message: format!("Plugin is in error state: {}", error),
+                    });
+                }
+                (None, None) => {
+                    return Err(PluginError::ExecutionError {
+                        message: format!("Plugin '{}' is not running", self.metadata.name),
+                    });
+                }
+            }
+        };
+        connection.send(command)
+    }
+
+    /// Why the plugin is not running, if it crashed or failed to start
+    pub fn error(&self) -> Option<String> {
+        self.runtime.state().error.clone()
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.runtime.state().disabled
+    }
+
+    /// Whether a reload or enable is still waiting for the process to start
+    pub fn is_starting(&self) -> bool {
+        self.runtime.state().starting
+    }
+
+    /// Time left until the plugin is restarted after a crash
+    pub fn restart_in(&self) -> Option<std::time::Duration> {
+        self.runtime
+            .state()
+            .restart_at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Counters of the calls made to the plugin since kiorg started
+    pub fn stats(&self) -> PluginStats {
+        self.runtime
+            .stats
+            .lock()
+            .expect("Failed to lock plugin stats")
+            .clone()
+    }
+
+    /// What the plugin wrote to stderr
+    pub fn log(&self) -> MutexGuard<'_, PluginLog> {
+        self.runtime.log.lock().expect("Failed to lock plugin log")
+    }
+
+    /// Restart the plugin process in the background, also after it crashed
+    /// too often to be restarted automatically. A failure to start shows in
+    /// [`Self::error`].
+    ///
+    /// The capabilities declared when kiorg started are kept.
+    pub fn reload(&self) -> Result<(), PluginError> {
+        if self.is_disabled() {
             return Err(PluginError::ExecutionError {
-                message: format!("Plugin is in error state: {}", error),
+                message: format!("Plugin '{}' is disabled", self.metadata.name),
             });
         }
+        self.runtime
+            .stats
+            .lock()
+            .expect("Failed to lock plugin stats")
+            .crashes_in_a_row = 0;
+        self.runtime.start_in_background();
+        Ok(())
+    }
 
-        // Create the preview command message
-        let engine_message = EngineMessage {
-            id: CallId::new(),
-            command,
-        };
+    /// Stop the plugin until it is enabled again
+    pub fn disable(&self) {
+        self.runtime.stop("Plugin disabled");
+        let mut state = self.runtime.state();
+        state.disabled = true;
+        state.error = None;
+    }
+
+    /// Start the plugin again in the background
+    pub fn enable(&self) {
+        self.runtime.state().disabled = false;
+        self.runtime
+            .stats
+            .lock()
+            .expect("Failed to lock plugin stats")
+            .crashes_in_a_row = 0;
+        self.runtime.start_in_background();
+    }
 
-        let plugin_name = &self.metadata.name;
+    /// Send a command and wait for the response, for commands whose
+    /// responses the caller checks itself
+    pub(crate) fn call(
+        &self,
+        command: EngineCommand,
+        timeout: std::time::Duration,
+    ) -> Result<PluginResponse, PluginError> {
+        self.send(command)?.wait(timeout)
+    }
+
+    /// Fetch the values of the declared columns for `paths`, one row per
+    /// path with one value per column
+    pub fn column_values(&self, paths: &[PathBuf]) -> Result<Vec<Vec<String>>, PluginError> {
         debug!(
-            "Sending preview message to plugin '{}' for '{}': {:?}",
-            plugin_name, file_path, engine_message
+            "Sending columns message to plugin '{}' for {} paths",
+            self.metadata.name,
+            paths.len()
         );
+        let command = EngineCommand::Columns {
+            paths: paths
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect(),
+        };
 
-        // Send the message to plugin stdin with length prefix
-        match communicate_with_plugin(
-            &mut state.process,
-            engine_message,
-            std::time::Duration::from_secs(5),
-            plugin_name,
-        ) {
-            Ok(plugin_response) => {
-                // Extract the preview content
-                match plugin_response {
-                    kiorg_plugin::PluginResponse::Preview { components } => Ok(components),
-                    kiorg_plugin::PluginResponse::Error { message } => {
-                        Err(PluginError::ExecutionError { message })
-                    }
-                    _ => Err(PluginError::ProtocolError {
-                        message: "Expected Preview response from plugin".to_string(),
-                    }),
+        match self.call(command, COLUMNS_TIMEOUT)? {
+            PluginResponse::Columns { rows } => {
+                if rows.len() != paths.len() {
+                    return Err(PluginError::ProtocolError {
+                        message: format!(
+                            "Expected {} rows of column values, got {}",
+                            paths.len(),
+                            rows.len()
+                        ),
+                    });
                 }
+                Ok(rows)
             }
-            Err(e) => {
-                state.error = Some(e.to_string());
-                Err(e)
-            }
+            PluginResponse::Error { message } => Err(PluginError::ExecutionError { message }),
+            _ => Err(PluginError::ProtocolError {
+                message: "Expected Columns response from plugin".to_string(),
+            }),
         }
     }
 }
 
-/// Helper to handle communication with a plugin process
-fn communicate_with_plugin(
-    child: &mut std::process::Child,
-    message: EngineMessage,
-    timeout: std::time::Duration,
-    plugin_name: &str,
-) -> Result<kiorg_plugin::PluginResponse, PluginError> {
-    let mut stdin = child.stdin.take().ok_or(PluginError::ExecutionError {
-        message: "Plugin stdin not available".to_string(),
-    })?;
-    let mut stdout = child.stdout.take().ok_or(PluginError::ExecutionError {
-        message: "Plugin stdout not available".to_string(),
-    })?;
-
-    let (tx, rx) = std::sync::mpsc::channel();
-
-    std::thread::spawn(move || {
-        // Send
-        if let Err(e) = kiorg_plugin::send_message_to_writer(&mut stdin, &message) {
-            let _ = tx.send(Err(format!("Failed to send message: {}", e)));
-            return;
-        }
-
-        // Read
-        let result: Result<kiorg_plugin::PluginResponse, _> =
-            kiorg_plugin::read_message_from_reader(&mut stdout);
-        match result {
-            Ok(response) => {
-                let _ = tx.send(Ok((response, stdin, stdout)));
-            }
-            Err(e) => {
-                let _ = tx.send(Err(format!("Failed to read response: {}", e)));
-            }
-        }
-    });
-
-    match rx.recv_timeout(timeout) {
-        Ok(Ok((plugin_response, stdin_back, stdout_back))) => {
-            debug!(
-                "Received response from plugin '{}': {:?}",
-                plugin_name, plugin_response
-            );
-            child.stdin = Some(stdin_back);
-            child.stdout = Some(stdout_back);
-            Ok(plugin_response)
-        }
-        other => {
-            // Helper to read stderr
-            let mut stderr_output = String::new();
-            if let Some(mut stderr) = child.stderr.take() {
-                use std::io::Read;
-                let _ = stderr.read_to_string(&mut stderr_output);
-            }
+/// A preview in flight, dropping it cancels the preview
+pub struct PreviewCall(PendingCall);
 
-            // Check if the process has exited
-            if let Ok(Some(status)) = child.try_wait() {
-                let error_msg = format!(
-                    "Plugin process exited unexpectedly: {}. Stderr: `{}`",
-                    status, stderr_output
-                );
-                debug!("Plugin '{}' crashed: {}", plugin_name, error_msg);
-                return Err(PluginError::ExecutionError { message: error_msg });
-            }
+impl PreviewCall {
+    /// Cancel the preview once `signal` fires or its sender is dropped
+    pub fn cancel_on(self, signal: std::sync::mpsc::Receiver<()>) -> Self {
+        self.0.cancel_on(signal);
+        self
+    }
 
-            // If process is still running (or we can't check), kill it
-            let _ = child.kill();
-            let _ = child.wait();
-
-            match other {
-                Ok(Err(msg)) => {
-                    let error_msg = format!(
-                        "Plugin communication error: {}. Stderr: `{}`",
-                        msg, stderr_output
-                    );
-                    error!("Plugin '{}' error: {}", plugin_name, error_msg);
-                    Err(PluginError::ProtocolError { message: error_msg })
-                }
-                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                    let error_msg = format!(
-                        "Timed out waiting for response from plugin '{}'. Stderr: `{}`",
-                        plugin_name, stderr_output
-                    );
-                    error!("Plugin '{}' error: {}", plugin_name, error_msg);
-                    Err(PluginError::ExecutionError { message: error_msg })
-                }
-                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
-                    let error_msg = format!(
-                        "Plugin response thread disconnected unexpectedly. Stderr: `{}`",
-                        stderr_output
-                    );
-                    error!("Plugin '{}' error: {}", plugin_name, error_msg);
-                    Err(PluginError::ExecutionError { message: error_msg })
-                }
-                Ok(Ok(_)) => unreachable!(),
-            }
+    /// Wait for the preview components
+    pub fn components(self) -> Result<Vec<kiorg_plugin::Component>, PluginError> {
+        match self.0.wait(PREVIEW_TIMEOUT)? {
+            PluginResponse::Preview { components } => Ok(components),
+            PluginResponse::Error { message } => Err(PluginError::ExecutionError { message }),
+            _ => Err(PluginError::ProtocolError {
+                message: "Expected Preview response from plugin".to_string(),
+            }),
         }
     }
 }
@@ -270,11 +611,25 @@ pub struct PluginManager {
     loaded: HashMap<String, Arc<LoadedPlugin>>,
     /// Failed plugins
     failed: Vec<FailedPlugin>,
+    /// Commands of all loaded plugins, sorted by plugin and command name
+    commands: Vec<CommandRef>,
+    /// File list columns of all loaded plugins, sorted by plugin name
+    columns: Vec<ColumnRef>,
+    /// Roots where plugin filesystems are mounted
+    mounts: Vec<PathBuf>,
+    /// Executable names of the plugins the user disabled
+    disabled: Vec<String>,
+    /// Plugins disabled since kiorg started, they were never run so only
+    /// their executable is known
+    not_started: Vec<PathBuf>,
+    /// Plugins enabled from [`Self::not_started`], loading in the background
+    starting: Vec<JoinHandle<(PathBuf, Result<LoadedPlugin, PluginError>)>>,
 }
 
 impl PluginManager {
-    /// Create a new plugin manager with config directory override
-    pub fn new(config_dir_override: Option<&PathBuf>) -> Self {
+    /// Create a new plugin manager with config directory override, the
+    /// plugins whose executable is named in `disabled` are not run
+    pub fn new(config_dir_override: Option<&PathBuf>, disabled: &[String]) -> Self {
         let config_dir = crate::config::get_kiorg_config_dir(config_dir_override);
         let plugin_dir = config_dir.join("plugins");
 
@@ -282,6 +637,12 @@ impl PluginManager {
             plugin_dir,
             loaded: HashMap::new(),
             failed: Vec::new(),
+            commands: Vec::new(),
+            columns: Vec::new(),
+            mounts: Vec::new(),
+            disabled: disabled.to_vec(),
+            not_started: Vec::new(),
+            starting: Vec::new(),
         }
     }
 
@@ -307,9 +668,15 @@ impl PluginManager {
             if let Some(filename) = path.file_name().and_then(|n| n.to_str())
                 && filename.starts_with(PLUGIN_PREFIX)
             {
-                paths.push(path);
+                if self.disabled.iter().any(|disabled| disabled == filename) {
+                    debug!("Plugin '{}' is disabled, not starting it", filename);
+                    self.not_started.push(path);
+                } else {
+                    paths.push(path);
+                }
             }
         }
+        self.not_started.sort();
 
         if paths.is_empty() {
             return Ok(());
@@ -328,77 +695,134 @@ impl PluginManager {
 
         for handle in handles {
             match handle.join() {
-                Ok((path, result)) => match result {
-                    Ok(plugin) => {
-                        let name = plugin.metadata.name.clone();
-
-                        // Skip if already loaded
-                        if self.loaded.contains_key(&name) {
-                            debug!("Plugin '{}' already loaded, skipping", name);
-                            continue;
-                        }
-
-                        debug!(
-                            "Plugin '{}' loaded successfully in {:?}",
-                            name, plugin.load_time
-                        );
-                        self.loaded.insert(name.clone(), Arc::new(plugin));
-
-                        // Remove from failed if it was there previously (by path)
-                        self.failed
-                            .retain(|failed_plugin| failed_plugin.path != path);
-                    }
-                    Err(e) => {
-                        warn!("Failed to load plugin from '{:?}': {}", path, e);
-                        // Remove existing failure for this path to avoid duplicates
-                        self.failed.retain(|p| p.path != path);
-                        self.failed.push(FailedPlugin {
-                            path: path.clone(),
-                            error: e.to_string(),
-                        });
-                    }
-                },
+                Ok((path, result)) => {
+                    self.add_loaded(path, result);
+                }
                 Err(err) => {
                     error!(err =? err, "Plugin loading thread panicked");
                 }
             }
         }
 
+        let mut plugins: Vec<Arc<LoadedPlugin>> = self.loaded.values().cloned().collect();
+        plugins.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+        for plugin in &plugins {
+            self.add_contributions(plugin);
+            self.mount_filesystem(plugin);
+        }
         Ok(())
     }
 
+    /// Keep the outcome of loading the plugin at `path`, returns the plugin
+    /// if it was added
+    fn add_loaded(
+        &mut self,
+        path: PathBuf,
+        result: Result<LoadedPlugin, PluginError>,
+    ) -> Option<Arc<LoadedPlugin>> {
+        match result {
+            Ok(plugin) => {
+                let name = plugin.metadata.name.clone();
+
+                // Skip if already loaded
+                if self.loaded.contains_key(&name) {
+                    debug!("Plugin '{}' already loaded, skipping", name);
+                    return None;
+                }
+
+                debug!(
+                    "Plugin '{}' loaded successfully in {:?}",
+                    name, plugin.load_time
+                );
+                let plugin = Arc::new(plugin);
+                self.loaded.insert(name, plugin.clone());
+
+                // Remove from failed if it was there previously (by path)
+                self.failed
+                    .retain(|failed_plugin| failed_plugin.path != path);
+                Some(plugin)
+            }
+            Err(e) => {
+                warn!("Failed to load plugin from '{:?}': {}", path, e);
+                // Remove existing failure for this path to avoid duplicates
+                self.failed.retain(|p| p.path != path);
+                self.failed.push(FailedPlugin {
+                    path,
+                    error: e.to_string(),
+                });
+                None
+            }
+        }
+    }
+
+    /// Mount the filesystem of a plugin at its `scheme://` root, the first
+    /// plugin mounted wins when two declare the same scheme
+    fn mount_filesystem(&mut self, plugin: &Arc<LoadedPlugin>) {
+        let Some(capability) = &plugin.metadata.capabilities.vfs else {
+            return;
+        };
+        let root = PluginFs::mount_root(&capability.scheme);
+        if crate::vfs::url_root(&root.join("root")).is_none() {
+            warn!(
+                "Plugin '{}' declares an invalid scheme '{}'",
+                plugin.metadata.name, capability.scheme
+            );
+            return;
+        }
+        if self.mounts.contains(&root) {
+            warn!(
+                "Scheme '{}' of plugin '{}' is already served by another plugin",
+                capability.scheme, plugin.metadata.name
+            );
+            return;
+        }
+        crate::vfs::mount(
+            root.clone(),
+            Arc::new(PluginFs::new(plugin.clone(), capability.read_only)),
+        );
+        self.mounts.push(root);
+    }
+
+    /// Append the commands and columns of a plugin, the ones already listed
+    /// keep their index
+    fn add_contributions(&mut self, plugin: &Arc<LoadedPlugin>) {
+        self.commands
+            .extend((0..plugin.commands.len()).map(|index| CommandRef {
+                plugin: plugin.clone(),
+                index,
+            }));
+        self.columns
+            .extend((0..plugin.columns.len()).map(|index| ColumnRef {
+                plugin: plugin.clone(),
+                index,
+            }));
+    }
+
     /// Load a single plugin from the given path
     fn load_single_plugin(path: &PathBuf) -> Result<LoadedPlugin, PluginError> {
-        // Start the plugin process
-        let mut cmd = Command::new(path);
-        cmd.stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
         let start_time = std::time::Instant::now();
 
-        let mut child = cmd.spawn().map_err(|e| PluginError::ExecutionError {
-            message: format!("Failed to spawn plugin process: {}", e),
-        })?;
+        // Start the plugin process
+        let log = Arc::new(Mutex::new(PluginLog::default()));
+        let mut process = Process::spawn(path, log.clone())?;
 
         // Perform hello handshake to get plugin metadata
-        let (metadata, error) = match Self::perform_hello_handshake(&mut child, path) {
+        let response = match process.hello(HELLO_TIMEOUT) {
+            Ok(response) => response,
+            Err(msg) => {
+                let error_msg = format!("Plugin communication error: {}. {}", msg, process.stop());
+                error!("Plugin '{}' error: {}", path.display(), error_msg);
+                return Err(PluginError::ProtocolError { message: error_msg });
+            }
+        };
+        let (metadata, error) = match Self::check_hello(response) {
             Ok(meta) => (meta, None),
             Err(PluginError::Incompatible {
                 protocol_version,
                 metadata,
-            }) => {
-                let major_version = protocol_version.split('.').next().unwrap_or("0");
-                (
-                    *metadata,
-                    Some(format!(
-                        "Incompatible protocol version. Plugin built for protocol major version: {}",
-                        major_version
-                    )),
-                )
-            }
+            }) => (*metadata, Some(incompatible_error(&protocol_version))),
             Err(e) => {
-                let _ = child.kill();
+                process.stop();
                 return Err(e);
             }
         };
@@ -410,7 +834,7 @@ impl PluginManager {
             match regex::Regex::new(&preview_cap.file_pattern) {
                 Ok(regex) => Some(regex),
                 Err(e) => {
-                    let _ = child.kill();
+                    process.stop();
                     return Err(PluginError::ExecutionError {
                         message: format!("Invalid regex pattern: {}", e),
                     });
@@ -420,47 +844,88 @@ impl PluginManager {
             None
         };
 
+        let mut commands = Vec::new();
+        for command in &metadata.capabilities.commands {
+            match regex::Regex::new(&command.file_pattern) {
+                Ok(file_regex) => commands.push(PluginCommand {
+                    name: command.name.clone(),
+                    shortcut: command.shortcut.clone(),
+                    multiple: command.multiple,
+                    file_regex,
+                }),
+                Err(e) => {
+                    process.stop();
+                    return Err(PluginError::ExecutionError {
+                        message: format!(
+                            "Invalid regex pattern for command '{}': {}",
+                            command.name, e
+                        ),
+                    });
+                }
+            }
+        }
+
+        let mut columns = Vec::new();
+        for column in &metadata.capabilities.columns {
+            match regex::Regex::new(&column.file_pattern) {
+                Ok(file_regex) => columns.push(PluginColumn {
+                    name: column.name.clone(),
+                    width: column.width.unwrap_or(DEFAULT_COLUMN_WIDTH),
+                    file_regex,
+                }),
+                Err(e) => {
+                    process.stop();
+                    return Err(PluginError::ExecutionError {
+                        message: format!(
+                            "Invalid regex pattern for column '{}': {}",
+                            column.name, e
+                        ),
+                    });
+                }
+            }
+        }
+
+        let runtime = Arc::new(Runtime {
+            name: metadata.name.clone(),
+            path: path.clone(),
+            state: Mutex::default(),
+            stats: Arc::default(),
+            log,
+        });
+        match error {
+            // Incompatible plugins are listed with the error, but not run
+            Some(error) => {
+                process.stop();
+                runtime.state().error = Some(error);
+            }
+            None => runtime
+                .attach(process)
+                .map_err(|message| PluginError::ExecutionError { message })?,
+        }
+
         Ok(LoadedPlugin {
             metadata,
             path: path.clone(),
-            state: Mutex::new(PluginState {
-                process: child,
-                error,
-            }),
+            runtime,
             load_time,
             preview_regex,
+            commands,
+            columns,
         })
     }
 
-    /// Perform hello handshake with a plugin to get metadata and capabilities
-    fn perform_hello_handshake(
-        child: &mut Child,
-        plugin_path: &std::path::Path,
-    ) -> Result<PluginMetadata, PluginError> {
-        let hello_message = EngineMessage {
-            id: CallId::new(),
-            command: EngineCommand::Hello {
-                protocol_version: kiorg_plugin::PROTOCOL_VERSION.to_string(),
-            },
-        };
-
-        match communicate_with_plugin(
-            child,
-            hello_message,
-            std::time::Duration::from_secs(2),
-            plugin_path.to_str().unwrap_or("unknown"),
-        )? {
-            kiorg_plugin::PluginResponse::Hello(hello_response) => Ok(hello_response),
-            kiorg_plugin::PluginResponse::VersionIncompatible {
+    /// Check the answer of a plugin to the hello message
+    fn check_hello(response: PluginResponse) -> Result<PluginMetadata, PluginError> {
+        match response {
+            PluginResponse::Hello(hello_response) => Ok(hello_response),
+            PluginResponse::VersionIncompatible {
                 protocol_version,
                 metadata,
             } => Err(PluginError::Incompatible {
                 protocol_version,
                 metadata: Box::new(metadata),
             }),
-            kiorg_plugin::PluginResponse::Error { message } => {
-                Err(PluginError::ExecutionError { message })
-            }
+            PluginResponse::Error { message } => Err(PluginError::ExecutionError { message }),
             _ => Err(PluginError::ProtocolError {
                 message: "Expected Hello response from plugin".to_string(),
             }),
@@ -495,19 +960,126 @@ impl PluginManager {
         self.loaded
             .values()
             .find(|plugin| {
-                plugin
-                    .preview_regex
-                    .as_ref()
-                    .is_some_and(|regex| regex.is_match(file_name))
+                !plugin.is_disabled()
+                    && plugin
+                        .preview_regex
+                        .as_ref()
+                        .is_some_and(|regex| regex.is_match(file_name))
             })
             .cloned()
     }
 
+    /// Executable names of the plugins the user disabled
+    pub fn disabled(&self) -> &[String] {
+        &self.disabled
+    }
+
+    /// Disabled plugins that were not started since kiorg started
+    pub fn list_not_started(&self) -> &[PathBuf] {
+        &self.not_started
+    }
+
+    /// Stop a loaded plugin until it is enabled again, or start it in the
+    /// background
+    ///
+    /// Its commands and columns stay in place so indices into
+    /// [`Self::commands`] and [`Self::columns`] remain valid, callers skip
+    /// the ones of disabled plugins.
+    pub fn set_disabled(&mut self, name: &str, disabled: bool) -> Result<(), PluginError> {
+        let plugin = self.loaded.get(name).ok_or_else(|| PluginError::NotFound {
+            name: name.to_string(),
+        })?;
+        let executable = executable_name(&plugin.path);
+        self.disabled.retain(|disabled| *disabled != executable);
+        if disabled {
+            self.disabled.push(executable);
+            self.disabled.sort();
+            plugin.disable();
+        } else {
+            plugin.enable();
+        }
+        Ok(())
+    }
+
+    /// Enable a plugin that was disabled when kiorg started, it is loaded in
+    /// the background and added by [`Self::finish_starting`]
+    pub fn start_disabled(&mut self, path: &Path) -> Result<(), PluginError> {
+        let index = self
+            .not_started
+            .iter()
+            .position(|not_started| not_started == path)
+            .ok_or_else(|| PluginError::NotFound {
+                name: path.display().to_string(),
+            })?;
+        let path = self.not_started.remove(index);
+        let executable = executable_name(&path);
+        self.disabled.retain(|disabled| *disabled != executable);
+        self.starting.push(std::thread::spawn(move || {
+            let result = Self::load_single_plugin(&path);
+            (path, result)
+        }));
+        Ok(())
+    }
+
+    /// Add the plugins loaded by [`Self::start_disabled`] that are ready,
+    /// returns whether there were any. Their commands and columns are
+    /// appended so existing indices stay valid.
+    pub fn finish_starting(&mut self) -> bool {
+        if self.starting.iter().all(|handle| !handle.is_finished()) {
+            return false;
+        }
+        let (finished, starting): (Vec<_>, Vec<_>) = std::mem::take(&mut self.starting)
+            .into_iter()
+            .partition(JoinHandle::is_finished);
+        self.starting = starting;
+        for handle in finished {
+            match handle.join() {
+                Ok((path, result)) => {
+                    if let Some(plugin) = self.add_loaded(path, result) {
+                        self.add_contributions(&plugin);
+                        self.mount_filesystem(&plugin);
+                    }
+                }
+                Err(err) => {
+                    error!(err =? err, "Plugin loading thread panicked");
+                }
+            }
+        }
+        true
+    }
+
+    /// Commands of all loaded plugins
+    pub fn commands(&self) -> &[CommandRef] {
+        &self.commands
+    }
+
+    /// Indices into [`Self::commands`] of the commands that can run on `paths`
+    pub fn commands_for(&self, paths: &[PathBuf]) -> Vec<usize> {
+        self.commands
+            .iter()
+            .enumerate()
+            .filter(|(_, command)| {
+                !command.plugin.is_disabled() && command.command().accepts(paths)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// File list columns of all loaded plugins
+    pub fn columns(&self) -> &[ColumnRef] {
+        &self.columns
+    }
+
     /// Shutdown plugin manager
     pub fn shutdown(&mut self) -> Result<(), PluginError> {
         // Unload all plugins
         let plugin_names: Vec<String> = self.loaded.keys().cloned().collect();
 
+        self.commands.clear();
+        self.columns.clear();
+        for root in self.mounts.drain(..) {
+            crate::vfs::unmount(&root);
+        }
         for name in plugin_names {
             if let Err(e) = self.unload_plugin(&name) {
                 warn!("Failed to unload plugin '{}' during shutdown: {}", name, e);
@@ -518,3 +1090,30 @@ impl PluginManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_accepts_paths() {
+        let command = PluginCommand {
+            name: "Convert".to_string(),
+            shortcut: None,
+            multiple: false,
+            file_regex: regex::Regex::new(r"\.png$").unwrap(),
+        };
+        let png = PathBuf::from("/photos/a.png");
+        assert!(command.accepts(std::slice::from_ref(&png)));
+        assert!(!command.accepts(&[]));
+        assert!(!command.accepts(&[PathBuf::from("/photos/a.jpg")]));
+        // A single path only
+        assert!(!command.accepts(&[png.clone(), PathBuf::from("/photos/b.png")]));
+
+        let command = PluginCommand {
+            multiple: true,
+            ..command
+        };
+        assert!(command.accepts(&[png, PathBuf::from("/photos/b.png")]));
+    }
+}
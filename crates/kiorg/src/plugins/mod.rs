@@ -3,11 +3,16 @@
 //! This module provides a simplified plugin system for discovering and managing
 //! external plugin executables.
 
+pub mod columns;
+pub mod connection;
+pub mod health;
 pub mod manager;
+pub mod vfs;
 
-pub use manager::PluginManager;
+pub use manager::{ColumnRef, CommandRef, ExecuteResult, PluginManager};
 
 // Re-export types from the kiorg_plugin crate
 pub use kiorg_plugin::{
-    CallId, EngineCommand, EngineMessage, HelloMessage, PluginMetadata, PluginResponse,
+    CallId, EngineCommand, EngineMessage, HelloMessage, PluginMessage, PluginMetadata,
+    PluginResponse,
 };
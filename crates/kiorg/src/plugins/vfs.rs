@@ -0,0 +1,380 @@
+//! Filesystems served by plugins under a URI scheme
+//!
+//! A plugin declaring `s3` serves every `s3://bucket/key` path. Requests go
+//! over the plugin protocol one operation at a time, file contents are
+//! transferred in chunks.
+
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use kiorg_plugin::{EngineCommand, FileInfo, PluginResponse};
+
+use super::manager::LoadedPlugin;
+use crate::utils::jobs::JobContext;
+use crate::vfs::{self, Vfs, VfsEntry, VfsMetadata};
+
+/// Remote sources can be slow to answer
+const VFS_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Bytes transferred per read or write request
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// The filesystem of a plugin, mounted at `scheme://`
+pub struct PluginFs {
+    plugin: Arc<LoadedPlugin>,
+    read_only: bool,
+}
+
+impl PluginFs {
+    #[must_use]
+    pub const fn new(plugin: Arc<LoadedPlugin>, read_only: bool) -> Self {
+        Self { plugin, read_only }
+    }
+
+    /// The path where the filesystem of `scheme` is mounted
+    #[must_use]
+    pub fn mount_root(scheme: &str) -> PathBuf {
+        PathBuf::from(format!("{scheme}://"))
+    }
+
+    fn request(&self, command: EngineCommand) -> io::Result<PluginResponse> {
+        request(&self.plugin, command)
+    }
+
+    fn check_writable(&self) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::ReadOnlyFilesystem,
+                format!("{} is read-only", self.plugin.metadata.name),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Run an operation that only reports success
+    fn run(&self, command: EngineCommand) -> io::Result<()> {
+        self.check_writable()?;
+        match self.request(command)? {
+            PluginResponse::VfsDone => Ok(()),
+            other => Err(unexpected(&other)),
+        }
+    }
+}
+
+fn request(plugin: &LoadedPlugin, command: EngineCommand) -> io::Result<PluginResponse> {
+    match plugin.call(command, VFS_TIMEOUT) {
+        Ok(PluginResponse::Error { message }) => Err(io::Error::other(message)),
+        Ok(response) => Ok(response),
+        Err(e) => Err(io::Error::other(e.to_string())),
+    }
+}
+
+fn unexpected(response: &PluginResponse) -> io::Error {
+    io::Error::other(format!("Unexpected response from plugin: {response:?}"))
+}
+
+fn path_arg(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+fn metadata_of(info: &FileInfo) -> VfsMetadata {
+    VfsMetadata {
+        is_dir: info.is_dir,
+        is_symlink: info.is_symlink,
+        size: if info.is_dir { 0 } else { info.size },
+        modified: SystemTime::UNIX_EPOCH + Duration::from_secs(info.modified),
+    }
+}
+
+fn entry_of(dir: &Path, info: &FileInfo) -> Option<VfsEntry> {
+    // Names come from the plugin, they must not point outside the directory
+    if info.name.is_empty() || info.name.contains('/') || info.name == "." || info.name == ".." {
+        return None;
+    }
+    Some(VfsEntry {
+        name: info.name.clone(),
+        path: dir.join(&info.name),
+        metadata: metadata_of(info),
+        is_hidden: info.name.starts_with('.'),
+    })
+}
+
+impl Vfs for PluginFs {
+    fn list(&self, path: &Path) -> io::Result<Vec<VfsEntry>> {
+        match self.request(EngineCommand::VfsList {
+            path: path_arg(path),
+        })? {
+            PluginResponse::VfsEntries { entries } => Ok(entries
+                .iter()
+                .filter_map(|info| entry_of(path, info))
+                .collect()),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<VfsMetadata> {
+        match self.request(EngineCommand::VfsStat {
+            path: path_arg(path),
+        })? {
+            PluginResponse::VfsStat { info } => Ok(metadata_of(&info)),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(PluginReader {
+            plugin: self.plugin.clone(),
+            path: path_arg(path),
+            offset: 0,
+            buffer: Vec::new(),
+            position: 0,
+            eof: false,
+        }))
+    }
+
+    fn write(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self.check_writable()?;
+        Ok(Box::new(PluginWriter {
+            plugin: self.plugin.clone(),
+            path: path_arg(path),
+            buffer: Vec::new(),
+            created: false,
+        }))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.run(EngineCommand::VfsCreateDir {
+            path: path_arg(path),
+        })
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.run(EngineCommand::VfsRename {
+            from: path_arg(from),
+            to: path_arg(to),
+        })
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.run(EngineCommand::VfsRemove {
+            path: path_arg(path),
+        })
+    }
+
+    /// The protocol has no copy, the data makes a round trip
+    fn copy(&self, src: &Path, dst: &Path, ctx: &mut JobContext) -> io::Result<()> {
+        self.check_writable()?;
+        vfs::copy_between(self, src, self, dst, ctx)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn parent(&self, path: &Path) -> Option<PathBuf> {
+        if vfs::url_root(path).is_none_or(|root| root == path) {
+            return None;
+        }
+        path.parent().map(Path::to_path_buf)
+    }
+}
+
+/// Reads a file a chunk at a time
+struct PluginReader {
+    plugin: Arc<LoadedPlugin>,
+    path: String,
+    /// Offset of the next chunk to request
+    offset: u64,
+    buffer: Vec<u8>,
+    /// Position of the next unread byte in `buffer`
+    position: usize,
+    eof: bool,
+}
+
+impl Read for PluginReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer.len() && !self.eof {
+            match request(
+                &self.plugin,
+                EngineCommand::VfsRead {
+                    path: self.path.clone(),
+                    offset: self.offset,
+                    len: CHUNK_SIZE as u64,
+                },
+            )? {
+                PluginResponse::VfsData { data } => {
+                    self.eof = data.is_empty();
+                    self.offset += data.len() as u64;
+                    self.buffer = data;
+                    self.position = 0;
+                }
+                other => return Err(unexpected(&other)),
+            }
+        }
+        let read = buf.len().min(self.buffer.len() - self.position);
+        buf[..read].copy_from_slice(&self.buffer[self.position..self.position + read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+/// Creates a file with the first chunk and appends the following ones
+struct PluginWriter {
+    plugin: Arc<LoadedPlugin>,
+    path: String,
+    buffer: Vec<u8>,
+    created: bool,
+}
+
+impl PluginWriter {
+    fn send(&mut self) -> io::Result<()> {
+        let command = EngineCommand::VfsWrite {
+            path: self.path.clone(),
+            data: std::mem::take(&mut self.buffer),
+            append: self.created,
+        };
+        match request(&self.plugin, command)? {
+            PluginResponse::VfsDone => {
+                self.created = true;
+                Ok(())
+            }
+            other => Err(unexpected(&other)),
+        }
+    }
+}
+
+impl Write for PluginWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.send()?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Sends what is buffered, empty files are created on the first flush
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() || !self.created {
+            self.send()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PluginWriter {
+    fn drop(&mut self) {
+        if !self.buffer.is_empty()
+            && let Err(e) = self.flush()
+        {
+            tracing::warn!("Failed to write {}: {e}", self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_stay_in_directory() {
+        let dir = Path::new("demo://local/docs");
+        let info = FileInfo {
+            name: ".notes".to_string(),
+            size: 3,
+            modified: 60,
+            ..Default::default()
+        };
+        let entry = entry_of(dir, &info).unwrap();
+        assert_eq!(entry.path, PathBuf::from("demo://local/docs/.notes"));
+        assert!(entry.is_hidden);
+        assert_eq!(
+            entry.metadata.modified,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60)
+        );
+
+        for name in ["", "..", "a/b"] {
+            let info = FileInfo {
+                name: name.to_string(),
+                ..Default::default()
+            };
+            assert!(entry_of(dir, &info).is_none());
+        }
+    }
+
+    /// Builds the demo plugin into its own target directory, the one of
+    /// the running test is locked by cargo
+    fn build_demo_plugin() -> PathBuf {
+        let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        let target_dir = workspace.join("target").join("demo_plugin_test");
+        let status = std::process::Command::new(env!("CARGO"))
+            .current_dir(&workspace)
+            .args([
+                "build",
+                "--quiet",
+                "-p",
+                "kiorg_plugin_demo",
+                "--target-dir",
+            ])
+            .arg(&target_dir)
+            .status()
+            .expect("Failed to run cargo");
+        assert!(status.success(), "Failed to build the demo plugin");
+        target_dir
+            .join("debug")
+            .join(format!("kiorg_plugin_demo{}", std::env::consts::EXE_SUFFIX))
+    }
+
+    #[test]
+    fn test_demo_plugin_round_trip() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let plugin_dir = config_dir.path().join("plugins");
+        std::fs::create_dir(&plugin_dir).unwrap();
+        std::fs::copy(
+            build_demo_plugin(),
+            plugin_dir.join(format!("kiorg_plugin_demo{}", std::env::consts::EXE_SUFFIX)),
+        )
+        .unwrap();
+        let mut manager =
+            crate::plugins::PluginManager::new(Some(&config_dir.path().to_path_buf()), &[]);
+        manager.load_plugins().unwrap();
+        assert!(manager.list_failed().is_empty());
+
+        let root = Path::new("demo://local");
+        let fs = vfs::for_path(root);
+        fs.list(root).unwrap();
+        // The demo serves a shared directory, keep to our own corner of it
+        let dir = root.join(format!("round_trip_{}", uuid::Uuid::new_v4()));
+        fs.create_dir(&dir).unwrap();
+
+        // Spans several chunks, so the writer appends and the reader
+        // requests more than once
+        let content: Vec<u8> = (0..CHUNK_SIZE * 2 + 100).map(|i| (i % 251) as u8).collect();
+        let file = dir.join("data.bin");
+        let mut writer = fs.write(&file).unwrap();
+        for chunk in content.chunks(CHUNK_SIZE / 3) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+
+        let entries = fs.list(&dir).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, file);
+        assert_eq!(entries[0].metadata.size, content.len() as u64);
+
+        let mut read_back = Vec::new();
+        fs.read(&file).unwrap().read_to_end(&mut read_back).unwrap();
+        assert!(read_back == content, "Content changed on the round trip");
+
+        let renamed = dir.join("renamed.bin");
+        fs.rename(&file, &renamed).unwrap();
+        assert!(fs.stat(&file).is_err());
+        assert_eq!(fs.stat(&renamed).unwrap().size, content.len() as u64);
+
+        fs.remove(&dir).unwrap();
+        assert!(fs.stat(&dir).is_err());
+        manager.shutdown().unwrap();
+    }
+}
@@ -1,18 +1,30 @@
 use egui::Ui;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::app::Clipboard;
 use crate::app::Kiorg;
 use crate::config;
 use crate::config::SortPreference;
+use crate::models::action_history::{
+    CopyOperation, ExtractOperation, MoveOperation, OverwriteBackup, OverwriteOperation,
+};
+use crate::models::dir_entry::DirEntry;
+use crate::models::tab::SortColumn;
+use crate::ui::editor_rename;
 use crate::ui::file_list::{self, ROW_HEIGHT, TableHeaderParams};
 use crate::ui::popup::PopupType;
-use crate::utils::file_operations;
+use crate::ui::popup::command_palette::{self, CommandItem};
+use crate::ui::popup::delete;
+use crate::ui::popup::paste_conflict::{self, PasteResolution, PasteResolutions};
+use crate::utils::archive::ArchiveFormat;
+use crate::utils::jobs::{self, JobContext, JobData, JobRecord};
+use crate::utils::{stash, trash};
+use crate::vfs;
 
 // TODO: make this configurable
 const PADDING_ROWS: usize = 3;
 
-fn new_unique_path_name_for_paste(
+pub(crate) fn new_unique_path_name_for_paste(
     path: &std::path::Path,
     current_path: &std::path::Path,
 ) -> PathBuf {
@@ -24,7 +36,7 @@ fn new_unique_path_name_for_paste(
 
     // Handle duplicate names
     let mut counter = 1;
-    while new_path.exists() {
+    while vfs::exists(&new_path) {
         let stem = path
             .file_stem()
             .and_then(|s| s.to_str())
@@ -41,91 +53,369 @@ fn new_unique_path_name_for_paste(
     new_path
 }
 
+/// Starts the clipboard paste operation (copy/cut) into `target_dir` as a background job
+/// Conflicting entries are handled according to `resolutions`, unresolved ones get renamed
+/// Returns None if there is nothing to paste
+pub fn start_clipboard_job(
+    clipboard: Option<Clipboard>,
+    target_dir: &Path,
+    resolutions: PasteResolutions,
+) -> Option<JobData> {
+    let target_dir = target_dir.to_path_buf();
+    match clipboard {
+        Some(Clipboard::Copy(paths)) => Some(jobs::spawn_job("Copying", move |ctx| {
+            paste_entries(&paths, &target_dir, &resolutions, false, ctx);
+            Ok(())
+        })),
+        Some(Clipboard::Cut(paths)) => Some(jobs::spawn_job("Moving", move |ctx| {
+            paste_entries(&paths, &target_dir, &resolutions, true, ctx);
+            Ok(())
+        })),
+        None => None, // No clipboard operation to perform
+    }
+}
+
 /// Handles clipboard paste operations (copy/cut)
-/// Returns true if any operation was performed
-pub fn handle_clipboard_operations(
-    clipboard: &mut Option<Clipboard>,
-    current_path: &std::path::Path,
-    action_history: &mut crate::models::action_history::TabActionHistory,
-    toasts: &mut crate::ui::egui_notify::Toasts,
-) -> bool {
-    match clipboard.take() {
-        Some(Clipboard::Copy(paths)) => {
-            let mut copy_operations = Vec::new();
-
-            paths.iter().for_each(|path| {
-                let new_path = new_unique_path_name_for_paste(path, current_path);
-                // Handle copying differently based on whether it's a file or directory
-                if path.is_dir() {
-                    if let Err(e) = file_operations::copy_dir_recursively(path, &new_path) {
-                        toasts.error(format!(
-                            "Failed to copy directory {} to {}: {e}",
-                            path.to_string_lossy(),
-                            new_path.to_string_lossy()
-                        ));
-                    } else {
-                        // Record successful copy operation
-                        copy_operations.push(crate::models::action_history::CopyOperation {
-                            source_path: path.clone(),
-                            target_path: new_path,
-                        });
-                    }
-                    return;
-                }
+/// Asks how to resolve conflicts before the paste job starts
+/// Returns true if there was anything to paste
+pub fn handle_clipboard_operations(app: &mut Kiorg, target_dir: &Path) -> bool {
+    if app.clipboard.is_some() && vfs::is_read_only_dir(target_dir) {
+        app.notify_error("Archives are read-only");
+        return false;
+    }
+    match app.clipboard.take() {
+        Some(clipboard) => {
+            paste_conflict::start(app, clipboard, target_dir, None);
+            if let Some(PopupType::PasteConflict(state)) = &mut app.show_popup {
+                state.restore_clipboard = true;
+            }
+            true
+        }
+        None => false,
+    }
+}
 
-                if let Err(e) = std::fs::copy(path, &new_path) {
-                    toasts.error(format!(
-                        "Failed to copy file {} to {}: {e}",
-                        path.to_string_lossy(),
-                        new_path.to_string_lossy()
-                    ));
-                } else {
-                    // Record successful copy operation
-                    copy_operations.push(crate::models::action_history::CopyOperation {
-                        source_path: path.clone(),
-                        target_path: new_path,
-                    });
-                }
-            });
+/// Worker side of a paste, records every entry that was copied or moved completely
+fn paste_entries(
+    paths: &[PathBuf],
+    target_dir: &Path,
+    resolutions: &PasteResolutions,
+    is_move: bool,
+    ctx: &mut JobContext,
+) {
+    if is_move {
+        ctx.set_totals(paths.len(), 0);
+    } else {
+        let (total_files, total_bytes) = vfs::count_entries(paths);
+        ctx.set_totals(total_files, total_bytes);
+    }
 
-            // Record operations if any operations succeeded
-            if !copy_operations.is_empty() {
-                action_history.add_action(crate::models::action_history::ActionType::Copy {
-                    operations: copy_operations,
-                });
-            }
+    for path in paths {
+        if ctx.is_cancelled() {
+            return;
         }
-        Some(Clipboard::Cut(paths)) => {
-            let mut move_operations = Vec::new();
-
-            paths.iter().for_each(|path| {
-                let new_path = new_unique_path_name_for_paste(path, current_path);
-                if let Err(e) = std::fs::rename(path, &new_path) {
-                    toasts.error(format!(
-                        "Failed to move {} to {}: {e}",
-                        path.to_string_lossy(),
-                        new_path.to_string_lossy()
-                    ));
-                } else {
-                    // Record successful move operation
-                    move_operations.push(crate::models::action_history::MoveOperation {
-                        source_path: path.clone(),
-                        target_path: new_path,
-                    });
-                }
-            });
+        if let Some(name) = path.file_name() {
+            paste_entry(path, &target_dir.join(name), resolutions, is_move, ctx);
+        }
+    }
+}
+
+fn paste_entry(
+    src: &Path,
+    dst: &Path,
+    resolutions: &PasteResolutions,
+    is_move: bool,
+    ctx: &mut JobContext,
+) {
+    let conflict = src == dst || vfs::exists(dst);
+    if !conflict {
+        paste_new(src, dst, is_move, ctx);
+        return;
+    }
+
+    // Pasting an entry onto itself and unresolved conflicts get a new name
+    let resolution = if src == dst {
+        PasteResolution::Rename
+    } else {
+        resolutions
+            .get(src)
+            .copied()
+            .unwrap_or(PasteResolution::Rename)
+    };
+    match resolution {
+        PasteResolution::Overwrite => overwrite_entry(src, dst, is_move, ctx),
+        PasteResolution::Skip => skip_entry(src, is_move, ctx),
+        PasteResolution::KeepNewer if is_newer(src, dst) => overwrite_entry(src, dst, is_move, ctx),
+        PasteResolution::KeepNewer => skip_entry(src, is_move, ctx),
+        PasteResolution::Merge if is_real_dir(src) && is_real_dir(dst) => {
+            merge_dirs(src, dst, resolutions, is_move, ctx);
+        }
+        PasteResolution::Merge | PasteResolution::Rename => {
+            let parent = dst.parent().unwrap_or(dst);
+            let new_path = new_unique_path_name_for_paste(src, parent);
+            paste_new(src, &new_path, is_move, ctx);
+        }
+    }
+}
 
-            // Record operations if any operations succeeded
-            if !move_operations.is_empty() {
-                action_history.add_action(crate::models::action_history::ActionType::Move {
-                    operations: move_operations,
-                });
+/// Copy or move `src` to the free path `dst` and record it for undo
+fn paste_new(src: &Path, dst: &Path, is_move: bool, ctx: &mut JobContext) {
+    match transfer(src, dst, is_move, ctx) {
+        // Copies out of an archive are undone like an extraction, into the undo stash
+        Ok(()) if vfs::for_path(src).is_read_only() => match stash::reserve(dst) {
+            Ok(backup) => ctx.record(JobRecord::Extract(ExtractOperation {
+                archive_path: src.to_path_buf(),
+                target_path: dst.to_path_buf(),
+                backup,
+            })),
+            Err(e) => ctx.warn(format!(
+                "Copied {} but it cannot be undone: {e}",
+                dst.to_string_lossy()
+            )),
+        },
+        Ok(()) if is_move => ctx.record(JobRecord::Move(MoveOperation {
+            source_path: src.to_path_buf(),
+            target_path: dst.to_path_buf(),
+        })),
+        Ok(()) => ctx.record(JobRecord::Copy(CopyOperation {
+            source_path: src.to_path_buf(),
+            target_path: dst.to_path_buf(),
+        })),
+        Err(e) => warn_failed_transfer(src, dst, is_move, &e, ctx),
+    }
+}
+
+/// Whether overwriting `path` removes it for good: it is neither on the stash
+/// device nor on a local mount with a trash
+pub fn overwrite_is_permanent(path: &Path) -> bool {
+    !stash::is_on_stash_device(path) && !(trash::is_supported() && vfs::is_local(path))
+}
+
+/// Replace `dst` with `src`, keeping the replaced entry in the undo stash so undo can restore it
+/// Entries on other devices are pasted next to `dst` first, see [`overwrite_through_temp`]
+fn overwrite_entry(src: &Path, dst: &Path, is_move: bool, ctx: &mut JobContext) {
+    if !stash::is_on_stash_device(dst) {
+        overwrite_through_temp(src, dst, is_move, ctx);
+        return;
+    }
+
+    let backup = match stash::stash(dst) {
+        Ok(backup) => backup,
+        Err(e) => {
+            ctx.warn(format!(
+                "Failed to overwrite {}: {e}",
+                dst.to_string_lossy()
+            ));
+            skip_entry(src, is_move, ctx);
+            return;
+        }
+    };
+
+    match transfer(src, dst, is_move, ctx) {
+        Ok(()) => ctx.record(JobRecord::Overwrite(OverwriteOperation {
+            source_path: src.to_path_buf(),
+            target_path: dst.to_path_buf(),
+            is_move,
+            backup: OverwriteBackup::Stash(backup),
+        })),
+        Err(e) => {
+            // Put the replaced entry back, the failed paste was already cleaned up
+            if let Err(restore_error) = stash::restore(&backup) {
+                ctx.warn(restore_error);
             }
+            warn_failed_transfer(src, dst, is_move, &e, ctx);
+        }
+    }
+}
+
+/// Overwrite `dst` by pasting under a temporary name next to it first, the
+/// replaced entry is only put aside once the paste succeeded. It goes to the
+/// trash of its mount, or is removed for good when there is none.
+fn overwrite_through_temp(src: &Path, dst: &Path, is_move: bool, ctx: &mut JobContext) {
+    let temp_path = temp_sibling(dst);
+    if let Err(e) = transfer(src, &temp_path, is_move, ctx) {
+        warn_failed_transfer(src, dst, is_move, &e, ctx);
+        return;
+    }
+
+    let backup = if overwrite_is_permanent(dst) {
+        delete::perform_delete(dst).map(|()| None)
+    } else {
+        trash::move_to_trash(dst).map(Some)
+    };
+    let backup = match backup {
+        Ok(backup) => backup,
+        Err(e) => {
+            ctx.warn(format!("Failed to overwrite {}: {e}", dst.display()));
+            undo_temp_paste(src, &temp_path, is_move, ctx);
+            return;
+        }
+    };
+
+    if let Err(e) = vfs::for_path(&temp_path).rename(&temp_path, dst) {
+        ctx.warn(format!("Failed to overwrite {}: {e}", dst.display()));
+        if let Some(op) = &backup
+            && let Err(restore_error) = trash::restore(op)
+        {
+            ctx.warn(restore_error);
+        }
+        undo_temp_paste(src, &temp_path, is_move, ctx);
+        return;
+    }
+
+    let (source_path, target_path) = (src.to_path_buf(), dst.to_path_buf());
+    match backup {
+        Some(op) => ctx.record(JobRecord::Overwrite(OverwriteOperation {
+            source_path,
+            target_path,
+            is_move,
+            backup: OverwriteBackup::Trash(op),
+        })),
+        None if is_move => ctx.record(JobRecord::Move(MoveOperation {
+            source_path,
+            target_path,
+        })),
+        None => ctx.record(JobRecord::Copy(CopyOperation {
+            source_path,
+            target_path,
+        })),
+    }
+}
+
+/// A free hidden name next to `path` to paste into before replacing it
+fn temp_sibling(path: &Path) -> PathBuf {
+    let name = format!(".kiorg-paste-{}", uuid::Uuid::new_v4().simple());
+    match path.parent() {
+        Some(parent) => parent.join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+/// Take back a paste into a temporary name that could not replace its target
+fn undo_temp_paste(src: &Path, temp_path: &Path, is_move: bool, ctx: &mut JobContext) {
+    let result = if is_move {
+        vfs::move_entry(temp_path, src, ctx)
+    } else {
+        vfs::for_path(temp_path).remove(temp_path)
+    };
+    if let Err(e) = result {
+        ctx.warn(format!(
+            "Failed to clean up {}, the pasted entry is left there: {e}",
+            temp_path.display()
+        ));
+    }
+}
+
+/// Paste the children of `src` into the existing directory `dst`
+fn merge_dirs(
+    src: &Path,
+    dst: &Path,
+    resolutions: &PasteResolutions,
+    is_move: bool,
+    ctx: &mut JobContext,
+) {
+    let children: Vec<PathBuf> = match vfs::for_path(src).list(src) {
+        Ok(entries) => entries.into_iter().map(|entry| entry.path).collect(),
+        Err(e) => {
+            ctx.warn(format!("Failed to read {}: {e}", src.to_string_lossy()));
+            skip_entry(src, is_move, ctx);
+            return;
+        }
+    };
+
+    ctx.start_file(src);
+    if is_move {
+        // Children are moved one by one instead of the directory as a whole
+        ctx.add_totals(children.len(), 0);
+    }
+
+    for child in &children {
+        if ctx.is_cancelled() {
+            return;
+        }
+        if let Some(name) = child.file_name() {
+            paste_entry(child, &dst.join(name), resolutions, is_move, ctx);
         }
-        _ => return false, // No clipboard operation to perform
     }
 
-    true
+    if is_move {
+        // Skipped children keep the source around, the vfs removes whole trees
+        // so the directory is only removed once it is empty
+        let fs = vfs::for_path(src);
+        let is_empty = fs.list(src).is_ok_and(|entries| entries.is_empty());
+        if is_empty && let Err(e) = fs.remove(src) {
+            ctx.warn(format!("Failed to remove {}: {e}", src.to_string_lossy()));
+        }
+    }
+}
+
+fn skip_entry(src: &Path, is_move: bool, ctx: &mut JobContext) {
+    if is_move {
+        ctx.skip(1, 0);
+    } else {
+        let (files, bytes) = vfs::count_entries(&[src.to_path_buf()]);
+        ctx.skip(files, bytes);
+    }
+}
+
+/// Copy or move a single entry, never leaving half copied entries behind
+fn transfer(src: &Path, dst: &Path, is_move: bool, ctx: &mut JobContext) -> std::io::Result<()> {
+    if is_move {
+        ctx.start_file(src);
+        // Falls back to copy-then-delete when crossing devices or filesystems
+        return vfs::move_entry(src, dst, ctx);
+    }
+
+    let result = vfs::copy(src, dst, ctx);
+    if result.is_err() {
+        vfs::remove_partial(dst);
+    }
+    result
+}
+
+fn warn_failed_transfer(
+    src: &Path,
+    dst: &Path,
+    is_move: bool,
+    error: &std::io::Error,
+    ctx: &mut JobContext,
+) {
+    if error.kind() == std::io::ErrorKind::Interrupted {
+        return;
+    }
+    if is_move {
+        ctx.warn(format!(
+            "Failed to move {} to {}: {error}",
+            src.to_string_lossy(),
+            dst.to_string_lossy()
+        ));
+    } else {
+        let kind = if is_real_dir(src) {
+            "directory"
+        } else {
+            "file"
+        };
+        ctx.warn(format!(
+            "Failed to copy {kind} {} to {}: {error}",
+            src.to_string_lossy(),
+            dst.to_string_lossy()
+        ));
+    }
+}
+
+fn is_real_dir(path: &Path) -> bool {
+    vfs::for_path(path)
+        .stat(path)
+        .is_ok_and(|meta| meta.is_dir && !meta.is_symlink)
+}
+
+/// Whether `src` was modified more recently than `dst`
+fn is_newer(src: &Path, dst: &Path) -> bool {
+    let modified = |path: &Path| vfs::for_path(path).stat(path).map(|meta| meta.modified);
+    match (modified(src), modified(dst)) {
+        (Ok(src_time), Ok(dst_time)) => src_time > dst_time,
+        _ => false,
+    }
 }
 
 fn scroll_by_filtered_index(
@@ -193,20 +483,29 @@ enum ContextMenuAction {
     Add,
     Paste,
     Rename,
+    RenameInEditor,
+    BulkRename,
+    Extract,
+    Compress,
     Delete,
     Copy,
     Cut,
     BulkDelete, // New action for bulk deletion
-    OpenWith,   // New action for opening with custom command
+    PermanentDelete,
+    OpenWith,             // New action for opening with custom command
+    PluginCommand(usize), // Index into the plugin manager's commands
 }
 
 /// Helper function to build the context menu items and return the chosen action.
-/// Takes a boolean indicating if pasting is possible, if a file is selected, and if there are marked entries.
+/// Takes a boolean indicating if pasting is possible, if a file is selected, if there are marked entries
+/// and if the selected file is an archive, along with the plugin commands that apply.
 fn show_context_menu(
     ui: &mut Ui,
     can_paste: bool,
     has_selection: bool,
     has_marked_entries: bool,
+    is_archive: bool,
+    plugin_commands: &[CommandItem],
 ) -> ContextMenuAction {
     let mut action = ContextMenuAction::None;
 
@@ -226,6 +525,16 @@ fn show_context_menu(
         ui.close();
     }
 
+    if ui.button("Rename in editor").clicked() {
+        action = ContextMenuAction::RenameInEditor;
+        ui.close();
+    }
+
+    if ui.button("Bulk rename...").clicked() {
+        action = ContextMenuAction::BulkRename;
+        ui.close();
+    }
+
     // Show bulk delete option when there are marked entries
     if has_marked_entries {
         // TODO: do we need to add enabled
@@ -241,6 +550,18 @@ fn show_context_menu(
         ui.close();
     }
 
+    if trash::is_supported()
+        && ui
+            .add_enabled(
+                has_selection || has_marked_entries,
+                egui::Button::new("Delete permanently"),
+            )
+            .clicked()
+    {
+        action = ContextMenuAction::PermanentDelete;
+        ui.close();
+    }
+
     // Add "Open with" option - enabled for both files and directories
     if ui
         .add_enabled(has_selection, egui::Button::new("Open with..."))
@@ -250,6 +571,22 @@ fn show_context_menu(
         ui.close();
     }
 
+    if is_archive && ui.button("Extract here").clicked() {
+        action = ContextMenuAction::Extract;
+        ui.close();
+    }
+
+    if ui
+        .add_enabled(
+            has_selection || has_marked_entries,
+            egui::Button::new("Compress..."),
+        )
+        .clicked()
+    {
+        action = ContextMenuAction::Compress;
+        ui.close();
+    }
+
     ui.separator();
 
     if ui
@@ -277,12 +614,58 @@ fn show_context_menu(
         ui.close();
     }
 
+    if !plugin_commands.is_empty() {
+        ui.separator();
+        for command in plugin_commands {
+            if ui.button(&command.label).clicked() {
+                action = ContextMenuAction::PluginCommand(command.index);
+                ui.close();
+            }
+        }
+    }
+
     action
 }
 
+/// Store the plugin column values fetched since the last frame and request
+/// the ones missing for the rows on screen
+fn update_plugin_columns(ctx: &egui::Context, app: &mut Kiorg) {
+    let columns = app.plugin_manager.columns();
+    if columns.is_empty() {
+        return;
+    }
+    let tab = app.tab_manager.current_tab_ref();
+    let sort_column = app.tab_manager.sort_column;
+
+    if app.column_cache.poll()
+        && let SortColumn::Plugin(column) = sort_column
+    {
+        let keys = app.column_cache.sort_keys(columns, column, &tab.entries);
+        app.tab_manager.resort_by_plugin_keys(keys);
+        app.reapply_search_filter();
+    }
+
+    let tab = app.tab_manager.current_tab_ref();
+    // Sorting needs the values of every entry, not just the visible ones
+    let entries: Vec<&DirEntry> = if matches!(sort_column, SortColumn::Plugin(_)) {
+        tab.entries.iter().collect()
+    } else if let Some(range) = &app.scroll_range {
+        let filtered = tab.get_cached_filtered_entries();
+        filtered[range.start.min(filtered.len())..range.end.min(filtered.len())]
+            .iter()
+            .map(|&index| &tab.entries[index])
+            .collect()
+    } else {
+        Vec::new()
+    };
+    app.column_cache
+        .request(ctx, app.plugin_manager.columns(), &entries);
+}
+
 /// Draws the center panel content.
 pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
     handle_file_drop(ui.ctx(), app);
+    update_plugin_columns(ui.ctx(), app);
 
     // --- State variables to capture changes from UI closures ---
     let mut new_selected_index = None; // For selection changes captured from the row click
@@ -303,10 +686,23 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
         ui.set_min_height(height);
         ui.set_max_height(height);
 
+        let plugin_columns = app.plugin_manager.columns();
+        // Columns of disabled plugins are hidden
+        let extra_columns: Vec<file_list::ExtraColumn> = plugin_columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| !column.plugin.is_disabled())
+            .map(|(index, column)| file_list::ExtraColumn {
+                index,
+                title: &column.column().name,
+                width: column.column().width,
+            })
+            .collect();
         let mut header_params = TableHeaderParams {
             colors: &app.colors,
             sort_column: &app.tab_manager.sort_column,
             sort_order: &app.tab_manager.sort_order,
+            extra_columns: &extra_columns,
             on_sort: &mut |column| {
                 sort_requested = Some(column);
             },
@@ -368,7 +764,12 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
                     scroll_ui.set_min_width(available_width);
 
                     if filtered_indices.is_empty() {
-                        scroll_ui.label("No matching entries found.");
+                        if tab_ref.is_listing() {
+                            scroll_ui
+                                .label(format!("Listing {}...", tab_ref.current_path.display()));
+                        } else {
+                            scroll_ui.label("No matching entries found.");
+                        }
                         return;
                     }
                     app.scroll_range = Some(row_range.clone());
@@ -429,11 +830,19 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
                                 .map(|dragged| dragged == &entry.meta.path)
                                 .unwrap_or(false);
 
+                        let extra_values: Vec<Option<&str>> = extra_columns
+                            .iter()
+                            .map(|column| {
+                                app.column_cache.value(plugin_columns, column.index, entry)
+                            })
+                            .collect();
+
                         // Draw the row and get its response
                         let row_response = file_list::draw_entry_row(
                             scroll_ui,
                             file_list::EntryRowParams {
                                 entry,
+                                match_indices: tab_ref.get_cached_match_indices(row_index),
                                 is_selected,
                                 colors: &app.colors,
                                 is_marked,
@@ -443,6 +852,8 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
                                 is_in_copy_clipboard,
                                 is_drag_source,
                                 is_drag_active,
+                                extra_columns: &extra_columns,
+                                extra_values: &extra_values,
                             },
                         );
 
@@ -473,11 +884,25 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
                             // Capture the action, don't perform it yet
                             // Pass only the necessary booleans, not the whole app
                             let has_marked_entries = !tab_ref.marked_entries.is_empty();
+                            let is_archive =
+                                !entry.is_dir && ArchiveFormat::detect(&entry.meta.path).is_some();
+                            let targets = if has_marked_entries {
+                                command_palette::target_paths(tab_ref)
+                            } else {
+                                vec![entry.meta.path.clone()]
+                            };
+                            let plugin_commands = command_palette::commands_for(
+                                &app.plugin_manager,
+                                &app.merged_shortcuts,
+                                &targets,
+                            );
                             context_menu_action = show_context_menu(
                                 menu_ui,
                                 app.clipboard.is_some(),
                                 true,
                                 has_marked_entries,
+                                is_archive,
+                                &plugin_commands,
                             );
                         });
                     } // End row loop
@@ -495,12 +920,25 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
             // Capture the action, don't perform it yet
             // Pass only the necessary booleans, not the whole app
             // For background context menu, no file is selected
-            let has_marked_entries = !app.tab_manager.current_tab_ref().marked_entries.is_empty();
+            let tab = app.tab_manager.current_tab_ref();
+            let has_marked_entries = !tab.marked_entries.is_empty();
+            // Only the marked entries can be acted on from the background
+            let plugin_commands = if has_marked_entries {
+                command_palette::commands_for(
+                    &app.plugin_manager,
+                    &app.merged_shortcuts,
+                    &command_palette::target_paths(tab),
+                )
+            } else {
+                Vec::new()
+            };
             context_menu_action = show_context_menu(
                 menu_ui,
                 app.clipboard.is_some(),
                 false, // No file is selected in background context menu
                 has_marked_entries,
+                false,
+                &plugin_commands,
             );
         });
     }
@@ -516,9 +954,9 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
 
     // Handle double-click navigation or file opening
     if let Some(path) = double_clicked_path {
-        if path.is_dir() {
+        if vfs::is_dir(&path) {
             app.navigate_to_dir(path);
-        } else if path.is_file() {
+        } else if vfs::exists(&path) {
             app.open_file(path);
         }
     }
@@ -542,24 +980,30 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
     // Handle context menu action captured from closures
     match context_menu_action {
         ContextMenuAction::Add => {
-            app.show_popup = Some(PopupType::AddEntry(String::new()));
+            if app.ensure_current_dir_writable() {
+                app.show_popup = Some(PopupType::AddEntry(String::new()));
+            }
         }
         ContextMenuAction::Paste => {
-            let current_tab = app.tab_manager.current_tab_mut();
-            if handle_clipboard_operations(
-                &mut app.clipboard,
-                &current_tab.current_path,
-                &mut current_tab.action_history,
-                &mut app.toasts,
-            ) {
-                // Clear marked entries after successful paste operation
-                app.tab_manager.current_tab_mut().marked_entries.clear();
-                app.refresh_entries();
-            }
+            // Marked entries are cleared once the paste job finishes
+            let current_path = app.tab_manager.current_tab_ref().current_path.clone();
+            handle_clipboard_operations(app, &current_path);
         }
         ContextMenuAction::Rename => {
             app.rename_selected_entry();
         }
+        ContextMenuAction::RenameInEditor => {
+            editor_rename::start(ui.ctx(), app);
+        }
+        ContextMenuAction::BulkRename => {
+            crate::ui::popup::bulk_rename::open(app);
+        }
+        ContextMenuAction::Extract => {
+            crate::ui::popup::extract::extract_selected_entry(app);
+        }
+        ContextMenuAction::Compress => {
+            crate::ui::popup::compress::open(app);
+        }
         ContextMenuAction::Delete => {
             app.delete_selected_entry();
         }
@@ -567,6 +1011,9 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
             // Same as Delete, but explicitly for marked entries
             app.delete_selected_entry();
         }
+        ContextMenuAction::PermanentDelete => {
+            app.permanently_delete_selected_entry();
+        }
         ContextMenuAction::Copy => {
             app.copy_selected_entries();
         }
@@ -581,13 +1028,28 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui, width: f32, height: f32) {
                 app.show_popup = Some(PopupType::OpenWith);
             }
         }
+        ContextMenuAction::PluginCommand(index) => {
+            command_palette::run_command(ui.ctx(), app, index);
+        }
         ContextMenuAction::None => {} // Do nothing
     }
 
     // Handle sort request captured from the header closure
     if let Some(column) = sort_requested {
+        if let SortColumn::Plugin(index) = column {
+            // Sort by the values fetched so far, the rest follow as they arrive
+            app.tab_manager.plugin_sort_keys = app.column_cache.sort_keys(
+                app.plugin_manager.columns(),
+                index,
+                &app.tab_manager.current_tab_ref().entries,
+            );
+        }
         // Borrow app mutably here - should be fine as UI closure is finished
         app.tab_manager.toggle_sort(column);
+        if matches!(app.tab_manager.sort_column, SortColumn::Plugin(_)) {
+            // Plugin columns are not kept in the sort preference
+            return;
+        }
         // Save sort preferences - requires immutable borrows followed by mutable config load/save
         app.config.sort_preference = Some(SortPreference {
             column: app.tab_manager.sort_column,
@@ -623,3 +1085,46 @@ fn handle_file_drop(ctx: &egui::Context, app: &mut Kiorg) {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::action_history::ActionType;
+    use crate::utils::rollback::RollbackManager;
+    use std::time::Duration;
+
+    #[test]
+    fn test_overwrite_through_temp_trashes_the_target() {
+        if !trash::is_supported() {
+            return;
+        }
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src/a.txt");
+        let dst = temp_dir.path().join("dst/a.txt");
+        std::fs::create_dir_all(src.parent().unwrap()).unwrap();
+        std::fs::create_dir_all(dst.parent().unwrap()).unwrap();
+        std::fs::write(&src, "new").unwrap();
+        std::fs::write(&dst, "old").unwrap();
+
+        let (job_src, job_dst) = (src.clone(), dst.clone());
+        let mut job = jobs::spawn_job("Copying", move |ctx| {
+            overwrite_through_temp(&job_src, &job_dst, false, ctx);
+            Ok(())
+        });
+        assert!(job.wait(Duration::from_secs(10)));
+        assert!(job.state.errors.is_empty(), "{:?}", job.state.errors);
+        assert_eq!(std::fs::read_to_string(&dst).unwrap(), "new");
+        // Nothing is left under the temporary name
+        assert_eq!(std::fs::read_dir(dst.parent().unwrap()).unwrap().count(), 1);
+
+        let actions = job.state.to_actions();
+        let Some(ActionType::Overwrite { operations }) = actions.first() else {
+            panic!("Expected an overwrite action, got {actions:?}");
+        };
+        assert!(matches!(operations[0].backup, OverwriteBackup::Trash(_)));
+
+        RollbackManager::new().rollback_action(&actions[0]).unwrap();
+        assert_eq!(std::fs::read_to_string(&dst).unwrap(), "old");
+        assert!(src.exists());
+    }
+}
@@ -0,0 +1,250 @@
+//! Bulk rename by editing entry names in `$EDITOR`, in the spirit of `vidir`
+//!
+//! The names of the marked entries (or of all listed entries when nothing is
+//! marked) are written to a temporary file, one numbered line per entry. Once
+//! the editor exits successfully the file is read back and the changed lines
+//! are applied as a single undoable rename action of the tab the rename was
+//! started in.
+
+use std::path::PathBuf;
+
+use crate::app::Kiorg;
+use crate::models::action_history::ActionType;
+use crate::utils::bulk_rename;
+
+/// How the end of the editing session is detected
+enum EditorProcess {
+    /// The editor runs in the built-in terminal, which closes when it exits.
+    /// The shell creates the marker file only when the editor succeeded.
+    #[cfg(not(target_os = "windows"))]
+    Terminal { success_marker: PathBuf },
+    /// The editor runs as its own process, the receiver gets its exit status
+    #[cfg(target_os = "windows")]
+    Process(std::sync::mpsc::Receiver<std::io::Result<std::process::ExitStatus>>),
+}
+
+/// A bulk rename waiting for the editor to exit
+pub struct EditorRename {
+    listing: tempfile::NamedTempFile,
+    /// Entries in the order of the listing lines
+    entries: Vec<PathBuf>,
+    /// Index of the tab the rename was started in and the directory it showed
+    tab_index: usize,
+    dir: PathBuf,
+    editor: EditorProcess,
+}
+
+/// Write the listing and open it in the editor
+pub fn start(ctx: &egui::Context, app: &mut Kiorg) {
+    if app.editor_rename.is_some() {
+        app.notify_info("A rename in the editor is already in progress");
+        return;
+    }
+    if !app.ensure_current_dir_writable() {
+        return;
+    }
+
+    let tab = app.tab_manager.current_tab_ref();
+    let (entries, unlisted): (Vec<PathBuf>, Vec<PathBuf>) = tab
+        .marked_or_listed_entries()
+        .into_iter()
+        .map(|entry| entry.meta.path.clone())
+        .partition(|path| bulk_rename::is_listable(path));
+    let working_dir = tab.current_path.clone();
+
+    if entries.is_empty() {
+        app.notify_info("Nothing to rename");
+        return;
+    }
+
+    let listing = match write_listing(&entries) {
+        Ok(listing) => listing,
+        Err(e) => {
+            app.notify_error(format!("Failed to write rename listing: {e}"));
+            return;
+        }
+    };
+
+    let tab_index = app.tab_manager.get_current_tab_index();
+    let editor = match launch_editor(ctx, app, working_dir.clone(), &listing) {
+        Ok(editor) => editor,
+        Err(e) => {
+            app.notify_error(e);
+            return;
+        }
+    };
+
+    if !unlisted.is_empty() {
+        app.toasts.info(format!(
+            "{} entries with unsupported names are left out",
+            unlisted.len()
+        ));
+    }
+    app.editor_rename = Some(EditorRename {
+        listing,
+        entries,
+        tab_index,
+        dir: working_dir,
+        editor,
+    });
+}
+
+fn write_listing(entries: &[PathBuf]) -> std::io::Result<tempfile::NamedTempFile> {
+    use std::io::Write;
+
+    let mut listing = tempfile::Builder::new()
+        .prefix("kiorg-rename-")
+        .suffix(".txt")
+        .tempfile()?;
+    listing.write_all(bulk_rename::format_listing(entries).as_bytes())?;
+    listing.flush()?;
+    Ok(listing)
+}
+
+/// Editor command configured by the user
+fn editor_command() -> Option<String> {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn launch_editor(
+    ctx: &egui::Context,
+    app: &mut Kiorg,
+    working_dir: PathBuf,
+    listing: &tempfile::NamedTempFile,
+) -> Result<EditorProcess, String> {
+    if app.terminal_ctx.is_some() {
+        return Err("Close the terminal before renaming in the editor".to_string());
+    }
+
+    // Let the shell split editor commands that come with arguments, and leave
+    // a marker when the editor exits successfully, e.g. not after `:cq`
+    let editor = editor_command().unwrap_or_else(|| "vi".to_string());
+    let mut success_marker = listing.path().as_os_str().to_owned();
+    success_marker.push(".ok");
+    let success_marker = PathBuf::from(success_marker);
+    let args = vec![
+        "-c".to_string(),
+        format!("{editor} \"$1\" && touch \"$2\""),
+        "kiorg-rename".to_string(),
+        listing.path().to_string_lossy().into_owned(),
+        success_marker.to_string_lossy().into_owned(),
+    ];
+    let terminal = crate::ui::terminal::TerminalContext::with_command(
+        ctx,
+        working_dir,
+        "/bin/sh".to_string(),
+        args,
+    )?;
+    app.terminal_ctx = Some(terminal);
+    Ok(EditorProcess::Terminal { success_marker })
+}
+
+#[cfg(target_os = "windows")]
+fn launch_editor(
+    ctx: &egui::Context,
+    _app: &mut Kiorg,
+    working_dir: PathBuf,
+    listing: &tempfile::NamedTempFile,
+) -> Result<EditorProcess, String> {
+    let editor = editor_command().unwrap_or_else(|| "notepad".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("notepad");
+    let mut command = std::process::Command::new(program);
+    command
+        .args(parts)
+        .arg(listing.path())
+        .current_dir(working_dir);
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let _ = sender.send(command.status());
+        ctx.request_repaint();
+    });
+    Ok(EditorProcess::Process(receiver))
+}
+
+/// Apply the edited listing once the editor has exited
+pub fn poll(app: &mut Kiorg) {
+    let Some(session) = &app.editor_rename else {
+        return;
+    };
+
+    let finished = match &session.editor {
+        #[cfg(not(target_os = "windows"))]
+        EditorProcess::Terminal { success_marker } => {
+            if app.terminal_ctx.is_some() {
+                return;
+            }
+            let succeeded = std::fs::remove_file(success_marker).is_ok();
+            if !succeeded {
+                app.editor_rename = None;
+                app.notify_error("Editor did not exit successfully, nothing renamed");
+                return;
+            }
+            true
+        }
+        #[cfg(target_os = "windows")]
+        EditorProcess::Process(receiver) => match receiver.try_recv() {
+            Ok(Ok(status)) if status.success() => true,
+            Ok(Ok(status)) => {
+                app.editor_rename = None;
+                app.notify_error(format!("Editor exited with {status}, nothing renamed"));
+                return;
+            }
+            Ok(Err(e)) => {
+                app.editor_rename = None;
+                app.notify_error(format!("Failed to run the editor: {e}"));
+                return;
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => false,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => true,
+        },
+    };
+
+    if finished && let Some(session) = app.editor_rename.take() {
+        // Tabs may have been closed or switched while the editor was open
+        let tab_index = app
+            .tab_manager
+            .find_tab_showing(session.tab_index, &session.dir);
+        match std::fs::read_to_string(session.listing.path()) {
+            Ok(edited) => apply_listing(app, tab_index, &session.entries, &edited),
+            Err(e) => app.notify_error(format!("Failed to read rename listing: {e}")),
+        }
+    }
+}
+
+/// Rename `entries` according to the edited listing and record it for undo in
+/// the tab at `tab_index`
+pub fn apply_listing(app: &mut Kiorg, tab_index: usize, entries: &[PathBuf], edited: &str) {
+    let renames = match bulk_rename::parse_listing(entries, edited).and_then(bulk_rename::plan) {
+        Ok(renames) => renames,
+        Err(e) => {
+            app.notify_error(format!("Nothing renamed: {e}"));
+            return;
+        }
+    };
+    if renames.is_empty() {
+        app.notify_info("No names were changed");
+        return;
+    }
+
+    if let Err(e) = bulk_rename::apply(&renames) {
+        app.notify_error(format!("Nothing renamed: {e}"));
+        return;
+    }
+
+    let count = renames.len();
+    if let Some(tab) = app.tab_manager.tab_mut(tab_index) {
+        tab.marked_entries.clear();
+        tab.action_history.add_action(ActionType::Rename {
+            operations: renames,
+        });
+    }
+    app.refresh_entries();
+    app.toasts.success(format!("Renamed {count} entries"));
+}
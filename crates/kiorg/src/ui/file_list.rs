@@ -3,7 +3,7 @@ use egui::{Align2, Ui};
 use crate::config::colors::AppColors;
 use crate::models::dir_entry::DirEntry;
 use crate::models::tab::{SortColumn, SortOrder};
-use crate::ui::style::{HEADER_FONT_SIZE, HEADER_ROW_HEIGHT};
+use crate::ui::style::{HEADER_FONT_SIZE, HEADER_ROW_HEIGHT, highlighted_text_job};
 
 const ICON_SIZE: f32 = 14.0;
 const ICON_WIDTH: f32 = 22.0;
@@ -14,10 +14,28 @@ const FILE_SIZE_WIDTH: f32 = 60.0;
 const SECONDARY_COLUMN_FONT_SIZE: f32 = 12.0;
 pub const ROW_HEIGHT: f32 = 20.0;
 
+/// A column contributed by a plugin, drawn between Name and Modified
+#[derive(Debug)]
+pub struct ExtraColumn<'a> {
+    /// Index into the plugin manager's columns
+    pub index: usize,
+    pub title: &'a str,
+    pub width: f32,
+}
+
+// Width taken by the plugin columns, including their padding
+fn extra_columns_width(columns: &[ExtraColumn]) -> f32 {
+    columns
+        .iter()
+        .map(|column| column.width + INTER_COLUMN_PADDING)
+        .sum()
+}
+
 pub struct TableHeaderParams<'a> {
     pub colors: &'a AppColors,
     pub sort_column: &'a SortColumn,
     pub sort_order: &'a SortOrder,
+    pub extra_columns: &'a [ExtraColumn<'a>],
     pub on_sort: &'a mut dyn FnMut(SortColumn),
 }
 
@@ -36,7 +54,8 @@ pub fn draw_table_header(ui: &mut Ui, params: &mut TableHeaderParams) -> egui::R
             + MODIFIED_DATE_WIDTH
             + INTER_COLUMN_PADDING // Padding between Modified and Size
             + FILE_SIZE_WIDTH
-            + HORIZONTAL_PADDING; // Padding at the end
+            + HORIZONTAL_PADDING // Padding at the end
+            + extra_columns_width(params.extra_columns);
 
     // Name width takes remaining space
     let name_width = (rect.width() - fixed_width_total).max(0.0);
@@ -50,6 +69,21 @@ pub fn draw_table_header(ui: &mut Ui, params: &mut TableHeaderParams) -> egui::R
     draw_header_column(ui, params, name_col_rect, "Name", SortColumn::Name);
     cursor.x += name_width + INTER_COLUMN_PADDING; // Advance cursor including padding
 
+    // --- Draw Plugin Columns ---
+    let extra_columns = params.extra_columns;
+    for column in extra_columns {
+        let col_rect =
+            egui::Rect::from_min_size(cursor, egui::vec2(column.width, HEADER_ROW_HEIGHT));
+        draw_header_column(
+            ui,
+            params,
+            col_rect,
+            column.title,
+            SortColumn::Plugin(column.index),
+        );
+        cursor.x += column.width + INTER_COLUMN_PADDING;
+    }
+
     // --- Draw Modified Column ---
     let mod_col_rect =
         egui::Rect::from_min_size(cursor, egui::vec2(MODIFIED_DATE_WIDTH, HEADER_ROW_HEIGHT));
@@ -124,6 +158,8 @@ fn draw_header_column(
 #[derive(Debug)]
 pub struct EntryRowParams<'a> {
     pub entry: &'a DirEntry,
+    /// Characters of the name matched by the search filter
+    pub match_indices: &'a [u32],
     pub is_selected: bool,
     pub colors: &'a AppColors,
     pub is_marked: bool,
@@ -133,6 +169,9 @@ pub struct EntryRowParams<'a> {
     pub is_in_copy_clipboard: bool,
     pub is_drag_active: bool,
     pub is_drag_source: bool,
+    pub extra_columns: &'a [ExtraColumn<'a>],
+    /// Plugin column values, in the order of `extra_columns`
+    pub extra_values: &'a [Option<&'a str>],
 }
 
 fn draw_icon(
@@ -189,6 +228,7 @@ fn draw_icon(
 pub fn draw_entry_row(ui: &mut Ui, params: EntryRowParams<'_>) -> egui::Response {
     let EntryRowParams {
         entry,
+        match_indices,
         is_selected,
         colors,
         is_marked,
@@ -198,6 +238,8 @@ pub fn draw_entry_row(ui: &mut Ui, params: EntryRowParams<'_>) -> egui::Response
         is_in_copy_clipboard,
         is_drag_active,
         is_drag_source,
+        extra_columns,
+        extra_values,
     } = params;
 
     let (rect, response) = ui.allocate_exact_size(
@@ -264,7 +306,8 @@ pub fn draw_entry_row(ui: &mut Ui, params: EntryRowParams<'_>) -> egui::Response
             + MODIFIED_DATE_WIDTH
             + INTER_COLUMN_PADDING // Padding between Modified and Size
             + FILE_SIZE_WIDTH
-            + HORIZONTAL_PADDING; // Padding at the end
+            + HORIZONTAL_PADDING // Padding at the end
+            + extra_columns_width(extra_columns);
 
     // Name width takes remaining space
     let name_width = (rect.width() - fixed_width_total).max(0.0);
@@ -294,19 +337,15 @@ pub fn draw_entry_row(ui: &mut Ui, params: EntryRowParams<'_>) -> egui::Response
         colors.fg
     };
 
-    let mut job = egui::text::LayoutJob {
-        text: name_text.clone(),
-        ..Default::default()
-    };
-
-    // Just add the whole text with normal color (no highlighting)
-    job.append(
+    // Highlight the characters matched by the search filter
+    let job = highlighted_text_job(
         &name_text,
-        0.0,
+        match_indices,
         egui::TextFormat {
             color: name_color,
             ..Default::default()
         },
+        colors.highlight,
     );
 
     let galley = ui.fonts_mut(|f| f.layout_job(job));
@@ -323,6 +362,21 @@ pub fn draw_entry_row(ui: &mut Ui, params: EntryRowParams<'_>) -> egui::Response
         colors.fg_light
     };
 
+    // --- Draw Plugin Columns ---
+    for (column, value) in extra_columns.iter().zip(extra_values) {
+        if let Some(value) = value {
+            let clip_rect = egui::Rect::from_min_size(cursor, egui::vec2(column.width, ROW_HEIGHT));
+            ui.painter().with_clip_rect(clip_rect).text(
+                cursor + egui::vec2(0.0, ROW_HEIGHT / 2.0),
+                Align2::LEFT_CENTER,
+                truncate_text(value, column.width),
+                egui::FontId::proportional(SECONDARY_COLUMN_FONT_SIZE),
+                secondary_font_color,
+            );
+        }
+        cursor.x += column.width + INTER_COLUMN_PADDING;
+    }
+
     // --- Draw Modified Column ---
     ui.painter().text(
         cursor + egui::vec2(0.0, ROW_HEIGHT / 2.0),
@@ -51,7 +51,11 @@ pub fn show_help_window(
                         (ShortcutAction::GoToLastEntry, "Jump to the last entry"),
                         (ShortcutAction::GoBackInHistory, "Go back in history"),
                         (ShortcutAction::GoForwardInHistory, "Go forward in history"),
-                        (ShortcutAction::ToggleHiddenFiles, "Toggle hidden files"),
+                        (
+                            ShortcutAction::ToggleHiddenFiles,
+                            "Cycle hidden and git ignored files",
+                        ),
+                        (ShortcutAction::ToggleFilters, "Toggle filter rules"),
                     ];
 
                     for (action, description) in navigation_actions {
@@ -85,6 +89,8 @@ pub fn show_help_window(
                             ShortcutAction::ShowActionHistory,
                             "Show action history popup",
                         ),
+                        (ShortcutAction::ShowTrash, "Show trash popup"),
+                        (ShortcutAction::ShowCommandPalette, "Run a plugin command"),
                     ];
 
                     for (action, description) in popup_actions {
@@ -141,12 +147,33 @@ pub fn show_help_window(
                         ),
                         (
                             ShortcutAction::DeleteEntry,
-                            "Delete selected file/directory",
+                            if crate::utils::trash::is_supported() {
+                                "Move selected file/directory to trash"
+                            } else {
+                                "Delete selected file/directory"
+                            },
+                        ),
+                        (
+                            ShortcutAction::PermanentlyDeleteEntry,
+                            "Permanently delete selected file/directory",
                         ),
                         (
                             ShortcutAction::RenameEntry,
                             "Rename selected file/directory",
                         ),
+                        (
+                            ShortcutAction::RenameInEditor,
+                            "Rename marked entries in $EDITOR",
+                        ),
+                        (
+                            ShortcutAction::BulkRename,
+                            "Rename marked entries by pattern",
+                        ),
+                        (ShortcutAction::ExtractArchive, "Extract selected archive"),
+                        (
+                            ShortcutAction::CompressEntries,
+                            "Compress marked entries into an archive",
+                        ),
                         (ShortcutAction::AddEntry, "Add file/directory"),
                         (ShortcutAction::SelectEntry, "Mark/unmark entry"),
                         (
@@ -178,8 +205,17 @@ pub fn show_help_window(
                 ui.heading(RichText::new("Search").color(colors.fg_light));
                 let table = egui::Grid::new("search_help_grid");
                 table.show(ui, |ui| {
-                    let search_actions =
-                        [(ShortcutAction::ActivateSearch, "Activate search filter")];
+                    let search_actions = [
+                        (ShortcutAction::ActivateSearch, "Activate search filter"),
+                        (
+                            ShortcutAction::ShowContentSearch,
+                            "Search file contents recursively",
+                        ),
+                        (
+                            ShortcutAction::ShowFind,
+                            "Find files and directories recursively",
+                        ),
+                    ];
                     for (action, description) in search_actions {
                         render_shortcut_display(ui, action, shortcuts, colors);
                         ui.label(description);
@@ -1,4 +1,5 @@
 pub mod center_panel;
+pub mod editor_rename;
 pub mod egui_notify;
 pub mod file_list;
 pub mod help_window;
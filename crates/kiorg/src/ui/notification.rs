@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::mpsc;
 
 use crate::app::Kiorg;
@@ -13,6 +14,8 @@ pub enum NotificationMessage {
     UpdateAvailable(Release), // Version string
     UpdateSuccess,            // Version string
     UpdateFailed(String),     // Error message
+    Refresh,                  // Reload the current directory
+    Navigate(PathBuf),        // Go to a directory, or reveal a file
 }
 
 /// Async notification system for handling background operation messages
@@ -79,6 +82,16 @@ pub fn check_notifications(app: &mut Kiorg) {
             NotificationMessage::Info(info) => {
                 notify_info(&mut app.toasts, &info);
             }
+            NotificationMessage::Refresh => {
+                app.refresh_entries();
+            }
+            NotificationMessage::Navigate(path) => {
+                if crate::vfs::is_dir(&path) {
+                    app.navigate_to_dir(path);
+                } else {
+                    app.reveal_path(&path);
+                }
+            }
         }
     }
 }
@@ -110,6 +110,20 @@ pub fn draw_path_navigation(
 
 #[must_use]
 pub fn get_path_components(path: &Path) -> Vec<(String, PathBuf)> {
+    // Remote paths start with the host, e.g. sftp://user@host / srv / builds
+    if let Some(root) = crate::vfs::url_root(path) {
+        let relative = path.strip_prefix(&root).unwrap_or(Path::new(""));
+        let mut current = root.clone();
+        let mut components = vec![(root.to_string_lossy().to_string(), root)];
+        for component in relative.components() {
+            if let std::path::Component::Normal(os_str) = component {
+                current.push(os_str);
+                components.push((os_str.to_string_lossy().to_string(), current.clone()));
+            }
+        }
+        return components;
+    }
+
     let mut components = Vec::new();
     let mut current = PathBuf::new();
 
@@ -269,6 +283,14 @@ mod tests {
         assert_eq!(components[1].0, "home");
         assert_eq!(components[2].0, "user");
         assert_eq!(components[3].0, "文档");
+
+        // Test path of a plugin filesystem
+        let path = PathBuf::from("s3://bucket/photos");
+        let components = get_path_components(&path);
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].0, "s3://bucket");
+        assert_eq!(components[1].0, "photos");
+        assert_eq!(components[1].1, path);
     }
 
     #[test]
@@ -311,6 +333,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sftp_path_components() {
+        let path = PathBuf::from("sftp://ci@build-01/srv/builds");
+        let components = get_path_components(&path);
+        assert_eq!(components.len(), 3);
+        assert_eq!(components[0].0, "sftp://ci@build-01");
+        assert_eq!(components[1].0, "srv");
+        assert_eq!(components[2].0, "builds");
+        assert_eq!(components[0].1, PathBuf::from("sftp://ci@build-01"));
+        assert_eq!(components[2].1, path);
+    }
+
     #[test]
     fn test_relative_paths() {
         // Test relative paths
@@ -1,13 +1,22 @@
 use crate::app::Kiorg;
 use crate::models::action_history::{ActionType, HistoryAction};
 use crate::ui::popup::PopupType;
-use crate::utils::file_operations;
-use crate::utils::rollback::RollbackManager;
+use crate::utils::bulk_rename;
+use crate::utils::jobs::JobContext;
+use crate::utils::rollback::{self, RollbackManager};
+use crate::utils::stash;
+use crate::utils::trash;
+use crate::vfs;
+use chrono::Local;
 use egui::{Context, Frame, RichText, ScrollArea};
+use std::io::Write;
 
 use super::window_utils::new_center_popup_window;
 
 /// Draw the action history popup with rollback options
+///
+/// Actions of all tabs, including closed ones, are listed, undo and redo apply
+/// to the current tab. Actions of closed tabs can no longer be undone.
 pub fn draw(ctx: &Context, app: &mut Kiorg) {
     if !matches!(app.show_popup, Some(PopupType::ActionHistory)) {
         return;
@@ -17,9 +26,42 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
     let mut should_redo_last = false;
 
     // Extract the data we need before entering the UI closure
-    let history = &app.tab_manager.current_tab_ref().action_history;
-    let active_actions = history.get_active_actions();
-    let rolled_back_actions = history.get_rolled_back_actions();
+    let current_tab = app.tab_manager.get_current_tab_index();
+    let open_histories = app
+        .tab_manager
+        .action_histories()
+        .enumerate()
+        .map(|(tab, history)| (Some(tab), history));
+    let closed_histories = app
+        .tab_manager
+        .closed_action_histories()
+        .iter()
+        .map(|history| (None, history));
+    let mut actions: Vec<TimelineEntry> = open_histories
+        .chain(closed_histories)
+        .flat_map(|(tab, history)| {
+            let active = history
+                .get_active_actions()
+                .iter()
+                .map(move |action| TimelineEntry {
+                    tab,
+                    action,
+                    is_active: true,
+                });
+            let rolled_back =
+                history
+                    .get_rolled_back_actions()
+                    .iter()
+                    .map(move |action| TimelineEntry {
+                        tab,
+                        action,
+                        is_active: false,
+                    });
+            active.chain(rolled_back)
+        })
+        .collect();
+    // Newest first
+    actions.sort_by(|a, b| b.action.timestamp.cmp(&a.action.timestamp));
 
     // Create a temporary boolean for the window's open state
     let mut window_open = true;
@@ -33,7 +75,7 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
                 .inner_margin(10.0)
                 .show(ui, |ui| {
                     ui.vertical(|ui| {
-                        if rolled_back_actions.is_empty() && active_actions.is_empty() {
+                        if actions.is_empty() {
                             ui.centered_and_justified(|ui| {
                                 ui.label("No file operations recorded yet");
                             });
@@ -43,8 +85,8 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
                         render_action_history_content(
                             ui,
                             app,
-                            active_actions,
-                            rolled_back_actions,
+                            &actions,
+                            current_tab,
                             &mut should_undo_last,
                             &mut should_redo_last,
                         )
@@ -63,7 +105,7 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
 
     // Handle actions after the UI closure
     if should_undo_last {
-        undo_last_action(app);
+        undo_last(app, true);
     }
 
     if should_redo_last {
@@ -71,43 +113,71 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
     }
 }
 
+/// An action in the cross tab timeline
+struct TimelineEntry<'a> {
+    /// Index of the open tab, `None` for actions of closed tabs
+    tab: Option<usize>,
+    action: &'a HistoryAction,
+    is_active: bool,
+}
+
 /// Render the main content of the action history popup
 fn render_action_history_content(
     ui: &mut egui::Ui,
     app: &Kiorg,
-    active_actions: &[HistoryAction],
-    rolled_back_actions: &[HistoryAction],
+    actions: &[TimelineEntry],
+    current_tab: usize,
     should_undo_last: &mut bool,
     should_redo_last: &mut bool,
 ) {
+    let show_tabs = app.tab_manager.get_tab_count() > 1
+        || actions.iter().any(|entry| entry.tab != Some(current_tab));
+    let today = Local::now().date_naive();
+
     // Scrollable list of actions in timeline format
     ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
-        // Helper function to render an action with its status
-        let mut render_action = |action: &HistoryAction, is_active: bool| {
+        for entry in actions {
+            let action = entry.action;
+            // Actions of other tabs are shown for reference only
+            let is_current = entry.tab == Some(current_tab);
+            let dimmed = !entry.is_active || !is_current;
+
             ui.horizontal(|ui| {
                 // Timestamp
-                let timestamp_color = if is_active {
-                    app.colors.fg_light
-                } else {
+                let timestamp_color = if dimmed {
                     app.colors.fg_light.gamma_multiply(0.6) // More dimmed
+                } else {
+                    app.colors.fg_light
                 };
 
+                // Journaled actions survive restarts, older ones need the date
+                let timestamp_format = if action.timestamp.date_naive() == today {
+                    "%H:%M:%S"
+                } else {
+                    "%Y-%m-%d %H:%M:%S"
+                };
                 ui.label(
-                    RichText::new(action.timestamp.format("%H:%M:%S").to_string())
+                    RichText::new(action.timestamp.format(timestamp_format).to_string())
                         .size(10.0)
                         .color(timestamp_color)
                         .family(egui::FontFamily::Monospace), // Monospace for better alignment
                 );
 
+                if show_tabs {
+                    let tab_label = match entry.tab {
+                        Some(tab) => format!("Tab {}", tab + 1),
+                        None => "Closed tab (read-only)".to_string(),
+                    };
+                    ui.label(RichText::new(tab_label).size(10.0).color(timestamp_color))
+                        .on_hover_text("Undo and redo only apply to the current tab");
+                }
+
                 ui.add_space(8.0);
 
                 // Action description with appropriate styling
                 let description = action.get_description();
 
-                if is_active {
-                    // Normal styling for active actions
-                    ui.label(RichText::new(&description));
-                } else {
+                if !entry.is_active {
                     // Struck through and dimmed for rolled back actions with prefix
                     ui.label(
                         RichText::new(format!("{} (rolled back)", description))
@@ -115,35 +185,44 @@ fn render_action_history_content(
                             .color(app.colors.fg_light.gamma_multiply(0.6))
                             .italics(),
                     );
+                } else if is_current {
+                    // Normal styling for active actions
+                    ui.label(RichText::new(&description));
+                } else {
+                    ui.label(RichText::new(&description).color(timestamp_color));
                 }
             });
 
             ui.separator();
-        };
-
-        // Show rolled back actions first (in reverse order - newest first)
-        for action in rolled_back_actions.iter().rev() {
-            render_action(action, false);
-        }
-
-        // Show active actions (in reverse order - newest first)
-        for action in active_actions.iter().rev() {
-            render_action(action, true);
         }
     });
 
     ui.separator();
 
+    let history = &app.tab_manager.current_tab_ref().action_history;
+    let last_action = history.get_last_rollbackable_action();
+    let stale_paths = last_action
+        .map(HistoryAction::stale_paths)
+        .unwrap_or_default();
+    if !stale_paths.is_empty() {
+        let changed = stale_paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        ui.label(
+            RichText::new(format!(
+                "Changed since the last action, undoing it may lose data: {changed}"
+            ))
+            .color(app.colors.error),
+        );
+    }
+
     // Footer with undo, redo and close buttons
     ui.horizontal(|ui| {
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
             // Redo Last Action button
-            let has_redoable_action = app
-                .tab_manager
-                .current_tab_ref()
-                .action_history
-                .get_last_redoable_action()
-                .is_some();
+            let has_redoable_action = history.get_last_redoable_action().is_some();
             ui.add_enabled_ui(has_redoable_action, |ui| {
                 if ui.button("Redo Last Action").clicked() {
                     *should_redo_last = true;
@@ -151,14 +230,13 @@ fn render_action_history_content(
             });
 
             // Undo Last Action button
-            let has_undoable_action = app
-                .tab_manager
-                .current_tab_ref()
-                .action_history
-                .get_last_rollbackable_action()
-                .is_some();
-            ui.add_enabled_ui(has_undoable_action, |ui| {
-                if ui.button("Undo Last Action").clicked() {
+            let undo_label = if stale_paths.is_empty() {
+                "Undo Last Action"
+            } else {
+                "Undo Last Action Anyway"
+            };
+            ui.add_enabled_ui(last_action.is_some(), |ui| {
+                if ui.button(undo_label).clicked() {
                     *should_undo_last = true;
                 }
             });
@@ -167,8 +245,28 @@ fn render_action_history_content(
 }
 
 /// Undo the most recent rollbackable action
+///
+/// Actions whose files changed since they were performed are refused, they can
+/// still be undone from the action history popup.
 pub fn undo_last_action(app: &mut Kiorg) {
+    undo_last(app, false);
+}
+
+fn undo_last(app: &mut Kiorg, force: bool) {
     let tab = app.tab_manager.current_tab_mut();
+    if !force && let Some(action) = tab.action_history.get_last_rollbackable_action() {
+        let stale_paths = action.stale_paths();
+        if let Some(path) = stale_paths.first() {
+            let message = format!(
+                "Not undoing \"{}\": '{}' changed since. Use the action history popup to undo anyway",
+                action.get_description(),
+                path.display()
+            );
+            app.toasts.error(message);
+            return;
+        }
+    }
+
     if let Some(action) = tab.action_history.undo_last_action() {
         // Perform the rollback
         let rollback_manager = RollbackManager::new();
@@ -205,12 +303,14 @@ pub fn redo_last_action(app: &mut Kiorg) {
         ActionType::Create { operations } => {
             for op in operations {
                 let result = if op.is_dir {
-                    std::fs::create_dir_all(&op.path)
+                    vfs::create_dir_all(&op.path)
                 } else {
                     if let Some(parent) = op.path.parent() {
-                        std::fs::create_dir_all(parent).ok();
+                        vfs::create_dir_all(parent).ok();
                     }
-                    std::fs::File::create(&op.path).map(|_| ())
+                    vfs::for_path(&op.path)
+                        .write(&op.path)
+                        .and_then(|mut file| file.flush())
                 };
 
                 match result {
@@ -228,13 +328,24 @@ pub fn redo_last_action(app: &mut Kiorg) {
                 }
             }
         }
+        ActionType::Rename { operations } if operations.len() > 1 => {
+            match bulk_rename::apply(operations) {
+                Ok(()) => {
+                    app.toasts
+                        .success(format!("Redone: Renamed {} entries", operations.len()));
+                }
+                Err(e) => {
+                    app.toasts.error(format!("Failed to redo rename: {e}"));
+                }
+            }
+        }
         ActionType::Rename { operations } => {
             for op in operations {
                 if let Some(parent) = op.new_path.parent() {
-                    std::fs::create_dir_all(parent).ok();
+                    vfs::create_dir_all(parent).ok();
                 }
 
-                match std::fs::rename(&op.old_path, &op.new_path) {
+                match vfs::for_path(&op.old_path).rename(&op.old_path, &op.new_path) {
                     Ok(_) => {
                         app.toasts.success(format!(
                             "Redone: Renamed '{}' to '{}'",
@@ -255,14 +366,17 @@ pub fn redo_last_action(app: &mut Kiorg) {
         }
         ActionType::Copy { operations } => {
             for op in operations {
-                let result = if op.source_path.is_dir() {
-                    file_operations::copy_dir_recursively(&op.source_path, &op.target_path)
-                } else {
-                    if let Some(parent) = op.target_path.parent() {
-                        std::fs::create_dir_all(parent).ok();
-                    }
-                    std::fs::copy(&op.source_path, &op.target_path).map(|_| ())
-                };
+                if let Some(parent) = op.target_path.parent() {
+                    vfs::create_dir_all(parent).ok();
+                }
+                let result = vfs::copy(
+                    &op.source_path,
+                    &op.target_path,
+                    &mut JobContext::detached(),
+                );
+                if result.is_err() {
+                    vfs::remove_partial(&op.target_path);
+                }
 
                 match result {
                     Ok(_) => {
@@ -286,10 +400,14 @@ pub fn redo_last_action(app: &mut Kiorg) {
         ActionType::Move { operations } => {
             for op in operations {
                 if let Some(parent) = op.target_path.parent() {
-                    std::fs::create_dir_all(parent).ok();
+                    vfs::create_dir_all(parent).ok();
                 }
 
-                match std::fs::rename(&op.source_path, &op.target_path) {
+                match vfs::move_entry(
+                    &op.source_path,
+                    &op.target_path,
+                    &mut JobContext::detached(),
+                ) {
                     Ok(_) => {
                         app.toasts.success(format!(
                             "Redone: Moved '{}' to '{}'",
@@ -308,5 +426,114 @@ pub fn redo_last_action(app: &mut Kiorg) {
                 }
             }
         }
+        ActionType::Overwrite { operations } => {
+            for op in operations {
+                // Put the replaced entry back where it was put aside before pasting again
+                let result = rollback::put_aside_overwritten(&op.backup).and_then(|()| {
+                    let ctx = &mut JobContext::detached();
+                    let pasted = if op.is_move {
+                        vfs::move_entry(&op.source_path, &op.target_path, ctx)
+                    } else {
+                        vfs::copy(&op.source_path, &op.target_path, ctx)
+                    };
+                    pasted.map_err(|e| e.to_string())
+                });
+
+                match result {
+                    Ok(_) => {
+                        app.toasts.success(format!(
+                            "Redone: Overwrote '{}' with '{}'",
+                            op.target_path.display(),
+                            op.source_path.display()
+                        ));
+                    }
+                    Err(e) => {
+                        app.toasts.error(format!(
+                            "Failed to redo overwrite of '{}': {}",
+                            op.target_path.display(),
+                            e
+                        ));
+                    }
+                }
+            }
+        }
+        ActionType::Extract { operations } => {
+            for op in operations {
+                match stash::restore(&op.backup) {
+                    Ok(_) => {
+                        app.toasts
+                            .success(format!("Redone: Extracted '{}'", op.target_path.display()));
+                    }
+                    Err(e) => {
+                        app.toasts.error(format!(
+                            "Failed to redo extraction of '{}': {}",
+                            op.target_path.display(),
+                            e
+                        ));
+                    }
+                }
+            }
+        }
+        ActionType::Compress { operations } => {
+            for op in operations {
+                match stash::restore(&op.backup) {
+                    Ok(_) => {
+                        app.toasts.success(format!(
+                            "Redone: Compressed '{}'",
+                            op.archive_path.display()
+                        ));
+                    }
+                    Err(e) => {
+                        app.toasts.error(format!(
+                            "Failed to redo compression of '{}': {}",
+                            op.archive_path.display(),
+                            e
+                        ));
+                    }
+                }
+            }
+        }
+        ActionType::Trash { operations } => {
+            for op in operations {
+                match trash::retrash(op) {
+                    Ok(_) => {
+                        app.toasts.success(format!(
+                            "Redone: Moved '{}' to trash",
+                            op.original_path.display()
+                        ));
+                    }
+                    Err(e) => {
+                        app.toasts.error(format!(
+                            "Failed to redo trash of '{}': {}",
+                            op.original_path.display(),
+                            e
+                        ));
+                    }
+                }
+            }
+        }
+        ActionType::Delete { operations } => {
+            for op in operations {
+                match stash::restash(op) {
+                    Ok(_) => {
+                        app.toasts
+                            .success(format!("Redone: Deleted '{}'", op.original_path.display()));
+                    }
+                    Err(e) => {
+                        app.toasts.error(format!(
+                            "Failed to redo delete of '{}': {}",
+                            op.original_path.display(),
+                            e
+                        ));
+                    }
+                }
+            }
+        }
     }
+
+    // The redone action produced new files, later changes are measured against them
+    app.tab_manager
+        .current_tab_mut()
+        .action_history
+        .refresh_last_action();
 }
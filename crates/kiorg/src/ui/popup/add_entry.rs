@@ -1,8 +1,8 @@
 use crate::app::Kiorg;
 use crate::models::action_history::{ActionType, CreateOperation};
 use crate::ui::popup::PopupType;
+use crate::vfs;
 use egui::{Context, Frame, Key, TextEdit};
-use std::fs;
 
 use super::window_utils::new_center_popup_window;
 
@@ -67,7 +67,7 @@ pub(crate) fn handle_key_press(ctx: &Context, app: &mut Kiorg) -> bool {
             let new_path = current_path.join(&entry_name);
 
             // Check if a file or directory with the same name already exists
-            if new_path.exists() {
+            if vfs::exists(&new_path) {
                 // Show error message and keep the popup open
                 app.notify_error(format!(
                     "Cannot create '{}': Entry with the same name already exists",
@@ -82,14 +82,15 @@ pub(crate) fn handle_key_press(ctx: &Context, app: &mut Kiorg) -> bool {
                 // Ensure parent directories exist before creating the final one
                 let parent = new_path.parent().unwrap_or(&current_path);
                 (
-                    fs::create_dir_all(parent).and_then(|_| fs::create_dir(&new_path)),
+                    vfs::create_dir_all(parent)
+                        .and_then(|_| vfs::for_path(&new_path).create_dir(&new_path)),
                     true,
                 )
             } else {
                 // Create file
                 // Ensure parent directories exist before creating the file
                 if let Some(parent) = new_path.parent()
-                    && let Err(e) = fs::create_dir_all(parent)
+                    && let Err(e) = vfs::create_dir_all(parent)
                 {
                     app.notify_error(format!(
                         "Failed to create parent directories for '{}': {}",
@@ -99,7 +100,10 @@ pub(crate) fn handle_key_press(ctx: &Context, app: &mut Kiorg) -> bool {
                     // Decide how to handle this error, maybe return early?
                     // For now, we'll proceed and let File::create handle the final error.
                 }
-                (fs::File::create(&new_path).map(|_| ()), false) // Discard the File handle
+                (
+                    vfs::for_path(&new_path).write(&new_path).map(|_| ()), // Discard the writer
+                    false,
+                )
             };
 
             match result {
@@ -0,0 +1,691 @@
+//! Pattern based bulk rename with a live before/after preview
+//!
+//! New names are built in three steps: the regex find/replace (capture groups
+//! such as `$1` are available, an empty find matches the whole name), then
+//! the `{...}` tokens, then the case conversion.
+
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use egui::{Context, Key, Modifiers, RichText};
+use regex::Regex;
+
+use super::PopupType;
+use super::window_utils::new_center_popup_window;
+use crate::app::Kiorg;
+use crate::models::action_history::{ActionType, RenameOperation};
+use crate::utils::bulk_rename;
+use crate::vfs;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Case conversion applied to the new names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseConversion {
+    #[default]
+    Keep,
+    Lower,
+    Upper,
+    Title,
+}
+
+impl CaseConversion {
+    const ALL: [Self; 4] = [Self::Keep, Self::Lower, Self::Upper, Self::Title];
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Keep => "Keep",
+            Self::Lower => "lower",
+            Self::Upper => "UPPER",
+            Self::Title => "Title",
+        }
+    }
+
+    fn apply(self, name: &str) -> String {
+        match self {
+            Self::Keep => name.to_string(),
+            Self::Lower => name.to_lowercase(),
+            Self::Upper => name.to_uppercase(),
+            Self::Title => {
+                let mut result = String::with_capacity(name.len());
+                let mut word_start = true;
+                for c in name.chars() {
+                    if word_start {
+                        result.extend(c.to_uppercase());
+                    } else {
+                        result.extend(c.to_lowercase());
+                    }
+                    // Dots are left out so extensions stay lower case
+                    word_start = c.is_whitespace() || c == '_' || c == '-';
+                }
+                result
+            }
+        }
+    }
+}
+
+/// How new names are built from the old ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenamePattern {
+    /// Regex matched against the old name
+    pub find: String,
+    /// Replacement for every match, may use capture groups and `{...}` tokens
+    pub replace: String,
+    pub case: CaseConversion,
+    /// First value of the `{n}` counter
+    pub start: u64,
+    pub step: u64,
+    /// Minimum number of digits of the counter, padded with zeros
+    pub padding: usize,
+}
+
+impl Default for RenamePattern {
+    fn default() -> Self {
+        Self {
+            find: String::new(),
+            replace: String::new(),
+            case: CaseConversion::Keep,
+            start: 1,
+            step: 1,
+            padding: 0,
+        }
+    }
+}
+
+/// Entry to rename along with the metadata tokens may refer to
+/// The path must be [listable](bulk_rename::is_listable), so its name is valid UTF-8
+#[derive(Debug, Clone)]
+pub struct RenameSource {
+    pub path: PathBuf,
+    pub modified: SystemTime,
+    /// Read on first use of an `{exif}` token, most patterns never need it
+    exif_date: OnceCell<Option<NaiveDateTime>>,
+}
+
+impl RenameSource {
+    pub fn new(path: PathBuf, modified: SystemTime) -> Self {
+        Self {
+            path,
+            modified,
+            exif_date: OnceCell::new(),
+        }
+    }
+
+    fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    fn exif_date(&self) -> Option<NaiveDateTime> {
+        *self.exif_date.get_or_init(|| read_exif_date(&self.path))
+    }
+}
+
+/// Date the picture was taken according to its EXIF data
+fn read_exif_date(path: &Path) -> Option<NaiveDateTime> {
+    let file = std::fs::File::open(path).ok()?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut std::io::BufReader::new(file))
+        .ok()?;
+    let field = exif
+        .get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)
+        .or_else(|| exif.get_field(exif::Tag::DateTime, exif::In::PRIMARY))?;
+    let exif::Value::Ascii(ref values) = field.value else {
+        return None;
+    };
+    let date = exif::DateTime::from_ascii(values.first()?).ok()?;
+    NaiveDate::from_ymd_opt(
+        i32::from(date.year),
+        u32::from(date.month),
+        u32::from(date.day),
+    )?
+    .and_hms_opt(
+        u32::from(date.hour),
+        u32::from(date.minute),
+        u32::from(date.second),
+    )
+}
+
+/// Outcome of the pattern for a single entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenamePreview {
+    pub old_path: PathBuf,
+    pub new_name: String,
+    /// Why the entry cannot be renamed, blocks applying the whole pattern
+    pub problem: Option<String>,
+}
+
+impl RenamePreview {
+    fn new_path(&self) -> PathBuf {
+        self.old_path.with_file_name(&self.new_name)
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.problem.is_none() && self.new_path() != self.old_path
+    }
+}
+
+/// Build the new name of every source and flag the ones that cannot be applied
+///
+/// # Errors
+///
+/// Returns an error string if the find pattern is not a valid regex.
+pub fn preview(
+    sources: &[RenameSource],
+    pattern: &RenamePattern,
+) -> Result<Vec<RenamePreview>, String> {
+    // An empty find matches the whole name, so the replacement acts as a template
+    let (find, replace) = match (pattern.find.is_empty(), pattern.replace.is_empty()) {
+        (true, true) => ("^(?s:.*)$", "$0"),
+        (true, false) => ("^(?s:.*)$", pattern.replace.as_str()),
+        (false, _) => (pattern.find.as_str(), pattern.replace.as_str()),
+    };
+    let regex = Regex::new(find).map_err(|e| format!("Invalid pattern: {e}"))?;
+
+    let mut previews: Vec<RenamePreview> = sources
+        .iter()
+        .enumerate()
+        .map(|(index, source)| {
+            let old_name = source.name();
+            let replaced = regex.replace_all(&old_name, replace);
+            let (new_name, problem) = match expand_tokens(&replaced, index, source, pattern) {
+                Ok(expanded) => {
+                    let new_name = pattern.case.apply(&expanded);
+                    let problem = bulk_rename::validate_name(&new_name).err();
+                    (new_name, problem)
+                }
+                Err(e) => (replaced.into_owned(), Some(e)),
+            };
+            RenamePreview {
+                old_path: source.path.clone(),
+                new_name,
+                problem,
+            }
+        })
+        .collect();
+
+    flag_collisions(&mut previews);
+    Ok(previews)
+}
+
+/// Flag entries that would end up on the same name or on an entry that stays
+fn flag_collisions(previews: &mut [RenamePreview]) {
+    let sources: HashSet<PathBuf> = previews
+        .iter()
+        .filter(|preview| preview.is_changed())
+        .map(|preview| preview.old_path.clone())
+        .collect();
+    let mut target_counts: HashMap<PathBuf, usize> = HashMap::new();
+    for preview in previews.iter().filter(|preview| preview.problem.is_none()) {
+        *target_counts.entry(preview.new_path()).or_default() += 1;
+    }
+
+    for preview in previews.iter_mut() {
+        if !preview.is_changed() {
+            continue;
+        }
+        let new_path = preview.new_path();
+        if target_counts.get(&new_path).copied().unwrap_or_default() > 1 {
+            preview.problem = Some("Same name as another entry".to_string());
+        } else if vfs::exists(&new_path)
+            && !sources.contains(&new_path)
+            && !crate::utils::file_operations::is_same_entry(&preview.old_path, &new_path)
+        {
+            preview.problem = Some("Name already exists".to_string());
+        }
+    }
+}
+
+/// Replace the `{n}`, `{date}` and `{exif}` tokens, the date tokens accept a
+/// strftime format as in `{date:%Y%m%d}`. Unknown tokens are kept as they are.
+fn expand_tokens(
+    template: &str,
+    index: usize,
+    source: &RenameSource,
+    pattern: &RenamePattern,
+) -> Result<String, String> {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        result.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}') else {
+            rest = &rest[open..];
+            break;
+        };
+        let token = &rest[open + 1..open + close];
+        let (name, format) = match token.split_once(':') {
+            Some((name, format)) => (name, Some(format)),
+            None => (token, None),
+        };
+
+        match name {
+            "n" => {
+                let number = pattern
+                    .start
+                    .saturating_add((index as u64).saturating_mul(pattern.step));
+                let _ = write!(result, "{number:0width$}", width = pattern.padding);
+            }
+            "date" => {
+                let date: DateTime<Local> = source.modified.into();
+                format_date(&mut result, &date.naive_local(), format)?;
+            }
+            "exif" => {
+                let date = source
+                    .exif_date()
+                    .ok_or_else(|| "No EXIF date".to_string())?;
+                format_date(&mut result, &date, format)?;
+            }
+            _ => result.push_str(&rest[open..=open + close]),
+        }
+        rest = &rest[open + close + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn format_date(out: &mut String, date: &NaiveDateTime, format: Option<&str>) -> Result<(), String> {
+    let format = format.unwrap_or(DEFAULT_DATE_FORMAT);
+    write!(out, "{}", date.format(format)).map_err(|_| format!("Invalid date format '{format}'"))
+}
+
+/// State of the bulk rename popup
+#[derive(Debug, Clone)]
+pub struct BulkRenameState {
+    pub sources: Vec<RenameSource>,
+    pub pattern: RenamePattern,
+    /// Preview of the pattern it was built for, the popup is drawn every frame
+    cached_preview: Option<(RenamePattern, Result<Vec<RenamePreview>, String>)>,
+}
+
+impl BulkRenameState {
+    #[must_use]
+    pub fn new(sources: Vec<RenameSource>) -> Self {
+        Self {
+            sources,
+            pattern: RenamePattern::default(),
+            cached_preview: None,
+        }
+    }
+
+    /// Preview of the current pattern, only built again once the pattern changed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error string if the find pattern is not a valid regex.
+    pub fn preview(&mut self) -> Result<Vec<RenamePreview>, String> {
+        let pattern = &self.pattern;
+        match &self.cached_preview {
+            Some((cached, previews)) if cached == pattern => previews.clone(),
+            _ => {
+                let previews = preview(&self.sources, pattern);
+                self.cached_preview = Some((pattern.clone(), previews.clone()));
+                previews
+            }
+        }
+    }
+}
+
+/// Open the popup for the marked entries, or all listed entries when nothing is marked
+pub fn open(app: &mut Kiorg) {
+    if !app.ensure_current_dir_writable() {
+        return;
+    }
+    // Names that are not valid UTF-8 would be written back mangled
+    let (sources, unlisted): (Vec<RenameSource>, Vec<RenameSource>) = app
+        .tab_manager
+        .current_tab_ref()
+        .marked_or_listed_entries()
+        .into_iter()
+        .map(|entry| RenameSource::new(entry.meta.path.clone(), entry.meta.modified))
+        .partition(|source| bulk_rename::is_listable(&source.path));
+
+    if sources.is_empty() {
+        app.notify_info("Nothing to rename");
+        return;
+    }
+    if !unlisted.is_empty() {
+        app.toasts.info(format!(
+            "{} entries with unsupported names are left out",
+            unlisted.len()
+        ));
+    }
+    app.show_popup = Some(PopupType::BulkRename(BulkRenameState::new(sources)));
+}
+
+/// Rename every changed entry as a single undoable action
+/// Returns false if nothing was renamed
+pub fn apply(app: &mut Kiorg, previews: &[RenamePreview]) -> bool {
+    let renames: Vec<RenameOperation> = previews
+        .iter()
+        .filter(|preview| preview.is_changed())
+        .map(|preview| RenameOperation {
+            old_path: preview.old_path.clone(),
+            new_path: preview.new_path(),
+        })
+        .collect();
+
+    let result = bulk_rename::plan(renames)
+        .and_then(|renames| bulk_rename::apply(&renames).map(|()| renames));
+    match result {
+        Ok(renames) if renames.is_empty() => {
+            app.notify_info("No names were changed");
+            false
+        }
+        Ok(renames) => {
+            let count = renames.len();
+            let tab = app.tab_manager.current_tab_mut();
+            tab.marked_entries.clear();
+            tab.action_history.add_action(ActionType::Rename {
+                operations: renames,
+            });
+            app.refresh_entries();
+            app.toasts.success(format!("Renamed {count} entries"));
+            true
+        }
+        Err(e) => {
+            app.notify_error(format!("Nothing renamed: {e}"));
+            false
+        }
+    }
+}
+
+/// Draw the bulk rename popup
+pub fn draw(ctx: &Context, app: &mut Kiorg) {
+    let Some(PopupType::BulkRename(state)) = &mut app.show_popup else {
+        return;
+    };
+
+    let previews = state.preview();
+    let changed = previews.as_ref().map_or(0, |previews| {
+        previews.iter().filter(|p| p.is_changed()).count()
+    });
+    let problems = previews.as_ref().map_or(0, |previews| {
+        previews.iter().filter(|p| p.problem.is_some()).count()
+    });
+    let can_apply = changed > 0 && problems == 0;
+
+    let error_color = app.colors.error;
+    let dim_color = app.colors.fg_light;
+    let mut keep_open = true;
+    let mut cancelled = false;
+    let mut confirmed = ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::Enter));
+    cancelled |= ctx.input(|i| i.key_pressed(Key::Escape));
+
+    new_center_popup_window("Bulk Rename")
+        .open(&mut keep_open)
+        .max_width(700.0)
+        .show(ctx, |ui| {
+            let pattern = &mut state.pattern;
+            egui::Grid::new("bulk_rename_pattern_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Find (regex):");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut pattern.find)
+                            .hint_text("empty matches the whole name")
+                            .desired_width(400.0),
+                    );
+                    ui.end_row();
+
+                    ui.label("Replace:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut pattern.replace)
+                            .hint_text("$1, {n}, {date}, {exif}")
+                            .desired_width(400.0),
+                    );
+                    ui.end_row();
+
+                    ui.label("Case:");
+                    ui.horizontal(|ui| {
+                        for case in CaseConversion::ALL {
+                            ui.selectable_value(&mut pattern.case, case, case.label());
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Counter {n}:");
+                    ui.horizontal(|ui| {
+                        ui.label("start");
+                        ui.add(egui::DragValue::new(&mut pattern.start));
+                        ui.label("step");
+                        ui.add(egui::DragValue::new(&mut pattern.step).range(1..=u64::MAX));
+                        ui.label("digits");
+                        ui.add(egui::DragValue::new(&mut pattern.padding).range(0..=12));
+                    });
+                    ui.end_row();
+                });
+
+            ui.label(
+                RichText::new(
+                    "Dates use strftime formats, e.g. {date:%Y%m%d} or {exif:%Y-%m-%d_%H%M%S}",
+                )
+                .small()
+                .color(dim_color),
+            );
+            ui.separator();
+
+            match &previews {
+                Err(e) => {
+                    ui.label(RichText::new(e).color(error_color));
+                }
+                Ok(previews) => {
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .auto_shrink([false, true])
+                        .show(ui, |ui| {
+                            egui::Grid::new("bulk_rename_preview_grid")
+                                .num_columns(3)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.label(RichText::new("Before").strong());
+                                    ui.label(RichText::new("After").strong());
+                                    ui.label("");
+                                    ui.end_row();
+                                    for preview in previews {
+                                        let old_name = preview
+                                            .old_path
+                                            .file_name()
+                                            .unwrap_or_default()
+                                            .to_string_lossy();
+                                        ui.label(old_name.as_ref());
+                                        match &preview.problem {
+                                            Some(problem) => {
+                                                ui.label(
+                                                    RichText::new(&preview.new_name)
+                                                        .color(error_color),
+                                                );
+                                                ui.label(RichText::new(problem).color(error_color));
+                                            }
+                                            None if preview.is_changed() => {
+                                                ui.label(&preview.new_name);
+                                                ui.label("");
+                                            }
+                                            None => {
+                                                ui.label(
+                                                    RichText::new(&preview.new_name)
+                                                        .color(dim_color),
+                                                );
+                                                ui.label("");
+                                            }
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                }
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if problems > 0 {
+                    ui.label(
+                        RichText::new(format!("{problems} entries cannot be renamed"))
+                            .color(error_color),
+                    );
+                } else {
+                    ui.label(format!(
+                        "{changed} of {} entries renamed",
+                        state.sources.len()
+                    ));
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("Cancel (Esc)").clicked() {
+                        cancelled = true;
+                    }
+                    if ui
+                        .add_enabled(can_apply, egui::Button::new("Rename (Ctrl+Enter)"))
+                        .clicked()
+                    {
+                        confirmed = true;
+                    }
+                });
+            });
+        });
+
+    if !keep_open || cancelled {
+        app.show_popup = None;
+    } else if confirmed
+        && can_apply
+        && let Ok(previews) = previews
+        && apply(app, &previews)
+    {
+        app.show_popup = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn sources(dir: &Path, names: &[&str]) -> Vec<RenameSource> {
+        names
+            .iter()
+            .map(|name| RenameSource::new(dir.join(name), SystemTime::UNIX_EPOCH))
+            .collect()
+    }
+
+    fn new_names(previews: &[RenamePreview]) -> Vec<&str> {
+        previews.iter().map(|p| p.new_name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_regex_capture_groups_and_counter() {
+        let dir = Path::new("/nonexistent");
+        let sources = sources(dir, &["IMG_001.jpg", "IMG_002.jpg", "notes.txt"]);
+        let pattern = RenamePattern {
+            find: r"^IMG_(\d+)\.(\w+)$".to_string(),
+            replace: "photo_{n}_${1}.$2".to_string(),
+            padding: 3,
+            start: 5,
+            step: 5,
+            ..RenamePattern::default()
+        };
+
+        let previews = preview(&sources, &pattern).unwrap();
+        assert_eq!(
+            new_names(&previews),
+            ["photo_005_001.jpg", "photo_010_002.jpg", "notes.txt"]
+        );
+        assert!(!previews[2].is_changed());
+    }
+
+    #[test]
+    fn test_template_dates_and_case() {
+        let dir = Path::new("/nonexistent");
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(86400 * 365);
+        let sources = vec![RenameSource::new(dir.join("Some File.TXT"), modified)];
+        let date = DateTime::<Local>::from(modified).format("%Y").to_string();
+
+        let pattern = RenamePattern {
+            replace: "{date:%Y} $0".to_string(),
+            case: CaseConversion::Title,
+            ..RenamePattern::default()
+        };
+        let previews = preview(&sources, &pattern).unwrap();
+        assert_eq!(new_names(&previews), [format!("{date} Some File.txt")]);
+
+        // Images without EXIF data cannot use the exif token
+        let pattern = RenamePattern {
+            replace: "{exif}".to_string(),
+            ..RenamePattern::default()
+        };
+        let previews = preview(&sources, &pattern).unwrap();
+        assert!(previews[0].problem.is_some());
+
+        // Unknown tokens are kept
+        let pattern = RenamePattern {
+            find: "File".to_string(),
+            replace: "{other}".to_string(),
+            case: CaseConversion::Lower,
+            ..RenamePattern::default()
+        };
+        let previews = preview(&sources, &pattern).unwrap();
+        assert_eq!(new_names(&previews), ["some {other}.txt"]);
+    }
+
+    #[test]
+    fn test_invalid_regex_is_reported() {
+        let pattern = RenamePattern {
+            find: "(".to_string(),
+            ..RenamePattern::default()
+        };
+        assert!(preview(&sources(Path::new("/"), &["a"]), &pattern).is_err());
+    }
+
+    #[test]
+    fn test_collisions_are_flagged() {
+        let temp_dir = tempdir().unwrap();
+        for name in ["a.txt", "b.txt", "keep.md"] {
+            std::fs::write(temp_dir.path().join(name), "").unwrap();
+        }
+        let sources = sources(temp_dir.path(), &["a.txt", "b.txt"]);
+
+        // Both entries end up with the same name
+        let pattern = RenamePattern {
+            find: r"^\w".to_string(),
+            replace: "x".to_string(),
+            ..RenamePattern::default()
+        };
+        let previews = preview(&sources, &pattern).unwrap();
+        assert!(previews.iter().all(|p| p.problem.is_some()));
+
+        // keep.md is not renamed, so its name is taken
+        let pattern = RenamePattern {
+            find: r"^a\.txt$".to_string(),
+            replace: "keep.md".to_string(),
+            ..RenamePattern::default()
+        };
+        let previews = preview(&sources, &pattern).unwrap();
+        assert!(previews[0].problem.is_some());
+        assert!(previews[1].problem.is_none());
+
+        // Swapping names is fine
+        let mut previews = preview(&sources, &RenamePattern::default()).unwrap();
+        previews[0].new_name = "b.txt".to_string();
+        previews[1].new_name = "a.txt".to_string();
+        flag_collisions(&mut previews);
+        assert!(previews.iter().all(|p| p.problem.is_none()));
+    }
+
+    #[test]
+    fn test_preview_is_cached_per_pattern() {
+        let mut state = BulkRenameState::new(sources(Path::new("/nonexistent"), &["a.txt"]));
+        assert_eq!(new_names(&state.preview().unwrap()), ["a.txt"]);
+
+        // Sources are not looked at again while the pattern stays the same
+        state.sources[0].path = PathBuf::from("/nonexistent/b.txt");
+        assert_eq!(new_names(&state.preview().unwrap()), ["a.txt"]);
+
+        state.pattern.case = CaseConversion::Upper;
+        assert_eq!(new_names(&state.preview().unwrap()), ["B.TXT"]);
+    }
+}
@@ -0,0 +1,229 @@
+//! Run the commands plugins declare on the marked or selected entries
+//!
+//! Commands show up in this palette, in the context menu of the file list,
+//! and can be bound to shortcuts in the `[shortcuts.PluginCommand]` table.
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+use super::PopupType;
+use crate::app::Kiorg;
+use crate::config::shortcuts::{
+    KeyboardShortcut, ShortcutAction, Shortcuts, shortcuts_helpers::get_shortcut_display,
+};
+use crate::models::tab::Tab;
+use crate::plugins::{CommandRef, PluginManager};
+use crate::ui::notification::NotificationMessage;
+use crate::ui::popup::fuzzy_search_popup::{
+    FuzzySearchAction, FuzzySearchItem, FuzzySearchPopupConfig, FuzzySearchState, fuzzy_filter,
+};
+use crate::vfs;
+
+static POPUP_CONFIG: FuzzySearchPopupConfig = FuzzySearchPopupConfig {
+    title: "Plugin Commands",
+    search_hint: "Run plugin command...",
+    empty_message: "No plugin commands for the selection",
+    no_match_message: "No matching commands found",
+    max_visible_results: Some(10),
+};
+
+/// A command that applies to the current selection
+#[derive(Debug, Clone)]
+pub struct CommandItem {
+    /// Index into the plugin manager's commands
+    pub index: usize,
+    pub label: String,
+    pub shortcut: Option<String>,
+}
+
+impl FuzzySearchItem for CommandItem {
+    fn display_text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.label)
+    }
+
+    fn secondary_text(&self) -> Option<Cow<'_, str>> {
+        self.shortcut.as_deref().map(Cow::Borrowed)
+    }
+}
+
+/// Name shown for a command in menus and the palette
+#[must_use]
+pub fn command_label(command: &CommandRef) -> String {
+    format!(
+        "{}: {}",
+        command.plugin.metadata.name,
+        command.command().name
+    )
+}
+
+/// Marked entries, or the selected entry when nothing is marked
+#[must_use]
+pub fn target_paths(tab: &Tab) -> Vec<PathBuf> {
+    if tab.marked_entries.is_empty() {
+        tab.selected_entry()
+            .map(|entry| vec![entry.meta.path.clone()])
+            .unwrap_or_default()
+    } else {
+        tab.marked_or_listed_entries()
+            .into_iter()
+            .map(|entry| entry.meta.path.clone())
+            .collect()
+    }
+}
+
+/// Commands that can run on `paths`
+#[must_use]
+pub fn commands_for(
+    plugin_manager: &PluginManager,
+    shortcuts: &Shortcuts,
+    paths: &[PathBuf],
+) -> Vec<CommandItem> {
+    plugin_manager
+        .commands_for(paths)
+        .into_iter()
+        .map(|index| CommandItem {
+            index,
+            label: command_label(&plugin_manager.commands()[index]),
+            shortcut: get_shortcut_display(shortcuts, ShortcutAction::PluginCommand(index))
+                .into_iter()
+                .next(),
+        })
+        .collect()
+}
+
+/// Bind plugin commands to the shortcuts from the config, or the defaults
+/// the plugins declare
+///
+/// Shortcuts that conflict with existing ones are skipped.
+pub fn bind_shortcuts(
+    shortcuts: &mut Shortcuts,
+    plugin_manager: &PluginManager,
+    user_shortcuts: Option<&Shortcuts>,
+) {
+    bind_shortcuts_from(shortcuts, plugin_manager, user_shortcuts, 0);
+}
+
+/// Same as [`bind_shortcuts`] for the commands from index `first` on, the
+/// ones of plugins started after the others
+pub fn bind_shortcuts_from(
+    shortcuts: &mut Shortcuts,
+    plugin_manager: &PluginManager,
+    user_shortcuts: Option<&Shortcuts>,
+    first: usize,
+) {
+    for (index, command) in plugin_manager.commands().iter().enumerate().skip(first) {
+        let id = command.id();
+        let bound = match user_shortcuts.and_then(|user| user.get_plugin_command(&id)) {
+            Some(user_bound) => user_bound.clone(),
+            None => match command.command().shortcut.as_deref() {
+                Some(text) => match KeyboardShortcut::parse(text) {
+                    Ok(shortcut) => vec![shortcut],
+                    Err(e) => {
+                        tracing::warn!("Invalid shortcut for plugin command '{id}': {e}");
+                        continue;
+                    }
+                },
+                None => continue,
+            },
+        };
+        for shortcut in bound {
+            if let Err(e) = shortcuts.add_shortcut(shortcut, ShortcutAction::PluginCommand(index)) {
+                tracing::warn!("Skipping shortcut of plugin command '{id}': {e}");
+            }
+        }
+    }
+}
+
+/// Run the command at `index` of the plugin manager's commands on the
+/// marked or selected entries
+///
+/// The plugin is called on a background thread, what it asks for afterwards
+/// comes back through the notification system.
+pub fn run_command(ctx: &egui::Context, app: &mut Kiorg, index: usize) {
+    let Some(command) = app.plugin_manager.commands().get(index).cloned() else {
+        return;
+    };
+    if command.plugin.is_disabled() {
+        app.notify_error(format!(
+            "Plugin '{}' is disabled",
+            command.plugin.metadata.name
+        ));
+        return;
+    }
+    let tab = app.tab_manager.current_tab_ref();
+    let cwd = tab.current_path.clone();
+    let paths = target_paths(tab);
+    if !paths.iter().all(|path| vfs::is_local(path)) {
+        app.notify_error("Plugin commands only work on local files");
+        return;
+    }
+    if !command.command().accepts(&paths) {
+        app.notify_error(format!(
+            "{} does not apply to the selection",
+            command_label(&command)
+        ));
+        return;
+    }
+
+    let sender = app.notification_system.get_sender();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        match command
+            .plugin
+            .execute(&command.command().name, &paths, &cwd)
+        {
+            Ok(result) => {
+                if let Some(message) = result.notification {
+                    let _ = sender.send(NotificationMessage::Info(message));
+                }
+                if let Some(path) = result.navigate {
+                    let _ = sender.send(NotificationMessage::Navigate(path));
+                } else if result.refresh {
+                    let _ = sender.send(NotificationMessage::Refresh);
+                }
+            }
+            Err(e) => {
+                let _ = sender.send(NotificationMessage::Error(format!(
+                    "{} failed: {e}",
+                    command_label(&command)
+                )));
+            }
+        }
+        ctx.request_repaint();
+    });
+}
+
+/// Open the palette with the commands that apply to the selection
+pub fn open(app: &mut Kiorg) {
+    app.show_popup = Some(PopupType::CommandPalette(FuzzySearchState::default()));
+}
+
+/// Draw the command palette
+pub fn draw(ctx: &egui::Context, app: &mut Kiorg) {
+    let Some(PopupType::CommandPalette(state)) = &mut app.show_popup else {
+        return;
+    };
+    state.update_query();
+
+    let paths = target_paths(app.tab_manager.current_tab_ref());
+    let items = commands_for(&app.plugin_manager, &app.merged_shortcuts, &paths);
+    let results = fuzzy_filter(&state.query, &items);
+    let action = crate::ui::popup::fuzzy_search_popup::draw(
+        ctx,
+        &POPUP_CONFIG,
+        &app.colors,
+        state,
+        &results,
+    );
+
+    match action {
+        FuzzySearchAction::KeepOpen => {}
+        FuzzySearchAction::Close => {
+            app.show_popup = None;
+        }
+        FuzzySearchAction::Selected(item) => {
+            app.show_popup = None;
+            run_command(ctx, app, item.index);
+        }
+    }
+}
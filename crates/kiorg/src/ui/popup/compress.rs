@@ -0,0 +1,179 @@
+//! Pack the marked entries into a new archive
+//!
+//! The archive is written next to the entries as a background job and recorded
+//! once it is complete, undo puts it into the undo stash.
+
+use std::path::PathBuf;
+
+use egui::{Context, Frame, RichText, TextEdit};
+
+use super::PopupType;
+use super::job_progress;
+use super::window_utils::new_center_popup_window;
+use crate::app::Kiorg;
+use crate::models::action_history::CompressOperation;
+use crate::utils::archive::{self, CompressFormat};
+use crate::utils::jobs::{JobRecord, spawn_job};
+use crate::utils::stash;
+
+/// Entries to compress along with the archive name being entered
+#[derive(Debug, Clone)]
+pub struct CompressState {
+    pub sources: Vec<PathBuf>,
+    /// Archive name without the extension of the format
+    pub name: String,
+    pub format: CompressFormat,
+}
+
+impl CompressState {
+    fn target(&self) -> Option<PathBuf> {
+        let parent = self.sources.first()?.parent()?;
+        let name = self.name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        Some(parent.join(format!("{name}.{}", self.format.extension())))
+    }
+
+    /// Why the archive cannot be created with the current name
+    fn problem(&self) -> Option<String> {
+        let name = self.name.trim();
+        if let Err(e) = crate::utils::bulk_rename::validate_name(name) {
+            return Some(e);
+        }
+        let target = self.target()?;
+        target
+            .symlink_metadata()
+            .is_ok()
+            .then(|| format!("{} already exists", target.display()))
+    }
+}
+
+/// Open the popup for the marked entries, or the selected entry when nothing is marked
+pub fn open(app: &mut Kiorg) {
+    if !app.ensure_current_dir_writable() {
+        return;
+    }
+    let tab = app.tab_manager.current_tab_ref();
+    let mut sources: Vec<PathBuf> = if tab.marked_entries.is_empty() {
+        tab.selected_entry()
+            .map(|entry| vec![entry.meta.path.clone()])
+            .unwrap_or_default()
+    } else {
+        tab.marked_entries.iter().cloned().collect()
+    };
+    if sources.is_empty() {
+        return;
+    }
+    sources.sort();
+
+    // A single entry names the archive, several are named after their directory
+    let name_source = match sources.as_slice() {
+        [single] => single.clone(),
+        [first, ..] => first.parent().map(PathBuf::from).unwrap_or_default(),
+        [] => PathBuf::new(),
+    };
+    let name = name_source.file_name().map_or_else(
+        || "archive".to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+
+    app.show_popup = Some(PopupType::Compress(CompressState {
+        sources,
+        name,
+        format: CompressFormat::default(),
+    }));
+}
+
+/// Start compressing in the background
+pub fn confirm(app: &mut Kiorg) {
+    let Some(PopupType::Compress(state)) = &app.show_popup else {
+        return;
+    };
+    if let Some(problem) = state.problem() {
+        app.notify_error(problem);
+        return;
+    }
+    let Some(PopupType::Compress(state)) = app.show_popup.take() else {
+        return;
+    };
+    let Some(target) = state.target() else {
+        return;
+    };
+
+    let archive_name = target
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let sources = state.sources;
+    let format = state.format;
+    let job = spawn_job("Compressing", move |ctx| {
+        let backup = stash::reserve(&target)?;
+        archive::compress(ctx, &sources, &target, format)?;
+        ctx.record(JobRecord::Compress(CompressOperation {
+            sources,
+            archive_path: target,
+            backup,
+        }));
+        Ok(())
+    })
+    .with_success_message(format!("Created {archive_name}"));
+    job_progress::start(app, job);
+}
+
+pub fn cancel(app: &mut Kiorg) {
+    app.show_popup = None;
+}
+
+/// Draw the compress popup
+pub fn draw(ctx: &Context, app: &mut Kiorg) {
+    let Some(PopupType::Compress(state)) = &mut app.show_popup else {
+        return;
+    };
+
+    let mut keep_open = true;
+    let colors = &app.colors;
+    let title = match state.sources.len() {
+        1 => "Compress".to_string(),
+        count => format!("Compress {count} entries"),
+    };
+
+    new_center_popup_window(&title)
+        .open(&mut keep_open)
+        .show(ctx, |ui| {
+            ui.set_max_width(450.0);
+            Frame::default()
+                .fill(colors.bg_extreme)
+                .inner_margin(5.0)
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        let response = ui.add(
+                            TextEdit::singleline(&mut state.name)
+                                .hint_text("Archive name...")
+                                .desired_width(300.0)
+                                .frame(false),
+                        );
+                        response.request_focus();
+                        ui.label(
+                            RichText::new(format!(".{}", state.format.extension()))
+                                .color(colors.fg_light),
+                        );
+                    });
+                });
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                for format in CompressFormat::ALL {
+                    ui.selectable_value(&mut state.format, format, format.label());
+                }
+            });
+            if let Some(problem) = state.problem() {
+                ui.add_space(5.0);
+                ui.label(RichText::new(problem).color(colors.error));
+            }
+        });
+
+    if !keep_open {
+        cancel(app);
+    }
+}
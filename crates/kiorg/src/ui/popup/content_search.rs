@@ -0,0 +1,293 @@
+//! Search file contents under the current directory, like ripgrep
+//!
+//! The search restarts in the background whenever the query or an option
+//! changes, matches are listed as they come in.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use egui::text::{LayoutJob, TextFormat, TextWrapping};
+use egui::{Context, FontId, Key, Modifiers, RichText, Stroke, TextEdit};
+
+use super::PopupType;
+use super::window_utils::new_center_popup_window;
+use crate::app::Kiorg;
+use crate::config::colors::AppColors;
+use crate::utils::content_search::{ContentMatch, ContentSearch, SearchEvent, SearchOptions};
+use crate::vfs;
+
+/// Where the search is at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchStatus {
+    /// Nothing to search for yet
+    Idle,
+    Running,
+    Done {
+        files: usize,
+        truncated: bool,
+    },
+    Error(String),
+}
+
+/// State for the content search popup
+#[derive(Debug)]
+pub struct ContentSearchState {
+    pub root: PathBuf,
+    pub options: SearchOptions,
+    pub matches: Vec<ContentMatch>,
+    pub selected_index: usize,
+    pub status: SearchStatus,
+    search: Option<ContentSearch>,
+    /// Options the current matches were found with
+    searched: Option<SearchOptions>,
+}
+
+impl ContentSearchState {
+    #[must_use]
+    pub fn new(root: PathBuf, show_hidden: bool) -> Self {
+        Self {
+            root,
+            options: SearchOptions {
+                query: String::new(),
+                regex: false,
+                case_sensitive: false,
+                show_hidden,
+            },
+            matches: Vec::new(),
+            selected_index: 0,
+            status: SearchStatus::Idle,
+            search: None,
+            searched: None,
+        }
+    }
+
+    /// Restart the search if the options changed and collect new matches
+    pub fn update(&mut self) {
+        if self.searched.as_ref() != Some(&self.options) {
+            self.searched = Some(self.options.clone());
+            self.matches.clear();
+            self.selected_index = 0;
+            // Dropping the previous search stops it
+            self.search = None;
+            self.status = if self.options.query.is_empty() {
+                SearchStatus::Idle
+            } else {
+                match ContentSearch::start(self.root.clone(), &self.options) {
+                    Ok(search) => {
+                        self.search = Some(search);
+                        SearchStatus::Running
+                    }
+                    Err(e) => SearchStatus::Error(e),
+                }
+            };
+        }
+
+        let Some(search) = &self.search else {
+            return;
+        };
+        for event in search.poll() {
+            match event {
+                SearchEvent::Match(content_match) => self.matches.push(content_match),
+                SearchEvent::Done { files, truncated } => {
+                    self.status = SearchStatus::Done { files, truncated };
+                }
+            }
+        }
+        if self.status != SearchStatus::Running {
+            self.search = None;
+        }
+    }
+}
+
+/// Open the popup for the current directory
+pub fn open(app: &mut Kiorg) {
+    let root = app.tab_manager.current_tab_ref().current_path.clone();
+    if !vfs::is_local(&root) {
+        app.notify_error("Content search only works in local directories");
+        return;
+    }
+    let show_hidden = app.tab_manager.show_hidden;
+    app.show_popup = Some(PopupType::ContentSearch(ContentSearchState::new(
+        root,
+        show_hidden,
+    )));
+}
+
+/// Select the file of a match in the center panel and scroll its preview to
+/// the matching line
+pub fn jump_to_match(app: &mut Kiorg, content_match: &ContentMatch) {
+    app.show_popup = None;
+    app.reveal_path(&content_match.path);
+    app.preview_scroll_line = Some((content_match.path.clone(), content_match.line_number));
+}
+
+/// `file:line:` followed by the line with its matches highlighted, cut off at
+/// the available width
+fn match_label(
+    location: &str,
+    content_match: &ContentMatch,
+    colors: &AppColors,
+    max_width: f32,
+) -> LayoutJob {
+    let font_id = FontId::monospace(13.0);
+    let plain = TextFormat::simple(font_id.clone(), colors.fg);
+    let highlighted = TextFormat {
+        underline: Stroke::new(1.0, colors.highlight),
+        ..TextFormat::simple(font_id.clone(), colors.highlight)
+    };
+
+    let mut job = LayoutJob::default();
+    job.append(
+        &format!("{location}:{}: ", content_match.line_number),
+        0.0,
+        TextFormat::simple(font_id, colors.link_text),
+    );
+    let line = &content_match.line;
+    let mut last = 0;
+    for range in &content_match.ranges {
+        job.append(&line[last..range.start], 0.0, plain.clone());
+        job.append(&line[range.clone()], 0.0, highlighted.clone());
+        last = range.end;
+    }
+    job.append(&line[last..], 0.0, plain);
+    job.wrap = TextWrapping::truncate_at_width(max_width);
+    job
+}
+
+fn status_text(state: &ContentSearchState) -> String {
+    let matches = state.matches.len();
+    match &state.status {
+        SearchStatus::Idle => "Type to search file contents".to_string(),
+        SearchStatus::Running => format!("{matches} matches, searching..."),
+        SearchStatus::Done {
+            files,
+            truncated: false,
+        } => format!("{matches} matches in {files} files"),
+        SearchStatus::Done {
+            files,
+            truncated: true,
+        } => format!("Showing the first {matches} matches, {files} files searched"),
+        SearchStatus::Error(e) => e.clone(),
+    }
+}
+
+/// Draw the content search popup
+pub fn draw(ctx: &Context, app: &mut Kiorg) {
+    let Some(PopupType::ContentSearch(state)) = &mut app.show_popup else {
+        return;
+    };
+    state.update();
+    if state.status == SearchStatus::Running {
+        ctx.request_repaint_after(Duration::from_millis(50));
+    }
+
+    let (toggle_regex, toggle_case) = ctx.input_mut(|i| {
+        (
+            i.consume_key(Modifiers::ALT, Key::R),
+            i.consume_key(Modifiers::ALT, Key::C),
+        )
+    });
+    state.options.regex ^= toggle_regex;
+    state.options.case_sensitive ^= toggle_case;
+
+    let (cancelled, confirmed, down, up) = ctx.input(|i| {
+        (
+            i.key_pressed(Key::Escape),
+            i.key_pressed(Key::Enter),
+            i.key_pressed(Key::ArrowDown),
+            i.key_pressed(Key::ArrowUp),
+        )
+    });
+    if down {
+        state.selected_index =
+            (state.selected_index + 1).min(state.matches.len().saturating_sub(1));
+    }
+    if up {
+        state.selected_index = state.selected_index.saturating_sub(1);
+    }
+
+    let colors = &app.colors;
+    let mut keep_open = true;
+    let mut clicked = None;
+
+    new_center_popup_window("Search in Files")
+        .open(&mut keep_open)
+        .max_width(800.0)
+        .show(ctx, |ui| {
+            ui.set_width(800.0);
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut state.options.query)
+                        .hint_text(format!("Search in {}", state.root.display()))
+                        .desired_width(560.0),
+                )
+                .request_focus();
+                ui.checkbox(&mut state.options.regex, "Regex (Alt+R)");
+                ui.checkbox(&mut state.options.case_sensitive, "Case (Alt+C)");
+            });
+
+            let status_color = if matches!(state.status, SearchStatus::Error(_)) {
+                colors.error
+            } else {
+                colors.fg_light
+            };
+            ui.label(RichText::new(status_text(state)).color(status_color));
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .max_height(320.0)
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    let max_width = ui.available_width();
+                    for (index, content_match) in state.matches.iter().enumerate() {
+                        let location = content_match
+                            .path
+                            .strip_prefix(&state.root)
+                            .unwrap_or(&content_match.path)
+                            .display()
+                            .to_string();
+                        let label = match_label(&location, content_match, colors, max_width);
+                        let response = ui.selectable_label(index == state.selected_index, label);
+                        if response.clicked() {
+                            clicked = Some(index);
+                        }
+                        if (down || up) && index == state.selected_index {
+                            response.scroll_to_me(None);
+                        }
+                    }
+                });
+
+            // Context around the selected match
+            if let Some(content_match) = state.matches.get(state.selected_index) {
+                ui.separator();
+                let first = content_match.line_number - content_match.before.len();
+                let lines = content_match
+                    .before
+                    .iter()
+                    .chain(std::iter::once(&content_match.line))
+                    .chain(&content_match.after);
+                for (offset, line) in lines.enumerate() {
+                    let number = first + offset;
+                    let color = if number == content_match.line_number {
+                        colors.fg
+                    } else {
+                        colors.fg_light
+                    };
+                    ui.label(
+                        RichText::new(format!("{number:>5}  {line}"))
+                            .monospace()
+                            .color(color),
+                    );
+                }
+            }
+        });
+
+    if cancelled || !keep_open {
+        app.show_popup = None;
+        return;
+    }
+    let target = clicked.or_else(|| confirmed.then_some(state.selected_index));
+    if let Some(content_match) = target.and_then(|index| state.matches.get(index)).cloned() {
+        jump_to_match(app, &content_match);
+    }
+}
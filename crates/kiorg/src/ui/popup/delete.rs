@@ -1,12 +1,12 @@
-use egui::{Context, ProgressBar, RichText};
+use egui::{Context, RichText};
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
-use std::thread;
 
-use super::PopupType;
 use super::utils::{ConfirmResult, show_confirm_popup};
-use super::window_utils::new_center_popup_window;
+use super::{PopupType, job_progress};
 use crate::config::colors::AppColors;
+use crate::utils::jobs::{JobRecord, spawn_job};
+use crate::utils::{stash, trash};
+use crate::vfs;
 
 /// Confirmation state for the delete popup
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -17,55 +17,45 @@ pub enum DeleteConfirmState {
     RecursiveConfirm,
 }
 
-/// Progress state for delete operation
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct DeleteProgressState {
-    pub total_files: usize,
-    pub current_file: usize,
-    pub current_path: String,
-    pub completed: bool,
-    pub error: Option<String>,
-}
+/// Result of the delete confirmation dialog
+pub type DeleteConfirmResult = ConfirmResult;
 
-/// Progress data containing state and receiver
-pub struct DeleteProgressData {
-    pub state: DeleteProgressState,
-    pub receiver: std::sync::mpsc::Receiver<DeleteProgressUpdate>,
+/// How confirmed entries are disposed of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteMode {
+    /// Move entries to the trash, can be undone from the action history
+    Trash,
+    /// Skip the trash, entries are kept in the undo stash when they live on its
+    /// filesystem and removed from disk otherwise
+    Permanent,
 }
 
-impl std::fmt::Debug for DeleteProgressData {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("DeleteProgressData")
-            .field("state", &self.state)
-            .field("receiver", &"<receiver>")
-            .finish()
+impl DeleteMode {
+    /// Mode used by the regular delete action, trash when the platform supports it
+    pub const fn default_mode() -> Self {
+        if trash::is_supported() {
+            Self::Trash
+        } else {
+            Self::Permanent
+        }
     }
-}
 
-impl PartialEq for DeleteProgressData {
-    fn eq(&self, other: &Self) -> bool {
-        // Compare only the state, not the receiver
-        self.state == other.state
+    fn title(self, is_bulk: bool) -> &'static str {
+        match (self, is_bulk) {
+            (Self::Trash, _) => "Move to Trash",
+            (Self::Permanent, false) => "Delete Confirmation",
+            (Self::Permanent, true) => "Bulk Delete Confirmation",
+        }
     }
-}
 
-impl Eq for DeleteProgressData {}
-
-/// Progress update message sent from background thread
-#[derive(Debug, Clone)]
-pub enum DeleteProgressUpdate {
-    Progress {
-        current: usize,
-        total: usize,
-        current_path: String,
-    },
-    Completed,
-    Error(String),
+    fn confirm_text(self) -> &'static str {
+        match self {
+            Self::Trash => "Move to Trash (Enter)",
+            Self::Permanent => "Delete (Enter)",
+        }
+    }
 }
 
-/// Result of the delete confirmation dialog
-pub type DeleteConfirmResult = ConfirmResult;
-
 /// Handle the delete confirmation process and show the popup
 pub fn handle_delete_confirmation(
     ctx: &Context,
@@ -73,6 +63,7 @@ pub fn handle_delete_confirmation(
     entries_to_delete: &[PathBuf],
     colors: &AppColors,
     state: &mut DeleteConfirmState,
+    mode: DeleteMode,
 ) -> DeleteConfirmResult {
     if !*show_delete_confirm || entries_to_delete.is_empty() {
         return DeleteConfirmResult::None;
@@ -80,25 +71,45 @@ pub fn handle_delete_confirmation(
 
     // Check if we're deleting a single entry or multiple entries
     let is_bulk_delete = entries_to_delete.len() > 1;
+    let title = mode.title(is_bulk_delete);
+
+    // Closing line of the second confirmation
+    let show_consequence = |ui: &mut egui::Ui| {
+        let undoable = mode == DeleteMode::Trash
+            || entries_to_delete
+                .iter()
+                .all(|path| stash::is_on_stash_device(path));
+        if undoable {
+            ui.label("Items can be restored from the action history.");
+        } else {
+            ui.label(RichText::new("This action cannot be undone!").color(colors.error));
+        }
+    };
 
     // For single entry deletion, use the existing logic
     if is_bulk_delete {
         // For bulk deletion, show a different confirmation dialog
-        let has_directories = entries_to_delete.iter().any(|path| path.is_dir());
+        let has_directories = entries_to_delete.iter().any(|path| is_dir(path));
 
         match *state {
             DeleteConfirmState::Initial => {
                 // Initial confirmation for bulk deletion
                 show_confirm_popup(
                     ctx,
-                    "Bulk Delete Confirmation",
+                    title,
                     show_delete_confirm,
                     |ui| {
                         ui.vertical_centered(|ui| {
-                            ui.label(format!(
-                                "Delete {} selected items?",
-                                entries_to_delete.len()
-                            ));
+                            ui.label(match mode {
+                                DeleteMode::Trash => format!(
+                                    "Move {} selected items to trash?",
+                                    entries_to_delete.len()
+                                ),
+                                DeleteMode::Permanent => format!(
+                                    "Permanently delete {} selected items?",
+                                    entries_to_delete.len()
+                                ),
+                            });
 
                             // Show the first few entries as examples
                             let max_to_show = 5.min(entries_to_delete.len());
@@ -120,7 +131,7 @@ pub fn handle_delete_confirmation(
                             }
                         });
                     },
-                    "Delete (Enter)",
+                    mode.confirm_text(),
                     "Cancel (Esc)",
                 )
             }
@@ -128,22 +139,30 @@ pub fn handle_delete_confirmation(
                 // Second confirmation specifically for bulk deletion with directories
                 show_confirm_popup(
                     ctx,
-                    "Bulk Delete Confirmation",
+                    title,
                     show_delete_confirm,
                     |ui| {
                         ui.vertical_centered(|ui| {
-                            ui.label("Are you SURE you want to delete these items?");
+                            ui.label(match mode {
+                                DeleteMode::Trash => {
+                                    "Are you SURE you want to move these items to the trash?"
+                                }
+                                DeleteMode::Permanent => {
+                                    "Are you SURE you want to delete these items?"
+                                }
+                            });
 
                             if has_directories {
-                                ui.label("Some selected items are directories and will be deleted recursively.");
+                                ui.label(match mode {
+                                    DeleteMode::Trash => "Some selected items are directories and will be moved with all their contents.",
+                                    DeleteMode::Permanent => "Some selected items are directories and will be deleted recursively.",
+                                });
                             }
 
-                            ui.label(
-                                RichText::new("This action cannot be undone!").color(colors.error),
-                            );
+                            show_consequence(ui);
                         });
                     },
-                    "Delete (Enter)",
+                    mode.confirm_text(),
                     "Cancel (Esc)",
                 )
             }
@@ -156,14 +175,14 @@ pub fn handle_delete_confirmation(
                 // Initial confirmation for any file or directory
                 show_confirm_popup(
                     ctx,
-                    "Delete Confirmation",
+                    title,
                     show_delete_confirm,
                     |ui| {
                         ui.vertical_centered(|ui| {
                             ui.label(path.display().to_string());
                         });
                     },
-                    "Delete (Enter)",
+                    mode.confirm_text(),
                     "Cancel (Esc)",
                 )
             }
@@ -171,23 +190,27 @@ pub fn handle_delete_confirmation(
                 // Second confirmation specifically for directories
                 show_confirm_popup(
                     ctx,
-                    "Delete Confirmation",
+                    title,
                     show_delete_confirm,
                     |ui| {
                         ui.vertical_centered(|ui| {
-                            ui.label("Are you SURE you want to delete");
+                            ui.label(match mode {
+                                DeleteMode::Trash => "Are you SURE you want to move",
+                                DeleteMode::Permanent => "Are you SURE you want to delete",
+                            });
 
                             // Highlight the filename with a background
                             ui.label(RichText::new(format!("{}", path.display())).strong());
 
-                            ui.label("and ALL its contents recursively?");
+                            ui.label(match mode {
+                                DeleteMode::Trash => "and ALL its contents to the trash?",
+                                DeleteMode::Permanent => "and ALL its contents recursively?",
+                            });
 
-                            ui.label(
-                                RichText::new("This action cannot be undone!").color(colors.error),
-                            );
+                            show_consequence(ui);
                         });
                     },
-                    "Delete (Enter)",
+                    mode.confirm_text(),
                     "Cancel (Esc)",
                 )
             }
@@ -202,182 +225,23 @@ pub fn handle_delete_confirmation(
 /// Returns an error string if the deletion fails, either due to permission issues,
 /// file system errors, or if the path doesn't exist.
 pub fn perform_delete(path: &Path) -> Result<(), String> {
-    let result = if path.is_dir() {
-        std::fs::remove_dir_all(path)
-    } else {
-        std::fs::remove_file(path)
-    };
-
-    result.map_err(|e| format!("Failed to delete: {e}"))
-}
-
-/// Count total files to delete (for progress tracking)
-fn count_files_to_delete(paths: &[PathBuf]) -> usize {
-    let mut count = 0;
-    for path in paths {
-        if path.is_dir() {
-            count += count_files_in_dir(path);
-        } else {
-            count += 1;
-        }
-    }
-    count
+    vfs::for_path(path)
+        .remove(path)
+        .map_err(|e| format!("Failed to delete: {e}"))
 }
 
-/// Recursively count files in a directory
-fn count_files_in_dir(dir: &Path) -> usize {
-    let mut count = 0;
-    if let Ok(entries) = std::fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            if entry.path().is_dir() {
-                count += count_files_in_dir(&entry.path());
-            } else {
-                count += 1;
-            }
-        }
-        // Count the directory itself
-        count += 1;
-    }
-    count
-}
-
-/// Delete directory recursively with progress updates
-fn delete_dir_with_progress(
-    dir: &Path,
-    progress_sender: &mpsc::Sender<DeleteProgressUpdate>,
-    current_file: &mut usize,
-    total_files: usize,
-) -> Result<(), String> {
-    if let Ok(entries) = std::fs::read_dir(dir) {
-        for entry in entries {
-            match entry {
-                Ok(entry) => {
-                    let path = entry.path();
-                    if path.is_dir() {
-                        delete_dir_with_progress(
-                            &path,
-                            progress_sender,
-                            current_file,
-                            total_files,
-                        )?;
-                    } else {
-                        *current_file += 1;
-                        let _ = progress_sender.send(DeleteProgressUpdate::Progress {
-                            current: *current_file,
-                            total: total_files,
-                            current_path: path.display().to_string(),
-                        });
-                        std::fs::remove_file(&path).map_err(|e| {
-                            format!("Failed to delete file {}: {e}", path.display())
-                        })?;
-                    }
-                }
-                Err(e) => return Err(format!("Failed to read directory entry: {e}")),
-            }
-        }
-    }
-
-    // Delete the directory itself
-    *current_file += 1;
-    let _ = progress_sender.send(DeleteProgressUpdate::Progress {
-        current: *current_file,
-        total: total_files,
-        current_path: dir.display().to_string(),
-    });
-
-    std::fs::remove_dir(dir)
-        .map_err(|e| format!("Failed to delete directory {}: {e}", dir.display()))
-}
-
-/// Handle progress popup UI
-pub fn handle_delete_progress(ctx: &Context, app: &mut crate::app::Kiorg) {
-    let mut should_close = false;
-    let mut error_msg = None;
-
-    // Check for progress updates
-    if let Some(PopupType::DeleteProgress(ref mut progress_data)) = app.show_popup {
-        while let Ok(update) = progress_data.receiver.try_recv() {
-            match update {
-                DeleteProgressUpdate::Progress {
-                    current,
-                    total,
-                    current_path,
-                } => {
-                    progress_data.state.current_file = current;
-                    progress_data.state.total_files = total;
-                    progress_data.state.current_path = current_path;
-                    // Request repaint to update progress UI
-                    ctx.request_repaint();
-                }
-                DeleteProgressUpdate::Completed => {
-                    should_close = true;
-                }
-                DeleteProgressUpdate::Error(error) => {
-                    should_close = true;
-                    error_msg = Some(error);
-                }
-            }
-        }
-    }
-
-    // Handle cleanup outside of the borrow
-    if should_close {
-        app.show_popup = None;
-
-        app.tab_manager.current_tab_mut().marked_entries.clear();
-        app.refresh_entries();
-
-        if let Some(error) = error_msg {
-            app.notify_error(error);
-        }
-
-        return;
-    }
-
-    // Show progress popup
-    if let Some(PopupType::DeleteProgress(ref progress_data)) = app.show_popup {
-        let state = &progress_data.state;
-        new_center_popup_window("Deletion Progress").show(ctx, |ui| {
-            ui.set_min_width(400.0);
-
-            ui.vertical_centered(|ui| {
-                ui.add_space(10.0);
-
-                // Progress bar
-                let progress = if state.total_files > 0 {
-                    state.current_file as f32 / state.total_files as f32
-                } else {
-                    0.0
-                };
-
-                ui.add(ProgressBar::new(progress).desired_width(350.0));
-
-                ui.add_space(10.0);
-
-                // Status text
-                ui.label(format!(
-                    "{} / {} files",
-                    state.current_file, state.total_files
-                ));
-
-                ui.add_space(5.0);
-
-                // Current file being deleted
-                if !state.current_path.is_empty() {
-                    ui.label(format!("Deleting: {}", state.current_path));
-                }
-
-                ui.add_space(10.0);
-            });
-        });
-    }
+/// Whether the entry is a directory that is deleted with all its contents
+fn is_dir(path: &Path) -> bool {
+    vfs::for_path(path)
+        .stat(path)
+        .is_ok_and(|meta| meta.is_dir && !meta.is_symlink)
 }
 
 /// Handle the confirmation of deletion
 pub fn confirm_delete(app: &mut crate::app::Kiorg) {
-    let (state, entries_to_delete) =
-        if let Some(PopupType::Delete(ref state, ref entries)) = app.show_popup {
-            (state.clone(), entries.clone())
+    let (state, entries_to_delete, mode) =
+        if let Some(PopupType::Delete(ref state, ref entries, mode)) = app.show_popup {
+            (state.clone(), entries.clone(), mode)
         } else {
             return;
         };
@@ -394,72 +258,82 @@ pub fn confirm_delete(app: &mut crate::app::Kiorg) {
             app.show_popup = Some(PopupType::Delete(
                 DeleteConfirmState::RecursiveConfirm,
                 entries_to_delete,
+                mode,
             ));
             return; // Return early without performing deletion
         }
     } else {
         let path = &entries_to_delete[0];
         // Check if we're in the initial state and dealing with a directory
-        if state == DeleteConfirmState::Initial && path.is_dir() {
+        if state == DeleteConfirmState::Initial && is_dir(path) {
             // For directories in initial state, move to second confirmation
             app.show_popup = Some(PopupType::Delete(
                 DeleteConfirmState::RecursiveConfirm,
                 entries_to_delete,
+                mode,
             ));
             return; // Return early without performing deletion
         }
     }
-    delete_async(app, entries_to_delete);
+    match mode {
+        DeleteMode::Trash => trash_async(app, entries_to_delete),
+        DeleteMode::Permanent => delete_async(app, entries_to_delete),
+    }
 }
 
-/// Start the async threaded deletion process
-fn delete_async(app: &mut crate::app::Kiorg, entries_to_delete: Vec<PathBuf>) {
-    let (tx, rx) = mpsc::channel();
-
-    // Set up progress state
-    let total_files = count_files_to_delete(&entries_to_delete);
-    let progress_state = DeleteProgressState {
-        total_files,
-        current_file: 0,
-        current_path: String::new(),
-        completed: false,
-        error: None,
-    };
+/// Move the entries to the trash as a background job, recorded for undo
+fn trash_async(app: &mut crate::app::Kiorg, entries_to_trash: Vec<PathBuf>) {
+    app.show_popup = None;
 
-    let progress_data = DeleteProgressData {
-        state: progress_state,
-        receiver: rx,
-    };
+    let job = spawn_job("Moving to Trash", move |ctx| {
+        ctx.set_totals(entries_to_trash.len(), 0);
+
+        for path in &entries_to_trash {
+            if ctx.is_cancelled() {
+                break;
+            }
 
-    // Switch to progress popup
-    app.show_popup = Some(PopupType::DeleteProgress(progress_data));
-
-    // Clone entries for the thread
-    thread::spawn(move || {
-        let total_files = count_files_to_delete(&entries_to_delete);
-        let mut current_file = 0;
-
-        for path in entries_to_delete {
-            let result = if path.is_dir() {
-                delete_dir_with_progress(&path, &tx, &mut current_file, total_files)
-            } else {
-                current_file += 1;
-                let _ = tx.send(DeleteProgressUpdate::Progress {
-                    current: current_file,
-                    total: total_files,
-                    current_path: path.display().to_string(),
-                });
-                std::fs::remove_file(&path).map_err(|e| format!("Failed to delete file: {e}"))
-            };
-
-            if let Err(error) = result {
-                let _ = tx.send(DeleteProgressUpdate::Error(error));
-                return;
+            ctx.start_file(path);
+            if !vfs::is_local(path) {
+                ctx.warn(format!("Cannot move {} to the trash", path.display()));
+                continue;
+            }
+            match trash::move_to_trash(path) {
+                Ok(op) => ctx.record(JobRecord::Trash(op)),
+                Err(e) => ctx.warn(e),
             }
         }
+        Ok(())
+    });
+    job_progress::start(app, job);
+}
+
+/// Delete the entries as a background job. Entries on the filesystem of the undo
+/// stash are moved there and recorded for undo, others are removed right away.
+fn delete_async(app: &mut crate::app::Kiorg, entries_to_delete: Vec<PathBuf>) {
+    app.show_popup = None;
+
+    let job = spawn_job("Deleting", move |ctx| {
+        ctx.set_totals(entries_to_delete.len(), 0);
 
-        let _ = tx.send(DeleteProgressUpdate::Completed);
+        for path in &entries_to_delete {
+            if ctx.is_cancelled() {
+                break;
+            }
+
+            ctx.start_file(path);
+            if stash::is_on_stash_device(path) {
+                match stash::stash(path) {
+                    Ok(op) => ctx.record(JobRecord::Delete(op)),
+                    Err(e) => ctx.warn(e),
+                }
+            } else if let Err(e) = perform_delete(path) {
+                ctx.warn(format!("{e} ({})", path.display()));
+            }
+        }
+        Ok(())
     });
+    job_progress::start(app, job);
 }
 
 pub fn cancel_delete(app: &mut crate::app::Kiorg) {
@@ -0,0 +1,190 @@
+//! Extract archives next to themselves as a background job
+//!
+//! Everything goes into a new directory named after the archive, recorded as a
+//! single extract action so the whole extraction can be undone.
+
+use std::path::{Path, PathBuf};
+
+use egui::{Context, Frame, TextEdit};
+
+use super::PopupType;
+use super::job_progress;
+use super::window_utils::new_center_popup_window;
+use crate::app::Kiorg;
+use crate::models::action_history::ExtractOperation;
+use crate::utils::archive::{self, ArchiveFormat, ExtractRequest};
+use crate::utils::jobs::{JobRecord, spawn_job};
+use crate::utils::stash;
+use crate::vfs;
+
+/// Archive waiting for its password before extraction starts
+#[derive(Clone)]
+pub struct ExtractPasswordState {
+    pub archive: PathBuf,
+    pub selection: Vec<String>,
+    pub password: String,
+}
+
+// The password never ends up in logs
+impl std::fmt::Debug for ExtractPasswordState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtractPasswordState")
+            .field("archive", &self.archive)
+            .field("selection", &self.selection)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Extract the selected entry of the current tab if it is an archive
+pub fn extract_selected_entry(app: &mut Kiorg) {
+    let tab = app.tab_manager.current_tab_ref();
+    let Some(entry) = tab.selected_entry() else {
+        return;
+    };
+    let archive = entry.meta.path.clone();
+    extract(app, archive, Vec::new());
+}
+
+/// Extract `selection` from the archive, or all of it when the selection is empty
+pub fn extract(app: &mut Kiorg, archive: PathBuf, selection: Vec<String>) {
+    if !vfs::is_local(&archive) {
+        app.notify_error("Archives are read-only");
+        return;
+    }
+    let Some(format) = ArchiveFormat::detect(&archive) else {
+        app.notify_error(format!("{} is not a supported archive", archive.display()));
+        return;
+    };
+
+    if format == ArchiveFormat::Zip {
+        match archive::zip_needs_password(&archive) {
+            Ok(true) => {
+                app.show_popup = Some(PopupType::ExtractPassword(ExtractPasswordState {
+                    archive,
+                    selection,
+                    password: String::new(),
+                }));
+                return;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                app.notify_error(e);
+                return;
+            }
+        }
+    }
+    start_job(app, archive, format, selection, None);
+}
+
+/// First name for the extraction directory that is not taken yet
+fn destination_for(archive: &Path) -> Option<PathBuf> {
+    let parent = archive.parent()?;
+    let name = archive::destination_name(archive);
+    let mut destination = parent.join(&name);
+    let mut counter = 1;
+    while destination.symlink_metadata().is_ok() {
+        counter += 1;
+        destination = parent.join(format!("{name} ({counter})"));
+    }
+    Some(destination)
+}
+
+fn start_job(
+    app: &mut Kiorg,
+    archive: PathBuf,
+    format: ArchiveFormat,
+    selection: Vec<String>,
+    password: Option<String>,
+) {
+    let Some(destination) = destination_for(&archive) else {
+        app.notify_error(format!("Cannot extract {}", archive.display()));
+        return;
+    };
+    let destination_name = destination
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+
+    let request = ExtractRequest {
+        archive,
+        format,
+        destination,
+        selection,
+        password,
+    };
+    app.show_popup = None;
+
+    let job = spawn_job("Extracting", move |ctx| {
+        let backup = stash::reserve(&request.destination)?;
+        std::fs::create_dir(&request.destination)
+            .map_err(|e| format!("Failed to create {}: {e}", request.destination.display()))?;
+        // Recorded up front so a failed or cancelled extraction can be undone too
+        ctx.record(JobRecord::Extract(ExtractOperation {
+            archive_path: request.archive.clone(),
+            target_path: request.destination.clone(),
+            backup,
+        }));
+        archive::extract(ctx, &request)
+    })
+    .with_success_message(format!("Extracted to {destination_name}"));
+    job_progress::start(app, job);
+}
+
+/// Start the extraction with the entered password
+pub fn confirm_password(app: &mut Kiorg) {
+    if let Some(PopupType::ExtractPassword(state)) = app.show_popup.take() {
+        start_job(
+            app,
+            state.archive,
+            ArchiveFormat::Zip,
+            state.selection,
+            Some(state.password),
+        );
+    }
+}
+
+pub fn cancel_password(app: &mut Kiorg) {
+    app.show_popup = None;
+}
+
+/// Draw the password prompt for encrypted zip archives
+pub fn draw(ctx: &Context, app: &mut Kiorg) {
+    let Some(PopupType::ExtractPassword(state)) = &mut app.show_popup else {
+        return;
+    };
+
+    let mut keep_open = true;
+    let archive_name = state
+        .archive
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+
+    new_center_popup_window("Archive Password")
+        .open(&mut keep_open)
+        .show(ctx, |ui| {
+            ui.label(format!("{archive_name} is encrypted"));
+            ui.add_space(5.0);
+            Frame::default()
+                .fill(app.colors.bg_extreme)
+                .inner_margin(5.0)
+                .show(ui, |ui| {
+                    ui.set_max_width(400.0);
+                    let response = ui.add(
+                        TextEdit::singleline(&mut state.password)
+                            .password(true)
+                            .hint_text("Enter password...")
+                            .desired_width(f32::INFINITY)
+                            .frame(false),
+                    );
+                    response.request_focus();
+                });
+        });
+
+    if !keep_open {
+        cancel_password(app);
+    }
+}
@@ -5,8 +5,7 @@ use super::window_utils::new_center_popup_window;
 use crate::app::Clipboard;
 use crate::app::Kiorg;
 use crate::config::shortcuts::ShortcutAction;
-use crate::ui::center_panel::handle_clipboard_operations;
-use crate::ui::popup::PopupType;
+use crate::ui::popup::{PopupType, paste_conflict};
 
 /// File drop operation types
 #[derive(Clone, Copy, PartialEq)]
@@ -88,36 +87,20 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
         // Handle the action
         match action {
             FileDropAction::Copy => {
-                app.clipboard = Some(Clipboard::Copy(dropped_files));
-
-                let current_tab = app.tab_manager.current_tab_mut();
-                if handle_clipboard_operations(
-                    &mut app.clipboard,
-                    &current_tab.current_path,
-                    &mut current_tab.action_history,
-                    &mut app.toasts,
-                ) {
-                    app.refresh_entries();
-                    app.toasts.success("Files copied successfully!");
-                }
-
                 app.show_popup = None;
+                paste_dropped_files(
+                    app,
+                    Clipboard::Copy(dropped_files),
+                    "Files copied successfully!",
+                );
             }
             FileDropAction::Move => {
-                app.clipboard = Some(Clipboard::Cut(dropped_files));
-
-                let current_tab = app.tab_manager.current_tab_mut();
-                if handle_clipboard_operations(
-                    &mut app.clipboard,
-                    &current_tab.current_path,
-                    &mut current_tab.action_history,
-                    &mut app.toasts,
-                ) {
-                    app.refresh_entries();
-                    app.toasts.success("Files moved successfully!");
-                }
-
                 app.show_popup = None;
+                paste_dropped_files(
+                    app,
+                    Clipboard::Cut(dropped_files),
+                    "Files moved successfully!",
+                );
             }
             FileDropAction::Cancel => {
                 app.show_popup = None;
@@ -132,6 +115,17 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
     }
 }
 
+/// Paste the dropped files into the current directory, asking about conflicts first
+fn paste_dropped_files(app: &mut Kiorg, clipboard: Clipboard, success_message: &str) {
+    let current_path = app.tab_manager.current_tab_ref().current_path.clone();
+    paste_conflict::start(
+        app,
+        clipboard,
+        &current_path,
+        Some(success_message.to_string()),
+    );
+}
+
 pub(crate) fn handle_key_press(
     ctx: &Context,
     app: &mut Kiorg,
@@ -145,37 +139,21 @@ pub(crate) fn handle_key_press(
                 return true; // Input handled
             }
             ShortcutAction::CopyEntry => {
-                app.clipboard = Some(Clipboard::Copy(dropped_files));
-
-                let current_tab = app.tab_manager.current_tab_mut();
-                if handle_clipboard_operations(
-                    &mut app.clipboard,
-                    &current_tab.current_path,
-                    &mut current_tab.action_history,
-                    &mut app.toasts,
-                ) {
-                    app.refresh_entries();
-                    app.toasts.success("Files copied successfully!");
-                }
-
                 app.show_popup = None;
+                paste_dropped_files(
+                    app,
+                    Clipboard::Copy(dropped_files),
+                    "Files copied successfully!",
+                );
                 return true; // Input handled
             }
             ShortcutAction::CutEntry => {
-                app.clipboard = Some(Clipboard::Cut(dropped_files));
-
-                let current_tab = app.tab_manager.current_tab_mut();
-                if handle_clipboard_operations(
-                    &mut app.clipboard,
-                    &current_tab.current_path,
-                    &mut current_tab.action_history,
-                    &mut app.toasts,
-                ) {
-                    app.refresh_entries();
-                    app.toasts.success("Files moved successfully!");
-                }
-
                 app.show_popup = None;
+                paste_dropped_files(
+                    app,
+                    Clipboard::Cut(dropped_files),
+                    "Files moved successfully!",
+                );
                 return true; // Input handled
             }
             _ => {}
@@ -0,0 +1,126 @@
+//! Find files and directories anywhere below the current directory
+//!
+//! Unlike the search filter, which only covers the entries of the current
+//! directory, this matches the relative paths of the whole subtree.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::PopupType;
+use crate::app::Kiorg;
+use crate::ui::popup::fuzzy_search_popup::{
+    FuzzyMatchResult, FuzzySearchAction, FuzzySearchItem, FuzzySearchPopupConfig, FuzzySearchState,
+};
+use crate::utils::find::{FileIndex, FindEntry};
+use crate::vfs;
+
+/// Number of best matches listed
+const MAX_RESULTS: usize = 100;
+
+/// State for the find popup
+#[derive(Debug)]
+pub struct FindState {
+    pub index: FileIndex,
+    pub fuzzy_state: FuzzySearchState,
+}
+
+impl FuzzySearchItem for FindEntry {
+    fn display_text(&self) -> Cow<'_, str> {
+        if self.is_dir {
+            Cow::Owned(format!("{}/", self.relative))
+        } else {
+            Cow::Borrowed(&self.relative)
+        }
+    }
+
+    fn search_text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.relative)
+    }
+}
+
+/// Open the popup and start indexing the current directory
+pub fn open(ctx: &egui::Context, app: &mut Kiorg) {
+    let root = app.tab_manager.current_tab_ref().current_path.clone();
+    if !vfs::is_local(&root) {
+        app.notify_error("Find only works in local directories");
+        return;
+    }
+    let ctx = ctx.clone();
+    let index = FileIndex::start(
+        root,
+        app.tab_manager.show_hidden,
+        Arc::new(move || ctx.request_repaint()),
+    );
+    app.show_popup = Some(PopupType::Find(FindState {
+        index,
+        fuzzy_state: FuzzySearchState::default(),
+    }));
+}
+
+/// Draw the find popup
+pub fn draw(ctx: &egui::Context, app: &mut Kiorg) {
+    let Some(PopupType::Find(state)) = &mut app.show_popup else {
+        return;
+    };
+
+    state.fuzzy_state.update_query();
+    state.index.set_query(&state.fuzzy_state.query);
+    let busy = state.index.tick();
+    if busy {
+        ctx.request_repaint_after(Duration::from_millis(50));
+    }
+
+    // Already ranked by the matcher, the score is not needed for ordering
+    let results: Vec<FuzzyMatchResult<FindEntry>> = state
+        .index
+        .results(MAX_RESULTS)
+        .into_iter()
+        .map(|found| FuzzyMatchResult {
+            item: found.entry,
+            score: 0,
+            indices: found.indices,
+        })
+        .collect();
+
+    let search_hint = format!(
+        "Find in {} ({} entries{})",
+        state.index.root().display(),
+        state.index.indexed_count(),
+        if busy { ", indexing..." } else { "" }
+    );
+    let config = FuzzySearchPopupConfig {
+        title: "Find",
+        search_hint: &search_hint,
+        empty_message: if busy {
+            "Indexing..."
+        } else {
+            "No entries found"
+        },
+        no_match_message: if busy {
+            "No matches yet, indexing..."
+        } else {
+            "No matching entries found"
+        },
+        max_visible_results: Some(MAX_RESULTS),
+    };
+
+    let action = crate::ui::popup::fuzzy_search_popup::draw(
+        ctx,
+        &config,
+        &app.colors,
+        &mut state.fuzzy_state,
+        &results,
+    );
+
+    match action {
+        FuzzySearchAction::KeepOpen => {}
+        FuzzySearchAction::Close => {
+            app.show_popup = None;
+        }
+        FuzzySearchAction::Selected(entry) => {
+            app.show_popup = None;
+            app.reveal_path(&entry.path);
+        }
+    }
+}
@@ -4,6 +4,7 @@
 //! a search bar with fuzzy matching and a scrollable list of selectable items.
 
 use crate::config::colors::AppColors;
+use crate::ui::style::highlighted_text_job;
 use egui::{Align, Color32, Frame, Key, Layout, Shadow, TextEdit, Vec2};
 use nucleo::{Config as NucleoConfig, Matcher, Utf32Str};
 use std::borrow::Cow;
@@ -68,6 +69,9 @@ impl FuzzySearchState {
 pub struct FuzzyMatchResult<T> {
     pub item: T,
     pub score: u16,
+    /// Matched char indices in the item's search text, highlighted in the
+    /// label when the label starts with the search text.
+    pub indices: Vec<u32>,
 }
 
 /// Perform fuzzy filtering on a list of items.
@@ -78,6 +82,7 @@ pub fn fuzzy_filter<T: FuzzySearchItem>(query: &str, items: &[T]) -> Vec<FuzzyMa
             .map(|item| FuzzyMatchResult {
                 item: item.clone(),
                 score: 0,
+                indices: Vec::new(),
             })
             .collect();
     }
@@ -96,12 +101,15 @@ pub fn fuzzy_filter<T: FuzzySearchItem>(query: &str, items: &[T]) -> Vec<FuzzyMa
             let search_text = item.search_text();
             let mut haystack_buf = Vec::new();
             let haystack_utf32 = Utf32Str::new(&search_text, &mut haystack_buf);
-            matcher
-                .fuzzy_match(haystack_utf32, needle_utf32)
-                .map(|score| FuzzyMatchResult {
-                    item: item.clone(),
-                    score,
-                })
+            let mut indices = Vec::new();
+            let score = matcher.fuzzy_indices(haystack_utf32, needle_utf32, &mut indices)?;
+            indices.sort_unstable();
+            indices.dedup();
+            Some(FuzzyMatchResult {
+                item: item.clone(),
+                score,
+                indices,
+            })
         })
         .collect();
 
@@ -314,11 +322,19 @@ fn render_item_list<T: FuzzySearchItem>(
                 );
                 content_ui.horizontal(|ui| {
                     ui.add_space(8.0);
-                    ui.label(
-                        egui::RichText::new(result.item.display_text().as_ref())
-                            .color(text_color)
-                            .size(14.0),
-                    );
+                    let display_text = result.item.display_text();
+                    let indices: &[u32] =
+                        if display_text.starts_with(result.item.search_text().as_ref()) {
+                            &result.indices
+                        } else {
+                            &[]
+                        };
+                    ui.label(highlighted_text_job(
+                        &display_text,
+                        indices,
+                        egui::TextFormat::simple(egui::FontId::proportional(14.0), text_color),
+                        colors.highlight,
+                    ));
                     if let Some(secondary) = result.item.secondary_text() {
                         ui.weak(secondary.as_ref());
                     }
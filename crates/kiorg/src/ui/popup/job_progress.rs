@@ -0,0 +1,149 @@
+use egui::{Context, ProgressBar};
+use humansize::{BINARY, format_size};
+use std::time::Duration;
+
+use super::PopupType;
+use super::window_utils::new_center_popup_window;
+use crate::app::Kiorg;
+use crate::utils::jobs::{JOB_GRACE_PERIOD, JobData, JobStatus};
+use crate::utils::stash;
+
+/// Track a freshly spawned job. Jobs that complete within the grace period are
+/// finished right away, longer ones switch to the progress popup.
+pub fn start(app: &mut Kiorg, mut job: JobData) {
+    if job.wait(JOB_GRACE_PERIOD) {
+        finish(app, job);
+    } else {
+        app.show_popup = Some(PopupType::JobProgress(job));
+    }
+}
+
+/// Request cancellation of the job shown in the progress popup
+pub fn cancel_job(app: &mut Kiorg) {
+    if let Some(PopupType::JobProgress(ref job)) = app.show_popup {
+        job.cancel();
+    }
+}
+
+/// Handle progress popup UI
+pub fn handle_job_progress(ctx: &Context, app: &mut Kiorg) {
+    let mut finished = false;
+
+    // Check for progress updates
+    if let Some(PopupType::JobProgress(ref mut job)) = app.show_popup {
+        if job.poll() {
+            ctx.request_repaint();
+        }
+        finished = job.is_finished();
+    }
+
+    // Handle cleanup outside of the borrow
+    if finished {
+        if let Some(PopupType::JobProgress(job)) = app.show_popup.take() {
+            finish(app, job);
+        }
+        return;
+    }
+
+    let mut cancel_clicked = false;
+
+    // Show progress popup
+    if let Some(PopupType::JobProgress(ref job)) = app.show_popup {
+        let state = &job.state;
+        new_center_popup_window(&state.title).show(ctx, |ui| {
+            ui.set_min_width(400.0);
+
+            ui.vertical_centered(|ui| {
+                ui.add_space(10.0);
+
+                ui.add(ProgressBar::new(state.fraction()).desired_width(350.0));
+
+                ui.add_space(10.0);
+
+                // Status text
+                ui.label(format!(
+                    "{} / {} files",
+                    state.current_file, state.total_files
+                ));
+                if state.total_bytes > 0 {
+                    ui.label(format!(
+                        "{} / {} ({}/s)",
+                        format_size(state.bytes_done, BINARY),
+                        format_size(state.total_bytes, BINARY),
+                        format_size(state.throughput() as u64, BINARY),
+                    ));
+                }
+                if let Some(eta) = state.eta() {
+                    ui.label(format!("About {}s remaining", eta.as_secs() + 1));
+                }
+
+                ui.add_space(5.0);
+
+                // Current file being processed
+                if !state.current_path.is_empty() {
+                    ui.label(state.current_path.as_str());
+                }
+
+                ui.add_space(10.0);
+
+                let cancel_label = if job.is_cancel_requested() {
+                    "Cancelling..."
+                } else {
+                    "Cancel (Esc)"
+                };
+                if ui
+                    .add_enabled(!job.is_cancel_requested(), egui::Button::new(cancel_label))
+                    .clicked()
+                {
+                    cancel_clicked = true;
+                }
+            });
+        });
+
+        // Keep throughput and elapsed time fresh even when no update arrives
+        ctx.request_repaint_after(Duration::from_millis(100));
+    }
+
+    if cancel_clicked {
+        cancel_job(app);
+    }
+}
+
+/// Record completed operations and report the outcome of a finished job
+fn finish(app: &mut Kiorg, job: JobData) {
+    let state = job.state;
+
+    let tab = app.tab_manager.current_tab_mut();
+    for action in state.to_actions() {
+        tab.action_history.add_action(action);
+    }
+    // Marked entries were the job input, jobs that touched nothing keep them
+    if !state.records.is_empty() {
+        tab.marked_entries.clear();
+    }
+    app.refresh_entries();
+
+    if state.used_stash() {
+        stash::enforce_retention_in_background(stash::Retention::from_config(&app.config));
+    }
+
+    for error in &state.errors {
+        app.toasts.error(error.clone());
+    }
+
+    match state.status {
+        JobStatus::Cancelled => {
+            app.notify_info(format!(
+                "{} cancelled, {} item(s) completed",
+                state.title,
+                state.records.len()
+            ));
+        }
+        JobStatus::Completed if state.errors.is_empty() => {
+            if let Some(message) = job.success_message {
+                app.toasts.success(message);
+            }
+        }
+        _ => {}
+    }
+}
@@ -35,14 +35,22 @@ pub mod about;
 pub mod action_history;
 pub mod add_entry;
 pub mod bookmark;
+pub mod bulk_rename;
+pub mod command_palette;
+pub mod compress;
+pub mod content_search;
 pub mod delete;
 pub mod ebook_viewer;
 pub mod exit;
+pub mod extract;
 pub mod file_drop;
+pub mod find;
 pub mod fuzzy_search_popup;
 pub mod generic_message;
 pub mod image_viewer;
+pub mod job_progress;
 pub mod open_with;
+pub mod paste_conflict;
 pub mod pdf_viewer;
 pub mod plugin;
 pub mod plugin_viewer;
@@ -52,6 +60,7 @@ pub mod sort_toggle;
 pub mod teleport;
 pub mod text_input_popup;
 pub mod theme;
+pub mod trash;
 pub mod utils;
 pub mod video_viewer;
 #[cfg(target_os = "macos")]
@@ -67,29 +76,41 @@ pub enum PopupType {
     Help,
     Exit,
     GenericMessage(String, String), // Title and message for generic popup
-    Delete(crate::ui::popup::delete::DeleteConfirmState, Vec<PathBuf>),
-    DeleteProgress(crate::ui::popup::delete::DeleteProgressData),
-    Rename(String),   // New name for the file/directory being renamed
-    OpenWith,         // Open file with custom command popup
-    AddEntry(String), // Name for the new file/directory being added
-    Bookmarks(usize), // Selected index in the bookmarks list
+    Delete(
+        crate::ui::popup::delete::DeleteConfirmState,
+        Vec<PathBuf>,
+        crate::ui::popup::delete::DeleteMode,
+    ),
+    JobProgress(crate::utils::jobs::JobData), // Background copy/move job progress
+    Rename(String),                           // New name for the file/directory being renamed
+    OpenWith,                                 // Open file with custom command popup
+    AddEntry(String),                         // Name for the new file/directory being added
+    Bookmarks(usize),                         // Selected index in the bookmarks list
     #[cfg(target_os = "windows")]
     WindowsDrives(usize), // Selected index in the drives list (Windows only)
     #[cfg(target_os = "macos")]
     Volumes(usize), // Selected index in the volumes list (macOS only)
-    Preview,          // Show file preview in a popup window
+    Preview,                                  // Show file preview in a popup window
     Pdf(Box<crate::ui::popup::pdf_viewer::PdfViewer>), // PDF app
     Ebook(Box<crate::ui::popup::ebook_viewer::EbookViewer>), // Ebook app
     Image(Box<crate::ui::popup::image_viewer::ImageViewer>), // Image app
     Video(Box<crate::ui::popup::video_viewer::VideoViewer>), // Video app
     Plugin(Box<crate::ui::popup::plugin_viewer::PluginViewer>), // Plugin app
-    Themes(String),   // Selected theme key in the themes list
-    Plugins,          // Show plugins list
-    FileDrop(Vec<PathBuf>), // List of dropped files
+    Themes(String),                           // Selected theme key in the themes list
+    Plugins,                                  // Show plugins list
+    FileDrop(Vec<PathBuf>),                   // List of dropped files
     Teleport(crate::ui::popup::teleport::TeleportState), // Teleport through visit history
-    UpdateConfirm(Release), // Show update confirmation with version info
+    UpdateConfirm(Release),                   // Show update confirmation with version info
     UpdateProgress(crate::ui::update::UpdateProgressData), // Show update progress during download
-    UpdateRestart,    // Show restart confirmation with version info
-    SortToggle,       // Show sort toggle popup for column sorting
-    ActionHistory,    // Show action history with rollback options
+    UpdateRestart,                            // Show restart confirmation with version info
+    SortToggle,                               // Show sort toggle popup for column sorting
+    ActionHistory,                            // Show action history with rollback options
+    Trash(crate::ui::popup::trash::TrashState), // Browse, restore and purge trashed items
+    PasteConflict(crate::ui::popup::paste_conflict::PasteConflictState), // Resolve existing paste targets
+    BulkRename(crate::ui::popup::bulk_rename::BulkRenameState), // Rename entries by pattern
+    ExtractPassword(crate::ui::popup::extract::ExtractPasswordState), // Password for an encrypted archive
+    Compress(crate::ui::popup::compress::CompressState), // Name and format of a new archive
+    ContentSearch(crate::ui::popup::content_search::ContentSearchState), // Search file contents recursively
+    Find(crate::ui::popup::find::FindState), // Find entries in the subtree by path
+    CommandPalette(crate::ui::popup::fuzzy_search_popup::FuzzySearchState), // Run plugin commands
 }
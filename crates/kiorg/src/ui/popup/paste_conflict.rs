@@ -0,0 +1,406 @@
+//! Paste conflict popup asking how to handle entries that already exist in the
+//! paste target before the copy/move job starts
+
+use chrono::{DateTime, Local};
+use egui::{Context, Key, RichText};
+use humansize::{BINARY, format_size};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use super::window_utils::new_center_popup_window;
+use super::{PopupType, job_progress};
+use crate::app::{Clipboard, Kiorg};
+use crate::ui::center_panel::{overwrite_is_permanent, start_clipboard_job};
+use crate::vfs;
+
+/// How to handle a paste target that already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PasteResolution {
+    /// Replace the existing entry, it is moved to the undo stash so undo can restore it.
+    /// Entries on other devices go to the trash of their mount, and are removed
+    /// for good where there is none.
+    Overwrite,
+    /// Leave the existing entry alone and do not paste
+    Skip,
+    /// Paste under a new `_N` suffixed name
+    Rename,
+    /// Paste the directory content into the existing directory
+    Merge,
+    /// Overwrite only when the pasted entry was modified more recently
+    KeepNewer,
+}
+
+impl PasteResolution {
+    const ALL: [Self; 5] = [
+        Self::Overwrite,
+        Self::Skip,
+        Self::Rename,
+        Self::Merge,
+        Self::KeepNewer,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Overwrite => "Overwrite (o)",
+            Self::Skip => "Skip (s)",
+            Self::Rename => "Rename (r)",
+            Self::Merge => "Merge (m)",
+            Self::KeepNewer => "Keep newer (n)",
+        }
+    }
+
+    fn from_key(key: Key) -> Option<Self> {
+        match key {
+            Key::O => Some(Self::Overwrite),
+            Key::S => Some(Self::Skip),
+            Key::R => Some(Self::Rename),
+            Key::M => Some(Self::Merge),
+            Key::N => Some(Self::KeepNewer),
+            _ => None,
+        }
+    }
+}
+
+/// Resolutions chosen per conflicting source path, consumed by the paste job
+pub type PasteResolutions = HashMap<PathBuf, PasteResolution>;
+
+/// A pasted entry whose target path is already taken
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasteConflict {
+    pub source: PathBuf,
+    pub target: PathBuf,
+}
+
+impl PasteConflict {
+    /// Detect a conflict for pasting `source` to `target`
+    ///
+    /// Pasting an entry onto itself is not a conflict, the job renames it.
+    fn detect(source: &Path, target: &Path) -> Option<Self> {
+        if source == target || !vfs::exists(target) {
+            return None;
+        }
+        Some(Self {
+            source: source.to_path_buf(),
+            target: target.to_path_buf(),
+        })
+    }
+
+    /// Merging only makes sense for two real directories
+    pub fn can_merge(&self) -> bool {
+        is_real_dir(&self.source) && is_real_dir(&self.target)
+    }
+
+    fn allows(&self, resolution: PasteResolution) -> bool {
+        resolution != PasteResolution::Merge || self.can_merge()
+    }
+
+    /// Conflicts between the children of two directories being merged
+    fn children(&self) -> Vec<Self> {
+        let Ok(entries) = vfs::for_path(&self.source).list(&self.source) else {
+            return Vec::new();
+        };
+        let mut children: Vec<Self> = entries
+            .into_iter()
+            .filter_map(|entry| Self::detect(&entry.path, &self.target.join(&entry.name)))
+            .collect();
+        children.sort_by(|a, b| a.source.cmp(&b.source));
+        children
+    }
+}
+
+fn is_real_dir(path: &Path) -> bool {
+    vfs::for_path(path)
+        .stat(path)
+        .is_ok_and(|meta| meta.is_dir && !meta.is_symlink)
+}
+
+/// State of the paste conflict popup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasteConflictState {
+    pub clipboard: Clipboard,
+    pub target_dir: PathBuf,
+    /// Conflicts still waiting for a decision, the front one is shown
+    pub pending: VecDeque<PasteConflict>,
+    pub resolutions: PasteResolutions,
+    pub apply_to_all: bool,
+    pub success_message: Option<String>,
+    /// Put the entries back on the clipboard when the paste is cancelled
+    pub restore_clipboard: bool,
+}
+
+impl PasteConflictState {
+    /// Record the resolution for the front conflict
+    /// Returns false if the resolution does not apply to it
+    fn resolve_front(&mut self, resolution: PasteResolution) -> bool {
+        let Some(conflict) = self.pending.front() else {
+            return false;
+        };
+        if !conflict.allows(resolution) {
+            return false;
+        }
+
+        let conflict = self.pending.pop_front().unwrap_or_else(|| unreachable!());
+        if resolution == PasteResolution::Merge {
+            // Conflicts inside the merged directory are asked about next
+            for child in conflict.children().into_iter().rev() {
+                self.pending.push_front(child);
+            }
+        }
+        self.resolutions.insert(conflict.source, resolution);
+        true
+    }
+}
+
+/// Paste `clipboard` into `target_dir`, asking how to resolve conflicts first
+pub fn start(
+    app: &mut Kiorg,
+    clipboard: Clipboard,
+    target_dir: &Path,
+    success_message: Option<String>,
+) {
+    let sources = match &clipboard {
+        Clipboard::Copy(paths) | Clipboard::Cut(paths) => paths,
+    };
+    let pending: VecDeque<PasteConflict> = sources
+        .iter()
+        .filter_map(|source| {
+            let name = source.file_name()?;
+            PasteConflict::detect(source, &target_dir.join(name))
+        })
+        .collect();
+
+    if pending.is_empty() {
+        run(
+            app,
+            clipboard,
+            target_dir,
+            PasteResolutions::new(),
+            success_message,
+        );
+        return;
+    }
+
+    app.show_popup = Some(PopupType::PasteConflict(PasteConflictState {
+        clipboard,
+        target_dir: target_dir.to_path_buf(),
+        pending,
+        resolutions: PasteResolutions::new(),
+        apply_to_all: false,
+        success_message,
+        restore_clipboard: false,
+    }));
+}
+
+fn run(
+    app: &mut Kiorg,
+    clipboard: Clipboard,
+    target_dir: &Path,
+    resolutions: PasteResolutions,
+    success_message: Option<String>,
+) {
+    if let Some(mut job) = start_clipboard_job(Some(clipboard), target_dir, resolutions) {
+        if let Some(message) = success_message {
+            job = job.with_success_message(message);
+        }
+        job_progress::start(app, job);
+    }
+}
+
+/// Apply a resolution to the shown conflict, and to the remaining ones when
+/// "apply to all" is enabled. Starts the paste once nothing is left to ask.
+pub fn resolve(app: &mut Kiorg, resolution: PasteResolution) {
+    let Some(PopupType::PasteConflict(state)) = &mut app.show_popup else {
+        return;
+    };
+    if !state.resolve_front(resolution) {
+        return;
+    }
+    if state.apply_to_all {
+        // Stop at the first conflict the resolution cannot handle
+        while state.resolve_front(resolution) {}
+    }
+    if !state.pending.is_empty() {
+        return;
+    }
+
+    if let Some(PopupType::PasteConflict(state)) = app.show_popup.take() {
+        run(
+            app,
+            state.clipboard,
+            &state.target_dir,
+            state.resolutions,
+            state.success_message,
+        );
+    }
+}
+
+/// Abort the paste, entries pasted from the clipboard go back onto it
+pub fn cancel(app: &mut Kiorg) {
+    if let Some(PopupType::PasteConflict(state)) = app.show_popup.take()
+        && state.restore_clipboard
+    {
+        app.clipboard = Some(state.clipboard);
+    }
+}
+
+/// Toggle applying the next resolution to all remaining conflicts
+pub fn toggle_apply_to_all(app: &mut Kiorg) {
+    if let Some(PopupType::PasteConflict(state)) = &mut app.show_popup {
+        state.apply_to_all = !state.apply_to_all;
+    }
+}
+
+/// Handle key presses while the popup is open
+pub fn handle_key_press(app: &mut Kiorg, key: Key) {
+    if key == Key::A {
+        toggle_apply_to_all(app);
+    } else if let Some(resolution) = PasteResolution::from_key(key) {
+        resolve(app, resolution);
+    }
+}
+
+/// Short description of an entry for comparing both sides of a conflict
+fn describe_entry(path: &Path) -> String {
+    let Ok(meta) = vfs::for_path(path).stat(path) else {
+        return "missing".to_string();
+    };
+    let modified = DateTime::<Local>::from(meta.modified)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    if meta.is_dir && !meta.is_symlink {
+        format!("directory, modified {modified}")
+    } else {
+        format!("{}, modified {modified}", format_size(meta.size, BINARY))
+    }
+}
+
+/// Draw the paste conflict popup
+pub fn draw(ctx: &Context, app: &mut Kiorg) {
+    let Some(PopupType::PasteConflict(state)) = &mut app.show_popup else {
+        return;
+    };
+    let Some(conflict) = state.pending.front().cloned() else {
+        return;
+    };
+    let remaining = state.pending.len() - 1;
+    let permanent = overwrite_is_permanent(&conflict.target);
+    let error_color = app.colors.error;
+
+    let mut keep_open = true;
+    let mut cancelled = false;
+    let mut chosen = None;
+    let mut apply_to_all = state.apply_to_all;
+
+    new_center_popup_window("Paste Conflict")
+        .open(&mut keep_open)
+        .max_width(500.0)
+        .show(ctx, |ui| {
+            ui.vertical(|ui| {
+                ui.label(RichText::new(conflict.target.display().to_string()).strong());
+                ui.label("already exists.");
+                ui.add_space(5.0);
+
+                egui::Grid::new("paste_conflict_grid").show(ui, |ui| {
+                    ui.label("Pasted:");
+                    ui.label(describe_entry(&conflict.source));
+                    ui.end_row();
+                    ui.label("Existing:");
+                    ui.label(describe_entry(&conflict.target));
+                    ui.end_row();
+                });
+                if permanent {
+                    ui.add_space(5.0);
+                    ui.label(RichText::new("Overwriting it cannot be undone!").color(error_color));
+                }
+
+                ui.add_space(10.0);
+                let label = if remaining > 0 {
+                    format!("Apply to all {remaining} remaining conflicts (a)")
+                } else {
+                    "Apply to all conflicts (a)".to_string()
+                };
+                ui.checkbox(&mut apply_to_all, label);
+
+                ui.add_space(10.0);
+                ui.horizontal_wrapped(|ui| {
+                    for resolution in PasteResolution::ALL {
+                        let button = egui::Button::new(resolution.label());
+                        if ui
+                            .add_enabled(conflict.allows(resolution), button)
+                            .clicked()
+                        {
+                            chosen = Some(resolution);
+                        }
+                    }
+                    if ui.button("Cancel (Esc)").clicked() {
+                        cancelled = true;
+                    }
+                });
+            });
+        });
+
+    state.apply_to_all = apply_to_all;
+    if !keep_open || cancelled {
+        cancel(app);
+    } else if let Some(resolution) = chosen {
+        resolve(app, resolution);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn state_for(conflicts: Vec<PasteConflict>) -> PasteConflictState {
+        PasteConflictState {
+            clipboard: Clipboard::Copy(Vec::new()),
+            target_dir: PathBuf::new(),
+            pending: conflicts.into(),
+            resolutions: PasteResolutions::new(),
+            apply_to_all: false,
+            success_message: None,
+            restore_clipboard: false,
+        }
+    }
+
+    #[test]
+    fn test_merge_queues_nested_conflicts() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("src/dir");
+        let target = temp_dir.path().join("dst/dir");
+        std::fs::create_dir_all(source.join("sub")).unwrap();
+        std::fs::create_dir_all(target.join("sub")).unwrap();
+        std::fs::write(source.join("a.txt"), "new").unwrap();
+        std::fs::write(target.join("a.txt"), "old").unwrap();
+        std::fs::write(source.join("only_in_source.txt"), "").unwrap();
+
+        let conflict = PasteConflict::detect(&source, &target).unwrap();
+        let mut state = state_for(vec![conflict]);
+
+        assert!(state.resolve_front(PasteResolution::Merge));
+        let pending: Vec<_> = state.pending.iter().map(|c| c.source.clone()).collect();
+        assert_eq!(pending, vec![source.join("a.txt"), source.join("sub")]);
+
+        // Files cannot be merged
+        assert!(!state.resolve_front(PasteResolution::Merge));
+        assert!(state.resolve_front(PasteResolution::Overwrite));
+        assert_eq!(
+            state.resolutions.get(&source.join("a.txt")),
+            Some(&PasteResolution::Overwrite)
+        );
+        assert_eq!(
+            state.resolutions.get(&source),
+            Some(&PasteResolution::Merge)
+        );
+    }
+
+    #[test]
+    fn test_pasting_onto_itself_is_not_a_conflict() {
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("a.txt");
+        std::fs::write(&file, "").unwrap();
+        assert!(PasteConflict::detect(&file, &file).is_none());
+        assert!(PasteConflict::detect(&file, &temp_dir.path().join("b.txt")).is_none());
+    }
+}
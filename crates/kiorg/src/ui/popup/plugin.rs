@@ -1,17 +1,42 @@
 use crate::app::Kiorg;
-use crate::config::shortcuts::ShortcutAction;
+use crate::config::{self, PluginsConfig, shortcuts::ShortcutAction};
+use crate::plugins::health::PluginStats;
 use crate::plugins::manager::{FailedPlugin, LoadedPlugin};
 use egui_extras::{Column, TableBuilder};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::window_utils::show_center_popup_window;
 
+/// Memory key of the name of the plugin whose log is shown
+const SHOWN_LOG_ID: &str = "plugins_popup_shown_log";
+
+/// A button clicked in the plugins table
+enum PluginAction {
+    ToggleLog(String),
+    Reload(String),
+    SetDisabled(String, bool),
+    /// Enable a plugin that was disabled when kiorg started
+    Start(PathBuf),
+}
+
+fn format_latency(stats: &PluginStats) -> String {
+    let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    match stats.average_latency() {
+        Some(average) => format!("{:.1}ms / {:.1}ms", ms(average), ms(stats.max_latency)),
+        None => "-".to_string(),
+    }
+}
+
 /// Helper function to display plugins in a table layout
 fn display_plugins_table<'a>(
     ui: &mut egui::Ui,
     plugins: impl Iterator<Item = (&'a String, &'a Arc<LoadedPlugin>)>,
+    not_started: &[PathBuf],
     colors: &crate::config::colors::AppColors,
-) {
+) -> Option<PluginAction> {
+    let mut action = None;
     TableBuilder::new(ui)
         .striped(true)
         .resizable(true)
@@ -19,46 +44,73 @@ fn display_plugins_table<'a>(
         .column(Column::auto().resizable(true))
         .column(Column::auto().resizable(true))
         .column(Column::auto().resizable(true))
+        .column(Column::auto().resizable(true))
+        .column(Column::auto().resizable(true))
+        .column(Column::auto().resizable(true))
+        .column(Column::auto().resizable(true))
         .column(Column::remainder())
+        .column(Column::auto())
         .header(20.0, |mut header| {
-            header.col(|ui| {
-                ui.colored_label(colors.fg_light, "Name");
-            });
-            header.col(|ui| {
-                ui.colored_label(colors.fg_light, "Version");
-            });
-            header.col(|ui| {
-                ui.colored_label(colors.fg_light, "Load Time");
-            });
-            header.col(|ui| {
-                ui.colored_label(colors.fg_light, "Description");
-            });
+            for title in [
+                "Name",
+                "Version",
+                "Load Time",
+                "Calls",
+                "Failures",
+                "Latency (avg / max)",
+                "Restarts",
+                "Description",
+                "",
+            ] {
+                header.col(|ui| {
+                    ui.colored_label(colors.fg_light, title);
+                });
+            }
         })
         .body(|mut body| {
             for (plugin_name, plugin) in plugins {
+                let stats = plugin.stats();
+                let disabled = plugin.is_disabled();
                 body.row(18.0, |mut row| {
-                    let (display_name, description, desc_color) =
-                        if let Some(error_msg) = &plugin.state.lock().unwrap().error {
-                            if error_msg.contains("Incompatible protocol version") {
-                                (
-                                    format!("🚨 {}", plugin_name),
-                                    format!("WARN: {}", error_msg),
-                                    colors.warn,
-                                )
-                            } else {
-                                (
-                                    format!("❌ {}", plugin_name),
-                                    format!("ERROR: {}", error_msg),
-                                    colors.error,
-                                )
-                            }
+                    let (display_name, description, desc_color) = if disabled {
+                        (
+                            format!("⏸ {}", plugin_name),
+                            "Disabled".to_string(),
+                            colors.fg_light,
+                        )
+                    } else if plugin.is_starting() {
+                        (
+                            format!("🔄 {}", plugin_name),
+                            "Starting".to_string(),
+                            colors.fg_light,
+                        )
+                    } else if let Some(error_msg) = plugin.error() {
+                        if error_msg.contains("Incompatible protocol version") {
+                            (
+                                format!("🚨 {}", plugin_name),
+                                format!("WARN: {}", error_msg),
+                                colors.warn,
+                            )
+                        } else if let Some(delay) = plugin.restart_in() {
+                            (
+                                format!("🔄 {}", plugin_name),
+                                format!("Restarting in {}s: {}", delay.as_secs().max(1), error_msg),
+                                colors.warn,
+                            )
                         } else {
                             (
-                                plugin_name.to_string(),
-                                plugin.metadata.description.clone(),
-                                colors.fg,
+                                format!("❌ {}", plugin_name),
+                                format!("ERROR: {}", error_msg),
+                                colors.error,
                             )
-                        };
+                        }
+                    } else {
+                        (
+                            plugin_name.to_string(),
+                            plugin.metadata.description.clone(),
+                            colors.fg,
+                        )
+                    };
 
                     // Name
                     row.col(|ui| {
@@ -76,13 +128,142 @@ fn display_plugins_table<'a>(
                         ui.label(time_text);
                     });
 
+                    // Health
+                    row.col(|ui| {
+                        ui.label(stats.responses.to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(stats.failures().to_string());
+                    });
+                    row.col(|ui| {
+                        ui.label(format_latency(&stats));
+                    });
+                    row.col(|ui| {
+                        ui.label(stats.restarts.to_string());
+                    });
+
                     // Description
                     row.col(|ui| {
                         ui.colored_label(desc_color, description);
                     });
+
+                    // Actions
+                    row.col(|ui| {
+                        if ui.small_button("Log").clicked() {
+                            action = Some(PluginAction::ToggleLog(plugin_name.clone()));
+                        }
+                        if ui
+                            .add_enabled(!disabled, egui::Button::new("Reload").small())
+                            .clicked()
+                        {
+                            action = Some(PluginAction::Reload(plugin_name.clone()));
+                        }
+                        let toggle = if disabled { "Enable" } else { "Disable" };
+                        if ui.small_button(toggle).clicked() {
+                            action =
+                                Some(PluginAction::SetDisabled(plugin_name.clone(), !disabled));
+                        }
+                    });
+                });
+            }
+            for path in not_started {
+                body.row(18.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label(format!(
+                            "⏸ {}",
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        ));
+                    });
+                    for _ in 0..6 {
+                        row.col(|ui| {
+                            ui.label("-");
+                        });
+                    }
+                    row.col(|ui| {
+                        ui.colored_label(colors.fg_light, "Disabled, not started");
+                    });
+                    row.col(|ui| {
+                        if ui.small_button("Enable").clicked() {
+                            action = Some(PluginAction::Start(path.clone()));
+                        }
+                    });
                 });
             }
         });
+    action
+}
+
+/// What a plugin wrote to stderr, across restarts
+fn display_log(
+    ui: &mut egui::Ui,
+    plugin_name: &str,
+    plugin: &LoadedPlugin,
+    colors: &crate::config::colors::AppColors,
+) {
+    ui.colored_label(colors.fg_light, format!("Log of {}", plugin_name));
+    let log = plugin.log();
+    if log.is_empty() {
+        ui.label("No output");
+        return;
+    }
+    egui::ScrollArea::vertical()
+        .id_salt("plugin_log")
+        .max_height(200.0)
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for line in log.lines() {
+                ui.label(egui::RichText::new(line).monospace());
+            }
+        });
+}
+
+fn apply_action(app: &mut Kiorg, ctx: &egui::Context, action: PluginAction) {
+    match action {
+        PluginAction::ToggleLog(name) => {
+            let id = egui::Id::new(SHOWN_LOG_ID);
+            ctx.data_mut(|d| {
+                let shown = d.get_temp_mut_or_default::<Option<String>>(id);
+                *shown = if shown.as_ref() == Some(&name) {
+                    None
+                } else {
+                    Some(name)
+                };
+            });
+        }
+        PluginAction::Reload(name) => {
+            let result = match app.plugin_manager.list_loaded().get(&name) {
+                Some(plugin) => plugin.reload(),
+                None => return,
+            };
+            if let Err(e) = result {
+                app.notify_error(format!("Failed to reload plugin '{name}': {e}"));
+            }
+        }
+        PluginAction::SetDisabled(name, disabled) => {
+            if let Err(e) = app.plugin_manager.set_disabled(&name, disabled) {
+                app.notify_error(format!("Failed to change plugin '{name}': {e}"));
+                return;
+            }
+            save_disabled(app);
+        }
+        PluginAction::Start(path) => {
+            if let Err(e) = app.plugin_manager.start_disabled(&path) {
+                app.notify_error(format!("Failed to start plugin: {e}"));
+                return;
+            }
+            save_disabled(app);
+        }
+    }
+}
+
+/// Remember the disabled plugins for the next start
+fn save_disabled(app: &mut Kiorg) {
+    let disabled = app.plugin_manager.disabled().to_vec();
+    app.config.plugins = Some(PluginsConfig { disabled });
+    if let Err(e) = config::save_config_with_override(&app.config, app.config_dir_override.as_ref())
+    {
+        app.notify_error(format!("Failed to save plugin settings: {e}"));
+    }
 }
 
 /// Helper function to display failed plugins in a grid layout
@@ -128,13 +309,32 @@ pub fn draw(app: &mut Kiorg, ctx: &egui::Context) {
 
     let loaded_plugins_map = app.plugin_manager.list_loaded();
     let failed_plugins_map = app.plugin_manager.list_failed();
+    let not_started = app.plugin_manager.list_not_started();
+    let shown_log = ctx.data(|d| {
+        d.get_temp::<Option<String>>(egui::Id::new(SHOWN_LOG_ID))
+            .flatten()
+    });
+    let mut plugin_action = None;
     let _ = show_center_popup_window("Plugins", ctx, &mut keep_open, |ui| {
-        if loaded_plugins_map.is_empty() && failed_plugins_map.is_empty() {
+        if loaded_plugins_map.is_empty() && failed_plugins_map.is_empty() && not_started.is_empty()
+        {
             ui.label("No plugins found");
         } else {
             egui::ScrollArea::vertical().show(ui, |ui| {
-                if !loaded_plugins_map.is_empty() {
-                    display_plugins_table(ui, loaded_plugins_map.iter(), &app.colors);
+                if !loaded_plugins_map.is_empty() || !not_started.is_empty() {
+                    plugin_action = display_plugins_table(
+                        ui,
+                        loaded_plugins_map.iter(),
+                        not_started,
+                        &app.colors,
+                    );
+                }
+
+                if let Some(name) = &shown_log
+                    && let Some(plugin) = loaded_plugins_map.get(name)
+                {
+                    ui.add_space(10.0);
+                    display_log(ui, name, plugin, &app.colors);
                 }
 
                 if !failed_plugins_map.is_empty() {
@@ -155,6 +355,13 @@ pub fn draw(app: &mut Kiorg, ctx: &egui::Context) {
         }
     });
 
+    // Keep restart countdowns, counters and logs current
+    ctx.request_repaint_after(Duration::from_secs(1));
+
+    if let Some(plugin_action) = plugin_action {
+        apply_action(app, ctx, plugin_action);
+    }
+
     if !keep_open {
         close_popup(app);
     }
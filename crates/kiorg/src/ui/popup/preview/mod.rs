@@ -12,7 +12,7 @@ use crate::ui::file_list::truncate_text;
 use crate::ui::popup::PopupApp;
 use crate::ui::popup::PopupType;
 use crate::ui::popup::window_utils::new_center_popup_window;
-use crate::ui::preview::loading::create_load_popup_meta_task;
+use crate::ui::preview::loading::{create_cancellable_popup_task, create_load_popup_meta_task};
 
 fn available_screen_width(ctx: &Context) -> f32 {
     let screen_width = ctx.content_rect().width();
@@ -37,14 +37,22 @@ pub fn handle_show_file_popup(app: &mut Kiorg, ctx: &egui::Context) {
             return;
         }
     };
-    let path = &entry.meta.path;
-
     if is_dir {
         // Show preview popup for directories
         app.show_popup = Some(PopupType::Preview);
         return;
     }
 
+    // Files of other filesystems are previewed from a local copy
+    let entry = match crate::vfs::local_entry(&entry) {
+        Ok(entry) => entry,
+        Err(e) => {
+            app.notify_error(e);
+            return;
+        }
+    };
+    let path = &entry.meta.path;
+
     // First check if any plugins can handle this file
     let plugin_result = if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
         app.plugin_manager.get_preview_plugin_for_file(file_name)
@@ -61,26 +69,31 @@ pub fn handle_show_file_popup(app: &mut Kiorg, ctx: &egui::Context) {
         let ctx_clone = ctx.clone();
 
         let available_width = available_screen_width(ctx);
-        let (rx, cancel_sender) = create_load_popup_meta_task(entry.meta.clone(), move |entry| {
-            let result = plugin.preview_popup(&entry.path.to_string_lossy(), available_width);
-            match result {
-                Ok(plugin_content) => {
-                    let content =
-                        PreviewContent::plugin_preview_from_components(plugin_content, &ctx_clone);
-                    // Extract components from PreviewContent
-                    match content {
-                        PreviewContent::PluginPreview { components } => {
-                            Ok(crate::ui::popup::plugin_viewer::PluginContent {
-                                filename,
-                                components,
-                            })
+        let (rx, cancel_sender) =
+            create_cancellable_popup_task(entry.meta.clone(), move |entry, cancel| {
+                let result = plugin
+                    .preview_popup(&entry.path.to_string_lossy(), available_width)
+                    .and_then(|call| call.cancel_on(cancel).components());
+                match result {
+                    Ok(plugin_content) => {
+                        let content = PreviewContent::plugin_preview_from_components(
+                            plugin_content,
+                            &ctx_clone,
+                        );
+                        // Extract components from PreviewContent
+                        match content {
+                            PreviewContent::PluginPreview { components } => {
+                                Ok(crate::ui::popup::plugin_viewer::PluginContent {
+                                    filename,
+                                    components,
+                                })
+                            }
+                            _ => Err("Unexpected content type for Plugin viewer".into()),
                         }
-                        _ => Err("Unexpected content type for Plugin viewer".into()),
                     }
+                    Err(e) => Err(format!("Plugin error: {}", e)),
                 }
-                Err(e) => Err(format!("Plugin error: {}", e)),
-            }
-        });
+            });
         app.show_popup = Some(PopupType::Plugin(Box::new(PopupApp::loading(
             path_buf,
             rx,
@@ -204,11 +217,17 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
     let popup_size = egui::vec2(screen_size.x * 0.9, screen_size.y * 0.9);
     let popup_content_width = popup_size.x * 0.9; // Calculate once
 
-    let window_title = {
+    let (window_title, selected_path) = {
         let tab = app.tab_manager.current_tab_ref();
         let selected_entry = tab.selected_entry();
-        selected_entry.map_or_else(|| "File Preview".to_string(), |entry| entry.name.clone())
+        (
+            selected_entry.map_or_else(|| "File Preview".to_string(), |entry| entry.name.clone()),
+            selected_entry
+                .map(|entry| entry.meta.path.clone())
+                .unwrap_or_default(),
+        )
     };
+    let mut extract_selection = None;
 
     new_center_popup_window(&truncate_text(&window_title, popup_content_width))
         .max_size(popup_size)
@@ -220,7 +239,14 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
             let available_height = ui.available_height();
 
             if let Some(content) = &mut app.preview_content {
-                render_content(ui, content, &app.colors, available_width, available_height);
+                extract_selection = render_content(
+                    ui,
+                    content,
+                    &selected_path,
+                    &app.colors,
+                    available_width,
+                    available_height,
+                );
             } else {
                 ui.vertical_centered(|ui| {
                     ui.label("No preview content available");
@@ -230,16 +256,21 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
 
     if !keep_open {
         close_popup(app);
+    } else if let Some(selection) = extract_selection {
+        close_popup(app);
+        crate::ui::popup::extract::extract(app, selected_path, selection);
     }
 }
 
+/// Returns the entries to extract when an archive preview asked for it
 fn render_content(
     ui: &mut egui::Ui,
     content: &mut PreviewContent,
+    selected_path: &std::path::Path,
     colors: &AppColors,
     available_width: f32,
     available_height: f32,
-) {
+) -> Option<Vec<String>> {
     // Display the preview content based on its type
     match content {
         PreviewContent::Text(text) => {
@@ -287,18 +318,20 @@ fn render_content(
             );
         }
         PreviewContent::Zip(zip_entries) => {
-            egui::ScrollArea::vertical()
+            return egui::ScrollArea::vertical()
                 .id_salt("zip_popup_scroll")
                 .show(ui, |ui| {
-                    crate::ui::preview::zip::render(ui, zip_entries, colors);
-                });
+                    crate::ui::preview::zip::render(ui, selected_path, zip_entries, colors)
+                })
+                .inner;
         }
         PreviewContent::Tar(tar_entries) => {
-            egui::ScrollArea::vertical()
+            return egui::ScrollArea::vertical()
                 .id_salt("tar_popup_scroll")
                 .show(ui, |ui| {
-                    crate::ui::preview::tar::render(ui, tar_entries, colors);
-                });
+                    crate::ui::preview::tar::render(ui, selected_path, tar_entries, colors)
+                })
+                .inner;
         }
         PreviewContent::PluginPreview { components } => {
             crate::ui::preview::plugin::render(
@@ -319,6 +352,7 @@ fn render_content(
             });
         }
     }
+    None
 }
 
 pub fn render_loading(ui: &mut egui::Ui, path: &std::path::Path, colors: &AppColors) {
@@ -1,6 +1,7 @@
 use crate::app::Kiorg;
 use crate::models::action_history::{ActionType, RenameOperation};
 use crate::ui::popup::PopupType;
+use crate::vfs;
 use egui::Context;
 
 use super::text_input_popup::{
@@ -33,7 +34,7 @@ pub fn handle_rename_confirmation(app: &mut Kiorg, ctx: &Context) {
             let parent = entry.meta.path.parent().unwrap_or(&tab.current_path);
             let new_path = parent.join(new_name);
 
-            if let Err(e) = std::fs::rename(&entry.meta.path, &new_path) {
+            if let Err(e) = vfs::for_path(&entry.meta.path).rename(&entry.meta.path, &new_path) {
                 app.notify_error(format!("Failed to rename: {e}"));
             } else {
                 // Record rename action in history
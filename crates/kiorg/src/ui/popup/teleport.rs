@@ -75,6 +75,7 @@ pub fn get_search_results(
                         entry: entry.clone(),
                     },
                     score: 0, // Score not relevant for empty query
+                    indices: Vec::new(),
                 })
             })
             .collect();
@@ -113,14 +114,17 @@ pub fn get_search_results(
             let mut haystack_buf = Vec::new();
             let haystack_utf32 = Utf32Str::new(&path_str, &mut haystack_buf);
 
-            matcher
-                .fuzzy_match(haystack_utf32, needle_utf32)
-                .map(|score| FuzzyMatchResult {
-                    item: TeleportSearchResult {
-                        entry: entry.clone(),
-                    },
-                    score,
-                })
+            let mut indices = Vec::new();
+            let score = matcher.fuzzy_indices(haystack_utf32, needle_utf32, &mut indices)?;
+            indices.sort_unstable();
+            indices.dedup();
+            Some(FuzzyMatchResult {
+                item: TeleportSearchResult {
+                    entry: entry.clone(),
+                },
+                score,
+                indices,
+            })
         })
         .collect();
 
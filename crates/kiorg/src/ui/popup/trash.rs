@@ -0,0 +1,252 @@
+use crate::app::Kiorg;
+use crate::ui::center_panel::new_unique_path_name_for_paste;
+use crate::ui::popup::fuzzy_search_popup::{
+    FuzzySearchAction, FuzzySearchItem, FuzzySearchPopupConfig, FuzzySearchState, fuzzy_filter,
+};
+use crate::ui::popup::utils::{ConfirmResult, show_confirm_popup};
+use crate::ui::popup::{PopupType, job_progress};
+use crate::utils::jobs::spawn_job;
+use crate::utils::trash::{self, TrashItem};
+use egui::{Context, Key, Modifiers, RichText};
+use humansize::{BINARY, format_size};
+use std::borrow::Cow;
+
+static POPUP_CONFIG: FuzzySearchPopupConfig = FuzzySearchPopupConfig {
+    title: "Trash",
+    search_hint: "Filter trash... (Enter: restore, Del: delete, Shift+Del: empty trash)",
+    empty_message: "Trash is empty",
+    no_match_message: "No matching items in trash",
+    max_visible_results: None,
+};
+
+/// Destructive action waiting for confirmation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrashConfirm {
+    /// Permanently delete a single item
+    Purge(TrashItem),
+    /// Permanently delete every item in the trash
+    Empty,
+}
+
+/// State for the trash popup
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TrashState {
+    pub items: Vec<TrashItem>,
+    pub query: String,
+    pub selected_index: usize,
+    pub confirm: Option<TrashConfirm>,
+}
+
+impl TrashState {
+    /// Load the current content of all trash directories
+    pub fn load() -> Self {
+        Self {
+            items: trash::list_all_items(),
+            ..Self::default()
+        }
+    }
+}
+
+impl FuzzySearchItem for TrashItem {
+    fn display_text(&self) -> Cow<'_, str> {
+        self.entry.original_path.to_string_lossy()
+    }
+
+    fn secondary_text(&self) -> Option<Cow<'_, str>> {
+        let deleted = self
+            .deletion_date
+            .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "unknown date".to_string());
+        Some(Cow::Owned(format!(
+            "{deleted}  {}",
+            format_size(self.size, BINARY)
+        )))
+    }
+}
+
+/// Draw the trash popup
+pub fn draw(ctx: &Context, app: &mut Kiorg) {
+    let mut state = if let Some(PopupType::Trash(ref state)) = app.show_popup {
+        state.clone()
+    } else {
+        return;
+    };
+
+    if let Some(confirm) = state.confirm.take() {
+        draw_confirm(ctx, app, state, confirm);
+        return;
+    }
+
+    // Take the delete keys before the search field sees them
+    let (empty_pressed, purge_pressed) = ctx.input_mut(|i| {
+        (
+            i.consume_key(Modifiers::SHIFT, Key::Delete),
+            i.consume_key(Modifiers::NONE, Key::Delete),
+        )
+    });
+
+    let mut fuzzy_state = FuzzySearchState::new(state.query.clone());
+    fuzzy_state.selected_index = state.selected_index;
+    let results = fuzzy_filter(&fuzzy_state.query, &state.items);
+
+    let action = crate::ui::popup::fuzzy_search_popup::draw(
+        ctx,
+        &POPUP_CONFIG,
+        &app.colors,
+        &mut fuzzy_state,
+        &results,
+    );
+
+    match action {
+        FuzzySearchAction::KeepOpen => {
+            state.query = fuzzy_state.query;
+            state.selected_index = fuzzy_state.selected_index;
+            if empty_pressed && !state.items.is_empty() {
+                state.confirm = Some(TrashConfirm::Empty);
+            } else if purge_pressed && let Some(result) = results.get(state.selected_index) {
+                state.confirm = Some(TrashConfirm::Purge(result.item.clone()));
+            }
+            app.show_popup = Some(PopupType::Trash(state));
+        }
+        FuzzySearchAction::Close => {
+            app.show_popup = None;
+        }
+        FuzzySearchAction::Selected(item) => {
+            if restore_item(app, &item) {
+                state.items.retain(|i| i != &item);
+                state.selected_index = state
+                    .selected_index
+                    .min(state.items.len().saturating_sub(1));
+            }
+            app.show_popup = Some(PopupType::Trash(state));
+        }
+    }
+}
+
+/// Restore an item, picking a new name when its original location is taken
+/// Returns true if the item left the trash
+fn restore_item(app: &mut Kiorg, item: &TrashItem) -> bool {
+    let original = &item.entry.original_path;
+    let target = match original.parent() {
+        Some(parent) if original.symlink_metadata().is_ok() => {
+            new_unique_path_name_for_paste(original, parent)
+        }
+        _ => original.clone(),
+    };
+
+    match trash::restore_to(&item.entry, &target) {
+        Ok(()) => {
+            if &target == original {
+                app.toasts
+                    .success(format!("Restored '{}'", original.display()));
+            } else {
+                app.toasts.info(format!(
+                    "'{}' already exists, restored as '{}'",
+                    original.display(),
+                    target.display()
+                ));
+            }
+            app.refresh_entries();
+            true
+        }
+        Err(e) => {
+            app.toasts.error(e);
+            false
+        }
+    }
+}
+
+/// Draw the confirmation for purging an item or emptying the trash
+fn draw_confirm(ctx: &Context, app: &mut Kiorg, mut state: TrashState, confirm: TrashConfirm) {
+    let error_color = app.colors.error;
+    let mut keep_open = true;
+    let mut result = show_confirm_popup(
+        ctx,
+        "Delete from Trash",
+        &mut keep_open,
+        |ui| {
+            ui.vertical_centered(|ui| {
+                match &confirm {
+                    TrashConfirm::Purge(item) => {
+                        ui.label("Permanently delete");
+                        let path = item.entry.original_path.display().to_string();
+                        ui.label(RichText::new(path).strong());
+                    }
+                    TrashConfirm::Empty => {
+                        ui.label(format!(
+                            "Permanently delete all {} items in the trash?",
+                            state.items.len()
+                        ));
+                    }
+                }
+                ui.label(RichText::new("This action cannot be undone!").color(error_color));
+            });
+        },
+        "Delete (Enter)",
+        "Cancel (Esc)",
+    );
+
+    // The popup owns keyboard input while open
+    ctx.input(|i| {
+        if i.key_pressed(Key::Enter) {
+            result = ConfirmResult::Confirm;
+        } else if i.key_pressed(Key::Escape) {
+            result = ConfirmResult::Cancel;
+        }
+    });
+
+    match result {
+        ConfirmResult::Confirm => match confirm {
+            TrashConfirm::Purge(item) => {
+                match trash::purge(&item.entry) {
+                    Ok(()) => {
+                        app.toasts.success(format!(
+                            "Permanently deleted '{}'",
+                            item.entry.original_path.display()
+                        ));
+                        state.items.retain(|i| i != &item);
+                        state.selected_index = state
+                            .selected_index
+                            .min(state.items.len().saturating_sub(1));
+                    }
+                    Err(e) => {
+                        app.toasts.error(e);
+                    }
+                }
+                app.show_popup = Some(PopupType::Trash(state));
+            }
+            TrashConfirm::Empty => {
+                app.show_popup = None;
+                empty_trash(app, state.items);
+            }
+        },
+        ConfirmResult::Cancel => {
+            app.show_popup = Some(PopupType::Trash(state));
+        }
+        ConfirmResult::None => {
+            state.confirm = Some(confirm);
+            app.show_popup = Some(PopupType::Trash(state));
+        }
+    }
+}
+
+/// Permanently delete all listed items as a background job
+fn empty_trash(app: &mut Kiorg, items: Vec<TrashItem>) {
+    let job = spawn_job("Emptying Trash", move |ctx| {
+        ctx.set_totals(items.len(), 0);
+
+        for item in &items {
+            if ctx.is_cancelled() {
+                break;
+            }
+
+            ctx.start_file(&item.entry.original_path);
+            if let Err(e) = trash::purge(&item.entry) {
+                ctx.warn(e);
+            }
+        }
+        Ok(())
+    })
+    .with_success_message("Trash emptied");
+    job_progress::start(app, job);
+}
@@ -0,0 +1,69 @@
+//! Entry selection and the extract button shared by the archive previews
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use egui::RichText;
+
+use crate::config::colors::AppColors;
+
+fn selection_id(archive: &Path) -> egui::Id {
+    egui::Id::new(("archive_preview_selection", archive))
+}
+
+/// Entries picked in the preview of an archive, kept in egui memory per archive
+pub fn load_selection(ui: &egui::Ui, archive: &Path) -> BTreeSet<String> {
+    ui.data(|data| data.get_temp(selection_id(archive)))
+        .unwrap_or_default()
+}
+
+/// Title of the preview followed by the extract button
+/// Returns the entries to extract once the button is clicked, empty for the whole archive
+pub fn header(
+    ui: &mut egui::Ui,
+    title: &str,
+    archive: &Path,
+    colors: &AppColors,
+) -> Option<Vec<String>> {
+    let selection = load_selection(ui, archive);
+    let mut extract = None;
+
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(title).color(colors.fg).strong());
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let label = if selection.is_empty() {
+                "Extract all".to_string()
+            } else {
+                format!("Extract {} selected", selection.len())
+            };
+            if ui.button(label).clicked() {
+                extract = Some(selection.iter().cloned().collect());
+            }
+            if !selection.is_empty() && ui.button("Clear selection").clicked() {
+                ui.data_mut(|data| data.remove::<BTreeSet<String>>(selection_id(archive)));
+            }
+        });
+    });
+    extract
+}
+
+/// Entry name that toggles its selection when clicked
+pub fn selectable_name(
+    ui: &mut egui::Ui,
+    archive: &Path,
+    selection: &BTreeSet<String>,
+    name: &str,
+    text: RichText,
+) {
+    if ui
+        .selectable_label(selection.contains(name), text)
+        .clicked()
+    {
+        ui.data_mut(|data| {
+            let selection = data.get_temp_mut_or_default::<BTreeSet<String>>(selection_id(archive));
+            if !selection.remove(name) {
+                selection.insert(name.to_string());
+            }
+        });
+    }
+}
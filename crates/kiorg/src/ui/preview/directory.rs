@@ -3,8 +3,8 @@
 use crate::config::colors::AppColors;
 use crate::models::preview_content::DirectoryEntry;
 use crate::ui::preview::{prefix_dir_name, prefix_file_name};
+use crate::vfs;
 use egui::RichText;
-use std::fs;
 use std::path::Path;
 
 /// Render directory content
@@ -49,21 +49,15 @@ pub fn render(ui: &mut egui::Ui, entries: &[DirectoryEntry], colors: &AppColors)
 
 /// Reuses `DirectoryEntry` for simplicity, as it has the required fields (name, is_dir)
 pub fn read_dir_entries(path: &Path) -> Result<Vec<DirectoryEntry>, String> {
-    let mut entries = Vec::new();
-    let read_dir = fs::read_dir(path).map_err(|e| format!("Failed to read directory: {e}"))?;
-
-    for entry_result in read_dir {
-        let entry = entry_result.map_err(|e| format!("Failed to read directory entry: {e}"))?;
-        let path = entry.path();
-        let name = path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        let is_dir = path.is_dir();
-
-        entries.push(DirectoryEntry { name, is_dir });
-    }
+    let mut entries: Vec<DirectoryEntry> = vfs::for_dir(path)
+        .list(path)
+        .map_err(|e| format!("Failed to read directory: {e}"))?
+        .into_iter()
+        .map(|entry| DirectoryEntry {
+            name: entry.name,
+            is_dir: entry.metadata.is_dir,
+        })
+        .collect();
 
     // Sort entries: directories first, then by name
     entries.sort_by(|a, b| {
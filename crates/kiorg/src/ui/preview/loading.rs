@@ -49,6 +49,15 @@ pub fn render(
 pub fn load_preview_async<F>(app: &mut Kiorg, entry: DirEntryMeta, processor: F)
 where
     F: FnOnce(DirEntryMeta) -> Result<PreviewContent, String> + Send + 'static,
+{
+    load_cancellable_preview_async(app, entry, |entry, _| processor(entry));
+}
+
+/// Like [`load_preview_async`], the processor gets the cancel signal to stop
+/// early when the preview is no longer wanted
+pub fn load_cancellable_preview_async<F>(app: &mut Kiorg, entry: DirEntryMeta, processor: F)
+where
+    F: FnOnce(DirEntryMeta, mpsc::Receiver<()>) -> Result<PreviewContent, String> + Send + 'static,
 {
     // Check for existing loading content and trigger cancel signal
     if let Some(PreviewContent::Loading {
@@ -60,7 +69,7 @@ where
     }
 
     let path = entry.path.clone();
-    let (receiver, cancel_sender) = create_preview_task(entry, processor);
+    let (receiver, cancel_sender) = create_cancellable_preview_task(entry, processor);
 
     // Set the initial loading state with the receiver
     app.preview_content = Some(PreviewContent::Loading {
@@ -77,6 +86,18 @@ pub fn create_preview_task<F>(
 ) -> (PreviewReceiver, mpsc::Sender<()>)
 where
     F: FnOnce(DirEntryMeta) -> Result<PreviewContent, String> + Send + 'static,
+{
+    create_cancellable_preview_task(entry, |entry, _| processor(entry))
+}
+
+/// Create an async preview content loading task whose processor gets the
+/// cancel signal
+fn create_cancellable_preview_task<F>(
+    entry: DirEntryMeta,
+    processor: F,
+) -> (PreviewReceiver, mpsc::Sender<()>)
+where
+    F: FnOnce(DirEntryMeta, mpsc::Receiver<()>) -> Result<PreviewContent, String> + Send + 'static,
 {
     // Create a channel for process result communication
     let (sender, receiver) = std::sync::mpsc::channel();
@@ -95,7 +116,7 @@ where
                 // Timeout reached, proceed with processing
             }
         }
-        let preview_result = processor(entry);
+        let preview_result = processor(entry, cancel_receiver);
         let _ = sender.send(preview_result);
     });
 
@@ -114,6 +135,16 @@ pub fn create_load_popup_meta_task<T, F>(entry: DirEntryMeta, processor: F) -> P
 where
     T: Send + 'static,
     F: FnOnce(DirEntryMeta) -> Result<T, String> + Send + 'static,
+{
+    create_cancellable_popup_task(entry, |entry, _| processor(entry))
+}
+
+/// Like [`create_load_popup_meta_task`], the processor gets the cancel signal
+/// to stop early when the popup is closed
+pub fn create_cancellable_popup_task<T, F>(entry: DirEntryMeta, processor: F) -> PopupLoadTask<T>
+where
+    T: Send + 'static,
+    F: FnOnce(DirEntryMeta, mpsc::Receiver<()>) -> Result<T, String> + Send + 'static,
 {
     let (sender, receiver) = std::sync::mpsc::channel();
     let (cancel_sender, cancel_receiver) = mpsc::channel();
@@ -123,7 +154,7 @@ where
         if cancel_receiver.try_recv().is_ok() {
             return;
         }
-        let _ = sender.send(processor(entry));
+        let _ = sender.send(processor(entry, cancel_receiver));
     });
 
     (Arc::new(Mutex::new(receiver)), cancel_sender)
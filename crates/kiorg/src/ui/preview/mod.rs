@@ -2,6 +2,7 @@
 
 pub const METADATA_TBL_KEY_COL_W: f32 = 100.0;
 
+pub mod archive;
 pub mod directory;
 pub mod ebook;
 pub mod image;
@@ -16,6 +17,7 @@ pub mod zip;
 use crate::app::Kiorg;
 use crate::models::preview_content::PreviewContent;
 use crate::utils::preview_cache;
+use crate::vfs;
 
 // return extension if available, otherwise return file name
 // returned values are always lowercased
@@ -44,7 +46,7 @@ pub fn path_to_ext_info(path: &std::path::Path) -> String {
         _ => {
             let last = parts[parts.len() - 1].to_lowercase();
             match last.as_str() {
-                "zstd" | "gz" | "bz2" | "xz" => {
+                "zst" | "zstd" | "gz" | "bz2" | "xz" => {
                     // Handle cases like tar.gz, tar.bz2, etc.
                     let second_last = parts[parts.len() - 2].to_lowercase();
                     if second_last != "tar" {
@@ -97,7 +99,7 @@ macro_rules! zip_extensions {
 #[macro_export]
 macro_rules! tar_extensions {
     () => {
-        "tar" | "tgz" | "tar.gz" | "tbz" | "tbz2" | "tar.bz2"
+        "tar" | "tgz" | "tar.gz" | "tbz" | "tbz2" | "tar.bz2" | "tzst" | "tar.zst" | "tar.zstd"
     };
 }
 
@@ -172,6 +174,15 @@ pub fn update_selected_cache(app: &mut Kiorg, ctx: &egui::Context) {
         return;
     }
 
+    // Files of other filesystems are previewed from a local copy
+    let entry = match vfs::local_entry(&entry) {
+        Ok(entry) => entry,
+        Err(e) => {
+            app.preview_content = Some(PreviewContent::text(e));
+            return;
+        }
+    };
+
     let cache_key = preview_cache::calculate_cache_key(&entry.meta);
     if let Some(cached) = preview_cache::load_preview(&cache_key) {
         match cached.try_into_preview_content(ctx) {
@@ -200,8 +211,11 @@ pub fn update_selected_cache(app: &mut Kiorg, ctx: &egui::Context) {
     if let Some(plugin) = plugin_result {
         let ctx_clone = ctx.clone();
         let available_width = app.calculate_right_panel_width(ctx);
-        loading::load_preview_async(app, entry.meta.clone(), move |entry| {
-            let result = plugin.preview(&entry.path.to_string_lossy(), available_width);
+        loading::load_cancellable_preview_async(app, entry.meta.clone(), move |entry, cancel| {
+            // Moving on to another file drops the response of this one
+            let result = plugin
+                .preview(&entry.path.to_string_lossy(), available_width)
+                .and_then(|call| call.cancel_on(cancel).components());
             match result {
                 Ok(plugin_content) => Ok(PreviewContent::plugin_preview_from_components(
                     plugin_content,
@@ -310,6 +324,7 @@ mod tests {
         assert_eq!(path_to_ext_info(Path::new("data.tar.bz2")), "tar.bz2");
         assert_eq!(path_to_ext_info(Path::new("backup.tar.xz")), "tar.xz");
         assert_eq!(path_to_ext_info(Path::new("file.tar.zstd")), "tar.zstd");
+        assert_eq!(path_to_ext_info(Path::new("file.tar.zst")), "tar.zst");
     }
 
     #[test]
@@ -2,23 +2,26 @@
 
 use egui::RichText;
 use std::fs::File;
-use std::io::BufReader;
-use tar::Archive;
+use std::path::Path;
 
 use crate::config::colors::AppColors;
 use crate::models::dir_entry::DirEntryMeta;
 use crate::models::preview_content::{CachedPreviewContent, TarEntry};
-use crate::ui::preview::{prefix_dir_name, prefix_file_name};
+use crate::ui::preview::{archive, prefix_dir_name, prefix_file_name};
+use crate::utils::archive::{ArchiveFormat, TarCompression, open_tar};
 use crate::utils::preview_cache;
 
 /// Render tar archive content
-pub fn render(ui: &mut egui::Ui, entries: &[TarEntry], colors: &AppColors) {
+/// Returns the entries to extract once asked for, empty for the whole archive
+pub fn render(
+    ui: &mut egui::Ui,
+    archive: &Path,
+    entries: &[TarEntry],
+    colors: &AppColors,
+) -> Option<Vec<String>> {
     // Display tar file contents
-    ui.label(
-        RichText::new("Tar Archive Contents:")
-            .color(colors.fg)
-            .strong(),
-    );
+    let extract = archive::header(ui, "Tar Archive Contents:", archive, colors);
+    let selection = archive::load_selection(ui, archive);
     ui.add_space(5.0);
 
     // Constants for the list
@@ -55,7 +58,13 @@ pub fn render(ui: &mut egui::Ui, entries: &[TarEntry], colors: &AppColors) {
                     } else {
                         prefix_file_name(&entry.name)
                     };
-                    ui.label(RichText::new(&name_text).color(colors.fg));
+                    archive::selectable_name(
+                        ui,
+                        archive,
+                        &selection,
+                        &entry.name,
+                        RichText::new(&name_text).color(colors.fg),
+                    );
 
                     // Push size to the right
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
@@ -72,6 +81,7 @@ pub fn render(ui: &mut egui::Ui, entries: &[TarEntry], colors: &AppColors) {
                 });
             }
         });
+    extract
 }
 
 /// Read entries from a tar file and return them as a vector of `TarEntry`
@@ -79,33 +89,12 @@ pub fn read_tar_entries(entry: DirEntryMeta) -> Result<Vec<TarEntry>, String> {
     let path = &entry.path;
     let file = File::open(path).map_err(|e| format!("Failed to open tar file: {e}"))?;
 
-    // Try to determine if it's compressed by the file extension
-    let mut archive: Box<dyn std::io::Read> = Box::new(BufReader::new(file));
-
-    // Handle compressed tar files
-    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-        match ext.to_lowercase().as_str() {
-            "gz" | "tgz" => {
-                let file =
-                    File::open(path).map_err(|e| format!("Failed to reopen tar.gz file: {e}"))?;
-                let gz = flate2::read::GzDecoder::new(BufReader::new(file));
-                archive = Box::new(gz);
-            }
-            "bz2" | "tbz" | "tbz2" => {
-                let file =
-                    File::open(path).map_err(|e| format!("Failed to reopen tar.bz2 file: {e}"))?;
-                let bz2 = bzip2::read::BzDecoder::new(BufReader::new(file));
-                archive = Box::new(bz2);
-            }
-            _ => {
-                let file =
-                    File::open(path).map_err(|e| format!("Failed to reopen tar file: {e}"))?;
-                archive = Box::new(BufReader::new(file));
-            }
-        }
-    }
-
-    let mut tar = Archive::new(archive);
+    // Compression is determined by the file extension
+    let compression = match ArchiveFormat::detect(path) {
+        Some(ArchiveFormat::Tar(compression)) => compression,
+        _ => TarCompression::None,
+    };
+    let mut tar = open_tar(file, compression)?;
     let mut entries = Vec::new();
 
     let tar_entries = tar
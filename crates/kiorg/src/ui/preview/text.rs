@@ -19,6 +19,7 @@ use crate::config::colors::AppColors;
 use crate::models::dir_entry::DirEntryMeta;
 use crate::models::preview_content::PreviewContent;
 use crate::ui::preview::loading::load_preview_async;
+use crate::utils::content_search::CONTEXT_LINES;
 
 static SYNTECT_SETTINGS: OnceLock<SyntectSettings> = OnceLock::new();
 
@@ -95,6 +96,19 @@ pub fn render_highlighted(ui: &mut egui::Ui, text: &str, language: &'static str)
         });
 }
 
+/// Scroll the preview so the 1-based `line` of text drawn from `top` is in
+/// view, with a few lines of context above it
+pub fn scroll_to_line(ui: &egui::Ui, top: f32, line: usize, text_style: &egui::TextStyle) {
+    let row_height = ui.text_style_height(text_style);
+    let first_line = line.saturating_sub(CONTEXT_LINES + 1);
+    let y = (first_line as f32).mul_add(row_height, top);
+    let rect = egui::Rect::from_min_size(
+        egui::pos2(ui.min_rect().left(), y),
+        egui::vec2(1.0, row_height),
+    );
+    ui.scroll_to_rect(rect, Some(egui::Align::TOP));
+}
+
 /// Render empty state when no file is selected
 pub fn render_empty(ui: &mut egui::Ui, colors: &AppColors) {
     ui.label(RichText::new("No file selected").color(colors.fg));
@@ -2,22 +2,26 @@
 
 use egui::RichText;
 use std::fs::File;
+use std::path::Path;
 use zip::ZipArchive;
 
 use crate::config::colors::AppColors;
 use crate::models::dir_entry::DirEntryMeta;
 use crate::models::preview_content::{CachedPreviewContent, ZipEntry};
-use crate::ui::preview::{prefix_dir_name, prefix_file_name};
+use crate::ui::preview::{archive, prefix_dir_name, prefix_file_name};
 use crate::utils::preview_cache;
 
 /// Render zip archive content
-pub fn render(ui: &mut egui::Ui, entries: &[ZipEntry], colors: &AppColors) {
+/// Returns the entries to extract once asked for, empty for the whole archive
+pub fn render(
+    ui: &mut egui::Ui,
+    archive: &Path,
+    entries: &[ZipEntry],
+    colors: &AppColors,
+) -> Option<Vec<String>> {
     // Display zip file contents
-    ui.label(
-        RichText::new("Zip Archive Contents:")
-            .color(colors.fg)
-            .strong(),
-    );
+    let extract = archive::header(ui, "Zip Archive Contents:", archive, colors);
+    let selection = archive::load_selection(ui, archive);
     ui.add_space(5.0);
 
     // TODO: calculate the correct row height
@@ -45,7 +49,13 @@ pub fn render(ui: &mut egui::Ui, entries: &[ZipEntry], colors: &AppColors) {
                 };
 
                 ui.horizontal(|ui| {
-                    ui.label(entry_text.color(colors.fg));
+                    archive::selectable_name(
+                        ui,
+                        archive,
+                        &selection,
+                        &entry.name,
+                        entry_text.color(colors.fg),
+                    );
                     if !entry.is_dir {
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             ui.label(
@@ -60,6 +70,7 @@ pub fn render(ui: &mut egui::Ui, entries: &[ZipEntry], colors: &AppColors) {
                 });
             }
         });
+    extract
 }
 
 /// Read entries from a zip file and return them as a vector of `ZipEntry`
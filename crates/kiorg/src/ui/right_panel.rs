@@ -15,6 +15,33 @@ pub fn draw(app: &mut Kiorg, _ctx: &egui::Context, ui: &mut Ui, width: f32, heig
     }
 
     let colors = &app.colors;
+    let selected_path = app
+        .tab_manager
+        .current_tab_ref()
+        .selected_entry()
+        .map(|entry| entry.meta.path.clone())
+        .unwrap_or_default();
+    let mut extract_selection = None;
+
+    // Line requested by content search, applied once the text is loaded
+    let scroll_to_line = match &app.preview_scroll_line {
+        Some((path, line)) if *path == selected_path => {
+            let loaded = app.cached_preview_path.as_ref() == Some(&selected_path)
+                && matches!(
+                    app.preview_content,
+                    Some(PreviewContent::Text(_) | PreviewContent::HighlightedCode { .. })
+                );
+            loaded.then_some(*line)
+        }
+        Some(_) => {
+            app.preview_scroll_line = None;
+            None
+        }
+        None => None,
+    };
+    if scroll_to_line.is_some() {
+        app.preview_scroll_line = None;
+    }
 
     ui.vertical(|ui| {
         ui.set_min_width(width);
@@ -58,10 +85,24 @@ pub fn draw(app: &mut Kiorg, _ctx: &egui::Context, ui: &mut Ui, width: f32, heig
                         });
                     }
                     Some(PreviewContent::Text(text)) => {
+                        let top = ui.cursor().top();
                         preview::text::render(ui, text, colors);
+                        if let Some(line) = scroll_to_line {
+                            preview::text::scroll_to_line(ui, top, line, &egui::TextStyle::Body);
+                        }
                     }
                     Some(PreviewContent::HighlightedCode { content, language }) => {
+                        // Code is drawn in a frame with a margin of 8 points
+                        let top = ui.cursor().top() + 8.0;
                         preview::text::render_highlighted(ui, content, language);
+                        if let Some(line) = scroll_to_line {
+                            preview::text::scroll_to_line(
+                                ui,
+                                top,
+                                line,
+                                &egui::TextStyle::Monospace,
+                            );
+                        }
                     }
                     Some(PreviewContent::PluginPreview { components }) => {
                         preview::plugin::render(
@@ -109,10 +150,12 @@ pub fn draw(app: &mut Kiorg, _ctx: &egui::Context, ui: &mut Ui, width: f32, heig
                         );
                     }
                     Some(PreviewContent::Zip(entries)) => {
-                        preview::zip::render(ui, entries, colors);
+                        extract_selection =
+                            preview::zip::render(ui, &selected_path, entries, colors);
                     }
                     Some(PreviewContent::Tar(entries)) => {
-                        preview::tar::render(ui, entries, colors);
+                        extract_selection =
+                            preview::tar::render(ui, &selected_path, entries, colors);
                     }
                     Some(PreviewContent::Directory(entries)) => {
                         preview::directory::render(ui, entries, colors);
@@ -130,4 +173,8 @@ pub fn draw(app: &mut Kiorg, _ctx: &egui::Context, ui: &mut Ui, width: f32, heig
             ui.label(egui::RichText::new("? for help").color(colors.fg_light));
         });
     });
+
+    if let Some(selection) = extract_selection {
+        crate::ui::popup::extract::extract(app, selected_path, selection);
+    }
 }
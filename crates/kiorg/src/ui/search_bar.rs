@@ -7,6 +7,8 @@ pub struct SearchBar {
     pub focus: bool,
     pub case_insensitive: bool,
     pub fuzzy: bool,
+    /// Keep fuzzy matches in the sort order instead of ranking them by score
+    pub keep_order: bool,
 }
 
 impl SearchBar {
@@ -17,6 +19,7 @@ impl SearchBar {
             focus: false,
             case_insensitive: true, // Default to case insensitive
             fuzzy: true,            // Default to fuzzy search
+            keep_order: false,      // Default to best match first
         }
     }
 
@@ -45,6 +48,7 @@ fn apply_new_query(app: &mut Kiorg) {
         &app.search_bar.query,
         app.search_bar.case_insensitive,
         app.search_bar.fuzzy,
+        app.search_bar.keep_order,
     );
 
     if let Some(&index) = tab.get_cached_filtered_entries().first() {
@@ -86,7 +90,7 @@ pub fn handle_key_press(ctx: &Context, app: &mut Kiorg) -> bool {
                 app.search_bar.close();
                 // Reset filter when closing search bar
                 let tab = app.tab_manager.current_tab_mut();
-                tab.update_filtered_cache(&None, false, false);
+                tab.update_filtered_cache(&None, false, false, false);
             }
 
             consumed
@@ -193,12 +197,39 @@ pub fn draw(ctx: &Context, app: &mut Kiorg) {
                             apply_new_query(app);
                         }
 
+                        // Keep sort order toggle button, only matters for fuzzy search
+                        let order_toggle_color = if app.search_bar.keep_order {
+                            app.colors.highlight
+                        } else {
+                            app.colors.fg_light
+                        };
+                        let order_tooltip_text = if app.search_bar.keep_order {
+                            "Click to rank fuzzy matches by score"
+                        } else {
+                            "Click to keep fuzzy matches in sort order"
+                        };
+                        let order_button_clicked = ui
+                            .add_enabled(
+                                app.search_bar.fuzzy,
+                                egui::Button::new(
+                                    egui::RichText::new("Ord").color(order_toggle_color),
+                                )
+                                .small()
+                                .frame(false),
+                            )
+                            .on_hover_text(order_tooltip_text)
+                            .clicked();
+                        if order_button_clicked {
+                            app.search_bar.keep_order = !app.search_bar.keep_order;
+                            apply_new_query(app);
+                        }
+
                         // Close button
                         if ui.button("×").clicked() {
                             app.search_bar.close();
                             // Reset filter when closing search bar
                             let tab = app.tab_manager.current_tab_mut();
-                            tab.update_filtered_cache(&None, false, false);
+                            tab.update_filtered_cache(&None, false, false, false);
                         }
                     });
                 });
@@ -1,3 +1,6 @@
+use egui::Color32;
+use egui::text::{LayoutJob, TextFormat};
+
 use crate::config::colors::AppColors;
 
 pub const HEADER_FONT_SIZE: f32 = 12.0;
@@ -9,3 +12,40 @@ pub fn section_title_text(text: &str, colors: &AppColors) -> egui::RichText {
         .color(colors.fg_light)
         .font(egui::FontId::proportional(HEADER_FONT_SIZE))
 }
+
+/// Lay out `text` with the chars at `indices` drawn in `highlight_color`
+///
+/// `indices` are sorted char indices, the way the fuzzy matcher reports them.
+/// Indices past the end of `text` are ignored.
+#[must_use]
+pub fn highlighted_text_job(
+    text: &str,
+    indices: &[u32],
+    format: TextFormat,
+    highlight_color: Color32,
+) -> LayoutJob {
+    let highlighted = TextFormat {
+        color: highlight_color,
+        ..format.clone()
+    };
+    let mut job = LayoutJob::default();
+    let mut append = |part: &str, is_match: bool| {
+        if !part.is_empty() {
+            let format = if is_match { &highlighted } else { &format };
+            job.append(part, 0.0, format.clone());
+        }
+    };
+
+    let mut run_start = 0;
+    let mut run_matched = false;
+    for (char_index, (byte_index, _)) in text.char_indices().enumerate() {
+        let is_match = u32::try_from(char_index).is_ok_and(|i| indices.binary_search(&i).is_ok());
+        if is_match != run_matched {
+            append(&text[run_start..byte_index], run_matched);
+            run_start = byte_index;
+            run_matched = is_match;
+        }
+    }
+    append(&text[run_start..], run_matched);
+    job
+}
@@ -19,7 +19,25 @@ mod implementation {
         ) -> Result<Self, String> {
             let system_shell = std::env::var("SHELL")
                 .map_err(|e| format!("SHELL variable is not defined: {e}"))?;
+            Self::spawn(ctx, working_directory, system_shell, Vec::new())
+        }
 
+        /// Run `program` instead of the user's shell, the terminal closes once it exits
+        pub fn with_command(
+            ctx: &egui::Context,
+            working_directory: std::path::PathBuf,
+            program: String,
+            args: Vec<String>,
+        ) -> Result<Self, String> {
+            Self::spawn(ctx, working_directory, program, args)
+        }
+
+        fn spawn(
+            ctx: &egui::Context,
+            working_directory: std::path::PathBuf,
+            shell: String,
+            args: Vec<String>,
+        ) -> Result<Self, String> {
             let (pty_proxy_sender, pty_proxy_receiver) = std::sync::mpsc::channel();
 
             let terminal_backend = egui_term::TerminalBackend::new(
@@ -27,7 +45,8 @@ mod implementation {
                 ctx.clone(),
                 pty_proxy_sender,
                 egui_term::BackendSettings {
-                    shell: system_shell,
+                    shell,
+                    args,
                     working_directory: Some(working_directory),
                     ..Default::default()
                 },
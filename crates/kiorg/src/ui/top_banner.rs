@@ -104,6 +104,35 @@ pub fn draw(app: &mut Kiorg, ui: &mut Ui) {
                         app.refresh_entries();
                     }
                 }
+
+                // Make entries hidden by the filter rules visible
+                let tab = app.tab_manager.current_tab_ref();
+                let filter_status = if tab.hidden_by_filters > 0 {
+                    Some((
+                        format!("{} filtered", tab.hidden_by_filters),
+                        "Entries hidden by filter rules, click to show them",
+                    ))
+                } else if !app.tab_manager.filters_enabled
+                    && app.tab_manager.filter_rules.applies_to(&tab.current_path)
+                {
+                    Some((
+                        "filters off".to_string(),
+                        "Filter rules are disabled, click to enable them",
+                    ))
+                } else {
+                    None
+                };
+                if let Some((text, tooltip)) = filter_status {
+                    ui.add_space(5.0);
+                    if ui
+                        .link(RichText::new(text).color(app.colors.warn))
+                        .on_hover_text(tooltip)
+                        .clicked()
+                    {
+                        app.tab_manager.toggle_filters();
+                        app.refresh_entries();
+                    }
+                }
             });
         });
         ui.separator();
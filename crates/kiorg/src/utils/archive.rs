@@ -0,0 +1,1189 @@
+//! Create and extract zip and tar archives
+//!
+//! Entry names come from the archive and cannot be trusted, every one of them
+//! goes through [`sanitize_entry_path`] so nothing is written outside of the
+//! destination directory.
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use crate::utils::jobs::JobContext;
+
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Compression applied on top of a tar archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TarCompression {
+    None,
+    Gzip,
+    Bzip2,
+    Zstd,
+}
+
+/// Archive formats that can be extracted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar(TarCompression),
+}
+
+impl ArchiveFormat {
+    /// Guess the format from the file extension
+    pub fn detect(path: &Path) -> Option<Self> {
+        match crate::ui::preview::path_to_ext_info(path).as_str() {
+            crate::zip_extensions!() => Some(Self::Zip),
+            "tar" => Some(Self::Tar(TarCompression::None)),
+            "tgz" | "tar.gz" => Some(Self::Tar(TarCompression::Gzip)),
+            "tbz" | "tbz2" | "tar.bz2" => Some(Self::Tar(TarCompression::Bzip2)),
+            "tzst" | "tar.zst" | "tar.zstd" => Some(Self::Tar(TarCompression::Zstd)),
+            _ => None,
+        }
+    }
+}
+
+/// Name of the directory an archive is extracted into, the archive name
+/// without its extensions
+pub fn destination_name(archive: &Path) -> String {
+    let name = archive
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let ext = crate::ui::preview::path_to_ext_info(archive);
+    let stem = name
+        .len()
+        .checked_sub(ext.len() + 1)
+        .filter(|&end| end > 0 && name.is_char_boundary(end))
+        .filter(|&end| name[end + 1..].eq_ignore_ascii_case(&ext))
+        .map_or(name.as_str(), |end| &name[..end]);
+    stem.to_string()
+}
+
+/// Open a tar archive, decompressing it on the fly
+///
+/// # Errors
+///
+/// Returns an error string if the file cannot be opened or the compression
+/// stream cannot be set up.
+pub fn open_tar<R: Read + 'static>(
+    reader: R,
+    compression: TarCompression,
+) -> Result<tar::Archive<Box<dyn Read>>, String> {
+    let reader = BufReader::new(reader);
+    let reader: Box<dyn Read> = match compression {
+        TarCompression::None => Box::new(reader),
+        TarCompression::Gzip => Box::new(flate2::read::GzDecoder::new(reader)),
+        TarCompression::Bzip2 => Box::new(bzip2::read::BzDecoder::new(reader)),
+        TarCompression::Zstd => Box::new(
+            zstd::stream::read::Decoder::with_buffer(reader)
+                .map_err(|e| format!("Failed to read zstd stream: {e}"))?,
+        ),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// Turn an entry name into a relative path inside the destination
+///
+/// Returns `None` for absolute names and names that climb out with `..`.
+pub fn sanitize_entry_path(name: &str) -> Option<PathBuf> {
+    // Zip archives made on Windows may use backslashes
+    let name = name.replace('\\', "/");
+    let mut path = PathBuf::new();
+    for component in Path::new(&name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// Whether the entry is one of the selected names or inside a selected directory
+/// An empty selection stands for the whole archive
+fn is_selected(path: &Path, selection: &[PathBuf]) -> bool {
+    selection.is_empty() || selection.iter().any(|selected| path.starts_with(selected))
+}
+
+/// Whether a zip archive has entries that need a password
+pub fn zip_needs_password(archive: &Path) -> Result<bool, String> {
+    let file = File::open(archive).map_err(|e| format!("Failed to open zip file: {e}"))?;
+    let mut zip =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip archive: {e}"))?;
+    for index in 0..zip.len() {
+        let entry = zip
+            .by_index_raw(index)
+            .map_err(|e| format!("Failed to read zip entry: {e}"))?;
+        if entry.encrypted() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// What to extract and where to
+#[derive(Clone)]
+pub struct ExtractRequest {
+    pub archive: PathBuf,
+    pub format: ArchiveFormat,
+    /// Existing directory the entries are written into
+    pub destination: PathBuf,
+    /// Entry names as listed by the preview, empty for the whole archive
+    pub selection: Vec<String>,
+    pub password: Option<String>,
+}
+
+// The password never ends up in logs
+impl std::fmt::Debug for ExtractRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtractRequest")
+            .field("archive", &self.archive)
+            .field("format", &self.format)
+            .field("destination", &self.destination)
+            .field("selection", &self.selection)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// Extract the archive, reporting progress to the job
+///
+/// Unsafe entries and links are skipped with a warning.
+///
+/// # Errors
+///
+/// Returns an error string if the archive cannot be read or an entry cannot
+/// be written.
+pub fn extract(ctx: &mut JobContext, request: &ExtractRequest) -> Result<(), String> {
+    let selection: Vec<PathBuf> = request
+        .selection
+        .iter()
+        .filter_map(|name| sanitize_entry_path(name))
+        .collect();
+    let file = File::open(&request.archive)
+        .map_err(|e| format!("Failed to open {}: {e}", request.archive.display()))?;
+
+    match request.format {
+        ArchiveFormat::Zip => extract_zip(ctx, file, request, &selection),
+        ArchiveFormat::Tar(compression) => {
+            extract_tar(ctx, file, compression, &request.destination, &selection)
+        }
+    }
+}
+
+fn extract_zip(
+    ctx: &mut JobContext,
+    file: File,
+    request: &ExtractRequest,
+    selection: &[PathBuf],
+) -> Result<(), String> {
+    let mut zip =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip archive: {e}"))?;
+
+    // The central directory knows every size, so progress is exact
+    let mut wanted = Vec::new();
+    let mut total_bytes = 0;
+    for index in 0..zip.len() {
+        let entry = zip
+            .by_index_raw(index)
+            .map_err(|e| format!("Failed to read zip entry: {e}"))?;
+        match sanitize_entry_path(entry.name()) {
+            Some(path) if is_selected(&path, selection) => {
+                total_bytes += entry.size();
+                wanted.push((index, path));
+            }
+            Some(_) => {}
+            None => ctx.warn(format!("Skipped unsafe entry '{}'", entry.name())),
+        }
+    }
+    ctx.set_totals(wanted.len(), total_bytes);
+
+    for (index, relative) in wanted {
+        ctx.check_cancelled().map_err(|e| e.to_string())?;
+
+        let mut entry = match &request.password {
+            Some(password) => zip.by_index_decrypt(index, password.as_bytes()),
+            None => zip.by_index(index),
+        }
+        .map_err(|e| match e {
+            zip::result::ZipError::InvalidPassword => "Wrong password".to_string(),
+            e => format!("Failed to read {}: {e}", relative.display()),
+        })?;
+
+        let target = request.destination.join(&relative);
+        ctx.start_file(&target);
+        if goes_through_link(&request.destination, &relative) {
+            ctx.skip(0, entry.size());
+            ctx.warn(format!("Skipped '{}' inside a link", relative.display()));
+        } else if entry.is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create {}: {e}", target.display()))?;
+        } else if entry.is_symlink() {
+            ctx.skip(0, entry.size());
+            ctx.warn(format!("Skipped link '{}'", relative.display()));
+        } else {
+            let mode = entry.unix_mode();
+            write_file(ctx, &mut entry, &target, mode)?;
+        }
+    }
+    Ok(())
+}
+
+fn extract_tar(
+    ctx: &mut JobContext,
+    file: File,
+    compression: TarCompression,
+    destination: &Path,
+    selection: &[PathBuf],
+) -> Result<(), String> {
+    // Counting entries would mean decompressing twice, so progress follows the
+    // compressed bytes read instead
+    let total_bytes = file.metadata().map(|meta| meta.len()).unwrap_or_default();
+    ctx.set_totals(0, total_bytes);
+    let bytes_read = Arc::new(AtomicU64::new(0));
+    let reader = CountingReader {
+        inner: file,
+        count: bytes_read.clone(),
+    };
+
+    let mut archive = open_tar(reader, compression)?;
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("Failed to read tar entries: {e}"))?;
+
+    // Links are created once everything else is written, so no entry is ever
+    // written through a link that came from the archive
+    let mut links = Vec::new();
+    let mut reported = 0;
+    for entry in entries {
+        ctx.check_cancelled().map_err(|e| e.to_string())?;
+        let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {e}"))?;
+
+        let name = entry
+            .path()
+            .map_err(|e| format!("Failed to read tar entry path: {e}"))?
+            .to_string_lossy()
+            .into_owned();
+        let Some(relative) = sanitize_entry_path(&name) else {
+            ctx.warn(format!("Skipped unsafe entry '{name}'"));
+            continue;
+        };
+        if !is_selected(&relative, selection) {
+            continue;
+        }
+
+        let target = destination.join(&relative);
+        ctx.add_totals(1, 0);
+        ctx.start_file(&target);
+        if goes_through_link(destination, &relative) {
+            ctx.warn(format!("Skipped '{name}' inside a link"));
+            continue;
+        }
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create {}: {e}", target.display()))?,
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                let mode = entry.header().mode().ok();
+                write_file(ctx, &mut entry, &target, mode)?;
+            }
+            tar::EntryType::Symlink => {
+                let link = entry.link_name().ok().flatten();
+                match link {
+                    Some(link) if stays_inside(&relative, &link) => {
+                        links.push((relative, link.into_owned()));
+                    }
+                    _ => ctx.warn(format!("Skipped link '{name}' leaving the archive")),
+                }
+            }
+            tar::EntryType::Link => {
+                let mode = entry.header().mode().ok();
+                let source = entry
+                    .link_name()
+                    .ok()
+                    .flatten()
+                    .and_then(|link| sanitize_entry_path(&link.to_string_lossy()));
+                match source {
+                    Some(source) if !goes_through_link(destination, &source) => {
+                        copy_hard_link(ctx, &destination.join(source), &target, mode)?;
+                    }
+                    _ => ctx.warn(format!("Skipped link '{name}' leaving the archive")),
+                }
+            }
+            _ => ctx.warn(format!("Skipped special entry '{name}'")),
+        }
+
+        // Keep the byte progress in step with the compressed stream
+        let read = bytes_read.load(Ordering::Relaxed).min(total_bytes);
+        ctx.add_bytes(read.saturating_sub(reported));
+        reported = reported.max(read);
+    }
+    ctx.add_bytes(total_bytes.saturating_sub(reported));
+
+    // A link is checked again as an earlier one may now sit on its path, as
+    // in `a -> .` followed by `a/b -> ..`
+    for (relative, link) in links {
+        ctx.check_cancelled().map_err(|e| e.to_string())?;
+        if goes_through_link(destination, &relative) {
+            ctx.warn(format!(
+                "Skipped link '{}' inside a link",
+                relative.display()
+            ));
+            continue;
+        }
+        if let Err(e) = create_symlink(&link, &destination.join(&relative)) {
+            ctx.warn(e);
+        }
+    }
+    Ok(())
+}
+
+/// Whether any parent of `relative` below `destination` is a symlink, writing
+/// there could land outside of the destination
+fn goes_through_link(destination: &Path, relative: &Path) -> bool {
+    relative
+        .ancestors()
+        .skip(1)
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .any(|parent| {
+            fs::symlink_metadata(destination.join(parent))
+                .is_ok_and(|meta| meta.file_type().is_symlink())
+        })
+}
+
+/// Whether a symlink at `link_path` pointing to `target` resolves inside the
+/// extraction root, judged on the path alone
+fn stays_inside(link_path: &Path, target: &Path) -> bool {
+    if target.is_absolute() {
+        return false;
+    }
+    let mut depth: usize = link_path.components().count().saturating_sub(1);
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(parent) => depth = parent,
+                None => return false,
+            },
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+#[cfg(unix)]
+fn create_symlink(link: &Path, target: &Path) -> Result<(), String> {
+    create_parent(target)?;
+    std::os::unix::fs::symlink(link, target)
+        .map_err(|e| format!("Failed to create link {}: {e}", target.display()))
+}
+
+#[cfg(not(unix))]
+fn create_symlink(_link: &Path, target: &Path) -> Result<(), String> {
+    Err(format!(
+        "Cannot create link {}: not supported on this platform",
+        target.display()
+    ))
+}
+
+/// Extract a hard link as a copy of the file it links to, which comes earlier
+/// in the archive. Links to entries that were not extracted are skipped.
+fn copy_hard_link(
+    ctx: &mut JobContext,
+    source: &Path,
+    target: &Path,
+    mode: Option<u32>,
+) -> Result<(), String> {
+    if !fs::symlink_metadata(source).is_ok_and(|meta| meta.is_file()) {
+        ctx.warn(format!(
+            "Skipped link '{}' to an entry that was not extracted",
+            target.display()
+        ));
+        return Ok(());
+    }
+    let mut file =
+        File::open(source).map_err(|e| format!("Failed to read {}: {e}", source.display()))?;
+    write_file(ctx, &mut file, target, mode)
+}
+
+fn create_parent(target: &Path) -> Result<(), String> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    Ok(())
+}
+
+/// Write an entry to a new file, never replacing what is already there
+fn write_file(
+    ctx: &mut JobContext,
+    reader: &mut dyn Read,
+    target: &Path,
+    mode: Option<u32>,
+) -> Result<(), String> {
+    create_parent(target)?;
+    let mut file = File::options()
+        .write(true)
+        .create_new(true)
+        .open(target)
+        .map_err(|e| format!("Failed to create {}: {e}", target.display()))?;
+
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+    loop {
+        ctx.check_cancelled().map_err(|e| e.to_string())?;
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Failed to extract {}: {e}", target.display())),
+        };
+        file.write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to write {}: {e}", target.display()))?;
+        ctx.add_bytes(read as u64);
+    }
+
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = file.set_permissions(fs::Permissions::from_mode(mode & 0o777));
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    Ok(())
+}
+
+/// An entry listed in an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Sanitized path relative to the archive root
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// List every entry of an archive without extracting anything
+///
+/// Unsafe names are left out. Directories that are only implied by the paths
+/// of their entries are listed as well, so every entry has a parent.
+///
+/// # Errors
+///
+/// Returns an error string if the archive cannot be read.
+pub fn read_index(archive: &Path, format: ArchiveFormat) -> Result<Vec<IndexEntry>, String> {
+    let file =
+        File::open(archive).map_err(|e| format!("Failed to open {}: {e}", archive.display()))?;
+    let mut entries = match format {
+        ArchiveFormat::Zip => read_zip_index(file)?,
+        ArchiveFormat::Tar(compression) => read_tar_index(file, compression)?,
+    };
+
+    let mut known: HashSet<PathBuf> = entries.iter().map(|entry| entry.path.clone()).collect();
+    let mut implied = Vec::new();
+    for entry in &entries {
+        for parent in entry.path.ancestors().skip(1) {
+            if parent.as_os_str().is_empty() || !known.insert(parent.to_path_buf()) {
+                continue;
+            }
+            implied.push(IndexEntry {
+                path: parent.to_path_buf(),
+                is_dir: true,
+                is_symlink: false,
+                size: 0,
+                modified: entry.modified,
+            });
+        }
+    }
+    entries.extend(implied);
+    Ok(entries)
+}
+
+fn read_zip_index(file: File) -> Result<Vec<IndexEntry>, String> {
+    let mut zip =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip archive: {e}"))?;
+    let mut entries = Vec::with_capacity(zip.len());
+    for index in 0..zip.len() {
+        let entry = zip
+            .by_index_raw(index)
+            .map_err(|e| format!("Failed to read zip entry: {e}"))?;
+        let Some(path) = sanitize_entry_path(entry.name()) else {
+            continue;
+        };
+        let modified = entry
+            .last_modified()
+            .and_then(zip_time)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        entries.push(IndexEntry {
+            path,
+            is_dir: entry.is_dir(),
+            is_symlink: entry.is_symlink(),
+            size: entry.size(),
+            modified,
+        });
+    }
+    Ok(entries)
+}
+
+/// Zip timestamps carry no time zone, they are taken as local time
+fn zip_time(time: zip::DateTime) -> Option<SystemTime> {
+    let local = chrono::NaiveDate::from_ymd_opt(
+        i32::from(time.year()),
+        u32::from(time.month()),
+        u32::from(time.day()),
+    )?
+    .and_hms_opt(
+        u32::from(time.hour()),
+        u32::from(time.minute()),
+        u32::from(time.second()),
+    )?
+    .and_local_timezone(chrono::Local)
+    .earliest()?;
+    Some(local.into())
+}
+
+fn read_tar_index(file: File, compression: TarCompression) -> Result<Vec<IndexEntry>, String> {
+    let mut archive = open_tar(file, compression)?;
+    let mut entries = Vec::new();
+    for entry in archive
+        .entries()
+        .map_err(|e| format!("Failed to read tar entries: {e}"))?
+    {
+        let entry = entry.map_err(|e| format!("Failed to read tar entry: {e}"))?;
+        let name = entry
+            .path()
+            .map_err(|e| format!("Failed to read tar entry path: {e}"))?
+            .to_string_lossy()
+            .into_owned();
+        let Some(path) = sanitize_entry_path(&name) else {
+            continue;
+        };
+        let header = entry.header();
+        let entry_type = header.entry_type();
+        let modified = header.mtime().map_or(SystemTime::UNIX_EPOCH, |secs| {
+            SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs)
+        });
+        entries.push(IndexEntry {
+            path,
+            is_dir: entry_type == tar::EntryType::Directory,
+            is_symlink: entry_type == tar::EntryType::Symlink,
+            size: header.size().unwrap_or(0),
+            modified,
+        });
+    }
+    Ok(entries)
+}
+
+/// Copy the content of a single file in the archive to `writer`
+///
+/// # Errors
+///
+/// Returns an error string if the archive cannot be read, the entry is missing
+/// or it needs a password.
+pub fn read_entry(
+    archive: &Path,
+    format: ArchiveFormat,
+    inner: &Path,
+    writer: &mut dyn Write,
+) -> Result<(), String> {
+    let file =
+        File::open(archive).map_err(|e| format!("Failed to open {}: {e}", archive.display()))?;
+    let missing = || format!("{} is not in the archive", inner.display());
+    match format {
+        ArchiveFormat::Zip => {
+            let mut zip = zip::ZipArchive::new(file)
+                .map_err(|e| format!("Failed to read zip archive: {e}"))?;
+            let index = (0..zip.len())
+                .find(|&index| {
+                    zip.name_for_index(index)
+                        .and_then(sanitize_entry_path)
+                        .is_some_and(|path| path == inner)
+                })
+                .ok_or_else(missing)?;
+            let mut entry = zip
+                .by_index(index)
+                .map_err(|e| format!("Failed to read {}: {e}", inner.display()))?;
+            io::copy(&mut entry, writer)
+                .map_err(|e| format!("Failed to read {}: {e}", inner.display()))?;
+        }
+        ArchiveFormat::Tar(compression) => {
+            let mut archive = open_tar(file, compression)?;
+            for entry in archive
+                .entries()
+                .map_err(|e| format!("Failed to read tar entries: {e}"))?
+            {
+                let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {e}"))?;
+                let matches = entry
+                    .path()
+                    .ok()
+                    .and_then(|path| sanitize_entry_path(&path.to_string_lossy()))
+                    .is_some_and(|path| path == inner);
+                if matches {
+                    io::copy(&mut entry, writer)
+                        .map_err(|e| format!("Failed to read {}: {e}", inner.display()))?;
+                    return Ok(());
+                }
+            }
+            return Err(missing());
+        }
+    }
+    Ok(())
+}
+
+/// Reader that keeps count of the bytes that went through it
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+/// Formats archives can be created in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressFormat {
+    #[default]
+    ZipDeflate,
+    ZipZstd,
+    TarGz,
+    TarBz2,
+    TarZst,
+}
+
+impl CompressFormat {
+    pub const ALL: [Self; 5] = [
+        Self::ZipDeflate,
+        Self::ZipZstd,
+        Self::TarGz,
+        Self::TarBz2,
+        Self::TarZst,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::ZipDeflate => "zip",
+            Self::ZipZstd => "zip (zstd)",
+            Self::TarGz => "tar.gz",
+            Self::TarBz2 => "tar.bz2",
+            Self::TarZst => "tar.zst",
+        }
+    }
+
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::ZipDeflate | Self::ZipZstd => "zip",
+            Self::TarGz => "tar.gz",
+            Self::TarBz2 => "tar.bz2",
+            Self::TarZst => "tar.zst",
+        }
+    }
+}
+
+/// File or directory to add to a new archive
+struct SourceEntry {
+    path: PathBuf,
+    /// Name inside the archive, always with `/` separators
+    name: String,
+    metadata: fs::Metadata,
+}
+
+/// List the sources and everything below them, parents before children
+fn collect_sources(sources: &[PathBuf]) -> io::Result<Vec<SourceEntry>> {
+    fn visit(path: &Path, name: String, entries: &mut Vec<SourceEntry>) -> io::Result<()> {
+        let metadata = fs::symlink_metadata(path)?;
+        let is_dir = metadata.is_dir();
+        entries.push(SourceEntry {
+            path: path.to_path_buf(),
+            name: name.clone(),
+            metadata,
+        });
+        if is_dir {
+            let mut children: Vec<_> = fs::read_dir(path)?.collect::<io::Result<_>>()?;
+            children.sort_by_key(|child| child.file_name());
+            for child in children {
+                let child_name = format!("{name}/{}", child.file_name().to_string_lossy());
+                visit(&child.path(), child_name, entries)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut entries = Vec::new();
+    for source in sources {
+        let name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| io::Error::other(format!("Cannot archive {}", source.display())))?;
+        visit(source, name, &mut entries)?;
+    }
+    Ok(entries)
+}
+
+/// Reader that reports the bytes read to the job and stops once it is cancelled
+struct ProgressReader<'a, R> {
+    inner: R,
+    ctx: &'a mut JobContext,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Not `Interrupted`, io::copy would retry that forever
+        if self.ctx.is_cancelled() {
+            return Err(io::Error::other("Operation cancelled"));
+        }
+        let read = self.inner.read(buf)?;
+        self.ctx.add_bytes(read as u64);
+        Ok(read)
+    }
+}
+
+/// Pack `sources` into a new archive at `target`, reporting progress to the job
+///
+/// Entries are stored under their own names, directories with all their content.
+/// A partially written archive is removed when packing fails or is cancelled.
+///
+/// # Errors
+///
+/// Returns an error string if the target already exists or a source cannot be read.
+pub fn compress(
+    ctx: &mut JobContext,
+    sources: &[PathBuf],
+    target: &Path,
+    format: CompressFormat,
+) -> Result<(), String> {
+    let entries =
+        collect_sources(sources).map_err(|e| format!("Failed to list entries to compress: {e}"))?;
+    let total_bytes = entries
+        .iter()
+        .filter(|entry| entry.metadata.is_file())
+        .map(|entry| entry.metadata.len())
+        .sum();
+    ctx.set_totals(entries.len(), total_bytes);
+
+    let file = File::options()
+        .write(true)
+        .create_new(true)
+        .open(target)
+        .map_err(|e| format!("Failed to create {}: {e}", target.display()))?;
+
+    let result = match format {
+        CompressFormat::ZipDeflate => {
+            write_zip(ctx, &entries, file, zip::CompressionMethod::Deflated)
+        }
+        CompressFormat::ZipZstd => write_zip(ctx, &entries, file, zip::CompressionMethod::Zstd),
+        CompressFormat::TarGz => {
+            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            write_tar(ctx, &entries, encoder).and_then(|encoder| encoder.finish().map(drop))
+        }
+        CompressFormat::TarBz2 => {
+            let encoder = bzip2::write::BzEncoder::new(file, bzip2::Compression::default());
+            write_tar(ctx, &entries, encoder).and_then(|encoder| encoder.finish().map(drop))
+        }
+        CompressFormat::TarZst => zstd::stream::write::Encoder::new(file, 0)
+            .and_then(|encoder| write_tar(ctx, &entries, encoder))
+            .and_then(|encoder| encoder.finish().map(drop)),
+    };
+
+    result.map_err(|e| {
+        let _ = fs::remove_file(target);
+        format!("Failed to write {}: {e}", target.display())
+    })
+}
+
+fn write_zip(
+    ctx: &mut JobContext,
+    entries: &[SourceEntry],
+    file: File,
+    method: zip::CompressionMethod,
+) -> io::Result<()> {
+    let mut zip = zip::ZipWriter::new(io::BufWriter::new(file));
+    for entry in entries {
+        ctx.check_cancelled()?;
+        ctx.start_file(&entry.path);
+
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(method)
+            .large_file(entry.metadata.len() > u64::from(u32::MAX));
+        #[cfg(unix)]
+        let options = {
+            use std::os::unix::fs::PermissionsExt;
+            options.unix_permissions(entry.metadata.permissions().mode() & 0o777)
+        };
+
+        let file_type = entry.metadata.file_type();
+        if file_type.is_dir() {
+            zip.add_directory(format!("{}/", entry.name), options)?;
+        } else if file_type.is_symlink() {
+            let link = fs::read_link(&entry.path)?;
+            zip.add_symlink(entry.name.as_str(), link.to_string_lossy(), options)?;
+        } else {
+            zip.start_file(entry.name.as_str(), options)?;
+            let mut reader = ProgressReader {
+                inner: File::open(&entry.path)?,
+                ctx: &mut *ctx,
+            };
+            io::copy(&mut reader, &mut zip)?;
+        }
+    }
+    zip.finish()?.flush()
+}
+
+/// Write the entries as a tar stream and hand back the writer to finish it
+fn write_tar<W: Write>(ctx: &mut JobContext, entries: &[SourceEntry], writer: W) -> io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+    for entry in entries {
+        ctx.check_cancelled()?;
+        ctx.start_file(&entry.path);
+
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&entry.metadata);
+        let file_type = entry.metadata.file_type();
+        if file_type.is_dir() {
+            builder.append_data(&mut header, &entry.name, io::empty())?;
+        } else if file_type.is_symlink() {
+            let link = fs::read_link(&entry.path)?;
+            builder.append_link(&mut header, &entry.name, link)?;
+        } else {
+            let reader = ProgressReader {
+                inner: File::open(&entry.path)?,
+                ctx: &mut *ctx,
+            };
+            builder.append_data(&mut header, &entry.name, reader)?;
+        }
+    }
+    builder.into_inner()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::jobs::{JobStatus, spawn_job};
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn run_extract(request: ExtractRequest) -> crate::utils::jobs::JobProgressState {
+        let mut job = spawn_job("Extracting", move |ctx| extract(ctx, &request));
+        assert!(job.wait(Duration::from_secs(10)));
+        job.state
+    }
+
+    fn write_tar(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut builder = tar::Builder::new(File::create(path).unwrap());
+        for (name, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_entry_type(tar::EntryType::Regular);
+            // Bypass the builder's own path checks to get unsafe names in
+            let name_field = &mut header.as_old_mut().name;
+            name_field[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_cksum();
+            builder.append(&header, *data).unwrap();
+        }
+        builder.finish().unwrap();
+    }
+
+    #[test]
+    fn test_detect_and_destination_name() {
+        assert_eq!(
+            ArchiveFormat::detect(Path::new("a.tar.gz")),
+            Some(ArchiveFormat::Tar(TarCompression::Gzip))
+        );
+        assert_eq!(
+            ArchiveFormat::detect(Path::new("a.tar.zst")),
+            Some(ArchiveFormat::Tar(TarCompression::Zstd))
+        );
+        assert_eq!(
+            ArchiveFormat::detect(Path::new("A.ZIP")),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(ArchiveFormat::detect(Path::new("a.txt")), None);
+
+        assert_eq!(destination_name(Path::new("/x/photos.tar.gz")), "photos");
+        assert_eq!(destination_name(Path::new("/x/Report.v2.ZIP")), "Report.v2");
+        assert_eq!(destination_name(Path::new("/x/.tar")), ".tar");
+    }
+
+    #[test]
+    fn test_debug_redacts_password() {
+        let request = ExtractRequest {
+            archive: PathBuf::from("secret.zip"),
+            format: ArchiveFormat::Zip,
+            destination: PathBuf::from("out"),
+            selection: Vec::new(),
+            password: Some("hunter2".to_string()),
+        };
+        let debug = format!("{request:?}");
+        assert!(debug.contains("secret.zip"));
+        assert!(!debug.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_sanitize_entry_path() {
+        assert_eq!(
+            sanitize_entry_path("dir/./file.txt"),
+            Some(PathBuf::from("dir/file.txt"))
+        );
+        assert_eq!(
+            sanitize_entry_path("dir\\file.txt"),
+            Some(PathBuf::from("dir/file.txt"))
+        );
+        assert_eq!(sanitize_entry_path("../evil"), None);
+        assert_eq!(sanitize_entry_path("dir/../../evil"), None);
+        assert_eq!(sanitize_entry_path("/etc/passwd"), None);
+        assert_eq!(sanitize_entry_path("./"), None);
+    }
+
+    #[test]
+    fn test_symlink_targets() {
+        assert!(stays_inside(Path::new("dir/link"), Path::new("../file")));
+        assert!(stays_inside(Path::new("link"), Path::new("dir/./file")));
+        assert!(!stays_inside(Path::new("link"), Path::new("../file")));
+        assert!(!stays_inside(
+            Path::new("dir/link"),
+            Path::new("/etc/passwd")
+        ));
+    }
+
+    #[test]
+    fn test_extract_tar_skips_unsafe_entries() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("archive.tar");
+        write_tar(
+            &archive,
+            &[
+                ("docs/a.txt", b"a"),
+                ("docs/b.txt", b"b"),
+                ("../evil.txt", b"evil"),
+                ("top.txt", b"top"),
+            ],
+        );
+        let destination = temp_dir.path().join("out");
+        fs::create_dir(&destination).unwrap();
+
+        let state = run_extract(ExtractRequest {
+            archive: archive.clone(),
+            format: ArchiveFormat::Tar(TarCompression::None),
+            destination: destination.clone(),
+            selection: Vec::new(),
+            password: None,
+        });
+
+        assert_eq!(state.status, JobStatus::Completed);
+        assert_eq!(state.errors.len(), 1, "{:?}", state.errors);
+        assert_eq!(fs::read(destination.join("docs/a.txt")).unwrap(), b"a");
+        assert_eq!(fs::read(destination.join("top.txt")).unwrap(), b"top");
+        assert!(!temp_dir.path().join("evil.txt").exists());
+        assert_eq!(state.bytes_done, state.total_bytes);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_tar_chained_links_stay_inside() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("archive.tar");
+        let mut builder = tar::Builder::new(File::create(&archive).unwrap());
+        for (name, link) in [("a", "."), ("a/b", "..")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(0);
+            header.set_entry_type(tar::EntryType::Symlink);
+            builder.append_link(&mut header, name, link).unwrap();
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "a/b/evil.txt", &b"evil"[..])
+            .unwrap();
+        builder.finish().unwrap();
+
+        let destination = temp_dir.path().join("out");
+        fs::create_dir(&destination).unwrap();
+        let state = run_extract(ExtractRequest {
+            archive,
+            format: ArchiveFormat::Tar(TarCompression::None),
+            destination: destination.clone(),
+            selection: Vec::new(),
+            password: None,
+        });
+
+        assert_eq!(state.status, JobStatus::Completed);
+        assert!(!temp_dir.path().join("evil.txt").exists());
+        assert!(!fs::symlink_metadata(destination.join("a/b")).is_ok_and(|meta| meta.is_symlink()));
+        for entry in fs::read_dir(temp_dir.path()).unwrap() {
+            let name = entry.unwrap().file_name();
+            assert!(name == "archive.tar" || name == "out", "{name:?}");
+        }
+    }
+
+    #[test]
+    fn test_extract_tar_hard_links() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("archive.tar");
+        let mut builder = tar::Builder::new(File::create(&archive).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(1);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "docs/a.txt", &b"a"[..])
+            .unwrap();
+        for (name, link) in [("docs/copy.txt", "docs/a.txt"), ("evil.txt", "../a.txt")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(0);
+            header.set_mode(0o644);
+            header.set_entry_type(tar::EntryType::Link);
+            builder.append_link(&mut header, name, link).unwrap();
+        }
+        builder.finish().unwrap();
+
+        let destination = temp_dir.path().join("out");
+        fs::create_dir(&destination).unwrap();
+        let state = run_extract(ExtractRequest {
+            archive,
+            format: ArchiveFormat::Tar(TarCompression::None),
+            destination: destination.clone(),
+            selection: Vec::new(),
+            password: None,
+        });
+
+        assert_eq!(state.status, JobStatus::Completed);
+        assert_eq!(state.errors.len(), 1, "{:?}", state.errors);
+        assert_eq!(fs::read(destination.join("docs/copy.txt")).unwrap(), b"a");
+        assert!(!destination.join("evil.txt").exists());
+    }
+
+    #[test]
+    fn test_extract_zip_selection() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("archive.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("docs/", options).unwrap();
+        writer.start_file("docs/a.txt", options).unwrap();
+        writer.write_all(b"a").unwrap();
+        writer.start_file("other.txt", options).unwrap();
+        writer.write_all(b"other").unwrap();
+        writer.finish().unwrap();
+        assert!(!zip_needs_password(&archive).unwrap());
+
+        let destination = temp_dir.path().join("out");
+        fs::create_dir(&destination).unwrap();
+        let state = run_extract(ExtractRequest {
+            archive,
+            format: ArchiveFormat::Zip,
+            destination: destination.clone(),
+            selection: vec!["docs/".to_string()],
+            password: None,
+        });
+
+        assert_eq!(state.status, JobStatus::Completed);
+        assert_eq!(fs::read(destination.join("docs/a.txt")).unwrap(), b"a");
+        assert!(!destination.join("other.txt").exists());
+    }
+
+    #[test]
+    fn test_read_index_and_entry() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("archive.tar");
+        write_tar(
+            &archive,
+            &[("docs/nested/a.txt", b"a"), ("../escape.txt", b"x")],
+        );
+        let format = ArchiveFormat::Tar(TarCompression::None);
+
+        let mut index = read_index(&archive, format).unwrap();
+        index.sort_by(|a, b| a.path.cmp(&b.path));
+        let listed: Vec<(&Path, bool)> = index
+            .iter()
+            .map(|entry| (entry.path.as_path(), entry.is_dir))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                (Path::new("docs"), true),
+                (Path::new("docs/nested"), true),
+                (Path::new("docs/nested/a.txt"), false),
+            ]
+        );
+
+        let mut content = Vec::new();
+        read_entry(
+            &archive,
+            format,
+            Path::new("docs/nested/a.txt"),
+            &mut content,
+        )
+        .unwrap();
+        assert_eq!(content, b"a");
+        assert!(read_entry(&archive, format, Path::new("missing.txt"), &mut content).is_err());
+    }
+
+    #[test]
+    fn test_compress_roundtrip() {
+        let temp_dir = tempdir().unwrap();
+        let docs = temp_dir.path().join("docs");
+        fs::create_dir_all(docs.join("nested")).unwrap();
+        fs::write(docs.join("a.txt"), "a").unwrap();
+        fs::write(docs.join("nested/b.txt"), "b").unwrap();
+        let single = temp_dir.path().join("single.txt");
+        fs::write(&single, "single").unwrap();
+
+        for format in CompressFormat::ALL {
+            let target = temp_dir
+                .path()
+                .join(format!("packed.{}", format.extension()));
+            let sources = vec![docs.clone(), single.clone()];
+            let job_target = target.clone();
+            let mut job = spawn_job("Compressing", move |ctx| {
+                compress(ctx, &sources, &job_target, format)
+            });
+            assert!(job.wait(Duration::from_secs(10)));
+            assert_eq!(job.state.status, JobStatus::Completed, "{format:?}");
+            assert_eq!(job.state.bytes_done, 8);
+
+            let destination = temp_dir.path().join(format!("out-{}", format.label()));
+            fs::create_dir(&destination).unwrap();
+            let state = run_extract(ExtractRequest {
+                format: ArchiveFormat::detect(&target).unwrap(),
+                archive: target,
+                destination: destination.clone(),
+                selection: Vec::new(),
+                password: None,
+            });
+            assert_eq!(state.status, JobStatus::Completed, "{format:?}");
+            assert_eq!(fs::read(destination.join("docs/a.txt")).unwrap(), b"a");
+            assert_eq!(
+                fs::read(destination.join("docs/nested/b.txt")).unwrap(),
+                b"b"
+            );
+            assert_eq!(fs::read(destination.join("single.txt")).unwrap(), b"single");
+        }
+    }
+
+    #[test]
+    fn test_compress_never_replaces_existing_file() {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("a.txt");
+        fs::write(&source, "a").unwrap();
+        let target = temp_dir.path().join("a.zip");
+        fs::write(&target, "existing").unwrap();
+
+        let job_target = target.clone();
+        let mut job = spawn_job("Compressing", move |ctx| {
+            compress(ctx, &[source], &job_target, CompressFormat::ZipDeflate)
+        });
+        assert!(job.wait(Duration::from_secs(10)));
+        assert_eq!(job.state.status, JobStatus::Failed);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "existing");
+    }
+}
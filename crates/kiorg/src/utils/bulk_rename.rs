@@ -0,0 +1,319 @@
+//! Rename many entries in one go
+//!
+//! Every entry is first moved to a temporary name next to it and only then to
+//! its final name, so swaps and cycles such as `a -> b, b -> a` work. A failure
+//! part way through moves everything back to where it was.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::models::action_history::RenameOperation;
+use crate::utils::file_operations;
+use crate::vfs;
+
+const LISTING_HEADER: &str = "\
+# Edit the names below and save to rename, lines are matched by their number.
+# Removing a line leaves its entry unchanged.
+";
+
+/// Check a set of renames before applying it
+/// Returns the renames that actually change a name
+///
+/// # Errors
+///
+/// Returns an error string if two entries would end up with the same name or
+/// a new name is taken by an entry that is not renamed away.
+pub fn plan(renames: Vec<RenameOperation>) -> Result<Vec<RenameOperation>, String> {
+    let renames: Vec<RenameOperation> = renames
+        .into_iter()
+        .filter(|op| op.old_path != op.new_path)
+        .collect();
+
+    let sources: HashSet<&Path> = renames.iter().map(|op| op.old_path.as_path()).collect();
+    let mut targets: HashMap<&Path, &Path> = HashMap::new();
+    for op in &renames {
+        if let Some(other) = targets.insert(&op.new_path, &op.old_path) {
+            return Err(format!(
+                "Both {} and {} would be renamed to {}",
+                other.display(),
+                op.old_path.display(),
+                op.new_path.display()
+            ));
+        }
+        // Case only renames on case insensitive filesystems find the entry itself
+        if vfs::exists(&op.new_path)
+            && !sources.contains(op.new_path.as_path())
+            && !file_operations::is_same_entry(&op.old_path, &op.new_path)
+        {
+            return Err(format!("{} already exists", op.new_path.display()));
+        }
+    }
+    Ok(renames)
+}
+
+/// Apply renames checked by [`plan`], all or nothing
+///
+/// # Errors
+///
+/// Returns an error string describing the rename that failed, entries renamed
+/// so far are moved back to their old names.
+pub fn apply(renames: &[RenameOperation]) -> Result<(), String> {
+    // Park every entry under a temporary name so no new name is still taken
+    let mut parked: Vec<(PathBuf, &RenameOperation)> = Vec::with_capacity(renames.len());
+    for op in renames {
+        let temp_path = temp_path_for(&op.old_path);
+        if let Err(e) = rename(&op.old_path, &temp_path) {
+            unpark(&parked);
+            return Err(format!("Failed to rename {}: {e}", op.old_path.display()));
+        }
+        parked.push((temp_path, op));
+    }
+
+    for (index, (temp_path, op)) in parked.iter().enumerate() {
+        // Renaming onto an existing file would silently replace it
+        let result = if vfs::exists(&op.new_path) {
+            Err(format!("{} already exists", op.new_path.display()))
+        } else {
+            rename(temp_path, &op.new_path).map_err(|e| {
+                format!(
+                    "Failed to rename {} to {}: {e}",
+                    op.old_path.display(),
+                    op.new_path.display()
+                )
+            })
+        };
+
+        if let Err(e) = result {
+            for (temp_path, op) in &parked[..index] {
+                let _ = rename(&op.new_path, temp_path);
+            }
+            unpark(&parked);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Move parked entries back to their old names
+fn unpark(parked: &[(PathBuf, &RenameOperation)]) {
+    for (temp_path, op) in parked.iter().rev() {
+        let _ = rename(temp_path, &op.old_path);
+    }
+}
+
+fn rename(from: &Path, to: &Path) -> std::io::Result<()> {
+    vfs::for_path(from).rename(from, to)
+}
+
+fn temp_path_for(path: &Path) -> PathBuf {
+    let name = format!(".kiorg-rename-{}", uuid::Uuid::new_v4().simple());
+    path.with_file_name(name)
+}
+
+/// The same renames in the opposite direction, used to undo them
+pub fn reversed(renames: &[RenameOperation]) -> Vec<RenameOperation> {
+    renames
+        .iter()
+        .map(|op| RenameOperation {
+            old_path: op.new_path.clone(),
+            new_path: op.old_path.clone(),
+        })
+        .collect()
+}
+
+/// Whether the name can be written to and read back from a listing line
+pub fn is_listable(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| !name.contains(['\n', '\r']))
+}
+
+/// Text listing the names of `paths` one per line, prefixed with their number
+/// Paths must be [listable](is_listable)
+pub fn format_listing(paths: &[PathBuf]) -> String {
+    let mut listing = LISTING_HEADER.to_string();
+    for (index, path) in paths.iter().enumerate() {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        listing.push_str(&format!("{}\t{name}\n", index + 1));
+    }
+    listing
+}
+
+/// Turn an edited listing of `paths` back into renames
+///
+/// # Errors
+///
+/// Returns an error string naming the first line that cannot be understood.
+pub fn parse_listing(paths: &[PathBuf], edited: &str) -> Result<Vec<RenameOperation>, String> {
+    let mut seen = HashSet::new();
+    let mut renames = Vec::new();
+
+    for (line_index, line) in edited.lines().enumerate() {
+        let line_number = line_index + 1;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // The name starts right after the tab following the number, so leading
+        // spaces are kept. Editors set to expand tabs may have replaced it with
+        // spaces, those lines lose the leading spaces of the name.
+        let (number, name) = line
+            .split_once('\t')
+            .or_else(|| {
+                line.split_once(' ')
+                    .map(|(number, name)| (number, name.trim_start_matches(' ')))
+            })
+            .ok_or_else(|| format!("Line {line_number}: expected a number and a name"))?;
+        let path = number
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .and_then(|index| paths.get(index))
+            .ok_or_else(|| format!("Line {line_number}: unknown entry number '{number}'"))?;
+        if !seen.insert(path) {
+            return Err(format!(
+                "Line {line_number}: entry {number} is listed twice"
+            ));
+        }
+
+        validate_name(name).map_err(|e| format!("Line {line_number}: {e}"))?;
+        renames.push(RenameOperation {
+            old_path: path.clone(),
+            new_path: path.with_file_name(name),
+        });
+    }
+    Ok(renames)
+}
+
+/// Make sure a new name stays a plain entry name inside its directory
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("name is empty".to_string());
+    }
+    if name == "." || name == ".." {
+        return Err(format!("'{name}' is not a valid name"));
+    }
+    if name.contains('/') || name.contains(std::path::MAIN_SEPARATOR) {
+        return Err(format!("'{name}' contains a path separator"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn rename(old_path: &Path, new_path: &Path) -> RenameOperation {
+        RenameOperation {
+            old_path: old_path.to_path_buf(),
+            new_path: new_path.to_path_buf(),
+        }
+    }
+
+    #[test]
+    fn test_apply_swaps_names() {
+        let temp_dir = tempdir().unwrap();
+        let a = temp_dir.path().join("a.txt");
+        let b = temp_dir.path().join("b.txt");
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+
+        let renames = plan(vec![rename(&a, &b), rename(&b, &a)]).unwrap();
+        apply(&renames).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "b");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "a");
+
+        // Undo swaps them back
+        apply(&reversed(&renames)).unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_plan_rejects_conflicts() {
+        let temp_dir = tempdir().unwrap();
+        let a = temp_dir.path().join("a");
+        let b = temp_dir.path().join("b");
+        let c = temp_dir.path().join("c");
+        fs::write(&a, "").unwrap();
+        fs::write(&b, "").unwrap();
+        fs::write(&c, "").unwrap();
+
+        // Two entries cannot share a name
+        let new = temp_dir.path().join("new");
+        assert!(plan(vec![rename(&a, &new), rename(&b, &new)]).is_err());
+        // c stays in place, so its name is taken
+        assert!(plan(vec![rename(&a, &c)]).is_err());
+        // Unchanged names are dropped
+        assert!(plan(vec![rename(&a, &a)]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_apply_rolls_back_on_failure() {
+        let temp_dir = tempdir().unwrap();
+        let a = temp_dir.path().join("a");
+        let b = temp_dir.path().join("b");
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+
+        // The second target appears after planning
+        let renames = plan(vec![
+            rename(&a, &temp_dir.path().join("x")),
+            rename(&b, &temp_dir.path().join("y")),
+        ])
+        .unwrap();
+        fs::write(temp_dir.path().join("y"), "y").unwrap();
+
+        assert!(apply(&renames).is_err());
+        assert_eq!(fs::read_to_string(&a).unwrap(), "a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+        assert!(!temp_dir.path().join("x").exists());
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_listing_roundtrip() {
+        let dir = Path::new("/some/dir");
+        let paths = vec![dir.join("one.txt"), dir.join("two.txt"), dir.join("three")];
+        let listing = format_listing(&paths);
+
+        // Unchanged listing maps every entry to itself
+        let renames = parse_listing(&paths, &listing).unwrap();
+        assert!(renames.iter().all(|op| op.old_path == op.new_path));
+
+        let edited = listing
+            .replace("one.txt", "uno.txt")
+            .replace("3\tthree\n", "");
+        let renames = parse_listing(&paths, &edited).unwrap();
+        assert_eq!(renames.len(), 2);
+        assert_eq!(renames[0].new_path, dir.join("uno.txt"));
+        assert_eq!(renames[1].new_path, dir.join("two.txt"));
+    }
+
+    #[test]
+    fn test_parse_listing_errors() {
+        let paths = vec![PathBuf::from("/dir/a"), PathBuf::from("/dir/b")];
+        assert!(parse_listing(&paths, "3\tc\n").is_err());
+        assert!(parse_listing(&paths, "1\tc\n1\td\n").is_err());
+        assert!(parse_listing(&paths, "1\tsub/c\n").is_err());
+        assert!(parse_listing(&paths, "1\t\n").is_err());
+        assert!(parse_listing(&paths, "nonsense\n").is_err());
+        // Spaces work as separator too
+        let renames = parse_listing(&paths, "2 new name\n").unwrap();
+        assert_eq!(renames[0].new_path, PathBuf::from("/dir/new name"));
+    }
+
+    #[test]
+    fn test_parse_listing_keeps_leading_spaces() {
+        let paths = vec![PathBuf::from("/dir/a"), PathBuf::from("/dir/  b")];
+        let renames = parse_listing(&paths, &format_listing(&paths)).unwrap();
+        assert_eq!(renames[1].new_path, PathBuf::from("/dir/  b"));
+
+        let renames = parse_listing(&paths, "1\t a\n").unwrap();
+        assert_eq!(renames[0].new_path, PathBuf::from("/dir/ a"));
+    }
+}
@@ -0,0 +1,348 @@
+//! Recursive search of file contents, the way ripgrep does it
+//!
+//! Directories are walked with the `ignore` crate, so `.gitignore`, `.ignore`
+//! and hidden files are skipped like ripgrep skips them. Binary files are
+//! left out.
+
+use std::fs;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
+
+use regex::{Regex, RegexBuilder};
+
+/// Lines shown before and after a matching line
+pub const CONTEXT_LINES: usize = 2;
+
+/// The search stops after this many matching lines
+pub const MAX_MATCHES: usize = 10_000;
+
+/// Larger files are skipped, they are read into memory in one go
+const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Files with a NUL byte in their first block are treated as binary
+const BINARY_CHECK_SIZE: usize = 8 * 1024;
+
+/// What to search for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub query: String,
+    /// Treat the query as a regex instead of literal text
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Search hidden files and directories as well
+    pub show_hidden: bool,
+}
+
+impl SearchOptions {
+    /// Compile the query
+    ///
+    /// # Errors
+    ///
+    /// Returns an error string if the query is not a valid regex.
+    pub fn build_regex(&self) -> Result<Regex, String> {
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|e| format!("Invalid regex: {e}"))
+    }
+}
+
+/// A matching line with the lines around it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentMatch {
+    pub path: PathBuf,
+    /// 1-based, like editors and ripgrep show it
+    pub line_number: usize,
+    pub line: String,
+    /// Byte ranges of the matches in `line`
+    pub ranges: Vec<Range<usize>>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// Progress reported by a running search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchEvent {
+    Match(ContentMatch),
+    /// The walk finished, `truncated` if it stopped at [`MAX_MATCHES`]
+    Done {
+        files: usize,
+        truncated: bool,
+    },
+}
+
+/// A search running on a background thread
+///
+/// Dropping it stops the search.
+#[derive(Debug)]
+pub struct ContentSearch {
+    receiver: mpsc::Receiver<SearchEvent>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ContentSearch {
+    /// Start searching the files under `root`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error string if the query is not a valid regex.
+    pub fn start(root: PathBuf, options: &SearchOptions) -> Result<Self, String> {
+        let regex = options.build_regex()?;
+        let show_hidden = options.show_hidden;
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let thread_cancelled = cancelled.clone();
+
+        std::thread::spawn(move || {
+            let (files, truncated) =
+                search_dir(&root, &regex, show_hidden, &thread_cancelled, |m| {
+                    sender.send(SearchEvent::Match(m)).is_ok()
+                });
+            let _ = sender.send(SearchEvent::Done { files, truncated });
+        });
+
+        Ok(Self {
+            receiver,
+            cancelled,
+        })
+    }
+
+    /// Events that arrived since the last call
+    pub fn poll(&self) -> Vec<SearchEvent> {
+        self.receiver.try_iter().collect()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ContentSearch {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Walk `root` in file name order and report every matching line
+///
+/// `on_match` returns false to stop the walk. Returns the number of files
+/// searched and whether the search stopped at [`MAX_MATCHES`].
+pub fn search_dir(
+    root: &Path,
+    regex: &Regex,
+    show_hidden: bool,
+    cancelled: &AtomicBool,
+    mut on_match: impl FnMut(ContentMatch) -> bool,
+) -> (usize, bool) {
+    let walker = ignore::WalkBuilder::new(root)
+        .hidden(!show_hidden)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    let mut files = 0;
+    let mut matches = 0;
+    for entry in walker {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        let Ok(entry) = entry else {
+            continue;
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+
+        files += 1;
+        let found = match search_file(entry.path(), regex) {
+            Ok(found) => found,
+            Err(e) => {
+                tracing::debug!("Failed to search {}: {e}", entry.path().display());
+                continue;
+            }
+        };
+        for content_match in found {
+            if matches == MAX_MATCHES {
+                return (files, true);
+            }
+            matches += 1;
+            if !on_match(content_match) {
+                return (files, false);
+            }
+        }
+    }
+    (files, false)
+}
+
+/// Matching lines of a single file, empty for binary and oversized files
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read.
+pub fn search_file(path: &Path, regex: &Regex) -> io::Result<Vec<ContentMatch>> {
+    let file = fs::File::open(path)?;
+    if file.metadata()?.len() > MAX_FILE_SIZE {
+        return Ok(Vec::new());
+    }
+    let mut bytes = Vec::new();
+    file.take(MAX_FILE_SIZE).read_to_end(&mut bytes)?;
+    if bytes[..bytes.len().min(BINARY_CHECK_SIZE)].contains(&0) {
+        return Ok(Vec::new());
+    }
+
+    let content = String::from_utf8_lossy(&bytes);
+    let lines: Vec<&str> = content.lines().collect();
+    let mut found = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let ranges: Vec<Range<usize>> = regex.find_iter(line).map(|m| m.range()).collect();
+        if ranges.is_empty() {
+            continue;
+        }
+        let before = lines[index.saturating_sub(CONTEXT_LINES)..index]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let after = lines[index + 1..(index + 1 + CONTEXT_LINES).min(lines.len())]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        found.push(ContentMatch {
+            path: path.to_path_buf(),
+            line_number: index + 1,
+            line: (*line).to_string(),
+            ranges,
+            before,
+            after,
+        });
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn options(query: &str) -> SearchOptions {
+        SearchOptions {
+            query: query.to_string(),
+            regex: false,
+            case_sensitive: false,
+            show_hidden: false,
+        }
+    }
+
+    fn collect(root: &Path, options: &SearchOptions) -> Vec<(String, usize)> {
+        let regex = options.build_regex().unwrap();
+        let mut found = Vec::new();
+        search_dir(
+            root,
+            &regex,
+            options.show_hidden,
+            &AtomicBool::new(false),
+            |m| {
+                let name = m
+                    .path
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                found.push((name, m.line_number));
+                true
+            },
+        );
+        found
+    }
+
+    #[test]
+    fn test_search_file_context() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("a.txt");
+        fs::write(&path, "one\ntwo\nthree Two\nfour\nfive\nsix").unwrap();
+
+        let regex = options("two").build_regex().unwrap();
+        let found = search_file(&path, &regex).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].line_number, 2);
+        assert_eq!(found[0].before, vec!["one"]);
+        assert_eq!(found[0].after, vec!["three Two", "four"]);
+        assert_eq!(found[1].line_number, 3);
+        assert_eq!(found[1].ranges, vec![6..9]);
+
+        fs::write(&path, b"two\0binary").unwrap();
+        assert!(search_file(&path, &regex).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_options() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("a.txt"), "Foo\nfoo.bar\nfoo-bar").unwrap();
+        fs::write(root.join(".hidden.txt"), "foo").unwrap();
+
+        let all = vec![
+            ("a.txt".to_string(), 1),
+            ("a.txt".to_string(), 2),
+            ("a.txt".to_string(), 3),
+        ];
+        assert_eq!(collect(root, &options("foo")), all);
+
+        let case_sensitive = SearchOptions {
+            case_sensitive: true,
+            ..options("Foo")
+        };
+        assert_eq!(
+            collect(root, &case_sensitive),
+            vec![("a.txt".to_string(), 1)]
+        );
+
+        // Literal dots only match dots, as a regex they match anything
+        assert_eq!(
+            collect(root, &options("foo.bar")),
+            vec![("a.txt".to_string(), 2)]
+        );
+        let regex = SearchOptions {
+            regex: true,
+            ..options("foo.bar")
+        };
+        assert_eq!(collect(root, &regex).len(), 2);
+        assert!(
+            SearchOptions {
+                regex: true,
+                ..options("(")
+            }
+            .build_regex()
+            .is_err()
+        );
+
+        let hidden = SearchOptions {
+            show_hidden: true,
+            ..options("foo")
+        };
+        assert_eq!(collect(root, &hidden)[0], (".hidden.txt".to_string(), 1));
+    }
+
+    #[test]
+    fn test_gitignore_is_respected() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        // .gitignore files only apply inside git repositories, like in ripgrep
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::create_dir(root.join("target")).unwrap();
+        fs::write(root.join("target/out.txt"), "needle").unwrap();
+        fs::write(root.join("src.txt"), "needle").unwrap();
+
+        assert_eq!(
+            collect(root, &options("needle")),
+            vec![("src.txt".to_string(), 1)]
+        );
+    }
+}
@@ -1,31 +1,424 @@
+use std::fs::{File, FileTimes, Metadata};
+use std::io::{Read, Write};
 use std::path::Path;
 
+use crate::utils::jobs::JobContext;
+
+/// Buffer size used when copying file contents with progress reporting
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Extended attribute namespace that is not copied, labels like
+/// `security.selinux` belong to the target location. Matches the default of `cp -a`.
+#[cfg(unix)]
+const SKIPPED_XATTR_PREFIX: &str = "security.";
+
 /// Recursively copy a directory from src to dst
+///
+/// Same as [`copy_path`], the destination directory is created if missing.
 pub fn copy_dir_recursively(src: &Path, dst: &Path) -> std::io::Result<()> {
-    // Create the destination directory if it doesn't exist
-    if !dst.exists() {
-        std::fs::create_dir_all(dst)?;
+    copy_path(src, dst)
+}
+
+/// Copy a file, directory or symlink from src to dst preserving its metadata
+///
+/// Problems that do not prevent the copy, like skipped special files, are logged.
+pub fn copy_path(src: &Path, dst: &Path) -> std::io::Result<()> {
+    TreeCopy::new(None).copy(src, dst)
+}
+
+/// Identity of a directory used to detect loops, e.g. from bind mounts
+#[cfg(unix)]
+type FileId = (u64, u64);
+#[cfg(not(unix))]
+type FileId = ();
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<FileId> {
+    None
+}
+
+/// Whether two paths name the same entry, e.g. differently cased paths on a
+/// case insensitive filesystem
+pub fn is_same_entry(a: &Path, b: &Path) -> bool {
+    match (a.symlink_metadata(), b.symlink_metadata()) {
+        (Ok(a_meta), Ok(b_meta)) => match (file_id(&a_meta), file_id(&b_meta)) {
+            (Some(a_id), Some(b_id)) => a_id == b_id,
+            _ => matches!(
+                (a.canonicalize(), b.canonicalize()),
+                (Ok(a), Ok(b)) if a == b
+            ),
+        },
+        _ => false,
     }
+}
 
-    // Iterate through the source directory entries
-    for entry in std::fs::read_dir(src)? {
-        let entry = entry?;
-        let entry_path = entry.path();
-        let file_name = entry.file_name();
-        let dst_path = dst.join(file_name);
+/// Copies a tree of entries the way `cp -a` does
+///
+/// Symlinks are recreated instead of followed and permissions, timestamps and
+/// extended attributes are carried over. FIFOs, sockets and device nodes are
+/// reported and skipped instead of failing the whole copy.
+struct TreeCopy<'a> {
+    ctx: Option<&'a mut JobContext>,
+    /// Directories currently being copied, from the root down
+    ancestors: Vec<FileId>,
+    /// The destination root, so copying a directory into itself terminates
+    dst_root: Option<FileId>,
+}
 
-        if entry_path.is_dir() {
-            // Recursively copy subdirectories
-            copy_dir_recursively(&entry_path, &dst_path)?;
+impl<'a> TreeCopy<'a> {
+    fn new(ctx: Option<&'a mut JobContext>) -> Self {
+        Self {
+            ctx,
+            ancestors: Vec::new(),
+            dst_root: None,
+        }
+    }
+
+    fn copy(&mut self, src: &Path, dst: &Path) -> std::io::Result<()> {
+        if let Some(ctx) = self.ctx.as_deref_mut() {
+            ctx.check_cancelled()?;
+        }
+
+        let metadata = std::fs::symlink_metadata(src)?;
+        let file_type = metadata.file_type();
+        if !(file_type.is_symlink() || file_type.is_dir() || file_type.is_file()) {
+            self.report(format!("Skipped special file {}", src.display()));
+            return Ok(());
+        }
+
+        if let Some(ctx) = self.ctx.as_deref_mut() {
+            ctx.start_file(src);
+        }
+        if file_type.is_symlink() {
+            copy_symlink(src, dst)
+        } else if file_type.is_dir() {
+            self.copy_dir(src, dst, &metadata)
         } else {
-            // Copy files
-            std::fs::copy(&entry_path, &dst_path)?;
+            self.copy_file(src, dst, &metadata)
+        }
+    }
+
+    fn copy_dir(&mut self, src: &Path, dst: &Path, metadata: &Metadata) -> std::io::Result<()> {
+        let id = file_id(metadata);
+        if let Some(id) = id
+            && (self.ancestors.contains(&id) || self.dst_root == Some(id))
+        {
+            self.report(format!(
+                "Skipped {} to avoid copying a directory into itself",
+                src.display()
+            ));
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(dst)?;
+        if self.ancestors.is_empty() {
+            self.dst_root = file_id(&std::fs::symlink_metadata(dst)?);
+        }
+
+        if let Some(id) = id {
+            self.ancestors.push(id);
+        }
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            self.copy(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        if id.is_some() {
+            self.ancestors.pop();
+        }
+
+        // Applied after the content, writing children updates the directory mtime
+        self.preserve_xattrs(src, dst);
+        if let Err(e) = open_dir(dst).and_then(|dir| dir.set_times(file_times(metadata))) {
+            self.report(format!(
+                "Failed to preserve timestamps of {}: {e}",
+                dst.display()
+            ));
+        }
+        std::fs::set_permissions(dst, metadata.permissions())
+    }
+
+    fn copy_file(&mut self, src: &Path, dst: &Path, metadata: &Metadata) -> std::io::Result<()> {
+        let mut reader = File::open(src)?;
+        let mut writer = File::create(dst)?;
+        match self.ctx.as_deref_mut() {
+            Some(ctx) => copy_content_with_progress(&mut reader, &mut writer, ctx)?,
+            None => {
+                std::io::copy(&mut reader, &mut writer)?;
+            }
+        }
+
+        // Attributes first, writing them may need the write permission we drop below
+        self.preserve_xattrs(src, dst);
+        if let Err(e) = writer.set_times(file_times(metadata)) {
+            self.report(format!(
+                "Failed to preserve timestamps of {}: {e}",
+                dst.display()
+            ));
+        }
+        writer.set_permissions(metadata.permissions())
+    }
+
+    fn preserve_xattrs(&mut self, src: &Path, dst: &Path) {
+        match copy_xattrs(src, dst) {
+            // Filesystems without extended attributes have nothing to copy
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {}
+            Err(e) => self.report(format!(
+                "Failed to copy extended attributes of {}: {e}",
+                src.display()
+            )),
+            Ok(()) => {}
         }
     }
 
+    fn report(&mut self, message: String) {
+        match self.ctx.as_deref_mut() {
+            Some(ctx) => ctx.warn(message),
+            None => tracing::warn!("{message}"),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn copy_xattrs(src: &Path, dst: &Path) -> std::io::Result<()> {
+    for name in xattr::list(src)? {
+        if name.to_string_lossy().starts_with(SKIPPED_XATTR_PREFIX) {
+            continue;
+        }
+        if let Some(value) = xattr::get(src, &name)? {
+            xattr::set(dst, &name, &value)?;
+        }
+    }
     Ok(())
 }
 
+#[cfg(not(unix))]
+fn copy_xattrs(_src: &Path, _dst: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Open a directory so its timestamps can be changed
+#[cfg(not(windows))]
+pub(crate) fn open_dir(path: &Path) -> std::io::Result<File> {
+    File::open(path)
+}
+
+/// Open a directory so its timestamps can be changed
+#[cfg(windows)]
+pub(crate) fn open_dir(path: &Path) -> std::io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    std::fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(windows_sys::Win32::Storage::FileSystem::FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+}
+
+/// Access and modification time of an entry, whichever the platform provides
+fn file_times(metadata: &Metadata) -> FileTimes {
+    let mut times = FileTimes::new();
+    if let Ok(accessed) = metadata.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    if let Ok(modified) = metadata.modified() {
+        times = times.set_modified(modified);
+    }
+    times
+}
+
+/// Recreate the symlink at src with the same target at dst
+fn copy_symlink(src: &Path, dst: &Path) -> std::io::Result<()> {
+    let target = std::fs::read_link(src)?;
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&target, dst)
+    }
+    #[cfg(windows)]
+    {
+        // Windows distinguishes links to directories, resolve relative to the link
+        let resolved = src
+            .parent()
+            .map_or_else(|| target.clone(), |parent| parent.join(&target));
+        if resolved.is_dir() {
+            std::os::windows::fs::symlink_dir(&target, dst)
+        } else {
+            std::os::windows::fs::symlink_file(&target, dst)
+        }
+    }
+}
+
+/// Count the number of entries and total bytes under the given paths
+/// Directories and symlinks count as one entry each so they show up in progress reporting.
+/// Symlinks are not followed and special files are left out since copying skips them.
+pub fn count_entries(paths: &[std::path::PathBuf]) -> (usize, u64) {
+    let mut files = 0;
+    let mut bytes = 0;
+    for path in paths {
+        count_entries_recursive(path, &mut files, &mut bytes);
+    }
+    (files, bytes)
+}
+
+fn count_entries_recursive(path: &Path, files: &mut usize, bytes: &mut u64) {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return;
+    };
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        *files += 1;
+        if let Ok(entries) = std::fs::read_dir(path) {
+            for entry in entries.flatten() {
+                count_entries_recursive(&entry.path(), files, bytes);
+            }
+        }
+    } else if file_type.is_file() {
+        *files += 1;
+        *bytes += metadata.len();
+    } else if file_type.is_symlink() {
+        *files += 1;
+    }
+}
+
+/// Copy a file or directory, reporting progress to the job context
+///
+/// Metadata is preserved the same way as [`copy_path`], problems that do not
+/// prevent the copy are reported as job warnings.
+/// On cancellation the partially written target is left for the caller to clean up
+/// and an `Interrupted` error is returned.
+pub fn copy_with_progress(src: &Path, dst: &Path, ctx: &mut JobContext) -> std::io::Result<()> {
+    TreeCopy::new(Some(ctx)).copy(src, dst)
+}
+
+fn copy_content_with_progress(
+    reader: &mut File,
+    writer: &mut File,
+    ctx: &mut JobContext,
+) -> std::io::Result<()> {
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+    loop {
+        ctx.check_cancelled()?;
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        writer.write_all(&buffer[..read])?;
+        ctx.add_bytes(read as u64);
+    }
+}
+
+/// Check whether a rename failed because source and target live on different filesystems
+pub fn is_cross_device_error(error: &std::io::Error) -> bool {
+    error.kind() == std::io::ErrorKind::CrossesDevices
+}
+
+/// Move a file or directory, falling back to copy-then-delete when the target
+/// is on a different filesystem than the source
+pub fn move_path(src: &Path, dst: &Path) -> std::io::Result<()> {
+    match std::fs::rename(src, dst) {
+        Err(e) if is_cross_device_error(&e) => {
+            let copy_result = copy_path(src, dst);
+            finish_cross_device_move(src, dst, copy_result)
+        }
+        result => result,
+    }
+}
+
+/// Same as [`move_path`] but reports copy progress for cross filesystem moves
+pub fn move_with_progress(src: &Path, dst: &Path, ctx: &mut JobContext) -> std::io::Result<()> {
+    match std::fs::rename(src, dst) {
+        Err(e) if is_cross_device_error(&e) => {
+            // Rename is instant, only the fallback copy contributes bytes to the totals
+            let (files, bytes) = count_entries(&[src.to_path_buf()]);
+            ctx.add_totals(files, bytes);
+            let copy_result = copy_with_progress(src, dst, ctx);
+            finish_cross_device_move(src, dst, copy_result)
+        }
+        result => result,
+    }
+}
+
+/// Verify the copy made for a cross filesystem move and remove the source
+fn finish_cross_device_move(
+    src: &Path,
+    dst: &Path,
+    copy_result: std::io::Result<()>,
+) -> std::io::Result<()> {
+    if let Err(e) = copy_result.and_then(|()| verify_copy(src, dst)) {
+        // The source is untouched at this point, so dropping the copy loses nothing
+        remove_partial(dst);
+        return Err(e);
+    }
+
+    let remove_result = if src.symlink_metadata().is_ok_and(|m| m.is_dir()) {
+        std::fs::remove_dir_all(src)
+    } else {
+        std::fs::remove_file(src)
+    };
+    remove_result.map_err(|e| {
+        std::io::Error::other(format!(
+            "copied to {} but failed to remove source: {e}",
+            dst.display()
+        ))
+    })
+}
+
+/// Compare entry count and total size of a copy against its source
+///
+/// Special files are skipped by the copy, a source holding any fails the
+/// verification so it is not removed along with them.
+fn verify_copy(src: &Path, dst: &Path) -> std::io::Result<()> {
+    if let Some(special) = find_special_file(src) {
+        return Err(std::io::Error::other(format!(
+            "cannot move special file {}",
+            special.display()
+        )));
+    }
+    let expected = count_entries(&[src.to_path_buf()]);
+    let actual = count_entries(&[dst.to_path_buf()]);
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "copy verification failed: expected {} entries ({} bytes), found {} entries ({} bytes)",
+            expected.0, expected.1, actual.0, actual.1
+        )))
+    }
+}
+
+/// First FIFO, socket or device node under `path`, symlinks are not followed
+fn find_special_file(path: &Path) -> Option<std::path::PathBuf> {
+    let file_type = std::fs::symlink_metadata(path).ok()?.file_type();
+    if file_type.is_dir() {
+        std::fs::read_dir(path)
+            .ok()?
+            .flatten()
+            .find_map(|entry| find_special_file(&entry.path()))
+    } else if file_type.is_file() || file_type.is_symlink() {
+        None
+    } else {
+        Some(path.to_path_buf())
+    }
+}
+
+/// Best effort removal of a partially copied file or directory
+pub fn remove_partial(path: &Path) {
+    if path.symlink_metadata().is_err() {
+        return;
+    }
+    let result = if path.symlink_metadata().is_ok_and(|m| m.is_dir()) {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to clean up {}: {e}", path.display());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -41,4 +434,176 @@ mod tests {
         let result = copy_dir_recursively(src, dst);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_copy_path_preserves_metadata() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        let dst = temp_dir.path().join("dst");
+        std::fs::create_dir(&src).unwrap();
+        std::fs::write(src.join("a.txt"), "content").unwrap();
+
+        let mtime = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        let times = FileTimes::new().set_modified(mtime);
+        File::options()
+            .write(true)
+            .open(src.join("a.txt"))
+            .unwrap()
+            .set_times(times)
+            .unwrap();
+        File::open(&src).unwrap().set_times(times).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(src.join("a.txt"), std::fs::Permissions::from_mode(0o640))
+                .unwrap();
+            std::fs::set_permissions(&src, std::fs::Permissions::from_mode(0o750)).unwrap();
+        }
+
+        copy_path(&src, &dst).unwrap();
+
+        let copied = std::fs::metadata(dst.join("a.txt")).unwrap();
+        assert_eq!(copied.modified().unwrap(), mtime);
+        assert_eq!(std::fs::metadata(&dst).unwrap().modified().unwrap(), mtime);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(copied.permissions().mode() & 0o777, 0o640);
+            let dir_mode = std::fs::metadata(&dst).unwrap().permissions().mode();
+            assert_eq!(dir_mode & 0o777, 0o750);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_path_recreates_symlinks_and_skips_special_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        let dst = temp_dir.path().join("dst");
+        std::fs::create_dir(&src).unwrap();
+        std::fs::write(src.join("a.txt"), "content").unwrap();
+        std::os::unix::fs::symlink("a.txt", src.join("link")).unwrap();
+        // Would recurse forever if symlinks were followed
+        std::os::unix::fs::symlink("..", src.join("parent")).unwrap();
+        let _socket = std::os::unix::net::UnixListener::bind(src.join("socket")).unwrap();
+
+        copy_path(&src, &dst).unwrap();
+
+        assert_eq!(
+            std::fs::read_link(dst.join("link")).unwrap(),
+            Path::new("a.txt")
+        );
+        assert_eq!(
+            std::fs::read_link(dst.join("parent")).unwrap(),
+            Path::new("..")
+        );
+        assert!(dst.join("socket").symlink_metadata().is_err());
+        assert_eq!(count_entries(&[src]), count_entries(&[dst]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_path_preserves_xattrs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("a.txt");
+        let dst = temp_dir.path().join("b.txt");
+        std::fs::write(&src, "content").unwrap();
+        if xattr::set(&src, "user.kiorg.test", b"value").is_err() {
+            // The filesystem backing the temp dir has no user xattrs
+            return;
+        }
+
+        copy_path(&src, &dst).unwrap();
+
+        assert_eq!(
+            xattr::get(&dst, "user.kiorg.test").unwrap(),
+            Some(b"value".to_vec())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_dir_into_itself_terminates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        std::fs::create_dir_all(src.join("sub")).unwrap();
+        std::fs::write(src.join("sub/a.txt"), "content").unwrap();
+
+        copy_path(&src, &src.join("sub/copy")).unwrap();
+
+        assert!(src.join("sub/copy/sub/a.txt").exists());
+        assert!(!src.join("sub/copy/sub/copy").exists());
+    }
+
+    #[test]
+    fn test_count_entries() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("dir");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "12345").unwrap();
+        std::fs::write(temp_dir.path().join("b.txt"), "123").unwrap();
+
+        let (files, bytes) = count_entries(&[dir, temp_dir.path().join("b.txt")]);
+        assert_eq!(files, 3);
+        assert_eq!(bytes, 8);
+    }
+
+    #[test]
+    fn test_finish_cross_device_move_removes_source() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        let dst = temp_dir.path().join("dst");
+        std::fs::create_dir(&src).unwrap();
+        std::fs::write(src.join("a.txt"), "content").unwrap();
+
+        let copy_result = copy_dir_recursively(&src, &dst);
+        finish_cross_device_move(&src, &dst, copy_result).unwrap();
+
+        assert!(!src.exists());
+        assert_eq!(
+            std::fs::read_to_string(dst.join("a.txt")).unwrap(),
+            "content"
+        );
+    }
+
+    #[test]
+    fn test_finish_cross_device_move_keeps_source_on_failed_verification() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        let dst = temp_dir.path().join("dst");
+        std::fs::create_dir(&src).unwrap();
+        std::fs::write(src.join("a.txt"), "content").unwrap();
+        // Simulate an incomplete copy
+        std::fs::create_dir(&dst).unwrap();
+
+        assert!(finish_cross_device_move(&src, &dst, Ok(())).is_err());
+        assert!(src.join("a.txt").exists());
+        assert!(!dst.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_finish_cross_device_move_keeps_source_with_fifo() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        let dst = temp_dir.path().join("dst");
+        std::fs::create_dir(&src).unwrap();
+        std::fs::write(src.join("a.txt"), "content").unwrap();
+        let status = std::process::Command::new("mkfifo")
+            .arg(src.join("fifo"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        // The copy skips the FIFO and still counts the same entries
+        let copy_result = copy_path(&src, &dst);
+        assert!(copy_result.is_ok());
+        assert!(finish_cross_device_move(&src, &dst, copy_result).is_err());
+
+        let fifo = src.join("fifo").symlink_metadata().unwrap();
+        assert!(!fifo.is_file() && !fifo.is_dir());
+        assert!(src.join("a.txt").exists());
+        assert!(!dst.exists());
+    }
 }
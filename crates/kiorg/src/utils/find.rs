@@ -0,0 +1,299 @@
+//! Recursive file name search, the way fzf does it
+//!
+//! The subtree is walked on a background thread and fed into `nucleo`'s
+//! streaming matcher, so results show up and get ranked while indexing is
+//! still running. The walk skips what `.gitignore`, `.ignore` and the hidden
+//! toggle would hide.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use nucleo::pattern::{CaseMatching, Normalization};
+use nucleo::{Config, Matcher, Nucleo};
+
+/// Time the matcher is given per frame
+const TICK_TIMEOUT_MS: u64 = 10;
+
+/// An entry found under the search root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FindEntry {
+    pub path: PathBuf,
+    /// Path relative to the search root, what the query is matched against
+    pub relative: String,
+    pub is_dir: bool,
+}
+
+/// A ranked entry with the chars of `relative` that matched the query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FindMatch {
+    pub entry: FindEntry,
+    pub indices: Vec<u32>,
+}
+
+/// Entries of a directory tree, matched against a query as they are found
+///
+/// Dropping it stops the walk.
+pub struct FileIndex {
+    root: PathBuf,
+    matcher: Nucleo<FindEntry>,
+    /// Finds the matched chars of the few results that get shown
+    indices_matcher: Matcher,
+    query: String,
+    indexed: Arc<AtomicUsize>,
+    walking: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for FileIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileIndex")
+            .field("root", &self.root)
+            .field("query", &self.query)
+            .field("indexed", &self.indexed)
+            .field("walking", &self.walking)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FileIndex {
+    /// Start indexing the entries under `root`
+    ///
+    /// `notify` is called from the matcher threads when new results are
+    /// ready, it is meant to request a repaint.
+    pub fn start(root: PathBuf, show_hidden: bool, notify: Arc<dyn Fn() + Send + Sync>) -> Self {
+        let matcher = Nucleo::new(Config::DEFAULT.match_paths(), notify, None, 1);
+        let injector = matcher.injector();
+        let indexed = Arc::new(AtomicUsize::new(0));
+        let walking = Arc::new(AtomicBool::new(true));
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let walk_root = root.clone();
+        let walk_indexed = indexed.clone();
+        let walk_running = walking.clone();
+        let walk_cancelled = cancelled.clone();
+        std::thread::spawn(move || {
+            walk(&walk_root, show_hidden, &walk_cancelled, |entry| {
+                injector.push(entry, |entry, columns| {
+                    columns[0] = entry.relative.as_str().into();
+                });
+                walk_indexed.fetch_add(1, Ordering::Relaxed);
+            });
+            walk_running.store(false, Ordering::Relaxed);
+        });
+
+        Self {
+            root,
+            matcher,
+            indices_matcher: Matcher::new(Config::DEFAULT.match_paths()),
+            query: String::new(),
+            indexed,
+            walking,
+            cancelled,
+        }
+    }
+
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Match against a new query, narrowing the previous matches when the
+    /// query only grew
+    pub fn set_query(&mut self, query: &str) {
+        if query == self.query {
+            return;
+        }
+        let append = query.starts_with(&self.query);
+        self.matcher
+            .pattern
+            .reparse(0, query, CaseMatching::Smart, Normalization::Smart, append);
+        self.query = query.to_string();
+    }
+
+    /// Let the matcher pick up new entries and query changes, returns true
+    /// while there is more work to do
+    pub fn tick(&mut self) -> bool {
+        // Checked first, entries pushed before the walk ended are then
+        // picked up by this tick
+        let indexing = self.is_indexing();
+        let status = self.matcher.tick(TICK_TIMEOUT_MS);
+        status.running || indexing
+    }
+
+    /// Best matches first, at most `limit` of them
+    pub fn results(&mut self, limit: usize) -> Vec<FindMatch> {
+        let snapshot = self.matcher.snapshot();
+        let pattern = self.matcher.pattern.column_pattern(0);
+        let count = snapshot
+            .matched_item_count()
+            .min(u32::try_from(limit).unwrap_or(u32::MAX));
+        snapshot
+            .matched_items(0..count)
+            .map(|item| {
+                let mut indices = Vec::new();
+                pattern.indices(
+                    item.matcher_columns[0].slice(..),
+                    &mut self.indices_matcher,
+                    &mut indices,
+                );
+                indices.sort_unstable();
+                indices.dedup();
+                FindMatch {
+                    entry: item.data.clone(),
+                    indices,
+                }
+            })
+            .collect()
+    }
+
+    #[must_use]
+    pub fn matched_count(&self) -> usize {
+        self.matcher.snapshot().matched_item_count() as usize
+    }
+
+    #[must_use]
+    pub fn indexed_count(&self) -> usize {
+        self.indexed.load(Ordering::Relaxed)
+    }
+
+    #[must_use]
+    pub fn is_indexing(&self) -> bool {
+        self.walking.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for FileIndex {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Walk `root` and report every entry below it
+pub fn walk(
+    root: &Path,
+    show_hidden: bool,
+    cancelled: &AtomicBool,
+    mut on_entry: impl FnMut(FindEntry),
+) {
+    let walker = ignore::WalkBuilder::new(root)
+        .hidden(!show_hidden)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    for entry in walker {
+        if cancelled.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(entry) = entry else {
+            continue;
+        };
+        // The root itself
+        if entry.depth() == 0 {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        on_entry(FindEntry {
+            relative: relative.to_string_lossy().into_owned(),
+            is_dir: entry.file_type().is_some_and(|t| t.is_dir()),
+            path: entry.into_path(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{Duration, Instant};
+    use tempfile::tempdir;
+
+    fn wait_until_idle(index: &mut FileIndex) {
+        let start = Instant::now();
+        while index.tick() {
+            assert!(start.elapsed() < Duration::from_secs(10), "index timed out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn relative_results(index: &mut FileIndex) -> Vec<String> {
+        index
+            .results(usize::MAX)
+            .into_iter()
+            .map(|found| found.entry.relative)
+            .collect()
+    }
+
+    #[test]
+    fn test_walk_respects_ignore_files() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        fs::create_dir_all(root.join("build")).unwrap();
+        fs::write(root.join("build/out.o"), "").unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "").unwrap();
+        fs::write(root.join(".env"), "").unwrap();
+
+        let mut found = Vec::new();
+        walk(root, false, &AtomicBool::new(false), |entry| {
+            found.push((PathBuf::from(entry.relative), entry.is_dir));
+        });
+        assert_eq!(
+            found,
+            vec![
+                (PathBuf::from("src"), true),
+                (Path::new("src").join("main.rs"), false)
+            ]
+        );
+
+        let mut found = Vec::new();
+        walk(root, true, &AtomicBool::new(false), |entry| {
+            found.push(entry.relative);
+        });
+        assert!(found.contains(&".env".to_string()));
+        assert!(!found.iter().any(|name| name.starts_with("build")));
+    }
+
+    #[test]
+    fn test_index_ranks_matches() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("docs/config")).unwrap();
+        fs::write(root.join("docs/config/notes.md"), "").unwrap();
+        fs::write(root.join("config.toml"), "").unwrap();
+        fs::write(root.join("readme.md"), "").unwrap();
+
+        let mut index = FileIndex::start(root.to_path_buf(), false, Arc::new(|| {}));
+        wait_until_idle(&mut index);
+        assert_eq!(index.indexed_count(), 5);
+        assert_eq!(index.matched_count(), 5);
+
+        index.set_query("config.toml");
+        wait_until_idle(&mut index);
+        assert_eq!(relative_results(&mut index), vec!["config.toml"]);
+        assert_eq!(index.results(1)[0].indices, (0..11).collect::<Vec<u32>>());
+
+        // Characters may be spread over the whole relative path
+        index.set_query("cnfmd");
+        wait_until_idle(&mut index);
+        assert_eq!(
+            relative_results(&mut index),
+            vec![
+                Path::new("docs")
+                    .join("config")
+                    .join("notes.md")
+                    .to_string_lossy()
+            ]
+        );
+
+        index.set_query("md");
+        wait_until_idle(&mut index);
+        let results = relative_results(&mut index);
+        assert_eq!(results.len(), 2);
+        assert!(results.contains(&"readme.md".to_string()));
+    }
+}
@@ -0,0 +1,203 @@
+//! Hide entries that git ignores
+//!
+//! Inside a git repository an entry is ignored following the `.gitignore`
+//! and `.ignore` files from its directory up to the repository root, then
+//! `.git/info/exclude`, then the global excludes file, the first one with a
+//! matching pattern decides. Outside of repositories nothing is ignored.
+//!
+//! Compiled ignore files are cached per repository root and rebuilt when
+//! their modification time changes.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::models::dir_entry::DirEntry;
+
+/// Per directory ignore files, `.ignore` is added last so it takes
+/// precedence like in ripgrep
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+struct CachedMatcher {
+    stamps: Vec<Option<SystemTime>>,
+    matcher: Gitignore,
+}
+
+/// Compiled ignore files of a repository, keyed by the directory holding them
+type RepoCache = HashMap<PathBuf, CachedMatcher>;
+
+static CACHE: LazyLock<Mutex<HashMap<PathBuf, RepoCache>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The work tree root of the repository containing `dir`
+#[must_use]
+pub fn repo_root(dir: &Path) -> Option<&Path> {
+    dir.ancestors().find(|dir| dir.join(".git").exists())
+}
+
+/// Drop the entries of `dir` that git ignores
+pub fn retain_not_ignored(dir: &Path, entries: &mut Vec<DirEntry>) {
+    let Some(root) = repo_root(dir) else {
+        return;
+    };
+    let Ok(mut cache) = CACHE.lock() else {
+        return;
+    };
+    let repo = cache.entry(root.to_path_buf()).or_default();
+
+    // Most specific first: the listed directory up to the root
+    let mut sources: Vec<(PathBuf, PathBuf, Vec<PathBuf>)> = dir
+        .ancestors()
+        .take_while(|ancestor| ancestor.starts_with(root))
+        .map(|ancestor| {
+            let files = IGNORE_FILES
+                .iter()
+                .map(|name| ancestor.join(name))
+                .collect();
+            (ancestor.to_path_buf(), ancestor.to_path_buf(), files)
+        })
+        .collect();
+    let git_dir = root.join(".git");
+    if git_dir.is_dir() {
+        let info = git_dir.join("info");
+        let exclude = info.join("exclude");
+        sources.push((info, root.to_path_buf(), vec![exclude]));
+    }
+
+    for (key, base, files) in &sources {
+        refresh(repo, key, base, files);
+    }
+    let matchers: Vec<&Gitignore> = sources
+        .iter()
+        .filter_map(|(key, _, _)| repo.get(key))
+        .map(|cached| &cached.matcher)
+        .chain(std::iter::once(global()))
+        .collect();
+
+    entries.retain(|entry| {
+        let decision = matchers
+            .iter()
+            .map(|matcher| matcher.matched(&entry.meta.path, entry.is_dir))
+            .find(|m| !m.is_none());
+        !matches!(decision, Some(Match::Ignore(_)))
+    });
+}
+
+/// Rebuild the cached matcher for `files` if any of them changed
+fn refresh(repo: &mut RepoCache, key: &Path, base: &Path, files: &[PathBuf]) {
+    let stamps: Vec<Option<SystemTime>> = files
+        .iter()
+        .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+        .collect();
+    if repo.get(key).is_some_and(|cached| cached.stamps == stamps) {
+        return;
+    }
+
+    let mut builder = GitignoreBuilder::new(base);
+    for (file, stamp) in files.iter().zip(&stamps) {
+        if stamp.is_some()
+            && let Some(e) = builder.add(file)
+        {
+            tracing::debug!("Failed to read {}: {e}", file.display());
+        }
+    }
+    let matcher = builder.build().unwrap_or_else(|e| {
+        tracing::debug!("Invalid ignore patterns in {}: {e}", base.display());
+        Gitignore::empty()
+    });
+    repo.insert(key.to_path_buf(), CachedMatcher { stamps, matcher });
+}
+
+/// The excludes file from the git config, read once
+fn global() -> &'static Gitignore {
+    static GLOBAL: OnceLock<Gitignore> = OnceLock::new();
+    GLOBAL.get_or_init(|| {
+        let (matcher, error) = Gitignore::global();
+        if let Some(e) = error {
+            tracing::debug!("Failed to read global git excludes: {e}");
+        }
+        matcher
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::dir_entry::DirEntryMeta;
+    use tempfile::tempdir;
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut entries: Vec<DirEntry> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                DirEntry::new(
+                    entry.file_name().to_string_lossy().into_owned(),
+                    DirEntryMeta {
+                        path: entry.path(),
+                        modified: SystemTime::now(),
+                    },
+                    entry.file_type().unwrap().is_dir(),
+                    false,
+                    0,
+                )
+            })
+            .collect();
+        retain_not_ignored(dir, &mut entries);
+        let mut names: Vec<String> = entries.into_iter().map(|entry| entry.name).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_nested_ignore_files() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::write(root.join(".git/info/exclude"), "*.local\n").unwrap();
+        fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("build.log"), "").unwrap();
+        fs::write(root.join("notes.local"), "").unwrap();
+        // Re-included in the subdirectory
+        fs::write(root.join("src/.gitignore"), "!keep.log\n").unwrap();
+        fs::write(root.join("src/keep.log"), "").unwrap();
+        fs::write(root.join("src/drop.log"), "").unwrap();
+
+        assert_eq!(names(root), vec![".git", ".gitignore", "src"]);
+        assert_eq!(names(&root.join("src")), vec![".gitignore", "keep.log"]);
+        // Inside an ignored directory its own entries are listed
+        fs::write(root.join("target/out.o"), "").unwrap();
+        assert_eq!(names(&root.join("target")), vec!["out.o"]);
+    }
+
+    #[test]
+    fn test_changed_ignore_file_is_reloaded() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(root.join("a.txt"), "").unwrap();
+        fs::write(root.join("b.txt"), "").unwrap();
+        assert_eq!(names(root), vec![".git", "a.txt", "b.txt"]);
+
+        fs::write(root.join(".ignore"), "a.txt\n").unwrap();
+        assert_eq!(names(root), vec![".git", ".ignore", "b.txt"]);
+    }
+
+    #[test]
+    fn test_outside_repository() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(".gitignore"), "*.txt\n").unwrap();
+        fs::write(root.join("a.txt"), "").unwrap();
+        if repo_root(root).is_none() {
+            assert_eq!(names(root), vec![".gitignore", "a.txt"]);
+        }
+    }
+}
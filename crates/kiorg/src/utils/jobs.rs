@@ -0,0 +1,517 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::models::action_history::{
+    ActionType, CompressOperation, CopyOperation, CreateOperation, ExtractOperation, MoveOperation,
+    OverwriteBackup, OverwriteOperation, StashOperation, TrashOperation,
+};
+
+/// How long the UI thread waits for a freshly started job before falling back
+/// to the progress popup. Keeps small operations from flashing a dialog.
+pub const JOB_GRACE_PERIOD: Duration = Duration::from_millis(200);
+
+/// Minimum interval between byte level progress reports sent by a worker
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Operation completed by a job that should be recorded in the action history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobRecord {
+    Create(CreateOperation),
+    Copy(CopyOperation),
+    Move(MoveOperation),
+    Trash(TrashOperation),
+    Delete(StashOperation),
+    Overwrite(OverwriteOperation),
+    Extract(ExtractOperation),
+    Compress(CompressOperation),
+}
+
+/// Progress update message sent from the worker thread
+#[derive(Debug, Clone)]
+pub enum JobUpdate {
+    Progress {
+        current_file: usize,
+        total_files: usize,
+        bytes_done: u64,
+        total_bytes: u64,
+        current_path: String,
+    },
+    /// An operation finished and can be recorded for undo
+    Record(JobRecord),
+    /// A single entry failed, the job keeps going with the remaining entries
+    Warning(String),
+    Completed,
+    Cancelled,
+    Error(String),
+}
+
+/// Lifecycle of a background job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+/// Progress state for a background job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobProgressState {
+    pub title: String,
+    pub total_files: usize,
+    pub current_file: usize,
+    pub total_bytes: u64,
+    pub bytes_done: u64,
+    pub current_path: String,
+    pub status: JobStatus,
+    /// Operations completed so far, in the order they finished
+    pub records: Vec<JobRecord>,
+    /// Per entry failures and the fatal error (if any)
+    pub errors: Vec<String>,
+    pub started_at: Instant,
+}
+
+impl JobProgressState {
+    fn new(title: String) -> Self {
+        Self {
+            title,
+            total_files: 0,
+            current_file: 0,
+            total_bytes: 0,
+            bytes_done: 0,
+            current_path: String::new(),
+            status: JobStatus::Running,
+            records: Vec::new(),
+            errors: Vec::new(),
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Fraction of work done, based on bytes when available and file count otherwise
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes > 0 {
+            self.bytes_done as f32 / self.total_bytes as f32
+        } else if self.total_files > 0 {
+            self.current_file as f32 / self.total_files as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Average throughput in bytes per second since the job started
+    pub fn throughput(&self) -> f64 {
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.bytes_done as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// Estimated remaining time based on the average throughput
+    pub fn eta(&self) -> Option<Duration> {
+        let throughput = self.throughput();
+        if throughput <= 0.0 || self.total_bytes <= self.bytes_done {
+            return None;
+        }
+        let remaining = (self.total_bytes - self.bytes_done) as f64 / throughput;
+        Some(Duration::from_secs_f64(remaining))
+    }
+
+    /// Group the completed records into action history entries
+    pub fn to_actions(&self) -> Vec<ActionType> {
+        let mut create_operations = Vec::new();
+        let mut copy_operations = Vec::new();
+        let mut move_operations = Vec::new();
+        let mut trash_operations = Vec::new();
+        let mut delete_operations = Vec::new();
+        let mut overwrite_operations = Vec::new();
+        let mut extract_operations = Vec::new();
+        let mut compress_operations = Vec::new();
+        for record in &self.records {
+            match record {
+                JobRecord::Create(op) => create_operations.push(op.clone()),
+                JobRecord::Copy(op) => copy_operations.push(op.clone()),
+                JobRecord::Move(op) => move_operations.push(op.clone()),
+                JobRecord::Trash(op) => trash_operations.push(op.clone()),
+                JobRecord::Delete(op) => delete_operations.push(op.clone()),
+                JobRecord::Overwrite(op) => overwrite_operations.push(op.clone()),
+                JobRecord::Extract(op) => extract_operations.push(op.clone()),
+                JobRecord::Compress(op) => compress_operations.push(op.clone()),
+            }
+        }
+
+        let mut actions = Vec::new();
+        if !create_operations.is_empty() {
+            actions.push(ActionType::Create {
+                operations: create_operations,
+            });
+        }
+        if !copy_operations.is_empty() {
+            actions.push(ActionType::Copy {
+                operations: copy_operations,
+            });
+        }
+        if !move_operations.is_empty() {
+            actions.push(ActionType::Move {
+                operations: move_operations,
+            });
+        }
+        if !trash_operations.is_empty() {
+            actions.push(ActionType::Trash {
+                operations: trash_operations,
+            });
+        }
+        if !delete_operations.is_empty() {
+            actions.push(ActionType::Delete {
+                operations: delete_operations,
+            });
+        }
+        if !overwrite_operations.is_empty() {
+            actions.push(ActionType::Overwrite {
+                operations: overwrite_operations,
+            });
+        }
+        if !extract_operations.is_empty() {
+            actions.push(ActionType::Extract {
+                operations: extract_operations,
+            });
+        }
+        if !compress_operations.is_empty() {
+            actions.push(ActionType::Compress {
+                operations: compress_operations,
+            });
+        }
+        actions
+    }
+
+    /// Whether the job put entries into the undo stash
+    pub fn used_stash(&self) -> bool {
+        self.records.iter().any(|record| {
+            matches!(
+                record,
+                JobRecord::Delete(_)
+                    | JobRecord::Overwrite(OverwriteOperation {
+                        backup: OverwriteBackup::Stash(_),
+                        ..
+                    })
+            )
+        })
+    }
+
+    fn apply(&mut self, update: JobUpdate) {
+        match update {
+            JobUpdate::Progress {
+                current_file,
+                total_files,
+                bytes_done,
+                total_bytes,
+                current_path,
+            } => {
+                self.current_file = current_file;
+                self.total_files = total_files;
+                self.bytes_done = bytes_done;
+                self.total_bytes = total_bytes;
+                self.current_path = current_path;
+            }
+            JobUpdate::Record(record) => self.records.push(record),
+            JobUpdate::Warning(warning) => self.errors.push(warning),
+            JobUpdate::Completed => self.status = JobStatus::Completed,
+            JobUpdate::Cancelled => self.status = JobStatus::Cancelled,
+            JobUpdate::Error(error) => {
+                self.errors.push(error);
+                self.status = JobStatus::Failed;
+            }
+        }
+    }
+}
+
+/// Progress data containing state, receiver and the cancellation flag
+pub struct JobData {
+    pub state: JobProgressState,
+    pub receiver: mpsc::Receiver<JobUpdate>,
+    cancel: Arc<AtomicBool>,
+    /// Message shown when the job completes without errors
+    pub success_message: Option<String>,
+}
+
+impl std::fmt::Debug for JobData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobData")
+            .field("state", &self.state)
+            .field("receiver", &"<receiver>")
+            .finish()
+    }
+}
+
+impl PartialEq for JobData {
+    fn eq(&self, other: &Self) -> bool {
+        // Compare only the state, not the receiver
+        self.state == other.state
+    }
+}
+
+impl Eq for JobData {}
+
+impl JobData {
+    #[must_use]
+    pub fn with_success_message(mut self, message: impl Into<String>) -> Self {
+        self.success_message = Some(message.into());
+        self
+    }
+
+    /// Ask the worker to stop at the next checkpoint
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancel_requested(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.status != JobStatus::Running
+    }
+
+    /// Apply all pending updates without blocking
+    /// Returns true if any update was received
+    pub fn poll(&mut self) -> bool {
+        let mut updated = false;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(update) => {
+                    self.state.apply(update);
+                    updated = true;
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    if !self.is_finished() {
+                        // Worker went away without reporting, most likely a panic
+                        self.state.apply(JobUpdate::Error(
+                            "Background job stopped unexpectedly".to_string(),
+                        ));
+                        updated = true;
+                    }
+                    break;
+                }
+            }
+        }
+        updated
+    }
+
+    /// Block for at most `timeout` waiting for the job to finish
+    /// Returns true if the job finished in time
+    pub fn wait(&mut self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while !self.is_finished() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            match self.receiver.recv_timeout(deadline - now) {
+                Ok(update) => self.state.apply(update),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    self.poll();
+                    break;
+                }
+            }
+        }
+        self.is_finished()
+    }
+}
+
+/// Handle given to job workers for reporting progress and checking cancellation
+pub struct JobContext {
+    sender: mpsc::Sender<JobUpdate>,
+    cancel: Arc<AtomicBool>,
+    current_file: usize,
+    total_files: usize,
+    bytes_done: u64,
+    total_bytes: u64,
+    current_path: String,
+    last_report: Instant,
+}
+
+impl JobContext {
+    /// A context for work done right away outside of a job, e.g. by undo and redo
+    /// Nobody listens to its updates and it is never cancelled
+    pub fn detached() -> Self {
+        let (sender, _) = mpsc::channel();
+        Self {
+            sender,
+            cancel: Arc::new(AtomicBool::new(false)),
+            current_file: 0,
+            total_files: 0,
+            bytes_done: 0,
+            total_bytes: 0,
+            current_path: String::new(),
+            last_report: Instant::now(),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Return an `Interrupted` error if cancellation was requested
+    pub fn check_cancelled(&self) -> std::io::Result<()> {
+        if self.is_cancelled() {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "Operation cancelled",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn set_totals(&mut self, total_files: usize, total_bytes: u64) {
+        self.total_files = total_files;
+        self.total_bytes = total_bytes;
+        self.report();
+    }
+
+    /// Grow the totals for work discovered while the job is running
+    pub fn add_totals(&mut self, files: usize, bytes: u64) {
+        self.total_files += files;
+        self.total_bytes += bytes;
+        self.report();
+    }
+
+    /// Drop work that was skipped from the totals
+    pub fn skip(&mut self, files: usize, bytes: u64) {
+        self.total_files = self.total_files.saturating_sub(files);
+        self.total_bytes = self.total_bytes.saturating_sub(bytes);
+        self.report();
+    }
+
+    /// Mark the start of work on a new file
+    pub fn start_file(&mut self, path: &Path) {
+        self.current_file += 1;
+        self.current_path = path.display().to_string();
+        self.report();
+    }
+
+    /// Account for bytes processed for the current file
+    pub fn add_bytes(&mut self, bytes: u64) {
+        self.bytes_done += bytes;
+        if self.last_report.elapsed() >= PROGRESS_REPORT_INTERVAL {
+            self.report();
+        }
+    }
+
+    pub fn record(&self, record: JobRecord) {
+        let _ = self.sender.send(JobUpdate::Record(record));
+    }
+
+    pub fn warn(&self, warning: String) {
+        let _ = self.sender.send(JobUpdate::Warning(warning));
+    }
+
+    fn report(&mut self) {
+        self.last_report = Instant::now();
+        let _ = self.sender.send(JobUpdate::Progress {
+            current_file: self.current_file,
+            total_files: self.total_files,
+            bytes_done: self.bytes_done,
+            total_bytes: self.total_bytes,
+            current_path: self.current_path.clone(),
+        });
+    }
+}
+
+/// Run `job` on a worker thread and return the data needed to track it
+pub fn spawn_job<F>(title: impl Into<String>, job: F) -> JobData
+where
+    F: FnOnce(&mut JobContext) -> Result<(), String> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    let cancel = Arc::new(AtomicBool::new(false));
+
+    let mut ctx = JobContext {
+        sender: tx,
+        cancel: cancel.clone(),
+        current_file: 0,
+        total_files: 0,
+        bytes_done: 0,
+        total_bytes: 0,
+        current_path: String::new(),
+        last_report: Instant::now(),
+    };
+
+    thread::spawn(move || {
+        let result = job(&mut ctx);
+        // Flush the final byte count before reporting completion
+        ctx.report();
+        let update = match result {
+            _ if ctx.is_cancelled() => JobUpdate::Cancelled,
+            Ok(()) => JobUpdate::Completed,
+            Err(e) => JobUpdate::Error(e),
+        };
+        let _ = ctx.sender.send(update);
+    });
+
+    JobData {
+        state: JobProgressState::new(title.into()),
+        receiver: rx,
+        cancel,
+        success_message: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_job_reports_records_and_completes() {
+        let mut job = spawn_job("test", |ctx| {
+            ctx.set_totals(1, 10);
+            ctx.start_file(Path::new("a.txt"));
+            ctx.add_bytes(10);
+            ctx.record(JobRecord::Copy(CopyOperation {
+                source_path: PathBuf::from("a.txt"),
+                target_path: PathBuf::from("b.txt"),
+            }));
+            Ok(())
+        });
+
+        assert!(job.wait(Duration::from_secs(5)));
+        assert_eq!(job.state.status, JobStatus::Completed);
+        assert_eq!(job.state.bytes_done, 10);
+        assert_eq!(job.state.records.len(), 1);
+        assert_eq!(job.state.to_actions().len(), 1);
+    }
+
+    #[test]
+    fn test_job_cancellation() {
+        let (started_tx, started_rx) = mpsc::channel();
+        let mut job = spawn_job("test", move |ctx| {
+            let _ = started_tx.send(());
+            while !ctx.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            ctx.check_cancelled().map_err(|e| e.to_string())
+        });
+
+        started_rx.recv().unwrap();
+        job.cancel();
+        assert!(job.wait(Duration::from_secs(5)));
+        assert_eq!(job.state.status, JobStatus::Cancelled);
+        assert!(job.state.records.is_empty());
+    }
+
+    #[test]
+    fn test_job_error() {
+        let mut job = spawn_job("test", |_ctx| Err("boom".to_string()));
+        assert!(job.wait(Duration::from_secs(5)));
+        assert_eq!(job.state.status, JobStatus::Failed);
+        assert_eq!(job.state.errors, vec!["boom".to_string()]);
+    }
+}
@@ -1,5 +1,13 @@
+pub mod archive;
+pub mod bulk_rename;
+pub mod content_search;
 pub mod file_operations;
+pub mod find;
 pub mod format;
+pub mod gitignore;
 pub mod icon;
+pub mod jobs;
 pub mod preview_cache;
 pub mod rollback;
+pub mod stash;
+pub mod trash;
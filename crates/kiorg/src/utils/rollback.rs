@@ -1,6 +1,37 @@
-use crate::models::action_history::ActionType;
+use crate::models::action_history::{
+    ActionType, CompressOperation, ExtractOperation, OverwriteBackup, OverwriteOperation,
+    StashOperation, TrashOperation,
+};
+use crate::utils::jobs::JobContext;
+use crate::utils::{bulk_rename, stash, trash};
+use crate::vfs;
 use std::path::PathBuf;
 
+/// Put the entry replaced by an overwrite back in place
+///
+/// # Errors
+///
+/// Returns an error string if the original location is taken or the entry is
+/// gone from where it was put aside.
+pub fn restore_overwritten(backup: &OverwriteBackup) -> Result<(), String> {
+    match backup {
+        OverwriteBackup::Stash(op) => stash::restore(op),
+        OverwriteBackup::Trash(op) => trash::restore(op),
+    }
+}
+
+/// Put a restored overwritten entry aside again, into the slot it came from
+///
+/// # Errors
+///
+/// Returns an error string if the slot is taken or the move fails.
+pub fn put_aside_overwritten(backup: &OverwriteBackup) -> Result<(), String> {
+    match backup {
+        OverwriteBackup::Stash(op) => stash::restash(op),
+        OverwriteBackup::Trash(op) => trash::retrash(op),
+    }
+}
+
 /// Result of a rollback operation
 #[derive(Debug, Clone)]
 pub enum RollbackResult {
@@ -55,6 +86,14 @@ impl RollbackManager {
                     Ok(format!("Rolled back {} create operations", success_count))
                 }
             }
+            // Bulk renames may swap names, so they are reverted all at once
+            ActionType::Rename { operations } if operations.len() > 1 => {
+                bulk_rename::apply(&bulk_rename::reversed(operations))?;
+                Ok(format!(
+                    "Rolled back {} rename operations",
+                    operations.len()
+                ))
+            }
             ActionType::Rename { operations } => {
                 let mut success_count = 0;
                 let mut errors = Vec::new();
@@ -134,25 +173,152 @@ impl RollbackManager {
                     Ok(format!("Rolled back {} move operations", success_count))
                 }
             }
+            ActionType::Trash { operations } => {
+                let mut success_count = 0;
+                let mut errors = Vec::new();
+                let mut single_result = None;
+
+                for op in operations.iter().rev() {
+                    match Self::rollback_trash(op) {
+                        RollbackResult::Success(msg) => {
+                            success_count += 1;
+                            if success_count == 1 {
+                                single_result = Some(msg);
+                            }
+                        }
+                        RollbackResult::Error(e) => errors.push(e),
+                        RollbackResult::PartialSuccess { errors: errs, .. } => errors.extend(errs),
+                    }
+                }
+
+                if !errors.is_empty() {
+                    Err(errors.join("; "))
+                } else if success_count == 1 {
+                    Ok(single_result.unwrap())
+                } else {
+                    Ok(format!("Restored {} items from trash", success_count))
+                }
+            }
+            ActionType::Delete { operations } => {
+                let mut success_count = 0;
+                let mut errors = Vec::new();
+                let mut single_result = None;
+
+                for op in operations.iter().rev() {
+                    match Self::rollback_delete(op) {
+                        RollbackResult::Success(msg) => {
+                            success_count += 1;
+                            if success_count == 1 {
+                                single_result = Some(msg);
+                            }
+                        }
+                        RollbackResult::Error(e) => errors.push(e),
+                        RollbackResult::PartialSuccess { errors: errs, .. } => errors.extend(errs),
+                    }
+                }
+
+                if !errors.is_empty() {
+                    Err(errors.join("; "))
+                } else if success_count == 1 {
+                    Ok(single_result.unwrap())
+                } else {
+                    Ok(format!("Restored {} deleted items", success_count))
+                }
+            }
+            ActionType::Overwrite { operations } => {
+                let mut success_count = 0;
+                let mut errors = Vec::new();
+                let mut single_result = None;
+
+                for op in operations.iter().rev() {
+                    match Self::rollback_overwrite(op) {
+                        RollbackResult::Success(msg) => {
+                            success_count += 1;
+                            if success_count == 1 {
+                                single_result = Some(msg);
+                            }
+                        }
+                        RollbackResult::Error(e) => errors.push(e),
+                        RollbackResult::PartialSuccess { errors: errs, .. } => errors.extend(errs),
+                    }
+                }
+
+                if !errors.is_empty() {
+                    Err(errors.join("; "))
+                } else if success_count == 1 {
+                    Ok(single_result.unwrap())
+                } else {
+                    Ok(format!(
+                        "Rolled back {} overwrite operations",
+                        success_count
+                    ))
+                }
+            }
+            ActionType::Extract { operations } => {
+                let mut success_count = 0;
+                let mut errors = Vec::new();
+                let mut single_result = None;
+
+                for op in operations.iter().rev() {
+                    match Self::rollback_extract(op) {
+                        RollbackResult::Success(msg) => {
+                            success_count += 1;
+                            if success_count == 1 {
+                                single_result = Some(msg);
+                            }
+                        }
+                        RollbackResult::Error(e) => errors.push(e),
+                        RollbackResult::PartialSuccess { errors: errs, .. } => errors.extend(errs),
+                    }
+                }
+
+                if !errors.is_empty() {
+                    Err(errors.join("; "))
+                } else if success_count == 1 {
+                    Ok(single_result.unwrap())
+                } else {
+                    Ok(format!("Rolled back {} extractions", success_count))
+                }
+            }
+            ActionType::Compress { operations } => {
+                let mut success_count = 0;
+                let mut errors = Vec::new();
+                let mut single_result = None;
+
+                for op in operations.iter().rev() {
+                    match Self::rollback_compress(op) {
+                        RollbackResult::Success(msg) => {
+                            success_count += 1;
+                            if success_count == 1 {
+                                single_result = Some(msg);
+                            }
+                        }
+                        RollbackResult::Error(e) => errors.push(e),
+                        RollbackResult::PartialSuccess { errors: errs, .. } => errors.extend(errs),
+                    }
+                }
+
+                if !errors.is_empty() {
+                    Err(errors.join("; "))
+                } else if success_count == 1 {
+                    Ok(single_result.unwrap())
+                } else {
+                    Ok(format!("Rolled back {} archives", success_count))
+                }
+            }
         }
     }
 
     /// Rollback a create operation by deleting the created file/directory
     fn rollback_create(path: &PathBuf, is_directory: bool) -> RollbackResult {
-        if !path.exists() {
+        if !vfs::exists(path) {
             return RollbackResult::Error(format!(
                 "Cannot rollback create: {} no longer exists",
                 path.display()
             ));
         }
 
-        let result = if is_directory {
-            std::fs::remove_dir_all(path)
-        } else {
-            std::fs::remove_file(path)
-        };
-
-        match result {
+        match vfs::for_path(path).remove(path) {
             Ok(()) => {
                 let item_type = if is_directory { "directory" } else { "file" };
                 RollbackResult::Success(format!("Deleted {} '{}'", item_type, path.display()))
@@ -163,21 +329,21 @@ impl RollbackManager {
 
     /// Rollback a rename operation by renaming back to original name
     fn rollback_rename(original_path: &PathBuf, current_path: &PathBuf) -> RollbackResult {
-        if !current_path.exists() {
+        if !vfs::exists(current_path) {
             return RollbackResult::Error(format!(
                 "Cannot rollback rename: {} no longer exists",
                 current_path.display()
             ));
         }
 
-        if original_path.exists() {
+        if vfs::exists(original_path) {
             return RollbackResult::Error(format!(
                 "Cannot rollback rename: {} already exists",
                 original_path.display()
             ));
         }
 
-        match std::fs::rename(current_path, original_path) {
+        match vfs::for_path(current_path).rename(current_path, original_path) {
             Ok(()) => RollbackResult::Success(format!(
                 "Renamed '{}' back to '{}'",
                 current_path.display(),
@@ -198,15 +364,120 @@ impl RollbackManager {
     }
 
     /// Rollback a move operation by moving back to original location
+    /// Moves that crossed filesystems are undone with copy-then-delete as well
     fn rollback_move(original_path: &PathBuf, current_path: &PathBuf) -> RollbackResult {
-        Self::rollback_rename(original_path, current_path)
+        if !vfs::exists(current_path) {
+            return RollbackResult::Error(format!(
+                "Cannot rollback move: {} no longer exists",
+                current_path.display()
+            ));
+        }
+
+        if vfs::exists(original_path) {
+            return RollbackResult::Error(format!(
+                "Cannot rollback move: {} already exists",
+                original_path.display()
+            ));
+        }
+
+        // Merged directories are removed once emptied, recreate them on the way back
+        if let Some(parent) = original_path.parent() {
+            vfs::create_dir_all(parent).ok();
+        }
+
+        match vfs::move_entry(current_path, original_path, &mut JobContext::detached()) {
+            Ok(()) => RollbackResult::Success(format!(
+                "Moved '{}' back to '{}'",
+                current_path.display(),
+                original_path.display()
+            )),
+            Err(e) => RollbackResult::Error(format!(
+                "Failed to move {} back to {}: {}",
+                current_path.display(),
+                original_path.display(),
+                e
+            )),
+        }
+    }
+
+    /// Rollback an overwrite by undoing the paste and restoring the replaced entry
+    fn rollback_overwrite(op: &OverwriteOperation) -> RollbackResult {
+        let undo_paste = if op.is_move {
+            Self::rollback_move(&op.source_path, &op.target_path)
+        } else {
+            Self::rollback_copy(&op.target_path, op.target_path.is_dir())
+        };
+        if let RollbackResult::Error(e) = undo_paste {
+            return RollbackResult::Error(e);
+        }
+
+        match restore_overwritten(&op.backup) {
+            Ok(()) => RollbackResult::Success(format!(
+                "Restored overwritten '{}'",
+                op.target_path.display()
+            )),
+            Err(e) => RollbackResult::Error(format!(
+                "Cannot restore overwritten {}: {}",
+                op.target_path.display(),
+                e
+            )),
+        }
+    }
+
+    /// Rollback an extraction by putting the extracted entries into the undo
+    /// stash, files added to them since are kept along
+    fn rollback_extract(op: &ExtractOperation) -> RollbackResult {
+        match stash::restash(&op.backup) {
+            Ok(()) => RollbackResult::Success(format!(
+                "Moved extracted '{}' to the undo stash",
+                op.target_path.display()
+            )),
+            Err(e) => RollbackResult::Error(format!("Cannot rollback extraction: {}", e)),
+        }
+    }
+
+    /// Rollback a compression by putting the archive into the undo stash
+    fn rollback_compress(op: &CompressOperation) -> RollbackResult {
+        match stash::restash(&op.backup) {
+            Ok(()) => RollbackResult::Success(format!(
+                "Moved archive '{}' to the undo stash",
+                op.archive_path.display()
+            )),
+            Err(e) => RollbackResult::Error(format!("Cannot rollback compression: {}", e)),
+        }
+    }
+
+    /// Rollback a trash operation by restoring the item to its original location
+    fn rollback_trash(op: &TrashOperation) -> RollbackResult {
+        match trash::restore(op) {
+            Ok(()) => RollbackResult::Success(format!(
+                "Restored '{}' from trash",
+                op.original_path.display()
+            )),
+            Err(e) => RollbackResult::Error(format!("Cannot rollback trash: {}", e)),
+        }
+    }
+
+    /// Rollback a delete operation by bringing the entry back from the undo stash
+    fn rollback_delete(op: &StashOperation) -> RollbackResult {
+        match stash::restore(op) {
+            Ok(()) => RollbackResult::Success(format!(
+                "Restored deleted '{}'",
+                op.original_path.display()
+            )),
+            Err(e) => RollbackResult::Error(format!("Cannot rollback delete: {}", e)),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::action_history::{ActionType, CreateOperation, RenameOperation};
+    use crate::models::action_history::{
+        ActionType, CreateOperation, MoveOperation, RenameOperation,
+    };
+    use crate::vfs::memory::MemoryFs;
+    use std::sync::Arc;
     use tempfile::tempdir;
 
     #[test]
@@ -281,4 +552,111 @@ mod tests {
         assert!(old_file.exists(), "File should be renamed back");
         assert!(!new_file.exists(), "New file should not exist");
     }
+
+    #[test]
+    fn test_rollback_delete() {
+        let temp_dir = tempdir().unwrap();
+        let test_file = temp_dir.path().join("deleted.txt");
+        std::fs::write(&test_file, "test content").unwrap();
+
+        let op = stash::stash(&test_file).unwrap();
+        assert!(!test_file.exists());
+
+        let action = ActionType::Delete {
+            operations: vec![op.clone()],
+        };
+
+        let manager = RollbackManager::new();
+        let result = manager.rollback_action(&action);
+
+        assert!(result.is_ok(), "Rollback should succeed");
+        assert_eq!(std::fs::read_to_string(&test_file).unwrap(), "test content");
+        assert!(!op.stashed_path.exists(), "Entry should leave the stash");
+    }
+
+    #[test]
+    fn test_rollback_extract_keeps_later_files() {
+        let temp_dir = tempdir().unwrap();
+        let extracted = temp_dir.path().join("archive");
+        let backup = stash::reserve(&extracted).unwrap();
+        std::fs::create_dir(&extracted).unwrap();
+        std::fs::write(extracted.join("added_later.txt"), "mine").unwrap();
+
+        let action = ActionType::Extract {
+            operations: vec![ExtractOperation {
+                archive_path: temp_dir.path().join("archive.zip"),
+                target_path: extracted.clone(),
+                backup: backup.clone(),
+            }],
+        };
+
+        let manager = RollbackManager::new();
+        let result = manager.rollback_action(&action);
+
+        assert!(result.is_ok(), "Rollback should succeed");
+        assert!(!extracted.exists());
+        assert_eq!(
+            std::fs::read_to_string(backup.stashed_path.join("added_later.txt")).unwrap(),
+            "mine"
+        );
+        stash::restore(&backup).unwrap();
+        assert!(extracted.join("added_later.txt").exists());
+    }
+
+    #[test]
+    fn test_rollback_bulk_rename_swap() {
+        let temp_dir = tempdir().unwrap();
+        let a = temp_dir.path().join("a.txt");
+        let b = temp_dir.path().join("b.txt");
+        std::fs::write(&a, "a").unwrap();
+        std::fs::write(&b, "b").unwrap();
+
+        // Swap the two names in one action
+        let operations = vec![
+            RenameOperation {
+                old_path: a.clone(),
+                new_path: b.clone(),
+            },
+            RenameOperation {
+                old_path: b.clone(),
+                new_path: a.clone(),
+            },
+        ];
+        bulk_rename::apply(&operations).unwrap();
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "b");
+
+        let manager = RollbackManager::new();
+        let result = manager.rollback_action(&ActionType::Rename { operations });
+
+        assert!(result.is_ok(), "Rollback should succeed");
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "a");
+        assert_eq!(std::fs::read_to_string(&b).unwrap(), "b");
+    }
+
+    #[test]
+    fn test_rollback_move_out_of_other_filesystem() {
+        let temp_dir = tempdir().unwrap();
+        let fs = MemoryFs::new(temp_dir.path().join("memory"));
+        let original = fs.add_file("docs/notes.txt", "notes");
+        let root = fs.root().to_path_buf();
+        vfs::mount(root.clone(), Arc::new(fs.clone()));
+
+        // Moved from the in-memory filesystem to the local one
+        let local = temp_dir.path().join("notes.txt");
+        vfs::move_entry(&original, &local, &mut JobContext::detached()).unwrap();
+        assert!(fs.file_content(&original).is_none());
+
+        let manager = RollbackManager::new();
+        let result = manager.rollback_action(&ActionType::Move {
+            operations: vec![MoveOperation {
+                source_path: original.clone(),
+                target_path: local.clone(),
+            }],
+        });
+
+        assert!(result.is_ok(), "Rollback should succeed: {result:?}");
+        assert_eq!(fs.file_content(&original), Some(b"notes".to_vec()));
+        assert!(!local.exists());
+        vfs::unmount(&root);
+    }
 }
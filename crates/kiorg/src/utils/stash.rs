@@ -0,0 +1,367 @@
+//! Local undo stash for deleted and overwritten entries
+//!
+//! Instead of being removed right away, entries are moved to
+//! `<cache dir>/stash/<id>/<name>` so the action history can bring them back.
+//! The modification time of the `<id>` directory is the time the entry was
+//! stashed, [`enforce_retention`] uses it to purge the oldest entries once they
+//! exceed the configured age or total size.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::config::Config;
+use crate::models::action_history::StashOperation;
+use crate::utils::file_operations;
+use crate::utils::preview_cache;
+
+const STASH_DIR_NAME: &str = "stash";
+pub const DEFAULT_MAX_SIZE_MB: u64 = 1024;
+pub const DEFAULT_MAX_AGE_DAYS: u64 = 7;
+
+/// Limits after which stashed entries are purged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// Total size of the stash in bytes
+    pub max_size: u64,
+    pub max_age: Duration,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_SIZE_MB * 1024 * 1024,
+            max_age: Duration::from_secs(DEFAULT_MAX_AGE_DAYS * 24 * 60 * 60),
+        }
+    }
+}
+
+impl Retention {
+    /// Retention configured in the `[undo_stash]` section, defaults fill the gaps
+    pub fn from_config(config: &Config) -> Self {
+        let mut retention = Self::default();
+        if let Some(undo_stash) = &config.undo_stash {
+            if let Some(max_size_mb) = undo_stash.max_size_mb {
+                retention.max_size = max_size_mb.saturating_mul(1024 * 1024);
+            }
+            if let Some(max_age_days) = undo_stash.max_age_days {
+                retention.max_age = Duration::from_secs(max_age_days.saturating_mul(24 * 60 * 60));
+            }
+        }
+        retention
+    }
+}
+
+/// Location of the stash, created on first use
+pub fn stash_dir() -> Option<PathBuf> {
+    let dir = preview_cache::get_cache_dir()?.join(STASH_DIR_NAME);
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+/// Whether `path` can be stashed with a rename, i.e. without copying its content
+pub fn is_on_stash_device(path: &Path) -> bool {
+    let Some(dir) = stash_dir() else {
+        return false;
+    };
+    same_device(path, &dir)
+}
+
+#[cfg(unix)]
+fn same_device(path: &Path, dir: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::symlink_metadata(path), fs::metadata(dir)) {
+        (Ok(path_meta), Ok(dir_meta)) => path_meta.dev() == dir_meta.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_device(path: &Path, dir: &Path) -> bool {
+    // Volumes are identified by their prefix, e.g. the drive letter
+    match (std::path::absolute(path), std::path::absolute(dir)) {
+        (Ok(path), Ok(dir)) => path.components().next() == dir.components().next(),
+        _ => false,
+    }
+}
+
+/// Move `path` into a fresh slot of the stash
+///
+/// # Errors
+///
+/// Returns an error string if the stash is unavailable or the entry cannot be
+/// moved into it.
+pub fn stash(path: &Path) -> Result<StashOperation, String> {
+    let dir = stash_dir().ok_or("Cannot determine the undo stash directory")?;
+    stash_into(path, &dir)
+}
+
+/// Pick a fresh stash slot for `path` without moving anything, [`restash`]
+/// puts the entry there once it has to go
+///
+/// # Errors
+///
+/// Returns an error string if the stash is unavailable.
+pub fn reserve(path: &Path) -> Result<StashOperation, String> {
+    let dir = stash_dir().ok_or("Cannot determine the undo stash directory")?;
+    new_slot(path, &dir)
+}
+
+fn new_slot(path: &Path, dir: &Path) -> Result<StashOperation, String> {
+    let original_path = std::path::absolute(path)
+        .map_err(|e| format!("Failed to resolve {}: {e}", path.display()))?;
+    let name = original_path
+        .file_name()
+        .ok_or_else(|| format!("Cannot stash {}", original_path.display()))?;
+    let slot = dir.join(uuid::Uuid::new_v4().simple().to_string());
+    Ok(StashOperation {
+        stashed_path: slot.join(name),
+        original_path,
+    })
+}
+
+fn stash_into(path: &Path, dir: &Path) -> Result<StashOperation, String> {
+    let op = new_slot(path, dir)?;
+    let slot = slot_of(&op)?;
+    fs::create_dir(slot).map_err(|e| format!("Failed to create {}: {e}", slot.display()))?;
+
+    file_operations::move_path(&op.original_path, &op.stashed_path).map_err(|e| {
+        let _ = fs::remove_dir(slot);
+        format!(
+            "Failed to move {} to the undo stash: {e}",
+            op.original_path.display()
+        )
+    })?;
+    Ok(op)
+}
+
+/// Move a restored entry back to the exact stash slot it was taken from
+///
+/// # Errors
+///
+/// Returns an error string if the slot is taken or the move fails.
+pub fn restash(op: &StashOperation) -> Result<(), String> {
+    if op.stashed_path.symlink_metadata().is_ok() {
+        return Err(format!(
+            "Stash entry {} is already in use",
+            op.stashed_path.display()
+        ));
+    }
+    let slot = slot_of(op)?;
+    fs::create_dir_all(slot).map_err(|e| format!("Failed to create {}: {e}", slot.display()))?;
+
+    file_operations::move_path(&op.original_path, &op.stashed_path).map_err(|e| {
+        let _ = fs::remove_dir(slot);
+        format!(
+            "Failed to move {} to the undo stash: {e}",
+            op.original_path.display()
+        )
+    })
+}
+
+/// Move a stashed entry back to its original location and drop its slot
+///
+/// # Errors
+///
+/// Returns an error string if the original location is occupied, the entry was
+/// purged from the stash or the move fails.
+pub fn restore(op: &StashOperation) -> Result<(), String> {
+    if op.stashed_path.symlink_metadata().is_err() {
+        return Err(format!(
+            "{} is no longer in the undo stash",
+            op.original_path.display()
+        ));
+    }
+    if op.original_path.symlink_metadata().is_ok() {
+        return Err(format!("{} already exists", op.original_path.display()));
+    }
+
+    if let Some(parent) = op.original_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    file_operations::move_path(&op.stashed_path, &op.original_path).map_err(|e| {
+        format!(
+            "Failed to restore {} from the undo stash: {e}",
+            op.original_path.display()
+        )
+    })?;
+
+    // The slot only held this entry
+    let _ = fs::remove_dir(slot_of(op)?);
+    Ok(())
+}
+
+/// Permanently delete a stashed entry together with its slot
+///
+/// # Errors
+///
+/// Returns an error string if the entry cannot be removed.
+pub fn purge(op: &StashOperation) -> Result<(), String> {
+    remove_slot(slot_of(op)?)
+}
+
+fn slot_of(op: &StashOperation) -> Result<&Path, String> {
+    op.stashed_path
+        .parent()
+        .ok_or_else(|| format!("Invalid stash path {}", op.stashed_path.display()))
+}
+
+fn remove_slot(slot: &Path) -> Result<(), String> {
+    match fs::remove_dir_all(slot) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to delete {}: {e}", slot.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Purge stashed entries older than the retention age, then the oldest ones
+/// until the stash fits the size limit. The newest entry is only subject to the
+/// age limit, a single entry larger than the limit is not purged right away.
+/// Returns the number of purged entries.
+pub fn enforce_retention(retention: Retention) -> usize {
+    stash_dir().map_or(0, |dir| enforce_retention_in(&dir, retention))
+}
+
+fn enforce_retention_in(dir: &Path, retention: Retention) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+
+    let now = SystemTime::now();
+    let mut slots: Vec<(PathBuf, SystemTime)> = entries
+        .flatten()
+        .map(|entry| {
+            let stashed_at = entry
+                .metadata()
+                .and_then(|meta| meta.modified())
+                .unwrap_or(now);
+            (entry.path(), stashed_at)
+        })
+        .collect();
+    // Newest first
+    slots.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let mut purged = 0;
+    let mut total_size = 0;
+    for (index, (slot, stashed_at)) in slots.iter().enumerate() {
+        let age = now.duration_since(*stashed_at).unwrap_or_default();
+        let (_, size) = file_operations::count_entries(std::slice::from_ref(slot));
+        total_size += size;
+
+        let expired = age >= retention.max_age;
+        let over_size = index > 0 && total_size > retention.max_size;
+        if (expired || over_size) && remove_slot(slot).is_ok() {
+            purged += 1;
+        }
+    }
+    purged
+}
+
+/// Run [`enforce_retention`] without blocking the caller
+pub fn enforce_retention_in_background(retention: Retention) {
+    std::thread::spawn(move || {
+        let purged = enforce_retention(retention);
+        if purged > 0 {
+            tracing::debug!("Purged {purged} entries from the undo stash");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_stash_restore_roundtrip() {
+        let temp_dir = tempdir().unwrap();
+        let stash = tempdir().unwrap();
+        let file = temp_dir.path().join("note.txt");
+        fs::write(&file, "content").unwrap();
+
+        let op = stash_into(&file, stash.path()).unwrap();
+        assert!(!file.exists());
+        assert_eq!(fs::read_to_string(&op.stashed_path).unwrap(), "content");
+
+        restore(&op).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "content");
+        assert!(!op.stashed_path.parent().unwrap().exists());
+
+        // Redo puts the entry back into the same slot
+        restash(&op).unwrap();
+        assert!(!file.exists());
+        assert!(op.stashed_path.exists());
+
+        purge(&op).unwrap();
+        assert!(!op.stashed_path.parent().unwrap().exists());
+        assert!(restore(&op).is_err());
+    }
+
+    #[test]
+    fn test_restore_refuses_occupied_target() {
+        let temp_dir = tempdir().unwrap();
+        let stash = tempdir().unwrap();
+        let dir = temp_dir.path().join("dir");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("child.txt"), "child").unwrap();
+
+        let op = stash_into(&dir, stash.path()).unwrap();
+        fs::write(&dir, "replacement").unwrap();
+        assert!(restore(&op).is_err());
+        assert!(op.stashed_path.join("child.txt").exists());
+        purge(&op).unwrap();
+    }
+
+    #[test]
+    fn test_enforce_retention_keeps_newest_entry() {
+        let temp_dir = tempdir().unwrap();
+        let stash = tempdir().unwrap();
+        let old = temp_dir.path().join("old.bin");
+        let new = temp_dir.path().join("new.bin");
+        fs::write(&old, vec![0u8; 64]).unwrap();
+        fs::write(&new, vec![0u8; 64]).unwrap();
+
+        let old_op = stash_into(&old, stash.path()).unwrap();
+        let old_slot = old_op.stashed_path.parent().unwrap();
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        file_operations::open_dir(old_slot)
+            .unwrap()
+            .set_modified(an_hour_ago)
+            .unwrap();
+        let new_op = stash_into(&new, stash.path()).unwrap();
+
+        // Both entries fit, nothing is purged
+        let roomy = Retention {
+            max_size: u64::MAX,
+            max_age: Duration::from_secs(24 * 3600),
+        };
+        enforce_retention_in(stash.path(), roomy);
+        assert!(old_op.stashed_path.exists());
+        assert!(new_op.stashed_path.exists());
+
+        // Over the size limit the oldest entry goes first, the newest one stays
+        // even though it exceeds the limit on its own
+        enforce_retention_in(
+            stash.path(),
+            Retention {
+                max_size: 1,
+                ..roomy
+            },
+        );
+        assert!(!old_op.stashed_path.exists());
+        assert!(new_op.stashed_path.exists());
+
+        // Expired entries are purged regardless of size
+        enforce_retention_in(
+            stash.path(),
+            Retention {
+                max_age: Duration::ZERO,
+                ..roomy
+            },
+        );
+        assert!(!new_op.stashed_path.exists());
+    }
+}
@@ -0,0 +1,660 @@
+//! Move to trash support following the freedesktop.org Trash specification
+//! <https://specifications.freedesktop.org/trash-spec/latest/>
+//!
+//! Items living on the same filesystem as the home trash (`$XDG_DATA_HOME/Trash`)
+//! are trashed there, items on other mounts go to `$topdir/.Trash/$uid` when the
+//! administrator provided a shared trash, and `$topdir/.Trash-$uid` otherwise.
+
+use chrono::{Local, NaiveDateTime};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::models::action_history::TrashOperation;
+use crate::utils::file_operations;
+
+const TRASH_INFO_HEADER: &str = "[Trash Info]";
+const TRASH_INFO_EXTENSION: &str = "trashinfo";
+const DELETION_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// An item found in one of the trash directories
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashItem {
+    pub entry: TrashOperation,
+    pub deletion_date: Option<NaiveDateTime>,
+    /// Size in bytes, directories are measured recursively
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+/// Whether moving to trash is available on this platform
+pub const fn is_supported() -> bool {
+    cfg!(all(unix, not(target_os = "macos")))
+}
+
+/// Location of the home trash, `$XDG_DATA_HOME/Trash`
+#[cfg(not(any(test, feature = "testing")))]
+pub fn home_trash_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("Trash"))
+}
+
+/// Tests get a home trash inside their temporary cache directory, so nothing
+/// lands in the real trash
+#[cfg(any(test, feature = "testing"))]
+pub fn home_trash_dir() -> Option<PathBuf> {
+    crate::utils::preview_cache::get_cache_dir().map(|dir| dir.join("Trash"))
+}
+
+/// Move `path` to the trash directory matching its filesystem
+///
+/// # Errors
+///
+/// Returns an error string if no usable trash directory exists for the path or
+/// the item cannot be moved into it.
+pub fn move_to_trash(path: &Path) -> Result<TrashOperation, String> {
+    let home_trash = home_trash_dir().ok_or("Cannot determine the home trash directory")?;
+    let path = std::path::absolute(path)
+        .map_err(|e| format!("Failed to resolve {}: {e}", path.display()))?;
+    let trash_dir = trash_dir_for(&path, &home_trash)
+        .map_err(|e| format!("No usable trash for {}: {e}", path.display()))?;
+    trash_into(&path, &trash_dir)
+}
+
+/// Move a restored item back to the exact trash location it was taken from
+///
+/// # Errors
+///
+/// Returns an error string if the trash slot is taken or the move fails.
+pub fn retrash(op: &TrashOperation) -> Result<(), String> {
+    if op.trashed_path.symlink_metadata().is_ok() || op.info_path.exists() {
+        return Err(format!(
+            "Trash entry {} is already in use",
+            op.trashed_path.display()
+        ));
+    }
+    let trash_dir = trash_dir_of(&op.info_path)
+        .ok_or_else(|| format!("Invalid trash info path {}", op.info_path.display()))?;
+    write_trash_info(&op.info_path, &op.original_path, trash_dir)
+        .map_err(|e| format!("Failed to write {}: {e}", op.info_path.display()))?;
+
+    fs::rename(&op.original_path, &op.trashed_path).map_err(|e| {
+        let _ = fs::remove_file(&op.info_path);
+        format!(
+            "Failed to move {} to trash: {e}",
+            op.original_path.display()
+        )
+    })
+}
+
+/// Restore a trashed item to its original location and drop its trash info
+///
+/// # Errors
+///
+/// Returns an error string if the original location is occupied, the item is
+/// gone from the trash or the move fails.
+pub fn restore(op: &TrashOperation) -> Result<(), String> {
+    restore_to(op, &op.original_path)
+}
+
+/// Restore a trashed item to `target` and drop its trash info
+///
+/// # Errors
+///
+/// Returns an error string if `target` is occupied, the item is gone from the
+/// trash or the move fails.
+pub fn restore_to(op: &TrashOperation, target: &Path) -> Result<(), String> {
+    if op.trashed_path.symlink_metadata().is_err() {
+        return Err(format!(
+            "{} is no longer in the trash",
+            op.original_path.display()
+        ));
+    }
+    if target.symlink_metadata().is_ok() {
+        return Err(format!("{} already exists", target.display()));
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    file_operations::move_path(&op.trashed_path, target)
+        .map_err(|e| format!("Failed to restore {} from trash: {e}", target.display()))?;
+
+    match fs::remove_file(&op.info_path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!(
+            "Restored {} but failed to remove {}: {e}",
+            target.display(),
+            op.info_path.display()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Permanently delete a trashed item together with its trash info
+///
+/// # Errors
+///
+/// Returns an error string if the item cannot be removed.
+pub fn purge(op: &TrashOperation) -> Result<(), String> {
+    let result = match op.trashed_path.symlink_metadata() {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(&op.trashed_path),
+        Ok(_) => fs::remove_file(&op.trashed_path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    };
+    result.map_err(|e| format!("Failed to delete {}: {e}", op.trashed_path.display()))?;
+
+    match fs::remove_file(&op.info_path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to delete {}: {e}", op.info_path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// List the items of every trash directory of the current user, newest first
+pub fn list_all_items() -> Vec<TrashItem> {
+    let mut items: Vec<TrashItem> = trash_dirs()
+        .iter()
+        .flat_map(|dir| list_items(dir))
+        .collect();
+    items.sort_by(|a, b| b.deletion_date.cmp(&a.deletion_date));
+    items
+}
+
+/// List the items of a single trash directory
+///
+/// Info files without a matching item, and items without info, are skipped.
+pub fn list_items(trash_dir: &Path) -> Vec<TrashItem> {
+    let Ok(entries) = fs::read_dir(trash_dir.join("info")) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let info_path = entry.path();
+            if info_path.extension()? != TRASH_INFO_EXTENSION {
+                return None;
+            }
+            let trashed_path = trash_dir.join("files").join(info_path.file_stem()?);
+            let meta = trashed_path.symlink_metadata().ok()?;
+            let contents = fs::read_to_string(&info_path).ok()?;
+            let (recorded_path, deletion_date) = parse_trash_info(&contents)?;
+
+            let original_path = match top_dir_of(trash_dir) {
+                Some(top) if recorded_path.is_relative() => top.join(recorded_path),
+                _ => recorded_path,
+            };
+            let size = if meta.is_dir() {
+                file_operations::count_entries(std::slice::from_ref(&trashed_path)).1
+            } else {
+                meta.len()
+            };
+
+            Some(TrashItem {
+                entry: TrashOperation {
+                    original_path,
+                    trashed_path,
+                    info_path,
+                },
+                deletion_date,
+                size,
+                is_dir: meta.is_dir(),
+            })
+        })
+        .collect()
+}
+
+/// Trash directories that may hold items of the current user
+pub fn trash_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = home_trash_dir().into_iter().collect();
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    if let Ok(uid) = current_uid() {
+        for top_dir in mount_points() {
+            for dir in [
+                top_dir.join(".Trash").join(uid.to_string()),
+                top_dir.join(format!(".Trash-{uid}")),
+            ] {
+                if dir.join("info").is_dir() && !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+    }
+
+    dirs
+}
+
+/// Parse the `Path` and `DeletionDate` keys of a `.trashinfo` file
+fn parse_trash_info(contents: &str) -> Option<(PathBuf, Option<NaiveDateTime>)> {
+    let mut lines = contents.lines().map(str::trim);
+    lines.find(|line| *line == TRASH_INFO_HEADER)?;
+
+    let mut path = None;
+    let mut deletion_date = None;
+    for line in lines {
+        if line.starts_with('[') {
+            // Start of another group
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Path=") {
+            path = Some(decode_path(value));
+        } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+            deletion_date = NaiveDateTime::parse_from_str(value, DELETION_DATE_FORMAT).ok();
+        }
+    }
+
+    path.map(|path| (path, deletion_date))
+}
+
+/// Move `path` into `trash_dir`, picking a name that is free in both `files` and `info`
+fn trash_into(path: &Path, trash_dir: &Path) -> Result<TrashOperation, String> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Cannot move {} to trash", path.display()))?;
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+
+    let name = name.to_string_lossy();
+    let (stem, extension) = match name.rfind('.') {
+        Some(pos) if pos > 0 => name.split_at(pos),
+        _ => (name.as_ref(), ""),
+    };
+
+    for counter in 1.. {
+        let candidate = if counter == 1 {
+            name.to_string()
+        } else {
+            format!("{stem}.{counter}{extension}")
+        };
+        let info_path = info_dir.join(format!("{candidate}.{TRASH_INFO_EXTENSION}"));
+        let trashed_path = files_dir.join(&candidate);
+
+        // The info file is created exclusively first to claim the name, as
+        // required by the spec for concurrent trash implementations
+        match write_trash_info(&info_path, path, trash_dir) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to write {}: {e}", info_path.display())),
+        }
+        if trashed_path.symlink_metadata().is_ok() {
+            // Orphaned file without info, leave it alone
+            let _ = fs::remove_file(&info_path);
+            continue;
+        }
+
+        return match fs::rename(path, &trashed_path) {
+            Ok(()) => Ok(TrashOperation {
+                original_path: path.to_path_buf(),
+                trashed_path,
+                info_path,
+            }),
+            Err(e) => {
+                let _ = fs::remove_file(&info_path);
+                Err(format!("Failed to move {} to trash: {e}", path.display()))
+            }
+        };
+    }
+    unreachable!()
+}
+
+/// Create the `.trashinfo` file, failing if it already exists
+fn write_trash_info(
+    info_path: &Path,
+    original_path: &Path,
+    trash_dir: &Path,
+) -> std::io::Result<()> {
+    // Top directory trashes store paths relative to the mount point
+    let recorded_path = top_dir_of(trash_dir)
+        .and_then(|top| original_path.strip_prefix(top).ok())
+        .unwrap_or(original_path);
+    let contents = format!(
+        "{TRASH_INFO_HEADER}\nPath={}\nDeletionDate={}\n",
+        encode_path(recorded_path),
+        Local::now().format(DELETION_DATE_FORMAT)
+    );
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(info_path)?;
+    file.write_all(contents.as_bytes())
+}
+
+/// The trash directory containing an `info/<name>.trashinfo` file
+fn trash_dir_of(info_path: &Path) -> Option<&Path> {
+    info_path.parent()?.parent()
+}
+
+/// Mount point owning a `$topdir/.Trash-$uid` or `$topdir/.Trash/$uid` directory
+fn top_dir_of(trash_dir: &Path) -> Option<&Path> {
+    let name = trash_dir.file_name()?.to_string_lossy();
+    if name.starts_with(".Trash-") {
+        return trash_dir.parent();
+    }
+    let parent = trash_dir.parent()?;
+    if parent.file_name()? == ".Trash" {
+        return parent.parent();
+    }
+    None
+}
+
+/// Percent encode a path for the `Path=` key, keeping `/` and unreserved characters
+pub fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path_bytes(path).iter() {
+        if byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Decode a percent encoded `Path=` value
+pub fn decode_path(value: &str) -> PathBuf {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    path_from_bytes(decoded)
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().as_bytes().to_vec()
+}
+
+/// Pick the trash directory for `path`, creating it when needed
+#[cfg(all(unix, not(target_os = "macos")))]
+fn trash_dir_for(path: &Path, home_trash: &Path) -> std::io::Result<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let device = path.symlink_metadata()?.dev();
+    let home_device = home_trash
+        .ancestors()
+        .find_map(|dir| fs::metadata(dir).ok())
+        .map(|meta| meta.dev());
+    if home_device == Some(device) {
+        create_trash_dir(home_trash)?;
+        return Ok(home_trash.to_path_buf());
+    }
+
+    let top_dir = mount_top_dir(path, device);
+    let uid = current_uid()?;
+
+    // An administrator created shared trash must be a real sticky directory
+    let shared = top_dir.join(".Trash");
+    if let Ok(meta) = shared.symlink_metadata()
+        && meta.is_dir()
+        && meta.mode() & 0o1000 != 0
+    {
+        let dir = shared.join(uid.to_string());
+        if create_trash_dir(&dir).is_ok() {
+            return Ok(dir);
+        }
+    }
+
+    let dir = top_dir.join(format!(".Trash-{uid}"));
+    create_trash_dir(&dir)?;
+    Ok(dir)
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn trash_dir_for(_path: &Path, _home_trash: &Path) -> std::io::Result<PathBuf> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "trash is not supported on this platform",
+    ))
+}
+
+/// Highest ancestor of `path` that still lives on `device`
+#[cfg(all(unix, not(target_os = "macos")))]
+fn mount_top_dir(path: &Path, device: u64) -> PathBuf {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut top = path.parent().unwrap_or(path);
+    for dir in path.ancestors().skip(1) {
+        match fs::metadata(dir) {
+            Ok(meta) if meta.dev() == device => top = dir,
+            _ => break,
+        }
+    }
+    top.to_path_buf()
+}
+
+/// Mount points listed in `/proc/self/mounts`
+#[cfg(all(unix, not(target_os = "macos")))]
+fn mount_points() -> Vec<PathBuf> {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(unescape_mount_path)
+        .collect()
+}
+
+/// Undo the octal escaping (`\040` for space) used in the mounts table
+#[cfg(all(unix, not(target_os = "macos")))]
+fn unescape_mount_path(field: &str) -> PathBuf {
+    let bytes = field.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(byte) = field
+                .get(i + 1..i + 4)
+                .and_then(|octal| u8::from_str_radix(octal, 8).ok())
+        {
+            unescaped.push(byte);
+            i += 4;
+        } else {
+            unescaped.push(bytes[i]);
+            i += 1;
+        }
+    }
+    path_from_bytes(unescaped)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn current_uid() -> std::io::Result<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    // /proc/self is owned by the effective uid of the running process
+    fs::metadata("/proc/self").map(|meta| meta.uid())
+}
+
+/// Create the trash directory with its `files` and `info` subdirectories
+#[cfg(all(unix, not(target_os = "macos")))]
+fn create_trash_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true).mode(0o700);
+    builder.create(dir.join("files"))?;
+    builder.create(dir.join("info"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(
+            encode_path(Path::new("/home/user/my file%.txt")),
+            "/home/user/my%20file%25.txt"
+        );
+        assert_eq!(encode_path(Path::new("dir/ü")), "dir/%C3%BC");
+    }
+
+    #[test]
+    fn test_decode_path() {
+        assert_eq!(
+            decode_path("/home/user/my%20file%25.txt"),
+            PathBuf::from("/home/user/my file%.txt")
+        );
+        assert_eq!(decode_path("dir/%C3%BC"), PathBuf::from("dir/ü"));
+        // Malformed escapes are kept verbatim
+        assert_eq!(decode_path("100%"), PathBuf::from("100%"));
+    }
+
+    #[test]
+    fn test_parse_trash_info() {
+        let (path, date) = parse_trash_info(
+            "[Trash Info]\nPath=foo/bar%20baz\nDeletionDate=2004-08-31T22:32:08\n",
+        )
+        .unwrap();
+        assert_eq!(path, PathBuf::from("foo/bar baz"));
+        assert_eq!(date.unwrap().to_string(), "2004-08-31 22:32:08".to_string());
+        assert!(parse_trash_info("Path=/no/header").is_none());
+    }
+
+    #[test]
+    fn test_list_restore_and_purge() {
+        let temp_dir = tempdir().unwrap();
+        let trash_dir = temp_dir.path().join(".Trash-1000");
+        fs::create_dir_all(trash_dir.join("files")).unwrap();
+        fs::create_dir_all(trash_dir.join("info")).unwrap();
+
+        let file = temp_dir.path().join("a.txt");
+        fs::write(&file, "12345").unwrap();
+        trash_into(&file, &trash_dir).unwrap();
+        let dir = temp_dir.path().join("dir");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("b.txt"), "123").unwrap();
+        trash_into(&dir, &trash_dir).unwrap();
+        // Info file whose item is gone is ignored
+        fs::write(
+            trash_dir.join("info/missing.trashinfo"),
+            "[Trash Info]\nPath=missing\n",
+        )
+        .unwrap();
+
+        let mut items = list_items(&trash_dir);
+        items.sort_by(|a, b| a.entry.original_path.cmp(&b.entry.original_path));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].entry.original_path, file);
+        assert_eq!(items[0].size, 5);
+        assert!(items[0].deletion_date.is_some());
+        assert_eq!(items[1].entry.original_path, dir);
+        assert_eq!(items[1].size, 3);
+        assert!(items[1].is_dir);
+
+        // Restore to another location when the original is occupied
+        fs::write(&file, "new").unwrap();
+        let renamed = temp_dir.path().join("a_1.txt");
+        restore_to(&items[0].entry, &renamed).unwrap();
+        assert_eq!(fs::read_to_string(&renamed).unwrap(), "12345");
+
+        purge(&items[1].entry).unwrap();
+        assert!(!items[1].entry.trashed_path.exists());
+        assert!(!items[1].entry.info_path.exists());
+        assert_eq!(list_items(&trash_dir).len(), 0);
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_tests_use_their_own_home_trash() {
+        let home_trash = home_trash_dir().unwrap();
+        assert!(home_trash.starts_with(crate::utils::preview_cache::get_cache_dir().unwrap()));
+
+        let temp_dir = tempdir().unwrap();
+        let file = temp_dir.path().join("a.txt");
+        fs::write(&file, "content").unwrap();
+        let op = move_to_trash(&file).unwrap();
+        assert!(op.trashed_path.starts_with(&home_trash));
+        restore(&op).unwrap();
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let temp_dir = tempdir().unwrap();
+        let trash_dir = temp_dir.path().join("Trash");
+        fs::create_dir_all(trash_dir.join("files")).unwrap();
+        fs::create_dir_all(trash_dir.join("info")).unwrap();
+
+        let first = temp_dir.path().join("notes.txt");
+        fs::write(&first, "first").unwrap();
+        let op = trash_into(&first, &trash_dir).unwrap();
+        assert!(!first.exists());
+        assert_eq!(op.trashed_path, trash_dir.join("files/notes.txt"));
+        let info = fs::read_to_string(&op.info_path).unwrap();
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains(&format!("Path={}\n", encode_path(&first))));
+
+        // A second item with the same name gets a distinct slot
+        fs::write(&first, "second").unwrap();
+        let second = trash_into(&first, &trash_dir).unwrap();
+        assert_eq!(second.trashed_path, trash_dir.join("files/notes.2.txt"));
+        assert!(trash_dir.join("info/notes.2.txt.trashinfo").exists());
+
+        restore(&second).unwrap();
+        assert_eq!(fs::read_to_string(&first).unwrap(), "second");
+        assert!(!second.info_path.exists());
+
+        // Restoring onto an existing file is refused
+        assert!(restore(&op).is_err());
+        assert!(op.trashed_path.exists());
+    }
+
+    #[test]
+    fn test_retrash_after_restore() {
+        let temp_dir = tempdir().unwrap();
+        let trash_dir = temp_dir.path().join(".Trash-1000");
+        fs::create_dir_all(trash_dir.join("files")).unwrap();
+        fs::create_dir_all(trash_dir.join("info")).unwrap();
+
+        let dir = temp_dir.path().join("project");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("file.txt"), "content").unwrap();
+
+        let op = trash_into(&dir, &trash_dir).unwrap();
+        // Top directory trashes record paths relative to the mount point
+        let info = fs::read_to_string(&op.info_path).unwrap();
+        assert!(info.contains("Path=project\n"));
+
+        restore(&op).unwrap();
+        assert!(dir.join("file.txt").exists());
+
+        retrash(&op).unwrap();
+        assert!(!dir.exists());
+        assert!(op.trashed_path.join("file.txt").exists());
+        assert!(op.info_path.exists());
+    }
+}
@@ -0,0 +1,294 @@
+//! Zip and tar archives browsed as read-only directories
+
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::{Vfs, VfsEntry, VfsMetadata, read_only_error};
+use crate::utils::archive::{self, ArchiveFormat, ExtractRequest, IndexEntry};
+use crate::utils::jobs::JobContext;
+
+/// The entries of one archive file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveFs {
+    archive: PathBuf,
+    format: ArchiveFormat,
+}
+
+impl ArchiveFs {
+    /// The archive a path points into, or the archive at the path itself
+    ///
+    /// Paths on the local filesystem, including plain files, give `None`.
+    #[must_use]
+    pub fn containing(path: &Path) -> Option<Self> {
+        for ancestor in path.ancestors() {
+            // Components inside an archive do not exist on disk
+            let Ok(metadata) = std::fs::metadata(ancestor) else {
+                continue;
+            };
+            if !metadata.is_file() {
+                return None;
+            }
+            return Some(Self {
+                archive: ancestor.to_path_buf(),
+                format: ArchiveFormat::detect(ancestor)?,
+            });
+        }
+        None
+    }
+
+    /// The archive file on disk
+    #[must_use]
+    pub fn archive(&self) -> &Path {
+        &self.archive
+    }
+
+    /// Path relative to the archive root, empty for the root itself
+    fn inner<'a>(&self, path: &'a Path) -> io::Result<&'a Path> {
+        path.strip_prefix(&self.archive).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not in {}", path.display(), self.archive.display()),
+            )
+        })
+    }
+
+    fn modified(&self) -> io::Result<SystemTime> {
+        std::fs::metadata(&self.archive)?.modified()
+    }
+
+    fn index(&self) -> io::Result<Arc<Vec<IndexEntry>>> {
+        let modified = self.modified()?;
+        let mut cache = INDEX_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = cache.as_ref()
+            && cached.archive == self.archive
+            && cached.modified == modified
+        {
+            return Ok(cached.entries.clone());
+        }
+
+        let entries =
+            Arc::new(archive::read_index(&self.archive, self.format).map_err(io::Error::other)?);
+        *cache = Some(CachedIndex {
+            archive: self.archive.clone(),
+            modified,
+            entries: entries.clone(),
+        });
+        Ok(entries)
+    }
+}
+
+struct CachedIndex {
+    archive: PathBuf,
+    modified: SystemTime,
+    entries: Arc<Vec<IndexEntry>>,
+}
+
+/// Index of the archive being browsed, kept until the archive changes or
+/// another one is opened
+static INDEX_CACHE: Mutex<Option<CachedIndex>> = Mutex::new(None);
+
+fn metadata_of(entry: &IndexEntry) -> VfsMetadata {
+    VfsMetadata {
+        is_dir: entry.is_dir,
+        is_symlink: entry.is_symlink,
+        size: if entry.is_dir { 0 } else { entry.size },
+        modified: entry.modified,
+    }
+}
+
+impl Vfs for ArchiveFs {
+    fn list(&self, path: &Path) -> io::Result<Vec<VfsEntry>> {
+        let inner = self.inner(path)?;
+        Ok(self
+            .index()?
+            .iter()
+            .filter(|entry| entry.path.parent() == Some(inner))
+            .filter_map(|entry| {
+                let name = entry.path.file_name()?.to_string_lossy().into_owned();
+                Some(VfsEntry {
+                    is_hidden: name.starts_with('.'),
+                    name,
+                    path: self.archive.join(&entry.path),
+                    metadata: metadata_of(entry),
+                })
+            })
+            .collect())
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<VfsMetadata> {
+        let inner = self.inner(path)?;
+        if inner.as_os_str().is_empty() {
+            return Ok(VfsMetadata {
+                is_dir: true,
+                is_symlink: false,
+                size: 0,
+                modified: self.modified()?,
+            });
+        }
+        self.index()?
+            .iter()
+            .find(|entry| entry.path == inner)
+            .map(metadata_of)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} is not in the archive", inner.display()),
+                )
+            })
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        // Entries are decompressed into an anonymous temporary file, so large
+        // files are not held in memory
+        let inner = self.inner(path)?;
+        let mut file = tempfile::tempfile()?;
+        archive::read_entry(&self.archive, self.format, inner, &mut file)
+            .map_err(io::Error::other)?;
+        file.rewind()?;
+        Ok(Box::new(file))
+    }
+
+    fn write(&self, _path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        Err(read_only_error())
+    }
+
+    fn create_dir(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only_error())
+    }
+
+    fn rename(&self, _from: &Path, _to: &Path) -> io::Result<()> {
+        Err(read_only_error())
+    }
+
+    fn remove(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only_error())
+    }
+
+    fn copy(&self, _src: &Path, _dst: &Path, _ctx: &mut JobContext) -> io::Result<()> {
+        Err(read_only_error())
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn watch_path(&self, _path: &Path) -> Option<PathBuf> {
+        Some(self.archive.clone())
+    }
+
+    /// Extracts the entry into a scratch directory next to the target and
+    /// moves it into place, a single pass over the archive
+    fn copy_to_local(
+        &self,
+        src: &Path,
+        dst: &Path,
+        ctx: &mut JobContext,
+    ) -> Option<io::Result<()>> {
+        Some(self.extract_to(src, dst, ctx))
+    }
+}
+
+impl ArchiveFs {
+    fn extract_to(&self, src: &Path, dst: &Path, ctx: &mut JobContext) -> io::Result<()> {
+        let inner = self.inner(src)?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "Invalid target path");
+        let parent = dst.parent().ok_or_else(invalid)?;
+        let name = dst.file_name().ok_or_else(invalid)?;
+
+        let scratch = parent.join(format!(".{}.kiorg-extract", name.to_string_lossy()));
+        std::fs::create_dir(&scratch)?;
+        ctx.start_file(src);
+        let result = archive::extract(
+            ctx,
+            &ExtractRequest {
+                archive: self.archive.clone(),
+                format: self.format,
+                destination: scratch.clone(),
+                selection: vec![inner.to_string_lossy().into_owned()],
+                password: None,
+            },
+        )
+        .map_err(io::Error::other)
+        .and_then(|()| std::fs::rename(scratch.join(inner), dst));
+        let _ = std::fs::remove_dir_all(&scratch);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs;
+    use tempfile::tempdir;
+
+    fn write_zip(path: &Path) {
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.start_file("docs/a.txt", options).unwrap();
+        writer.write_all(b"a").unwrap();
+        writer.start_file("b.txt", options).unwrap();
+        writer.write_all(b"b").unwrap();
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_archive_paths() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("a.zip");
+        write_zip(&archive);
+        std::fs::write(temp_dir.path().join("plain.txt"), "").unwrap();
+
+        assert_eq!(ArchiveFs::containing(temp_dir.path()), None);
+        assert_eq!(
+            ArchiveFs::containing(&temp_dir.path().join("plain.txt")),
+            None
+        );
+        assert_eq!(
+            ArchiveFs::containing(&temp_dir.path().join("missing/x")),
+            None
+        );
+        let fs = ArchiveFs::containing(&archive.join("docs/a.txt")).unwrap();
+        assert_eq!(fs.archive(), archive);
+
+        assert!(vfs::is_dir(&archive));
+        assert!(vfs::is_dir(&archive.join("docs")));
+        assert!(!vfs::is_dir(&archive.join("b.txt")));
+        assert!(vfs::is_local(&archive));
+        assert!(!vfs::is_local(&archive.join("b.txt")));
+        assert!(vfs::is_read_only_dir(&archive));
+        assert!(!vfs::is_read_only_dir(temp_dir.path()));
+        assert_eq!(vfs::watch_path(&archive.join("docs")), Some(archive));
+    }
+
+    #[test]
+    fn test_list_and_read() {
+        let temp_dir = tempdir().unwrap();
+        let archive = temp_dir.path().join("a.zip");
+        write_zip(&archive);
+
+        let mut names: Vec<(String, bool)> = vfs::read_dir_entries(&archive, false, false)
+            .into_iter()
+            .map(|entry| (entry.name, entry.is_dir))
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![("b.txt".to_string(), false), ("docs".to_string(), true)]
+        );
+
+        let entries = vfs::read_dir_entries(&archive.join("docs"), false, false);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].meta.path, archive.join("docs/a.txt"));
+        assert_eq!(entries[0].size, 1);
+
+        let copy = vfs::local_copy(&entries[0].meta.path).unwrap();
+        assert_eq!(std::fs::read(copy).unwrap(), b"a");
+        assert!(
+            vfs::for_path(&archive.join("b.txt"))
+                .remove(&archive.join("b.txt"))
+                .is_err()
+        );
+    }
+}
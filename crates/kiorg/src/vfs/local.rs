@@ -0,0 +1,107 @@
+//! The local filesystem
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::{Vfs, VfsEntry, VfsMetadata};
+use crate::utils::file_operations;
+use crate::utils::jobs::JobContext;
+
+/// Filesystem of the machine kiorg runs on, backed by `std::fs`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalFs;
+
+fn metadata_of(path: &Path, metadata: &fs::Metadata) -> VfsMetadata {
+    let is_symlink = metadata.file_type().is_symlink();
+    // For symlinks, we need to follow the link to determine if target is a directory
+    // This is the only case where we need the additional syscall
+    let is_dir = if is_symlink {
+        path.is_dir()
+    } else {
+        metadata.is_dir()
+    };
+    VfsMetadata {
+        is_dir,
+        is_symlink,
+        size: if is_dir { 0 } else { metadata.len() },
+        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+    }
+}
+
+fn is_hidden(name: &str, metadata: &fs::Metadata) -> bool {
+    // For Windows, check the "hidden" file attribute.
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const HIDDEN_ATTRIBUTE: u32 = 0x2;
+        let _ = name;
+        (metadata.file_attributes() & HIDDEN_ATTRIBUTE) != 0
+    }
+
+    // For Unix-like systems, check for a leading dot.
+    #[cfg(not(windows))]
+    {
+        let _ = metadata;
+        name.starts_with('.')
+    }
+}
+
+impl Vfs for LocalFs {
+    fn list(&self, path: &Path) -> io::Result<Vec<VfsEntry>> {
+        Ok(fs::read_dir(path)?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().into_owned();
+                // Metadata of the entry itself, links are not followed
+                let metadata = entry.metadata().ok()?;
+                Some(VfsEntry {
+                    is_hidden: is_hidden(&name, &metadata),
+                    metadata: metadata_of(&path, &metadata),
+                    name,
+                    path,
+                })
+            })
+            .collect())
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<VfsMetadata> {
+        let metadata = fs::symlink_metadata(path)?;
+        Ok(metadata_of(path, &metadata))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(File::open(path)?))
+    }
+
+    fn write(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        let file = File::options().write(true).create_new(true).open(path)?;
+        Ok(Box::new(file))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        if fs::symlink_metadata(path)?.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+    }
+
+    fn copy(&self, src: &Path, dst: &Path, ctx: &mut JobContext) -> io::Result<()> {
+        file_operations::copy_with_progress(src, dst, ctx)
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        Some(path.to_path_buf())
+    }
+}
@@ -0,0 +1,321 @@
+//! In-memory filesystem for test fixtures
+//!
+//! Mount it with [`super::mount`] to browse and paste into it like any other
+//! directory without touching the disk.
+
+use std::collections::BTreeMap;
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::{Vfs, VfsEntry, VfsMetadata};
+use crate::utils::jobs::JobContext;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Dir { modified: SystemTime },
+    File { data: Vec<u8>, modified: SystemTime },
+}
+
+impl Node {
+    fn metadata(&self) -> VfsMetadata {
+        match self {
+            Self::Dir { modified } => VfsMetadata {
+                is_dir: true,
+                is_symlink: false,
+                size: 0,
+                modified: *modified,
+            },
+            Self::File { data, modified } => VfsMetadata {
+                is_dir: false,
+                is_symlink: false,
+                size: data.len() as u64,
+                modified: *modified,
+            },
+        }
+    }
+}
+
+type Nodes = Arc<Mutex<BTreeMap<PathBuf, Node>>>;
+
+/// Filesystem keeping every entry in memory, rooted at the path it is mounted at
+#[derive(Debug, Clone)]
+pub struct MemoryFs {
+    root: PathBuf,
+    nodes: Nodes,
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} does not exist", path.display()),
+    )
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} already exists", path.display()),
+    )
+}
+
+impl MemoryFs {
+    /// An empty filesystem with only its root directory
+    #[must_use]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        let mut nodes = BTreeMap::new();
+        nodes.insert(
+            root.clone(),
+            Node::Dir {
+                modified: SystemTime::now(),
+            },
+        );
+        Self {
+            root,
+            nodes: Arc::new(Mutex::new(nodes)),
+        }
+    }
+
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Add a file with its parent directories, relative to the root
+    pub fn add_file(&self, relative: impl AsRef<Path>, data: impl Into<Vec<u8>>) -> PathBuf {
+        let path = self.root.join(relative);
+        let mut nodes = self.lock();
+        for parent in path.ancestors().skip(1) {
+            if !parent.starts_with(&self.root) {
+                break;
+            }
+            nodes.entry(parent.to_path_buf()).or_insert(Node::Dir {
+                modified: SystemTime::now(),
+            });
+        }
+        nodes.insert(
+            path.clone(),
+            Node::File {
+                data: data.into(),
+                modified: SystemTime::now(),
+            },
+        );
+        path
+    }
+
+    /// Content of a file, `None` for directories and missing entries
+    #[must_use]
+    pub fn file_content(&self, path: &Path) -> Option<Vec<u8>> {
+        match self.lock().get(path) {
+            Some(Node::File { data, .. }) => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<PathBuf, Node>> {
+        self.nodes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn ensure_parent(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> io::Result<()> {
+        let parent = path.parent().ok_or_else(|| not_found(path))?;
+        match nodes.get(parent) {
+            Some(Node::Dir { .. }) => Ok(()),
+            _ => Err(not_found(parent)),
+        }
+    }
+
+    /// Paths of the entry and everything below it
+    fn subtree(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> Vec<PathBuf> {
+        nodes
+            .keys()
+            .filter(|key| key.starts_with(path))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Writer that appends to a file node as data comes in
+struct MemoryWriter {
+    path: PathBuf,
+    nodes: Nodes,
+}
+
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut nodes = self.nodes.lock().unwrap_or_else(|e| e.into_inner());
+        match nodes.get_mut(&self.path) {
+            Some(Node::File { data, modified }) => {
+                data.extend_from_slice(buf);
+                *modified = SystemTime::now();
+                Ok(buf.len())
+            }
+            _ => Err(not_found(&self.path)),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Vfs for MemoryFs {
+    fn list(&self, path: &Path) -> io::Result<Vec<VfsEntry>> {
+        let nodes = self.lock();
+        match nodes.get(path) {
+            Some(Node::Dir { .. }) => {}
+            _ => return Err(not_found(path)),
+        }
+        Ok(nodes
+            .iter()
+            .filter(|(key, _)| key.parent() == Some(path))
+            .filter_map(|(key, node)| {
+                let name = key.file_name()?.to_string_lossy().into_owned();
+                Some(VfsEntry {
+                    is_hidden: name.starts_with('.'),
+                    name,
+                    path: key.clone(),
+                    metadata: node.metadata(),
+                })
+            })
+            .collect())
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<VfsMetadata> {
+        self.lock()
+            .get(path)
+            .map(Node::metadata)
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        let data = self.file_content(path).ok_or_else(|| not_found(path))?;
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    fn write(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        let mut nodes = self.lock();
+        Self::ensure_parent(&nodes, path)?;
+        if nodes.contains_key(path) {
+            return Err(already_exists(path));
+        }
+        nodes.insert(
+            path.to_path_buf(),
+            Node::File {
+                data: Vec::new(),
+                modified: SystemTime::now(),
+            },
+        );
+        Ok(Box::new(MemoryWriter {
+            path: path.to_path_buf(),
+            nodes: self.nodes.clone(),
+        }))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.lock();
+        Self::ensure_parent(&nodes, path)?;
+        if nodes.contains_key(path) {
+            return Err(already_exists(path));
+        }
+        nodes.insert(
+            path.to_path_buf(),
+            Node::Dir {
+                modified: SystemTime::now(),
+            },
+        );
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut nodes = self.lock();
+        if !nodes.contains_key(from) {
+            return Err(not_found(from));
+        }
+        Self::ensure_parent(&nodes, to)?;
+        if nodes.contains_key(to) {
+            return Err(already_exists(to));
+        }
+        for key in Self::subtree(&nodes, from) {
+            if let Some(node) = nodes.remove(&key) {
+                let relative = key.strip_prefix(from).unwrap_or(Path::new(""));
+                nodes.insert(to.join(relative), node);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.lock();
+        if !nodes.contains_key(path) {
+            return Err(not_found(path));
+        }
+        for key in Self::subtree(&nodes, path) {
+            nodes.remove(&key);
+        }
+        Ok(())
+    }
+
+    fn copy(&self, src: &Path, dst: &Path, ctx: &mut JobContext) -> io::Result<()> {
+        let mut nodes = self.lock();
+        Self::ensure_parent(&nodes, dst)?;
+        if nodes.contains_key(dst) {
+            return Err(already_exists(dst));
+        }
+        for key in Self::subtree(&nodes, src) {
+            ctx.check_cancelled()?;
+            let Some(node) = nodes.get(&key).cloned() else {
+                continue;
+            };
+            ctx.start_file(&key);
+            if let Node::File { data, .. } = &node {
+                ctx.add_bytes(data.len() as u64);
+            }
+            let relative = key.strip_prefix(src).unwrap_or(Path::new(""));
+            nodes.insert(dst.join(relative), node);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::jobs::{JobStatus, spawn_job};
+    use crate::vfs;
+    use std::time::Duration;
+
+    #[test]
+    fn test_mounted_memory_fs() {
+        let fs = MemoryFs::new("/kiorg-memory-test");
+        fs.add_file("docs/a.txt", "a");
+        vfs::mount(fs.root().to_path_buf(), Arc::new(fs.clone()));
+
+        let root = fs.root().to_path_buf();
+        assert!(vfs::is_dir(&root.join("docs")));
+        let entries = vfs::read_dir_entries(&root.join("docs"), false, false);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].size, 1);
+
+        // Copy out to the local filesystem and back in under another name
+        let temp_dir = tempfile::tempdir().unwrap();
+        let local = temp_dir.path().join("docs");
+        let copied_back = root.join("docs-copy");
+        let (from, to) = (root.join("docs"), local.clone());
+        let (back_from, back_to) = (local.clone(), copied_back.clone());
+        let mut job = spawn_job("Copying", move |ctx| {
+            vfs::copy(&from, &to, ctx).map_err(|e| e.to_string())?;
+            vfs::move_entry(&back_from, &back_to, ctx).map_err(|e| e.to_string())
+        });
+        assert!(job.wait(Duration::from_secs(10)));
+        assert_eq!(job.state.status, JobStatus::Completed);
+
+        assert!(!local.exists());
+        assert_eq!(
+            fs.file_content(&copied_back.join("a.txt")),
+            Some(b"a".to_vec())
+        );
+        vfs::unmount(&root);
+    }
+}
@@ -0,0 +1,582 @@
+//! Virtual filesystems entries are listed from and file operations go through
+//!
+//! Paths stay plain `PathBuf`s everywhere, [`for_path`] picks the filesystem
+//! that owns them:
+//! - filesystems mounted at a root path with [`mount`], e.g. in-memory fixtures
+//! - zip and tar archives, `/tmp/photos.zip/2024/a.jpg` stands for
+//!   `2024/a.jpg` inside `/tmp/photos.zip`
+//! - remote hosts over SFTP, `sftp://user@host/srv/builds` is mounted the
+//!   first time it is used
+//! - URI schemes served by plugins, e.g. `s3://bucket/photos`, mounted at
+//!   `s3://` when the plugin is loaded
+//! - the local filesystem for everything else
+
+pub mod archive;
+pub mod local;
+#[cfg(any(test, feature = "testing"))]
+pub mod memory;
+pub mod sftp;
+pub mod ssh_config;
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::SystemTime;
+
+use crate::models::dir_entry::{DirEntry, DirEntryMeta};
+use crate::utils::archive::ArchiveFormat;
+use crate::utils::gitignore;
+use crate::utils::jobs::JobContext;
+use crate::utils::preview_cache;
+
+pub use archive::ArchiveFs;
+pub use local::LocalFs;
+pub use sftp::SftpFs;
+
+/// Files larger than this are not copied to the local filesystem just to preview them
+pub const MAX_PREVIEW_COPY_SIZE: u64 = 32 * 1024 * 1024;
+
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Kind, size and modification time of an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VfsMetadata {
+    /// For links, whether the link points to a directory
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// An entry listed by [`Vfs::list`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VfsEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub metadata: VfsMetadata,
+    /// Hidden unless the user asks for hidden files
+    pub is_hidden: bool,
+}
+
+impl From<VfsEntry> for DirEntry {
+    fn from(entry: VfsEntry) -> Self {
+        let metadata = entry.metadata;
+        Self::new(
+            entry.name,
+            DirEntryMeta {
+                path: entry.path,
+                modified: metadata.modified,
+            },
+            metadata.is_dir,
+            metadata.is_symlink,
+            if metadata.is_dir { 0 } else { metadata.size },
+        )
+    }
+}
+
+/// Operations every filesystem provides
+///
+/// Paths are full paths including the root the filesystem is found at.
+pub trait Vfs: Send + Sync {
+    /// Entries of the directory at `path`
+    fn list(&self, path: &Path) -> io::Result<Vec<VfsEntry>>;
+
+    /// Metadata of a single entry
+    fn stat(&self, path: &Path) -> io::Result<VfsMetadata>;
+
+    /// Read the content of a file
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read + Send>>;
+
+    /// Create a new file, failing if the path is taken
+    fn write(&self, path: &Path) -> io::Result<Box<dyn Write + Send>>;
+
+    /// Create a single directory, its parent has to exist
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Remove a file, or a directory with everything in it
+    fn remove(&self, path: &Path) -> io::Result<()>;
+
+    /// Copy an entry to another path of the same filesystem
+    fn copy(&self, src: &Path, dst: &Path, ctx: &mut JobContext) -> io::Result<()>;
+
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// The path on the local filesystem, for entries that are accessible directly
+    fn local_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    /// The directory one level up, `None` at the root of the filesystem
+    fn parent(&self, path: &Path) -> Option<PathBuf> {
+        path.parent().map(Path::to_path_buf)
+    }
+
+    /// The local path to watch for changes of the directory
+    fn watch_path(&self, path: &Path) -> Option<PathBuf> {
+        self.local_path(path)
+    }
+
+    /// Copy an entry to the local filesystem in one go
+    ///
+    /// Returns `None` for filesystems without a faster way than reading the
+    /// entries one by one.
+    fn copy_to_local(
+        &self,
+        _src: &Path,
+        _dst: &Path,
+        _ctx: &mut JobContext,
+    ) -> Option<io::Result<()>> {
+        None
+    }
+}
+
+pub(crate) fn read_only_error() -> io::Error {
+    io::Error::new(io::ErrorKind::ReadOnlyFilesystem, "Archives are read-only")
+}
+
+static LOCAL: LazyLock<Arc<dyn Vfs>> = LazyLock::new(|| Arc::new(LocalFs));
+
+/// Filesystems mounted at a root path
+static MOUNTS: RwLock<Vec<(PathBuf, Arc<dyn Vfs>)>> = RwLock::new(Vec::new());
+/// Number of mounts at plain local paths, see [`is_plainly_local`]
+static LOCAL_MOUNTS: AtomicUsize = AtomicUsize::new(0);
+
+fn count_local_mounts(mounts: &[(PathBuf, Arc<dyn Vfs>)]) {
+    let count = mounts
+        .iter()
+        .filter(|(root, _)| url_root(root).is_none())
+        .count();
+    LOCAL_MOUNTS.store(count, Ordering::Relaxed);
+}
+
+/// Whether the path is on the local filesystem, told without touching the
+/// disk: it has no scheme, nothing is mounted at a local path and none of
+/// its ancestors is named like an archive
+fn is_plainly_local(path: &Path) -> bool {
+    url_root(path).is_none()
+        && LOCAL_MOUNTS.load(Ordering::Relaxed) == 0
+        && !path
+            .ancestors()
+            .any(|ancestor| ArchiveFormat::detect(ancestor).is_some())
+}
+
+/// The local filesystem
+#[must_use]
+pub fn local() -> Arc<dyn Vfs> {
+    LOCAL.clone()
+}
+
+/// Serve every path under `root` from `fs`, replacing what was mounted there before
+pub fn mount(root: PathBuf, fs: Arc<dyn Vfs>) {
+    let mut mounts = MOUNTS.write().unwrap_or_else(|e| e.into_inner());
+    mounts.retain(|(mounted, _)| mounted != &root);
+    mounts.push((root, fs));
+    count_local_mounts(&mounts);
+}
+
+pub fn unmount(root: &Path) {
+    let mut mounts = MOUNTS.write().unwrap_or_else(|e| e.into_inner());
+    mounts.retain(|(mounted, _)| mounted != root);
+    count_local_mounts(&mounts);
+}
+
+fn mounted(path: &Path) -> Option<Arc<dyn Vfs>> {
+    {
+        let mounts = MOUNTS.read().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, fs)) = mounts
+            .iter()
+            .filter(|(root, _)| path.starts_with(root))
+            .max_by_key(|(root, _)| root.components().count())
+        {
+            return Some(fs.clone());
+        }
+    }
+
+    // Remote hosts are mounted on first use, the connection is made lazily
+    let fs: Arc<dyn Vfs> = Arc::new(SftpFs::for_url(path)?);
+    mount(sftp::root_of(path)?, fs.clone());
+    Some(fs)
+}
+
+/// The root of a `scheme://authority/...` path, e.g. `s3://bucket` for
+/// `s3://bucket/photos`
+#[must_use]
+pub fn url_root(path: &Path) -> Option<PathBuf> {
+    let (scheme, rest) = path.to_str()?.split_once("://")?;
+    if scheme.is_empty()
+        || !scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    {
+        return None;
+    }
+    let authority = rest.split('/').next().unwrap_or_default();
+    if authority.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(format!("{scheme}://{authority}")))
+}
+
+/// The filesystem owning the entry at `path`
+///
+/// An archive file itself belongs to the filesystem it is stored on, only the
+/// entries inside it belong to the archive.
+#[must_use]
+pub fn for_path(path: &Path) -> Arc<dyn Vfs> {
+    if is_plainly_local(path) {
+        return local();
+    }
+    if let Some(fs) = mounted(path) {
+        return fs;
+    }
+    match ArchiveFs::containing(path) {
+        Some(fs) if fs.archive() != path => Arc::new(fs),
+        _ => local(),
+    }
+}
+
+/// The filesystem serving the entries of the directory at `path`, which is
+/// the archive itself when `path` is an archive file
+#[must_use]
+pub fn for_dir(path: &Path) -> Arc<dyn Vfs> {
+    if is_plainly_local(path) {
+        return local();
+    }
+    if let Some(fs) = mounted(path) {
+        return fs;
+    }
+    match ArchiveFs::containing(path) {
+        Some(fs) => Arc::new(fs),
+        None => local(),
+    }
+}
+
+/// Read the entries of the directory at `path`
+/// Unreadable directories are listed as empty, `hide_ignored` leaves out
+/// what git ignores in local repositories
+#[must_use]
+pub fn read_dir_entries(path: &Path, show_hidden: bool, hide_ignored: bool) -> Vec<DirEntry> {
+    try_read_dir_entries(path, show_hidden, hide_ignored).unwrap_or_else(|e| {
+        tracing::debug!("Failed to list {}: {e}", path.display());
+        Vec::new()
+    })
+}
+
+/// Read the entries of the directory at `path`, failing when it cannot be listed
+///
+/// # Errors
+///
+/// Returns the error of listing the directory.
+pub fn try_read_dir_entries(
+    path: &Path,
+    show_hidden: bool,
+    hide_ignored: bool,
+) -> io::Result<Vec<DirEntry>> {
+    let fs = for_dir(path);
+    let mut entries: Vec<DirEntry> = fs
+        .list(path)?
+        .into_iter()
+        .filter(|entry| show_hidden || !entry.is_hidden)
+        .map(DirEntry::from)
+        .collect();
+    if hide_ignored && let Some(local) = fs.local_path(path) {
+        gitignore::retain_not_ignored(&local, &mut entries);
+    }
+    Ok(entries)
+}
+
+/// Whether listing the directory can take long enough to block the UI, which
+/// is the case for directories on remote hosts
+#[must_use]
+pub fn is_slow_to_list(path: &Path) -> bool {
+    url_root(path).is_some()
+}
+
+/// Whether the path can be navigated into: a directory, an archive or a
+/// directory in another filesystem
+#[must_use]
+pub fn is_dir(path: &Path) -> bool {
+    let fs = for_dir(path);
+    match fs.local_path(path) {
+        Some(local) => local.is_dir(),
+        None => fs.stat(path).is_ok_and(|metadata| metadata.is_dir),
+    }
+}
+
+/// The directory one level up, `None` at the root of a remote host
+#[must_use]
+pub fn parent(path: &Path) -> Option<PathBuf> {
+    for_dir(path).parent(path)
+}
+
+/// Whether the entry is stored on the local filesystem
+#[must_use]
+pub fn is_local(path: &Path) -> bool {
+    for_path(path).local_path(path).is_some()
+}
+
+/// Whether the entries of the directory cannot be changed, which is the case
+/// for archives and every directory inside them
+#[must_use]
+pub fn is_read_only_dir(dir: &Path) -> bool {
+    for_dir(dir).is_read_only()
+}
+
+/// The path to watch for changes of the directory, the archive file when
+/// browsing an archive
+#[must_use]
+pub fn watch_path(path: &Path) -> Option<PathBuf> {
+    for_dir(path).watch_path(path)
+}
+
+/// Whether an entry exists at `path`, links count even if they are broken
+#[must_use]
+pub fn exists(path: &Path) -> bool {
+    for_path(path).stat(path).is_ok()
+}
+
+/// Create a directory along with any missing parents
+///
+/// # Errors
+///
+/// Returns the error of the first directory that could not be created.
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+    if exists(path) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    for_path(path).create_dir(path)
+}
+
+/// Copy `src` to the free path `dst`, the two may be on different filesystems
+///
+/// # Errors
+///
+/// Returns the first error that stops the copy. On cancellation an
+/// `Interrupted` error is returned and the partial copy is left for the caller.
+pub fn copy(src: &Path, dst: &Path, ctx: &mut JobContext) -> io::Result<()> {
+    let (from, to) = (for_path(src), for_path(dst));
+    if Arc::ptr_eq(&from, &to) {
+        return from.copy(src, dst, ctx);
+    }
+    if to.local_path(dst).is_some()
+        && let Some(result) = from.copy_to_local(src, dst, ctx)
+    {
+        return result;
+    }
+    copy_between(from.as_ref(), src, to.as_ref(), dst, ctx)
+}
+
+/// Move `src` to the free path `dst`, copying and removing the source when
+/// the two are on different filesystems
+///
+/// # Errors
+///
+/// Returns an error if the entry could not be moved. When the copy succeeded
+/// but the source could not be removed, both are kept.
+pub fn move_entry(src: &Path, dst: &Path, ctx: &mut JobContext) -> io::Result<()> {
+    let (from, to) = (for_path(src), for_path(dst));
+    if Arc::ptr_eq(&from, &to) {
+        // Local moves fall back to copy-then-delete when crossing devices
+        return match from.local_path(src) {
+            Some(_) => crate::utils::file_operations::move_with_progress(src, dst, ctx),
+            None => from.rename(src, dst),
+        };
+    }
+    if from.is_read_only() {
+        return Err(read_only_error());
+    }
+
+    // Like moves across devices, only the copy contributes bytes to the totals
+    let (files, bytes) = count_entries(&[src.to_path_buf()]);
+    ctx.add_totals(files, bytes);
+    if let Err(e) = copy(src, dst, ctx) {
+        remove_partial(dst);
+        return Err(e);
+    }
+    from.remove(src).map_err(|e| {
+        io::Error::other(format!(
+            "copied to {} but failed to remove source: {e}",
+            dst.display()
+        ))
+    })
+}
+
+/// Best effort removal of a partially copied entry
+pub fn remove_partial(path: &Path) {
+    let fs = for_path(path);
+    if fs.stat(path).is_err() {
+        return;
+    }
+    if let Err(e) = fs.remove(path) {
+        tracing::warn!("Failed to clean up {}: {e}", path.display());
+    }
+}
+
+/// Copy an entry between two filesystems, reading it one entry at a time
+///
+/// Links cannot be recreated portably and are skipped with a warning.
+pub(crate) fn copy_between(
+    from: &dyn Vfs,
+    src: &Path,
+    to: &dyn Vfs,
+    dst: &Path,
+    ctx: &mut JobContext,
+) -> io::Result<()> {
+    ctx.check_cancelled()?;
+    ctx.start_file(src);
+    let metadata = from.stat(src)?;
+    if metadata.is_symlink {
+        ctx.warn(format!("Skipped link {}", src.display()));
+        return Ok(());
+    }
+    if metadata.is_dir {
+        to.create_dir(dst)?;
+        for entry in from.list(src)? {
+            copy_between(from, &entry.path, to, &dst.join(&entry.name), ctx)?;
+        }
+        return Ok(());
+    }
+
+    let mut reader = from.read(src)?;
+    let mut writer = to.write(dst)?;
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+    loop {
+        ctx.check_cancelled()?;
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buffer[..read])?;
+        ctx.add_bytes(read as u64);
+    }
+    writer.flush()
+}
+
+/// Count the entries and bytes under the given paths for progress reporting
+#[must_use]
+pub fn count_entries(paths: &[PathBuf]) -> (usize, u64) {
+    let mut files = 0;
+    let mut bytes = 0;
+    for path in paths {
+        let fs = for_path(path);
+        if fs.local_path(path).is_some() {
+            let (local_files, local_bytes) =
+                crate::utils::file_operations::count_entries(std::slice::from_ref(path));
+            files += local_files;
+            bytes += local_bytes;
+        } else {
+            count_recursive(fs.as_ref(), path, &mut files, &mut bytes);
+        }
+    }
+    (files, bytes)
+}
+
+fn count_recursive(fs: &dyn Vfs, path: &Path, files: &mut usize, bytes: &mut u64) {
+    let Ok(metadata) = fs.stat(path) else {
+        return;
+    };
+    *files += 1;
+    if metadata.is_symlink {
+        return;
+    }
+    if metadata.is_dir {
+        for entry in fs.list(path).unwrap_or_default() {
+            count_recursive(fs, &entry.path, files, bytes);
+        }
+    } else {
+        *bytes += metadata.size;
+    }
+}
+
+/// A copy of the file on the local filesystem
+///
+/// Files of other filesystems are copied to the cache directory once per
+/// modification time. Local files are returned as they are.
+///
+/// # Errors
+///
+/// Returns an error string if the file cannot be read.
+pub fn local_copy(path: &Path) -> Result<PathBuf, String> {
+    let fs = for_path(path);
+    if let Some(local) = fs.local_path(path) {
+        return Ok(local);
+    }
+    let metadata = fs
+        .stat(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let key = preview_cache::calculate_cache_key(&DirEntryMeta {
+        path: path.to_path_buf(),
+        modified: metadata.modified,
+    });
+    let dir = preview_cache::get_cache_dir()
+        .ok_or("No cache directory available")?
+        .join("vfs")
+        .join(key);
+    let name = path.file_name().ok_or("Invalid path")?;
+    let target = dir.join(name);
+    if target.exists() {
+        return Ok(target);
+    }
+
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    // Written under another name first so a failed copy is never picked up
+    let partial = dir.join(".partial");
+    let result = fs
+        .read(path)
+        .and_then(|mut reader| {
+            let mut file = File::create(&partial)?;
+            io::copy(&mut reader, &mut file)?;
+            std::fs::rename(&partial, &target)
+        })
+        .map_err(|e| format!("Failed to copy {}: {e}", path.display()));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result.map(|()| target)
+}
+
+/// The entry itself for local files, or a copy pointing at a local version
+/// of a file from another filesystem
+///
+/// # Errors
+///
+/// Returns an error string if the file is too large to copy or cannot be read.
+pub fn local_entry(entry: &DirEntry) -> Result<DirEntry, String> {
+    if entry.is_dir || is_local(&entry.meta.path) {
+        return Ok(entry.clone());
+    }
+    if entry.size > MAX_PREVIEW_COPY_SIZE {
+        return Err(format!(
+            "{} is too large to preview here, copy it to a local directory first",
+            entry.name
+        ));
+    }
+    let mut local = entry.clone();
+    local.meta.path = local_copy(&entry.meta.path)?;
+    Ok(local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Paths that are plainly local also depend on what other tests mount
+    #[test]
+    fn test_archives_and_remote_paths_are_not_plainly_local() {
+        assert!(!is_plainly_local(Path::new("/home/me/docs.zip/a.txt")));
+        assert!(!is_plainly_local(Path::new("/home/me/backup.tar.gz")));
+        assert!(!is_plainly_local(Path::new("sftp://me@host/home/me")));
+    }
+}
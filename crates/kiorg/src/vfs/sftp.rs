@@ -0,0 +1,537 @@
+//! Remote directories over SFTP
+//!
+//! `sftp://user@host:port/path` paths are served by one [`SftpFs`] per
+//! `user@host:port`. Connections honour `~/.ssh/config`, authenticate with the
+//! ssh agent or the configured identity files and only accept hosts listed in
+//! `~/.ssh/known_hosts`.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use ssh2::{
+    CheckResult, ErrorCode, FileStat, KnownHostFileKind, OpenFlags, OpenType, RenameFlags, Session,
+    Sftp,
+};
+
+use super::{Vfs, VfsEntry, VfsMetadata, ssh_config};
+use crate::utils::jobs::JobContext;
+
+pub const SCHEME: &str = "sftp://";
+
+const DEFAULT_PORT: u16 = 22;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const SESSION_TIMEOUT_MS: u32 = 30_000;
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// The root of the remote host a `sftp://` path points to, e.g.
+/// `sftp://user@host` for `sftp://user@host/srv/builds`
+#[must_use]
+pub fn root_of(path: &Path) -> Option<PathBuf> {
+    let rest = path.to_str()?.strip_prefix(SCHEME)?;
+    let authority = rest.split('/').next().unwrap_or_default();
+    if authority.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(format!("{SCHEME}{authority}")))
+}
+
+/// The host part of a `sftp://` path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshTarget {
+    pub user: Option<String>,
+    /// Host name or `~/.ssh/config` alias
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl SshTarget {
+    /// Parse `user@host:port`, where only the host is required
+    #[must_use]
+    pub fn parse(authority: &str) -> Option<Self> {
+        let (user, host_port) = match authority.rsplit_once('@') {
+            Some((user, host_port)) => (Some(user.to_string()), host_port),
+            None => (None, authority),
+        };
+        // IPv6 addresses are written in brackets, e.g. [::1]:2222
+        let (host, port) = if let Some(bracketed) = host_port.strip_prefix('[') {
+            let (host, rest) = bracketed.split_once(']')?;
+            (host, rest.strip_prefix(':'))
+        } else {
+            match host_port.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (host_port, None),
+            }
+        };
+        if host.is_empty() {
+            return None;
+        }
+        let port = match port {
+            Some(port) => Some(port.parse().ok()?),
+            None => None,
+        };
+        Some(Self {
+            user: user.filter(|user| !user.is_empty()),
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+/// An SFTP session, kept open while it works
+struct Connection {
+    // The sftp channel needs the session to stay alive
+    _session: Session,
+    sftp: Sftp,
+}
+
+/// Connections not used by any operation, ready for the next one
+type IdleConnections = Arc<Mutex<Vec<Connection>>>;
+
+fn give_back(idle: &IdleConnections, connection: Connection) {
+    let mut idle = idle.lock().unwrap_or_else(|e| e.into_inner());
+    if idle.len() < MAX_IDLE_CONNECTIONS {
+        idle.push(connection);
+    }
+}
+
+/// The filesystem of a remote host, connected on first use
+///
+/// Every operation takes a connection of its own, so listing a directory does
+/// not wait for a transfer to finish. Files being read or written keep their
+/// connection until they are dropped.
+pub struct SftpFs {
+    root: PathBuf,
+    target: SshTarget,
+    idle: IdleConnections,
+}
+
+impl SftpFs {
+    /// The filesystem of the host a `sftp://` path points to, without connecting
+    #[must_use]
+    pub fn for_url(path: &Path) -> Option<Self> {
+        let root = root_of(path)?;
+        let authority = root.to_str()?.strip_prefix(SCHEME)?;
+        Some(Self {
+            target: SshTarget::parse(authority)?,
+            root,
+            idle: Arc::default(),
+        })
+    }
+
+    /// Path on the remote host, always absolute
+    fn remote(&self, path: &Path) -> io::Result<PathBuf> {
+        let relative = path.strip_prefix(&self.root).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not on {}", path.display(), self.root.display()),
+            )
+        })?;
+        Ok(Path::new("/").join(relative))
+    }
+
+    fn local(&self, remote: &Path) -> PathBuf {
+        self.root.join(remote.strip_prefix("/").unwrap_or(remote))
+    }
+
+    /// An idle connection, or a new one when all of them are busy
+    fn take_connection(&self) -> io::Result<Connection> {
+        let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        match idle {
+            Some(connection) => Ok(connection),
+            None => connect(&self.target),
+        }
+    }
+
+    /// Run an operation on a connection of its own
+    ///
+    /// The connection is dropped when the session fails, so the next operation
+    /// reconnects.
+    fn with_sftp<T>(&self, op: impl FnOnce(&Sftp) -> Result<T, ssh2::Error>) -> io::Result<T> {
+        let connection = self.take_connection()?;
+        let result = op(&connection.sftp);
+        if !result.as_ref().is_err_and(is_session_error) {
+            give_back(&self.idle, connection);
+        }
+        result.map_err(io::Error::from)
+    }
+
+    /// Open a file that keeps its connection while it is read or written
+    fn open_file(
+        &self,
+        op: impl FnOnce(&Sftp) -> Result<ssh2::File, ssh2::Error>,
+    ) -> io::Result<RemoteFile> {
+        let connection = self.take_connection()?;
+        match op(&connection.sftp) {
+            Ok(file) => Ok(RemoteFile {
+                file: Some(file),
+                connection: Some(connection),
+                idle: Arc::clone(&self.idle),
+                failed: false,
+            }),
+            Err(e) => {
+                if !is_session_error(&e) {
+                    give_back(&self.idle, connection);
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    fn remove_remote(sftp: &Sftp, remote: &Path) -> Result<(), ssh2::Error> {
+        let stat = sftp.lstat(remote)?;
+        if !stat.is_dir() {
+            return sftp.unlink(remote);
+        }
+        for (child, _) in sftp.readdir(remote)? {
+            Self::remove_remote(sftp, &child)?;
+        }
+        sftp.rmdir(remote)
+    }
+}
+
+fn is_session_error(e: &ssh2::Error) -> bool {
+    matches!(e.code(), ErrorCode::Session(_))
+}
+
+/// A remote file, its connection goes back to the idle ones once the file is
+/// dropped without having failed
+struct RemoteFile {
+    file: Option<ssh2::File>,
+    connection: Option<Connection>,
+    idle: IdleConnections,
+    failed: bool,
+}
+
+impl RemoteFile {
+    fn file(&mut self) -> &mut ssh2::File {
+        self.file.as_mut().expect("the file is only taken on drop")
+    }
+
+    fn track<T>(&mut self, result: io::Result<T>) -> io::Result<T> {
+        self.failed |= result.is_err();
+        result
+    }
+}
+
+impl Read for RemoteFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.file().read(buf);
+        self.track(result)
+    }
+}
+
+impl Write for RemoteFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.file().write(buf);
+        self.track(result)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.file().flush();
+        self.track(result)
+    }
+}
+
+impl Drop for RemoteFile {
+    fn drop(&mut self) {
+        // The handle is closed before its connection can serve others
+        drop(self.file.take());
+        if let Some(connection) = self.connection.take()
+            && !self.failed
+        {
+            give_back(&self.idle, connection);
+        }
+    }
+}
+
+fn metadata_of(sftp: &Sftp, remote: &Path, stat: &FileStat) -> VfsMetadata {
+    let is_symlink = stat.file_type().is_symlink();
+    // Links are followed to tell whether they point to a directory
+    let is_dir = if is_symlink {
+        sftp.stat(remote).is_ok_and(|target| target.is_dir())
+    } else {
+        stat.is_dir()
+    };
+    VfsMetadata {
+        is_dir,
+        is_symlink,
+        size: if is_dir { 0 } else { stat.size.unwrap_or(0) },
+        modified: stat.mtime.map_or(SystemTime::UNIX_EPOCH, |mtime| {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(mtime)
+        }),
+    }
+}
+
+fn connect(target: &SshTarget) -> io::Result<Connection> {
+    let config = ssh_config::load(&target.host);
+    let host = config.host_name.unwrap_or_else(|| target.host.clone());
+    let port = target.port.or(config.port).unwrap_or(DEFAULT_PORT);
+    let user = target
+        .user
+        .clone()
+        .or(config.user)
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .ok_or_else(|| io::Error::other(format!("No user name given for {host}")))?;
+
+    let address = (host.as_str(), port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::other(format!("Cannot resolve {host}")))?;
+    let tcp = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+
+    let mut session = Session::new()?;
+    session.set_tcp_stream(tcp);
+    session.set_timeout(SESSION_TIMEOUT_MS);
+    session.handshake()?;
+    check_host_key(&session, &host, port)?;
+    authenticate(&session, &user, &config.identity_files)?;
+
+    let sftp = session.sftp()?;
+    Ok(Connection {
+        _session: session,
+        sftp,
+    })
+}
+
+/// Refuse hosts whose key is not in `~/.ssh/known_hosts`, the same as
+/// `StrictHostKeyChecking yes`
+fn check_host_key(session: &Session, host: &str, port: u16) -> io::Result<()> {
+    let mut known_hosts = session.known_hosts()?;
+    if let Some(home) = dirs::home_dir() {
+        // A missing file leaves the list empty and every host unknown
+        let _ = known_hosts.read_file(
+            &home.join(".ssh").join("known_hosts"),
+            KnownHostFileKind::OpenSSH,
+        );
+    }
+    let (key, _) = session
+        .host_key()
+        .ok_or_else(|| io::Error::other(format!("{host} did not send a host key")))?;
+    match known_hosts.check_port(host, port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::NotFound => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{host} is not in ~/.ssh/known_hosts, connect with ssh once to add it"),
+        )),
+        CheckResult::Mismatch => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("Host key of {host} does not match ~/.ssh/known_hosts"),
+        )),
+        CheckResult::Failure => Err(io::Error::other(format!(
+            "Failed to check the host key of {host}"
+        ))),
+    }
+}
+
+/// Authenticate with the ssh agent, then with identity files without a passphrase
+fn authenticate(session: &Session, user: &str, identity_files: &[PathBuf]) -> io::Result<()> {
+    if session.userauth_agent(user).is_ok() && session.authenticated() {
+        return Ok(());
+    }
+
+    let defaults = dirs::home_dir()
+        .map(|home| {
+            ["id_ed25519", "id_ecdsa", "id_rsa"]
+                .iter()
+                .map(|name| home.join(".ssh").join(name))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let candidates: &[PathBuf] = if identity_files.is_empty() {
+        &defaults
+    } else {
+        identity_files
+    };
+    for identity in candidates.iter().filter(|identity| identity.exists()) {
+        if session
+            .userauth_pubkey_file(user, None, identity, None)
+            .is_ok()
+            && session.authenticated()
+        {
+            return Ok(());
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!("Authentication as {user} failed, add your key to the ssh agent"),
+    ))
+}
+
+impl Vfs for SftpFs {
+    fn list(&self, path: &Path) -> io::Result<Vec<VfsEntry>> {
+        let remote = self.remote(path)?;
+        let entries = self.with_sftp(|sftp| {
+            Ok(sftp
+                .readdir(&remote)?
+                .into_iter()
+                .map(|(child, stat)| {
+                    let metadata = metadata_of(sftp, &child, &stat);
+                    (child, metadata)
+                })
+                .collect::<Vec<_>>())
+        })?;
+        Ok(entries
+            .into_iter()
+            .filter_map(|(child, metadata)| {
+                let name = child.file_name()?.to_string_lossy().into_owned();
+                Some(VfsEntry {
+                    is_hidden: name.starts_with('.'),
+                    name,
+                    path: self.local(&child),
+                    metadata,
+                })
+            })
+            .collect())
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<VfsMetadata> {
+        let remote = self.remote(path)?;
+        self.with_sftp(|sftp| {
+            let stat = sftp.lstat(&remote)?;
+            Ok(metadata_of(sftp, &remote, &stat))
+        })
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        let remote = self.remote(path)?;
+        let file = self.open_file(|sftp| sftp.open(&remote))?;
+        Ok(Box::new(file))
+    }
+
+    fn write(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        let remote = self.remote(path)?;
+        let file = self.open_file(|sftp| {
+            sftp.open_mode(
+                &remote,
+                OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::EXCLUSIVE,
+                0o644,
+                OpenType::File,
+            )
+        })?;
+        Ok(Box::new(file))
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let remote = self.remote(path)?;
+        self.with_sftp(|sftp| sftp.mkdir(&remote, 0o755))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (self.remote(from)?, self.remote(to)?);
+        self.with_sftp(|sftp| sftp.rename(&from, &to, Some(RenameFlags::NATIVE)))
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        let remote = self.remote(path)?;
+        self.with_sftp(|sftp| Self::remove_remote(sftp, &remote))
+    }
+
+    /// SFTP has no server side copy, the data makes a round trip
+    fn copy(&self, src: &Path, dst: &Path, ctx: &mut JobContext) -> io::Result<()> {
+        super::copy_between(self, src, self, dst, ctx)
+    }
+
+    fn parent(&self, path: &Path) -> Option<PathBuf> {
+        if path == self.root {
+            return None;
+        }
+        path.parent().map(Path::to_path_buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_of() {
+        assert_eq!(
+            root_of(Path::new("sftp://ci@build-01/srv/builds")),
+            Some(PathBuf::from("sftp://ci@build-01"))
+        );
+        assert_eq!(
+            root_of(Path::new("sftp://build-01")),
+            Some(PathBuf::from("sftp://build-01"))
+        );
+        assert_eq!(root_of(Path::new("sftp:///srv")), None);
+        assert_eq!(root_of(Path::new("/srv/builds")), None);
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            SshTarget::parse("ci@build-01:2222"),
+            Some(SshTarget {
+                user: Some("ci".to_string()),
+                host: "build-01".to_string(),
+                port: Some(2222),
+            })
+        );
+        assert_eq!(
+            SshTarget::parse("[::1]:22"),
+            Some(SshTarget {
+                user: None,
+                host: "::1".to_string(),
+                port: Some(22),
+            })
+        );
+        assert_eq!(SshTarget::parse("ci@"), None);
+        assert_eq!(SshTarget::parse("host:port"), None);
+    }
+
+    #[test]
+    fn test_remote_paths() {
+        let fs = SftpFs::for_url(Path::new("sftp://ci@build-01/srv")).unwrap();
+        let root = PathBuf::from("sftp://ci@build-01");
+        assert_eq!(
+            fs.remote(&root.join("srv/a.txt")).unwrap(),
+            PathBuf::from("/srv/a.txt")
+        );
+        assert_eq!(fs.remote(&root).unwrap(), PathBuf::from("/"));
+        assert_eq!(
+            fs.local(Path::new("/srv/a.txt")),
+            PathBuf::from("sftp://ci@build-01/srv/a.txt")
+        );
+        assert_eq!(fs.parent(&root.join("srv")), Some(root.clone()));
+        assert_eq!(fs.parent(&root), None);
+    }
+
+    /// Runs against a real server when `KIORG_SFTP_TEST_URL` points to a
+    /// writable directory, e.g. `sftp://me@localhost:2222/tmp` for a local
+    /// sshd in a container
+    #[test]
+    fn test_copy_to_server() {
+        use crate::utils::jobs::{JobStatus, spawn_job};
+        use crate::vfs;
+
+        let Ok(url) = std::env::var("KIORG_SFTP_TEST_URL") else {
+            return;
+        };
+        let remote_dir = PathBuf::from(url).join(format!("kiorg-test-{}", std::process::id()));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let local = temp_dir.path().join("docs");
+        std::fs::create_dir(&local).unwrap();
+        std::fs::write(local.join("a.txt"), "a").unwrap();
+
+        let (from, to) = (local.clone(), remote_dir.clone());
+        let mut job = spawn_job("Copying", move |ctx| {
+            vfs::copy(&from, &to, ctx).map_err(|e| e.to_string())
+        });
+        assert!(job.wait(Duration::from_secs(30)));
+        assert_eq!(job.state.status, JobStatus::Completed);
+
+        let entries = vfs::read_dir_entries(&remote_dir, false, false);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "a.txt");
+        let copy = vfs::local_copy(&entries[0].meta.path).unwrap();
+        assert_eq!(std::fs::read_to_string(copy).unwrap(), "a");
+
+        vfs::for_path(&remote_dir).remove(&remote_dir).unwrap();
+        assert!(!vfs::exists(&remote_dir));
+    }
+}
@@ -0,0 +1,167 @@
+//! The parts of `~/.ssh/config` needed to connect to a host
+//!
+//! Only `Host` blocks and the `HostName`, `User`, `Port` and `IdentityFile`
+//! keywords are read. `Match` blocks are skipped.
+
+use std::path::{Path, PathBuf};
+
+/// Settings that apply to a host
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HostConfig {
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_files: Vec<PathBuf>,
+}
+
+/// Settings for `host` from the user's `~/.ssh/config`, empty if there is none
+#[must_use]
+pub fn load(host: &str) -> HostConfig {
+    let Some(home) = dirs::home_dir() else {
+        return HostConfig::default();
+    };
+    match std::fs::read_to_string(home.join(".ssh").join("config")) {
+        Ok(content) => parse(&content, host, &home),
+        Err(_) => HostConfig::default(),
+    }
+}
+
+/// Settings for `host` from the content of a config file
+///
+/// Like ssh, the first value found for a keyword wins, so specific hosts are
+/// expected before wildcard blocks.
+#[must_use]
+pub fn parse(content: &str, host: &str, home: &Path) -> HostConfig {
+    let mut config = HostConfig::default();
+    // Lines before the first block apply to every host
+    let mut active = true;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((keyword, value)) = line.split_once(|c: char| c.is_whitespace() || c == '=')
+        else {
+            continue;
+        };
+        let value = value
+            .trim()
+            .trim_start_matches('=')
+            .trim()
+            .trim_matches('"');
+
+        match keyword.to_ascii_lowercase().as_str() {
+            "host" => active = host_matches(value, host),
+            "match" => active = false,
+            _ if !active => {}
+            "hostname" => {
+                config
+                    .host_name
+                    .get_or_insert_with(|| value.replace("%h", host));
+            }
+            "user" => {
+                config.user.get_or_insert_with(|| value.to_string());
+            }
+            "port" => {
+                if config.port.is_none() {
+                    config.port = value.parse().ok();
+                }
+            }
+            "identityfile" => config.identity_files.push(expand_home(value, home)),
+            _ => {}
+        }
+    }
+    config
+}
+
+/// Whether a `Host` line applies, any negated pattern excludes the host
+fn host_matches(patterns: &str, host: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split_whitespace() {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(negated.as_bytes(), host.as_bytes()) {
+                return false;
+            }
+        } else if wildcard_match(pattern.as_bytes(), host.as_bytes()) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Match `*` and `?` wildcards
+fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| wildcard_match(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && wildcard_match(rest, &text[1..]),
+        Some((c, rest)) => text
+            .split_first()
+            .is_some_and(|(t, text)| c.eq_ignore_ascii_case(t) && wildcard_match(rest, text)),
+    }
+}
+
+fn expand_home(value: &str, home: &Path) -> PathBuf {
+    if let Some(rest) = value.strip_prefix("~/") {
+        home.join(rest)
+    } else if let Some(rest) = value.strip_prefix("%d/") {
+        home.join(rest)
+    } else {
+        PathBuf::from(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "
+# Defaults first
+IdentityFile ~/.ssh/id_shared
+
+Host build-* !build-legacy
+    HostName %h.example.com
+    User ci
+    Port=2222
+    IdentityFile \"~/.ssh/id_build\"
+
+Match exec \"true\"
+    User ignored
+
+Host *
+    User fallback
+    Port 22
+";
+
+    #[test]
+    fn test_parse_host_blocks() {
+        let home = Path::new("/home/me");
+
+        let config = parse(CONFIG, "build-01", home);
+        assert_eq!(config.host_name.as_deref(), Some("build-01.example.com"));
+        assert_eq!(config.user.as_deref(), Some("ci"));
+        assert_eq!(config.port, Some(2222));
+        assert_eq!(
+            config.identity_files,
+            vec![
+                PathBuf::from("/home/me/.ssh/id_shared"),
+                PathBuf::from("/home/me/.ssh/id_build"),
+            ]
+        );
+
+        let config = parse(CONFIG, "build-legacy", home);
+        assert_eq!(config.host_name, None);
+        assert_eq!(config.user.as_deref(), Some("fallback"));
+        assert_eq!(config.port, Some(22));
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match(b"*", b"anything"));
+        assert!(wildcard_match(b"build-??", b"BUILD-01"));
+        assert!(!wildcard_match(b"build-??", b"build-1"));
+        assert!(wildcard_match(b"*.example.com", b"a.example.com"));
+        assert!(!wildcard_match(b"*.example.com", b"example.com"));
+    }
+}
@@ -734,3 +734,51 @@ fn test_undo_redo_invalidates_redo_stack() {
     assert!(!file1.exists(), "file1.txt should still not exist");
     assert!(file2.exists(), "file2.txt should still exist");
 }
+
+#[test]
+fn test_undo_refuses_changed_files_and_journal_is_saved() {
+    let temp_dir = tempdir().unwrap();
+    let source_file = temp_dir.path().join("source.txt");
+    let copied_file = temp_dir.path().join("source_1.txt");
+    std::fs::write(&source_file, "test content").unwrap();
+
+    let mut harness = create_harness(&temp_dir);
+
+    // Copy and paste the file next to itself
+    harness.key_press(Key::Y);
+    harness.step();
+    harness.key_press(Key::P);
+    wait_for_condition(|| {
+        harness.step();
+        !harness
+            .state()
+            .tab_manager
+            .current_tab_ref()
+            .action_history
+            .is_empty()
+    });
+
+    // The action is journaled so it survives a restart
+    let config_dir = harness.state().config_dir_override.clone();
+    let histories = kiorg::models::action_history::load_journal(config_dir.as_ref())
+        .unwrap()
+        .tabs;
+    assert_eq!(histories.len(), 1);
+    assert_eq!(histories[0].get_active_actions().len(), 1);
+
+    // Undoing would delete the edits made to the copy
+    std::fs::write(&copied_file, "edited after the copy").unwrap();
+    harness.key_press(Key::U);
+    harness.step();
+    assert!(copied_file.exists(), "Changed copy should not be removed");
+    assert!(
+        harness
+            .state()
+            .tab_manager
+            .current_tab_ref()
+            .action_history
+            .get_last_rollbackable_action()
+            .is_some(),
+        "Refused undo should keep the action"
+    );
+}
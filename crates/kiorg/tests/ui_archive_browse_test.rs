@@ -0,0 +1,115 @@
+#[path = "mod/ui_test_helpers.rs"]
+mod ui_test_helpers;
+
+use egui::Key;
+use tempfile::tempdir;
+use ui_test_helpers::{create_harness, create_test_zip, ctrl_modifiers, wait_for_condition};
+
+fn select_by_name(harness: &mut ui_test_helpers::TestHarness<'_>, name: &str) {
+    let tab = harness.state_mut().tab_manager.current_tab_mut();
+    let index = tab
+        .entries
+        .iter()
+        .position(|entry| entry.name == name)
+        .unwrap_or_else(|| panic!("{name} should be listed"));
+    tab.update_selection(index);
+    harness.step();
+}
+
+fn entry_names(harness: &ui_test_helpers::TestHarness<'_>) -> Vec<String> {
+    let mut names: Vec<String> = harness
+        .state()
+        .tab_manager
+        .current_tab_ref()
+        .entries
+        .iter()
+        .map(|entry| entry.name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_browse_zip_as_directory() {
+    let temp_dir = tempdir().unwrap();
+    let archive = create_test_zip(&temp_dir.path().join("archive.zip"));
+
+    let mut harness = create_harness(&temp_dir);
+    select_by_name(&mut harness, "archive.zip");
+
+    harness.key_press(Key::L);
+    harness.step();
+    assert_eq!(
+        harness.state().tab_manager.current_tab_ref().current_path,
+        archive
+    );
+    assert_eq!(
+        entry_names(&harness),
+        vec!["file1.txt", "file2.txt", "subdir"]
+    );
+
+    select_by_name(&mut harness, "subdir");
+    harness.key_press(Key::L);
+    harness.step();
+    assert_eq!(
+        harness.state().tab_manager.current_tab_ref().current_path,
+        archive.join("subdir")
+    );
+    assert_eq!(entry_names(&harness), vec!["file3.txt"]);
+
+    // Going back up leaves the archive again
+    harness.key_press(Key::H);
+    harness.step();
+    harness.key_press(Key::H);
+    harness.step();
+    assert_eq!(
+        harness.state().tab_manager.current_tab_ref().current_path,
+        temp_dir.path()
+    );
+}
+
+#[test]
+fn test_copy_out_of_zip_and_read_only() {
+    let temp_dir = tempdir().unwrap();
+    let archive = create_test_zip(&temp_dir.path().join("archive.zip"));
+
+    let mut harness = create_harness(&temp_dir);
+    harness.state_mut().navigate_to_dir(archive.clone());
+    harness.step();
+
+    // Nothing inside the archive can be changed
+    select_by_name(&mut harness, "file1.txt");
+    harness.key_press(Key::D);
+    harness.step();
+    assert!(harness.state().show_popup.is_none());
+    assert!(archive.exists());
+
+    harness.key_press(Key::Y);
+    harness.step();
+    harness.key_press(Key::H);
+    harness.step();
+    harness.key_press(Key::P);
+
+    let copied = temp_dir.path().join("file1.txt");
+    wait_for_condition(|| {
+        harness.step();
+        harness.state().show_popup.is_none() && copied.exists()
+    });
+    assert_eq!(
+        std::fs::read_to_string(&copied).unwrap(),
+        "Content of file1.txt"
+    );
+    // No scratch directory is left behind
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+
+    // Undo stashes the copy, redo brings it back from the stash
+    harness.key_press(Key::U);
+    harness.step();
+    assert!(!copied.exists());
+    harness.key_press_modifiers(ctrl_modifiers(), Key::R);
+    harness.step();
+    assert_eq!(
+        std::fs::read_to_string(&copied).unwrap(),
+        "Content of file1.txt"
+    );
+}
@@ -61,10 +61,10 @@ fn test_bulk_delete_with_space_key() {
     {
         let app = harness.state();
         assert!(
-            matches!(app.show_popup, Some(PopupType::Delete(_, _))),
+            matches!(app.show_popup, Some(PopupType::Delete(..))),
             "Delete popup should be shown"
         );
-        if let Some(PopupType::Delete(_, entries)) = &app.show_popup {
+        if let Some(PopupType::Delete(_, entries, _)) = &app.show_popup {
             assert_eq!(
                 entries.len(),
                 2,
@@ -80,7 +80,7 @@ fn test_bulk_delete_with_space_key() {
     // Verify we're now in the recursive confirmation state (second confirmation required)
     {
         let app = harness.state();
-        if let Some(PopupType::Delete(state, _)) = &app.show_popup {
+        if let Some(PopupType::Delete(state, _, _)) = &app.show_popup {
             assert_eq!(
                 *state,
                 kiorg::ui::popup::delete::DeleteConfirmState::RecursiveConfirm,
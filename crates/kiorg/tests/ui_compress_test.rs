@@ -0,0 +1,92 @@
+#[path = "mod/ui_test_helpers.rs"]
+mod ui_test_helpers;
+
+use egui::Key;
+use kiorg::ui::popup::PopupType;
+use kiorg::utils::archive::CompressFormat;
+use tempfile::tempdir;
+use ui_test_helpers::{
+    create_harness, create_test_files, ctrl_modifiers, shift_modifiers, wait_for_condition,
+};
+
+#[test]
+fn test_compress_marked_entries_and_undo() {
+    let temp_dir = tempdir().unwrap();
+    let files = create_test_files(&[
+        temp_dir.path().join("a.txt"),
+        temp_dir.path().join("b.txt"),
+        temp_dir.path().join("c.txt"),
+    ]);
+
+    let mut harness = create_harness(&temp_dir);
+    {
+        let tab = harness.state_mut().tab_manager.current_tab_mut();
+        tab.marked_entries.insert(files[0].clone());
+        tab.marked_entries.insert(files[1].clone());
+    }
+
+    harness.key_press_modifiers(shift_modifiers(), Key::C);
+    harness.step();
+    match &mut harness.state_mut().show_popup {
+        Some(PopupType::Compress(state)) => {
+            assert_eq!(state.sources, vec![files[0].clone(), files[1].clone()]);
+            state.name = "bundle".to_string();
+            state.format = CompressFormat::TarGz;
+        }
+        _ => panic!("Compress popup should be open"),
+    }
+
+    harness.key_press(Key::Enter);
+    let archive = temp_dir.path().join("bundle.tar.gz");
+    wait_for_condition(|| {
+        harness.step();
+        harness.state().show_popup.is_none()
+    });
+    assert!(archive.exists());
+    assert!(
+        harness
+            .state()
+            .tab_manager
+            .current_tab_ref()
+            .marked_entries
+            .is_empty()
+    );
+
+    // Undo stashes the archive and leaves the sources alone
+    harness.key_press(Key::U);
+    harness.step();
+    assert!(!archive.exists());
+    assert!(files.iter().all(|file| file.exists()));
+
+    // Redo brings the same archive back
+    harness.key_press_modifiers(ctrl_modifiers(), Key::R);
+    harness.step();
+    assert!(archive.exists());
+}
+
+#[test]
+fn test_compress_refuses_existing_archive() {
+    let temp_dir = tempdir().unwrap();
+    let files = create_test_files(&[
+        temp_dir.path().join("a.txt"),
+        temp_dir.path().join("a.txt.zip"),
+    ]);
+    std::fs::write(&files[1], "existing").unwrap();
+
+    let mut harness = create_harness(&temp_dir);
+    harness.key_press_modifiers(shift_modifiers(), Key::C);
+    harness.step();
+    assert!(matches!(
+        harness.state().show_popup,
+        Some(PopupType::Compress(_))
+    ));
+
+    // The default name of a.txt clashes with a.txt.zip
+    harness.key_press(Key::Enter);
+    harness.step();
+    assert!(matches!(
+        harness.state().show_popup,
+        Some(PopupType::Compress(_))
+    ));
+    assert_eq!(std::fs::read_to_string(&files[1]).unwrap(), "existing");
+}
@@ -0,0 +1,77 @@
+#[path = "mod/ui_test_helpers.rs"]
+mod ui_test_helpers;
+
+use egui::Key;
+use kiorg::ui::popup::PopupType;
+use kiorg::ui::popup::content_search::SearchStatus;
+use tempfile::tempdir;
+use ui_test_helpers::{create_harness, ctrl_shift_modifiers, wait_for_condition};
+
+#[test]
+fn test_content_search_jumps_to_match() {
+    let temp_dir = tempdir().unwrap();
+    std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+    let notes = temp_dir.path().join("sub").join("notes.txt");
+    std::fs::write(&notes, "first\nsecond\nthe needle\nlast").unwrap();
+    std::fs::write(temp_dir.path().join("other.txt"), "nothing here").unwrap();
+
+    let mut harness = create_harness(&temp_dir);
+    harness.key_press_modifiers(ctrl_shift_modifiers(), Key::F);
+    harness.step();
+    match &mut harness.state_mut().show_popup {
+        Some(PopupType::ContentSearch(state)) => {
+            assert_eq!(state.status, SearchStatus::Idle);
+            state.options.query = "NEEDLE".to_string();
+        }
+        _ => panic!("Content search popup should be open"),
+    }
+
+    wait_for_condition(|| {
+        harness.step();
+        matches!(
+            &harness.state().show_popup,
+            Some(PopupType::ContentSearch(state)) if matches!(state.status, SearchStatus::Done { .. })
+        )
+    });
+    match &harness.state().show_popup {
+        Some(PopupType::ContentSearch(state)) => {
+            assert_eq!(state.matches.len(), 1);
+            assert_eq!(state.matches[0].path, notes);
+            assert_eq!(state.matches[0].line_number, 3);
+            assert_eq!(state.matches[0].before, vec!["first", "second"]);
+        }
+        _ => panic!("Content search popup should still be open"),
+    }
+
+    harness.key_press(Key::Enter);
+    harness.step();
+    assert!(harness.state().show_popup.is_none());
+    let tab = harness.state().tab_manager.current_tab_ref();
+    assert_eq!(tab.current_path, temp_dir.path().join("sub"));
+    assert_eq!(tab.selected_entry().map(|e| &e.meta.path), Some(&notes));
+}
+
+#[test]
+fn test_content_search_invalid_regex() {
+    let temp_dir = tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("a.txt"), "text").unwrap();
+
+    let mut harness = create_harness(&temp_dir);
+    harness.key_press_modifiers(ctrl_shift_modifiers(), Key::F);
+    harness.step();
+    if let Some(PopupType::ContentSearch(state)) = &mut harness.state_mut().show_popup {
+        state.options.query = "(".to_string();
+        state.options.regex = true;
+    }
+    harness.step();
+    match &harness.state().show_popup {
+        Some(PopupType::ContentSearch(state)) => {
+            assert!(matches!(state.status, SearchStatus::Error(_)));
+        }
+        _ => panic!("Content search popup should be open"),
+    }
+
+    harness.key_press(Key::Escape);
+    harness.step();
+    assert!(harness.state().show_popup.is_none());
+}
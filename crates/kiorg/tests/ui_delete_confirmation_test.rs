@@ -4,7 +4,7 @@ mod ui_test_helpers;
 use egui::Key;
 use kiorg::ui::popup::PopupType;
 use tempfile::tempdir;
-use ui_test_helpers::{create_harness, create_test_files, wait_for_condition};
+use ui_test_helpers::{create_harness, create_test_files, shift_modifiers, wait_for_condition};
 
 #[test]
 fn test_folder_delete_double_confirmation() {
@@ -43,12 +43,12 @@ fn test_folder_delete_double_confirmation() {
 
     // Verify delete popup is shown
     assert!(
-        matches!(harness.state().show_popup, Some(PopupType::Delete(_, _))),
+        matches!(harness.state().show_popup, Some(PopupType::Delete(..))),
         "Delete popup should be open"
     );
 
     // Verify we're in the initial confirmation state
-    if let Some(PopupType::Delete(state, _)) = &harness.state().show_popup {
+    if let Some(PopupType::Delete(state, _, _)) = &harness.state().show_popup {
         assert_eq!(
             *state,
             kiorg::ui::popup::delete::DeleteConfirmState::Initial,
@@ -63,7 +63,7 @@ fn test_folder_delete_double_confirmation() {
     harness.step();
 
     // Verify we're now in the recursive confirmation state
-    if let Some(PopupType::Delete(state, _)) = &harness.state().show_popup {
+    if let Some(PopupType::Delete(state, _, _)) = &harness.state().show_popup {
         assert_eq!(
             *state,
             kiorg::ui::popup::delete::DeleteConfirmState::RecursiveConfirm,
@@ -121,7 +121,7 @@ fn test_folder_delete_cancel_first_confirmation() {
 
     // Verify delete popup is shown
     assert!(
-        matches!(harness.state().show_popup, Some(PopupType::Delete(_, _))),
+        matches!(harness.state().show_popup, Some(PopupType::Delete(..))),
         "Delete popup should be open"
     );
 
@@ -166,7 +166,7 @@ fn test_folder_delete_cancel_second_confirmation() {
     harness.step();
 
     // Verify we're in the recursive confirmation state
-    if let Some(PopupType::Delete(state, _)) = &harness.state().show_popup {
+    if let Some(PopupType::Delete(state, _, _)) = &harness.state().show_popup {
         assert_eq!(
             *state,
             kiorg::ui::popup::delete::DeleteConfirmState::RecursiveConfirm,
@@ -192,3 +192,85 @@ fn test_folder_delete_cancel_second_confirmation() {
         "Directory should still exist after cancellation"
     );
 }
+
+#[test]
+fn test_file_delete_moves_to_trash_and_undo_restores() {
+    if !kiorg::utils::trash::is_supported() {
+        return;
+    }
+
+    let temp_dir = tempdir().unwrap();
+    let test_files = create_test_files(&[temp_dir.path().join("trash_me.txt")]);
+
+    let mut harness = create_harness(&temp_dir);
+    harness
+        .state_mut()
+        .tab_manager
+        .current_tab_mut()
+        .selected_index = 0;
+    harness.step();
+
+    // Press 'd' and confirm, files go to trash by default
+    harness.key_press(Key::D);
+    harness.step();
+    assert!(matches!(
+        harness.state().show_popup,
+        Some(PopupType::Delete(
+            _,
+            _,
+            kiorg::ui::popup::delete::DeleteMode::Trash
+        ))
+    ));
+    harness.key_press(Key::Enter);
+    wait_for_condition(|| {
+        harness.step();
+        harness.state().show_popup.is_none()
+    });
+    assert!(!test_files[0].exists(), "File should be moved to trash");
+
+    // The trash operation is recorded and can be undone
+    harness.key_press(Key::U);
+    harness.step();
+    assert!(test_files[0].exists(), "File should be restored from trash");
+}
+
+#[test]
+fn test_file_permanent_delete() {
+    let temp_dir = tempdir().unwrap();
+    let test_files = create_test_files(&[temp_dir.path().join("gone.txt")]);
+
+    let mut harness = create_harness(&temp_dir);
+    harness
+        .state_mut()
+        .tab_manager
+        .current_tab_mut()
+        .selected_index = 0;
+    harness.step();
+
+    // Press 'Shift+d' to permanently delete
+    harness.key_press_modifiers(shift_modifiers(), Key::D);
+    harness.step();
+    assert!(matches!(
+        harness.state().show_popup,
+        Some(PopupType::Delete(
+            _,
+            _,
+            kiorg::ui::popup::delete::DeleteMode::Permanent
+        ))
+    ));
+
+    harness.key_press(Key::Enter);
+    wait_for_condition(|| {
+        harness.step();
+        harness.state().show_popup.is_none()
+    });
+    assert!(!test_files[0].exists(), "File should be deleted");
+
+    // The file went to the undo stash, undo brings it back
+    harness.key_press(Key::U);
+    harness.step();
+    assert!(
+        test_files[0].exists(),
+        "File should be restored from the stash"
+    );
+}
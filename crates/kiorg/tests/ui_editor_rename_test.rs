@@ -0,0 +1,92 @@
+#[path = "mod/ui_test_helpers.rs"]
+mod ui_test_helpers;
+
+use egui::Key;
+use kiorg::utils::bulk_rename;
+use tempfile::tempdir;
+use ui_test_helpers::{create_harness, create_test_files};
+
+#[test]
+fn test_editor_rename_swap_is_undone_at_once() {
+    let temp_dir = tempdir().unwrap();
+    let files = create_test_files(&[
+        temp_dir.path().join("a.txt"),
+        temp_dir.path().join("b.txt"),
+        temp_dir.path().join("c.txt"),
+    ]);
+    std::fs::write(&files[0], "a").unwrap();
+    std::fs::write(&files[1], "b").unwrap();
+
+    let mut harness = create_harness(&temp_dir);
+
+    // Swap a and b and rename c, as if edited in $EDITOR
+    let edited = bulk_rename::format_listing(&files)
+        .replace("1\ta.txt", "1\tb.txt")
+        .replace("2\tb.txt", "2\ta.txt")
+        .replace("3\tc.txt", "3\td.txt");
+    kiorg::ui::editor_rename::apply_listing(harness.state_mut(), 0, &files, &edited);
+    harness.step();
+
+    assert_eq!(std::fs::read_to_string(&files[0]).unwrap(), "b");
+    assert_eq!(std::fs::read_to_string(&files[1]).unwrap(), "a");
+    assert!(!files[2].exists());
+    assert!(temp_dir.path().join("d.txt").exists());
+
+    // One undo reverts every rename of the listing
+    harness.key_press(Key::U);
+    harness.step();
+
+    assert_eq!(std::fs::read_to_string(&files[0]).unwrap(), "a");
+    assert_eq!(std::fs::read_to_string(&files[1]).unwrap(), "b");
+    assert!(files[2].exists());
+    assert!(!temp_dir.path().join("d.txt").exists());
+}
+
+#[test]
+fn test_editor_rename_conflict_renames_nothing() {
+    let temp_dir = tempdir().unwrap();
+    let files = create_test_files(&[temp_dir.path().join("a.txt"), temp_dir.path().join("b.txt")]);
+
+    let mut harness = create_harness(&temp_dir);
+
+    // Both entries are given the same name
+    let edited = bulk_rename::format_listing(&files).replace("2\tb.txt", "2\ta.txt");
+    kiorg::ui::editor_rename::apply_listing(harness.state_mut(), 0, &files, &edited);
+    harness.step();
+
+    assert!(files[0].exists());
+    assert!(files[1].exists());
+    assert!(
+        harness
+            .state()
+            .tab_manager
+            .current_tab_ref()
+            .action_history
+            .is_empty(),
+        "Nothing should be recorded for undo"
+    );
+}
+
+#[test]
+fn test_editor_rename_is_recorded_in_its_tab() {
+    let temp_dir = tempdir().unwrap();
+    let files = create_test_files(&[temp_dir.path().join("a.txt")]);
+
+    let mut harness = create_harness(&temp_dir);
+
+    // Another tab became current while the editor was open
+    let tab_manager = &mut harness.state_mut().tab_manager;
+    tab_manager.add_tab(std::env::temp_dir());
+    let tab_index = tab_manager.find_tab_showing(0, temp_dir.path());
+    assert_eq!(tab_index, 0);
+
+    let edited = bulk_rename::format_listing(&files).replace("1\ta.txt", "1\tb.txt");
+    kiorg::ui::editor_rename::apply_listing(harness.state_mut(), tab_index, &files, &edited);
+    harness.step();
+
+    assert!(temp_dir.path().join("b.txt").exists());
+    let tab_manager = &harness.state().tab_manager;
+    assert!(tab_manager.current_tab_ref().action_history.is_empty());
+    let histories: Vec<_> = tab_manager.action_histories().collect();
+    assert_eq!(histories[0].get_active_actions().len(), 1);
+}
@@ -0,0 +1,72 @@
+#[path = "mod/ui_test_helpers.rs"]
+mod ui_test_helpers;
+
+use egui::Key;
+use tempfile::tempdir;
+use ui_test_helpers::{
+    create_harness, create_test_tar, create_test_zip, ctrl_modifiers, shift_modifiers,
+    wait_for_condition,
+};
+
+#[test]
+fn test_extract_tar_and_undo() {
+    let temp_dir = tempdir().unwrap();
+    create_test_tar(&temp_dir.path().join("archive.tar"));
+
+    let mut harness = create_harness(&temp_dir);
+
+    harness.key_press_modifiers(shift_modifiers(), Key::X);
+    let extracted = temp_dir.path().join("archive");
+    wait_for_condition(|| {
+        harness.step();
+        harness.state().show_popup.is_none() && extracted.join("subdir/file3.txt").exists()
+    });
+
+    assert_eq!(
+        std::fs::read_to_string(extracted.join("file1.txt")).unwrap(),
+        "Content of file1.txt"
+    );
+    assert_eq!(
+        std::fs::read_to_string(extracted.join("subdir/file3.txt")).unwrap(),
+        "Content of file3.txt in subdir"
+    );
+
+    // Undo puts everything that was extracted into the undo stash
+    harness.key_press(Key::U);
+    harness.step();
+    assert!(!extracted.exists());
+    assert!(temp_dir.path().join("archive.tar").exists());
+
+    // Redo brings it back as it was
+    harness.key_press_modifiers(ctrl_modifiers(), Key::R);
+    harness.step();
+    assert_eq!(
+        std::fs::read_to_string(extracted.join("subdir/file3.txt")).unwrap(),
+        "Content of file3.txt in subdir"
+    );
+}
+
+#[test]
+fn test_extract_selected_zip_entries_next_to_existing_dir() {
+    let temp_dir = tempdir().unwrap();
+    let archive = create_test_zip(&temp_dir.path().join("archive.zip"));
+    // The archive name is taken, a new directory is used instead
+    std::fs::create_dir(temp_dir.path().join("archive")).unwrap();
+
+    let mut harness = create_harness(&temp_dir);
+    kiorg::ui::popup::extract::extract(harness.state_mut(), archive, vec!["subdir".to_string()]);
+
+    let extracted = temp_dir.path().join("archive (2)");
+    wait_for_condition(|| {
+        harness.step();
+        harness.state().show_popup.is_none() && extracted.join("subdir/file3.txt").exists()
+    });
+
+    assert!(!extracted.join("file1.txt").exists());
+    assert!(
+        std::fs::read_dir(temp_dir.path().join("archive"))
+            .unwrap()
+            .next()
+            .is_none()
+    );
+}
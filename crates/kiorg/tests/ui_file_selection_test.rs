@@ -60,10 +60,10 @@ fn test_ctrl_a_select_all_and_delete() {
     {
         let app = harness.state();
         assert!(
-            matches!(app.show_popup, Some(PopupType::Delete(_, _))),
+            matches!(app.show_popup, Some(PopupType::Delete(..))),
             "Delete popup should be shown"
         );
-        if let Some(PopupType::Delete(_, entries)) = &app.show_popup {
+        if let Some(PopupType::Delete(_, entries, _)) = &app.show_popup {
             assert_eq!(
                 entries.len(),
                 5,
@@ -76,12 +76,12 @@ fn test_ctrl_a_select_all_and_delete() {
     harness.key_press(Key::Enter);
     wait_for_condition(|| {
         harness.step();
-        matches!(harness.state().show_popup, Some(PopupType::Delete(_, _)))
+        matches!(harness.state().show_popup, Some(PopupType::Delete(..)))
     });
 
     // Verify we're in the recursive confirmation state
     {
-        if let Some(PopupType::Delete(state, _)) = &harness.state().show_popup {
+        if let Some(PopupType::Delete(state, _, _)) = &harness.state().show_popup {
             assert_eq!(
                 *state,
                 kiorg::ui::popup::delete::DeleteConfirmState::RecursiveConfirm,
@@ -0,0 +1,83 @@
+#[path = "mod/ui_test_helpers.rs"]
+mod ui_test_helpers;
+
+use egui::Key;
+use tempfile::tempdir;
+use ui_test_helpers::{create_harness_with_config_dir, create_test_files};
+
+fn listed_names(harness: &ui_test_helpers::TestHarness<'_>) -> Vec<String> {
+    harness
+        .state()
+        .tab_manager
+        .current_tab_ref()
+        .entries
+        .iter()
+        .map(|entry| entry.name.clone())
+        .collect()
+}
+
+#[test]
+fn test_filter_rules_hide_entries_until_toggled() {
+    let temp_dir = tempdir().unwrap();
+    create_test_files(&[
+        temp_dir.path().join("main.c"),
+        temp_dir.path().join("main.o"),
+        temp_dir.path().join("target"),
+    ]);
+
+    let config_dir = tempdir().unwrap();
+    std::fs::write(
+        config_dir.path().join("config.toml"),
+        r#"
+[[filters]]
+exclude = ["*.o", "target/"]
+"#,
+    )
+    .unwrap();
+
+    let mut harness = create_harness_with_config_dir(&temp_dir, config_dir);
+    assert_eq!(listed_names(&harness), vec!["main.c"]);
+    assert_eq!(
+        harness
+            .state()
+            .tab_manager
+            .current_tab_ref()
+            .hidden_by_filters,
+        2
+    );
+
+    // zf shows everything again
+    harness.key_press(Key::Z);
+    harness.key_press(Key::F);
+    harness.step();
+    assert_eq!(listed_names(&harness), vec!["main.c", "main.o", "target"]);
+    assert_eq!(
+        harness
+            .state()
+            .tab_manager
+            .current_tab_ref()
+            .hidden_by_filters,
+        0
+    );
+
+    harness.key_press(Key::Z);
+    harness.key_press(Key::F);
+    harness.step();
+    assert_eq!(listed_names(&harness), vec!["main.c"]);
+}
+
+#[test]
+fn test_invalid_filter_pattern_is_a_config_error() {
+    let config_dir = tempdir().unwrap();
+    std::fs::write(
+        config_dir.path().join("config.toml"),
+        "[[filters]]\nexclude = [\"[\"]\n",
+    )
+    .unwrap();
+
+    let result = kiorg::config::load_config_with_override(Some(&config_dir.path().to_path_buf()));
+    assert!(matches!(
+        result,
+        Err(kiorg::config::ConfigError::ValueError(_, _))
+    ));
+}
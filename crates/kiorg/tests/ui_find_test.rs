@@ -0,0 +1,56 @@
+#[path = "mod/ui_test_helpers.rs"]
+mod ui_test_helpers;
+
+use egui::Key;
+use kiorg::ui::popup::PopupType;
+use tempfile::tempdir;
+use ui_test_helpers::{create_harness, shift_modifiers, wait_for_condition};
+
+#[test]
+fn test_find_selects_nested_entry() {
+    let temp_dir = tempdir().unwrap();
+    let nested = temp_dir.path().join("a").join("b");
+    std::fs::create_dir_all(&nested).unwrap();
+    let target = nested.join("report.pdf");
+    std::fs::write(&target, "").unwrap();
+    std::fs::write(nested.join("notes.txt"), "").unwrap();
+    std::fs::write(temp_dir.path().join("readme.md"), "").unwrap();
+
+    let mut harness = create_harness(&temp_dir);
+    harness.key_press_modifiers(shift_modifiers(), Key::F);
+    harness.step();
+    match &mut harness.state_mut().show_popup {
+        Some(PopupType::Find(state)) => {
+            state.fuzzy_state.query = "rep.pdf".to_string();
+        }
+        _ => panic!("Find popup should be open"),
+    }
+
+    assert!(wait_for_condition(|| {
+        harness.step();
+        match &harness.state().show_popup {
+            Some(PopupType::Find(state)) => {
+                !state.index.is_indexing() && state.index.matched_count() == 1
+            }
+            _ => false,
+        }
+    }));
+    match &mut harness.state_mut().show_popup {
+        Some(PopupType::Find(state)) => {
+            let results = state.index.results(10);
+            assert_eq!(results[0].entry.path, target);
+            assert_eq!(
+                std::path::Path::new(&results[0].entry.relative),
+                std::path::Path::new("a").join("b").join("report.pdf")
+            );
+        }
+        _ => panic!("Find popup should still be open"),
+    }
+
+    harness.key_press(Key::Enter);
+    harness.step();
+    assert!(harness.state().show_popup.is_none());
+    let tab = harness.state().tab_manager.current_tab_ref();
+    assert_eq!(tab.current_path, nested);
+    assert_eq!(tab.selected_entry().map(|e| &e.meta.path), Some(&target));
+}
@@ -0,0 +1,96 @@
+#[path = "mod/ui_test_helpers.rs"]
+mod ui_test_helpers;
+
+use std::sync::Arc;
+
+use egui::Key;
+use kiorg::vfs::{self, memory::MemoryFs};
+use tempfile::tempdir;
+use ui_test_helpers::{create_harness, wait_for_condition};
+
+fn entry_names(harness: &ui_test_helpers::TestHarness<'_>) -> Vec<String> {
+    let mut names: Vec<String> = harness
+        .state()
+        .tab_manager
+        .current_tab_ref()
+        .entries
+        .iter()
+        .map(|entry| entry.name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_browse_and_paste_into_memory_fs() {
+    let temp_dir = tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("local.txt"), "local").unwrap();
+
+    // Mounted under the temp dir so parallel tests never share the mount
+    let fs = MemoryFs::new(temp_dir.path().join("memory"));
+    fs.add_file("notes.txt", "notes");
+    fs.add_file("docs/a.txt", "a");
+    let root = fs.root().to_path_buf();
+    vfs::mount(root.clone(), Arc::new(fs.clone()));
+
+    let mut harness = create_harness(&temp_dir);
+
+    // Copy a local file, then paste it into the in-memory directory
+    let tab = harness.state_mut().tab_manager.current_tab_mut();
+    let index = tab
+        .entries
+        .iter()
+        .position(|entry| entry.name == "local.txt")
+        .unwrap();
+    tab.update_selection(index);
+    harness.step();
+    harness.key_press(Key::Y);
+    harness.step();
+
+    harness.state_mut().navigate_to_dir(root.clone());
+    harness.step();
+    assert_eq!(entry_names(&harness), vec!["docs", "notes.txt"]);
+
+    harness.key_press(Key::P);
+    assert!(wait_for_condition(|| {
+        harness.step();
+        fs.file_content(&root.join("local.txt")).is_some()
+    }));
+    assert_eq!(
+        fs.file_content(&root.join("local.txt")),
+        Some(b"local".to_vec())
+    );
+    assert!(temp_dir.path().join("local.txt").exists());
+
+    harness.state_mut().refresh_entries();
+    harness.step();
+    assert_eq!(
+        entry_names(&harness),
+        vec!["docs", "local.txt", "notes.txt"]
+    );
+
+    vfs::unmount(&root);
+}
+
+#[test]
+fn test_remote_dir_is_listed_in_background() {
+    let temp_dir = tempdir().unwrap();
+
+    // Paths with a scheme are listed on a worker thread like remote hosts
+    let root = std::path::PathBuf::from(format!("memory://kiorg-{}", std::process::id()));
+    let fs = MemoryFs::new(root.clone());
+    fs.add_file("notes.txt", "notes");
+    vfs::mount(root.clone(), Arc::new(fs));
+
+    let mut harness = create_harness(&temp_dir);
+    harness.state_mut().navigate_to_dir(root.clone());
+    assert!(harness.state().tab_manager.current_tab_ref().is_listing());
+
+    assert!(wait_for_condition(|| {
+        harness.step();
+        !harness.state().tab_manager.current_tab_ref().is_listing()
+    }));
+    assert_eq!(entry_names(&harness), vec!["notes.txt"]);
+
+    vfs::unmount(&root);
+}
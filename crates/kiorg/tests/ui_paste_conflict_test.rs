@@ -0,0 +1,103 @@
+#[path = "mod/ui_test_helpers.rs"]
+mod ui_test_helpers;
+
+use egui::Key;
+use kiorg::app::Clipboard;
+use kiorg::ui::popup::PopupType;
+use tempfile::tempdir;
+use ui_test_helpers::{create_harness, wait_for_condition};
+
+#[test]
+fn test_paste_conflict_overwrite_and_undo() {
+    let source_dir = tempdir().unwrap();
+    let target_dir = tempdir().unwrap();
+    let source = source_dir.path().join("note.txt");
+    let target = target_dir.path().join("note.txt");
+    std::fs::write(&source, "new").unwrap();
+    std::fs::write(&target, "old").unwrap();
+
+    let mut harness = create_harness(&target_dir);
+    harness.state_mut().clipboard = Some(Clipboard::Copy(vec![source.clone()]));
+    harness.key_press(Key::P);
+    harness.step();
+
+    // The existing file is not touched until a resolution is picked
+    assert!(
+        matches!(
+            harness.state().show_popup,
+            Some(PopupType::PasteConflict(_))
+        ),
+        "Paste conflict popup should be open"
+    );
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "old");
+
+    harness.key_press(Key::O);
+    wait_for_condition(|| {
+        harness.step();
+        harness.state().show_popup.is_none()
+    });
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+    assert!(
+        !target_dir.path().join("note_1.txt").exists(),
+        "Overwrite should not create a renamed copy"
+    );
+
+    // Undo removes the pasted file and brings back the overwritten one
+    harness.key_press(Key::U);
+    harness.step();
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "old");
+    assert_eq!(std::fs::read_to_string(&source).unwrap(), "new");
+}
+
+#[test]
+fn test_paste_conflict_skip_all_and_cancel() {
+    let source_dir = tempdir().unwrap();
+    let target_dir = tempdir().unwrap();
+    let names = ["a.txt", "b.txt", "c.txt"];
+    for name in names {
+        std::fs::write(source_dir.path().join(name), "new").unwrap();
+    }
+    // c.txt does not conflict and is pasted regardless
+    for name in &names[..2] {
+        std::fs::write(target_dir.path().join(name), "old").unwrap();
+    }
+    let sources: Vec<_> = names.iter().map(|n| source_dir.path().join(n)).collect();
+
+    let mut harness = create_harness(&target_dir);
+
+    // Cancelling keeps the entries on the clipboard
+    harness.state_mut().clipboard = Some(Clipboard::Copy(sources.clone()));
+    harness.key_press(Key::P);
+    harness.step();
+    harness.key_press(Key::Escape);
+    harness.step();
+    assert!(harness.state().show_popup.is_none());
+    assert_eq!(
+        harness.state().clipboard,
+        Some(Clipboard::Copy(sources.clone()))
+    );
+    assert!(!target_dir.path().join("c.txt").exists());
+
+    // Skip with "apply to all" resolves both conflicts at once
+    harness.key_press(Key::P);
+    harness.step();
+    harness.key_press(Key::A);
+    harness.step();
+    harness.key_press(Key::S);
+    wait_for_condition(|| {
+        harness.step();
+        harness.state().show_popup.is_none()
+    });
+
+    for name in &names[..2] {
+        assert_eq!(
+            std::fs::read_to_string(target_dir.path().join(name)).unwrap(),
+            "old"
+        );
+    }
+    assert_eq!(
+        std::fs::read_to_string(target_dir.path().join("c.txt")).unwrap(),
+        "new"
+    );
+    assert!(!target_dir.path().join("a_1.txt").exists());
+}
@@ -61,7 +61,7 @@ fn test_crash_reproduction_filtered_deletion() {
     harness.step();
 
     // Verify we're in the initial confirmation state
-    if let Some(PopupType::Delete(state, _)) = &harness.state().show_popup {
+    if let Some(PopupType::Delete(state, _, _)) = &harness.state().show_popup {
         assert_eq!(
             *state,
             kiorg::ui::popup::delete::DeleteConfirmState::Initial,
@@ -76,7 +76,7 @@ fn test_crash_reproduction_filtered_deletion() {
     harness.step();
 
     // Verify we're now in the recursive confirmation state
-    if let Some(PopupType::Delete(state, _)) = &harness.state().show_popup {
+    if let Some(PopupType::Delete(state, _, _)) = &harness.state().show_popup {
         assert_eq!(
             *state,
             kiorg::ui::popup::delete::DeleteConfirmState::RecursiveConfirm,
@@ -84,3 +84,53 @@ fn test_ctrl_h_toggle_hidden_files() {
         assert_eq!(tab.entries.len(), 1);
     }
 }
+
+// Dotfiles are only hidden by their name outside of Windows
+#[cfg(not(windows))]
+#[test]
+fn test_ctrl_h_hides_git_ignored_files() {
+    let temp_dir = tempdir().unwrap();
+    create_test_files(&[
+        temp_dir.path().join(".git"),
+        temp_dir.path().join("target"),
+        temp_dir.path().join("main.rs"),
+        temp_dir.path().join("debug.log"),
+    ]);
+    std::fs::write(temp_dir.path().join(".gitignore"), "target/\n*.log\n").unwrap();
+
+    let mut harness = create_harness(&temp_dir);
+    let names = |app: &kiorg::Kiorg| {
+        let mut names: Vec<String> = app
+            .tab_manager
+            .current_tab_ref()
+            .entries
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect();
+        names.sort();
+        names
+    };
+    assert_eq!(
+        names(harness.state()),
+        vec!["debug.log", "main.rs", "target"]
+    );
+
+    // Shown, then hidden along with the git ignored entries
+    harness.key_press_modifiers(ctrl_modifiers(), Key::H);
+    harness.step();
+    assert_eq!(names(harness.state()).len(), 6);
+    harness.key_press_modifiers(ctrl_modifiers(), Key::H);
+    harness.step();
+    assert!(harness.state().tab_manager.hide_ignored);
+    assert_eq!(names(harness.state()), vec!["main.rs"]);
+
+    // Back to hiding hidden files only
+    harness.key_press_modifiers(ctrl_modifiers(), Key::H);
+    harness.step();
+    assert!(!harness.state().tab_manager.show_hidden);
+    assert!(!harness.state().tab_manager.hide_ignored);
+    assert_eq!(
+        names(harness.state()),
+        vec!["debug.log", "main.rs", "target"]
+    );
+}
@@ -0,0 +1,75 @@
+#[path = "mod/ui_test_helpers.rs"]
+mod ui_test_helpers;
+
+use egui::Key;
+use kiorg::ui::popup::PopupType;
+use tempfile::tempdir;
+use ui_test_helpers::{
+    create_harness, create_test_files, ctrl_shift_modifiers, wait_for_condition,
+};
+
+#[test]
+fn test_trash_popup_restores_item() {
+    if !kiorg::utils::trash::is_supported() {
+        return;
+    }
+
+    let temp_dir = tempdir().unwrap();
+    // Unique name so the filter only matches this item even with a populated trash
+    let name = format!("kiorg_trash_test_{}.txt", uuid::Uuid::new_v4().simple());
+    let test_files = create_test_files(&[temp_dir.path().join(&name)]);
+
+    let mut harness = create_harness(&temp_dir);
+    harness
+        .state_mut()
+        .tab_manager
+        .current_tab_mut()
+        .selected_index = 0;
+    harness.step();
+
+    // Move the file to trash
+    harness.key_press(Key::D);
+    harness.step();
+    harness.key_press(Key::Enter);
+    wait_for_condition(|| {
+        harness.step();
+        harness.state().show_popup.is_none()
+    });
+    assert!(!test_files[0].exists(), "File should be moved to trash");
+
+    // Open the trash popup and filter down to the trashed file
+    harness.key_press_modifiers(ctrl_shift_modifiers(), Key::T);
+    harness.step();
+    match &harness.state().show_popup {
+        Some(PopupType::Trash(state)) => assert!(
+            state
+                .items
+                .iter()
+                .any(|item| item.entry.original_path == test_files[0]),
+            "Trashed file should be listed"
+        ),
+        _ => panic!("Trash popup should be open"),
+    }
+
+    harness
+        .input_mut()
+        .events
+        .push(egui::Event::Text(name.clone()));
+    harness.step();
+
+    // Enter restores the selected item
+    harness.key_press(Key::Enter);
+    harness.step();
+
+    assert!(test_files[0].exists(), "File should be restored");
+    match &harness.state().show_popup {
+        Some(PopupType::Trash(state)) => assert!(
+            !state
+                .items
+                .iter()
+                .any(|item| item.entry.original_path == test_files[0]),
+            "Restored file should leave the trash listing"
+        ),
+        _ => panic!("Trash popup should stay open after restoring"),
+    }
+}
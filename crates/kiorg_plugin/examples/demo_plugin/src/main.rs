@@ -1,16 +1,21 @@
 //! Demo plugin demonstrating the simplified kiorg plugin system
 //!
 //! This plugin demonstrates the basic Hello/Preview protocol, always
-//! returning "hello world" for preview requests.
+//! returning "hello world" for preview requests. It also serves a local
+//! directory under `demo://local` to demonstrate plugin filesystems.
+
+mod vfs;
 
 use kiorg_plugin::{
-    PluginCapabilities, PluginHandler, PluginMetadata, PluginResponse, PreviewCapability,
+    ColumnCapability, CommandCapability, PluginCapabilities, PluginHandler, PluginMetadata,
+    PluginResponse, PreviewCapability, VfsCapability,
 };
 
 const ICON_BYTES: &[u8] = include_bytes!("../../../../../assets/icons/1024x1024@2x.png");
 
 struct DemoPlugin {
     metadata: PluginMetadata,
+    fs: vfs::LocalDirFs,
 }
 
 impl PluginHandler for DemoPlugin {
@@ -45,6 +50,62 @@ impl PluginHandler for DemoPlugin {
         }
     }
 
+    fn on_execute(&mut self, command: &str, paths: &[String], _cwd: &str) -> PluginResponse {
+        match command {
+            "Say hello" => PluginResponse::Execute {
+                refresh: false,
+                notification: Some(format!("Hello from demo plugin to {} entries", paths.len())),
+                navigate: None,
+            },
+            _ => PluginResponse::Error {
+                message: format!("Unknown command: {}", command),
+            },
+        }
+    }
+
+    fn on_columns(&mut self, paths: &[String]) -> PluginResponse {
+        PluginResponse::Columns {
+            rows: paths
+                .iter()
+                .map(|path| {
+                    let length = std::path::Path::new(path)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().chars().count())
+                        .unwrap_or_default();
+                    vec![length.to_string()]
+                })
+                .collect(),
+        }
+    }
+
+    fn on_vfs_list(&mut self, path: &str) -> PluginResponse {
+        self.fs.list(path)
+    }
+
+    fn on_vfs_stat(&mut self, path: &str) -> PluginResponse {
+        self.fs.stat(path)
+    }
+
+    fn on_vfs_read(&mut self, path: &str, offset: u64, len: u64) -> PluginResponse {
+        self.fs.read(path, offset, len)
+    }
+
+    fn on_vfs_write(&mut self, path: &str, data: &[u8], append: bool) -> PluginResponse {
+        self.fs.write(path, data, append)
+    }
+
+    fn on_vfs_create_dir(&mut self, path: &str) -> PluginResponse {
+        self.fs.create_dir(path)
+    }
+
+    fn on_vfs_rename(&mut self, from: &str, to: &str) -> PluginResponse {
+        self.fs.rename(from, to)
+    }
+
+    fn on_vfs_remove(&mut self, path: &str) -> PluginResponse {
+        self.fs.remove(path)
+    }
+
     fn metadata(&self) -> PluginMetadata {
         self.metadata.clone()
     }
@@ -63,8 +124,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 preview: Some(PreviewCapability {
                     file_pattern: r"^kiorg$".to_string(), // Match files named "kiorg"
                 }),
+                commands: vec![CommandCapability {
+                    name: "Say hello".to_string(),
+                    file_pattern: ".*".to_string(),
+                    shortcut: None,
+                    multiple: true,
+                }],
+                columns: vec![ColumnCapability {
+                    name: "Name Length".to_string(),
+                    file_pattern: ".*".to_string(),
+                    width: Some(80.0),
+                }],
+                vfs: Some(VfsCapability {
+                    scheme: vfs::SCHEME.to_string(),
+                    read_only: false,
+                }),
             },
         },
+        fs: vfs::LocalDirFs::new(),
     }
     .run();
     Ok(())
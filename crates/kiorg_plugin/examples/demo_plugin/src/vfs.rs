@@ -0,0 +1,159 @@
+//! `demo://local/...` paths backed by a local directory
+//!
+//! The directory is `$KIORG_DEMO_VFS_ROOT`, or `kiorg_demo_vfs` in the
+//! temporary directory.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use kiorg_plugin::{FileInfo, PluginResponse};
+
+pub const SCHEME: &str = "demo";
+
+const ROOT_URL: &str = "demo://local";
+
+pub struct LocalDirFs {
+    root: PathBuf,
+}
+
+impl LocalDirFs {
+    pub fn new() -> Self {
+        let root = std::env::var_os("KIORG_DEMO_VFS_ROOT")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| std::env::temp_dir().join("kiorg_demo_vfs"));
+        Self { root }
+    }
+
+    /// The local path a `demo://local/...` path stands for
+    fn local(&self, path: &str) -> io::Result<PathBuf> {
+        let relative = path
+            .strip_prefix(ROOT_URL)
+            .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Not a demo path: {}", path),
+                )
+            })?
+            .trim_start_matches('/');
+        if Path::new(relative)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid path: {}", path),
+            ));
+        }
+        if relative.is_empty() {
+            // The root is created on first use
+            fs::create_dir_all(&self.root)?;
+        }
+        Ok(self.root.join(relative))
+    }
+
+    pub fn list(&self, path: &str) -> PluginResponse {
+        respond(self.local(path).and_then(|dir| {
+            let mut entries = Vec::new();
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                entries.push(file_info(&entry.path())?);
+            }
+            Ok(PluginResponse::VfsEntries { entries })
+        }))
+    }
+
+    pub fn stat(&self, path: &str) -> PluginResponse {
+        respond(
+            self.local(path)
+                .and_then(|local| file_info(&local))
+                .map(|info| PluginResponse::VfsStat { info }),
+        )
+    }
+
+    pub fn read(&self, path: &str, offset: u64, len: u64) -> PluginResponse {
+        respond(self.local(path).and_then(|local| {
+            let mut file = File::open(local)?;
+            file.seek(SeekFrom::Start(offset))?;
+            let mut data = Vec::new();
+            file.take(len).read_to_end(&mut data)?;
+            Ok(PluginResponse::VfsData { data })
+        }))
+    }
+
+    pub fn write(&self, path: &str, data: &[u8], append: bool) -> PluginResponse {
+        respond(self.local(path).and_then(|local| {
+            let mut file = if append {
+                OpenOptions::new().append(true).open(local)?
+            } else {
+                OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(local)?
+            };
+            file.write_all(data)?;
+            Ok(PluginResponse::VfsDone)
+        }))
+    }
+
+    pub fn create_dir(&self, path: &str) -> PluginResponse {
+        respond(
+            self.local(path)
+                .and_then(fs::create_dir)
+                .map(|()| PluginResponse::VfsDone),
+        )
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> PluginResponse {
+        respond(
+            self.local(from)
+                .and_then(|from| Ok((from, self.local(to)?)))
+                .and_then(|(from, to)| fs::rename(from, to))
+                .map(|()| PluginResponse::VfsDone),
+        )
+    }
+
+    pub fn remove(&self, path: &str) -> PluginResponse {
+        respond(self.local(path).and_then(|local| {
+            if fs::symlink_metadata(&local)?.is_dir() {
+                fs::remove_dir_all(local)?;
+            } else {
+                fs::remove_file(local)?;
+            }
+            Ok(PluginResponse::VfsDone)
+        }))
+    }
+}
+
+fn file_info(local: &Path) -> io::Result<FileInfo> {
+    let metadata = fs::symlink_metadata(local)?;
+    let is_symlink = metadata.file_type().is_symlink();
+    let is_dir = if is_symlink {
+        local.is_dir()
+    } else {
+        metadata.is_dir()
+    };
+    Ok(FileInfo {
+        name: local
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        is_dir,
+        is_symlink,
+        size: metadata.len(),
+        modified: metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_secs())
+            .unwrap_or_default(),
+    })
+}
+
+fn respond(result: io::Result<PluginResponse>) -> PluginResponse {
+    result.unwrap_or_else(|e| PluginResponse::Error {
+        message: e.to_string(),
+    })
+}
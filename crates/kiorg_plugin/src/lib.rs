@@ -6,24 +6,37 @@
 use bytecheck::CheckBytes;
 use rkyv::{bytecheck, Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 pub use uuid;
 pub use uuid::Uuid;
 
 /// Protocol version for compatibility checking
 /// Major version changes indicate incompatible protocol changes
-pub const PROTOCOL_VERSION: &str = "0.0.1";
+pub const PROTOCOL_VERSION: &str = "1.0.0";
+
+/// Major version of the protocol before responses carried call ids, engines
+/// speaking it are still served during the transition to 1.0
+pub const LEGACY_PROTOCOL_MAJOR: &str = "0";
 
 /// Check if the provided engine version is compatible with this plugin library version
 pub fn check_compatibility(engine_version: &str) -> bool {
     let engine_major = engine_version.split('.').next().unwrap_or("0");
     let my_major = PROTOCOL_VERSION.split('.').next().unwrap_or("0");
 
-    engine_major == my_major
+    engine_major == my_major || engine_major == LEGACY_PROTOCOL_MAJOR
+}
+
+/// Whether the engine speaks protocol 0.x, which sends one call at a time and
+/// expects bare responses without the [`PluginMessage`] envelope
+pub fn is_legacy_protocol(engine_version: &str) -> bool {
+    engine_version.split('.').next().unwrap_or("0") == LEGACY_PROTOCOL_MAJOR
 }
 
 /// Unique identifier for plugin calls - serialized as bytes for efficiency
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CallId(#[serde(with = "uuid_bytes")] pub Uuid);
 
 impl CallId {
@@ -66,10 +79,19 @@ pub type StreamId = Uuid;
 pub type HelloMessage = PluginMetadata;
 
 /// Plugin capabilities
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PluginCapabilities {
     /// Preview rendering capabilities
     pub preview: Option<PreviewCapability>,
+    /// Commands the user can run on selected files
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<CommandCapability>,
+    /// Extra columns shown in the file list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<ColumnCapability>,
+    /// Filesystem served under a URI scheme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vfs: Option<VfsCapability>,
 }
 
 /// Preview rendering capability
@@ -79,16 +101,126 @@ pub struct PreviewCapability {
     pub file_pattern: String,
 }
 
+/// A named action the user can run on the selected files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandCapability {
+    /// Command name, shown in menus and sent back in `EngineCommand::Execute`
+    pub name: String,
+    /// Regex pattern every selected file name has to match
+    pub file_pattern: String,
+    /// Default shortcut such as "ctrl+shift+g" or "gs", users can rebind it
+    #[serde(default)]
+    pub shortcut: Option<String>,
+    /// Whether the command accepts several paths at once
+    #[serde(default)]
+    pub multiple: bool,
+}
+
+/// An extra column of the file list, filled through `EngineCommand::Columns`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnCapability {
+    /// Column title shown in the table header
+    pub name: String,
+    /// Regex pattern file names have to match to get a value
+    pub file_pattern: String,
+    /// Column width in points
+    #[serde(default)]
+    pub width: Option<f32>,
+}
+
+/// A filesystem browsed through `scheme://authority/path` paths, e.g.
+/// `s3://bucket/photos` or `docker://container/etc`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VfsCapability {
+    /// URI scheme without `://`
+    pub scheme: String,
+    /// Whether only listing and reading are supported
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// An entry of a plugin filesystem
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileInfo {
+    pub name: String,
+    /// For links, whether the link points to a directory
+    #[serde(default)]
+    pub is_dir: bool,
+    #[serde(default)]
+    pub is_symlink: bool,
+    #[serde(default)]
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch
+    #[serde(default)]
+    pub modified: u64,
+}
+
 /// Commands that can be sent from engine to plugin
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "_T")]
 pub enum EngineCommand {
     /// Initial handshake message
-    Hello { protocol_version: String },
+    Hello {
+        protocol_version: String,
+    },
     /// Preview command - takes a file path and available width
-    Preview { path: String, available_width: f32 },
+    Preview {
+        path: String,
+        available_width: f32,
+    },
     /// Preview popup command - takes a file path and available width
-    PreviewPopup { path: String, available_width: f32 },
+    PreviewPopup {
+        path: String,
+        available_width: f32,
+    },
+    /// Run a command declared in the capabilities on the given paths
+    Execute {
+        command: String,
+        paths: Vec<String>,
+        cwd: String,
+    },
+    /// Values of the declared columns for a batch of paths
+    Columns {
+        paths: Vec<String>,
+    },
+    /// Entries of the directory at a `scheme://` path
+    VfsList {
+        path: String,
+    },
+    /// The entry at a `scheme://` path, without following links
+    VfsStat {
+        path: String,
+    },
+    /// Up to `len` bytes of a file starting at `offset`, no data marks the end
+    VfsRead {
+        path: String,
+        offset: u64,
+        len: u64,
+    },
+    /// Write `data` to a new file, failing if the path is taken, or append
+    /// it to the file when `append` is set
+    VfsWrite {
+        path: String,
+        data: Vec<u8>,
+        append: bool,
+    },
+    /// Create a single directory, its parent exists
+    VfsCreateDir {
+        path: String,
+    },
+    VfsRename {
+        from: String,
+        to: String,
+    },
+    /// Remove a file, or a directory with everything in it
+    VfsRemove {
+        path: String,
+    },
+    /// The engine no longer needs the response to call `id`, a plugin that
+    /// has not started on it yet skips it without responding
+    Cancel {
+        id: CallId,
+    },
 }
 
 /// Message sent from engine to plugin
@@ -100,6 +232,15 @@ pub struct EngineMessage {
     pub command: EngineCommand,
 }
 
+/// Message sent from plugin to engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginMessage {
+    /// Identifier of the engine message this responds to
+    pub id: CallId,
+    /// The response to the command
+    pub response: PluginResponse,
+}
+
 /// Response from plugin to engine
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "_T")]
@@ -108,6 +249,29 @@ pub enum PluginResponse {
     Hello(HelloMessage),
     /// Preview response with content to display
     Preview { components: Vec<Component> },
+    /// Command finished, with what the engine should do next
+    Execute {
+        /// Reload the current directory
+        #[serde(default)]
+        refresh: bool,
+        /// Message shown to the user
+        #[serde(default)]
+        notification: Option<String>,
+        /// Directory to navigate to
+        #[serde(default)]
+        navigate: Option<String>,
+    },
+    /// Column values, one row per requested path with one value per
+    /// declared column, in declaration order
+    Columns { rows: Vec<Vec<String>> },
+    /// Entries of a listed directory
+    VfsEntries { entries: Vec<FileInfo> },
+    /// A single entry
+    VfsStat { info: FileInfo },
+    /// Bytes read from a file
+    VfsData { data: Vec<u8> },
+    /// A write, create dir, rename or remove succeeded
+    VfsDone,
     /// Version incompatible response
     VersionIncompatible {
         protocol_version: String,
@@ -221,6 +385,37 @@ pub trait PluginHandler {
     fn on_preview_popup(&mut self, path: &str, available_width: f32) -> PluginResponse {
         self.on_preview(path, available_width)
     }
+    fn on_execute(&mut self, command: &str, _paths: &[String], _cwd: &str) -> PluginResponse {
+        PluginResponse::Error {
+            message: format!("Unknown command: {}", command),
+        }
+    }
+    fn on_columns(&mut self, _paths: &[String]) -> PluginResponse {
+        PluginResponse::Error {
+            message: "Columns are not supported".to_string(),
+        }
+    }
+    fn on_vfs_list(&mut self, path: &str) -> PluginResponse {
+        vfs_unsupported("List", path)
+    }
+    fn on_vfs_stat(&mut self, path: &str) -> PluginResponse {
+        vfs_unsupported("Stat", path)
+    }
+    fn on_vfs_read(&mut self, path: &str, _offset: u64, _len: u64) -> PluginResponse {
+        vfs_unsupported("Read", path)
+    }
+    fn on_vfs_write(&mut self, path: &str, _data: &[u8], _append: bool) -> PluginResponse {
+        vfs_unsupported("Write", path)
+    }
+    fn on_vfs_create_dir(&mut self, path: &str) -> PluginResponse {
+        vfs_unsupported("Create directory", path)
+    }
+    fn on_vfs_rename(&mut self, from: &str, _to: &str) -> PluginResponse {
+        vfs_unsupported("Rename", from)
+    }
+    fn on_vfs_remove(&mut self, path: &str) -> PluginResponse {
+        vfs_unsupported("Remove", path)
+    }
     fn metadata(&self) -> PluginMetadata;
 
     fn run(mut self)
@@ -237,50 +432,45 @@ pub trait PluginHandler {
 
     /// Run the main loop for a plugin
     ///
-    /// This function will read messages from stdin and dispatch them to the handler.
-    /// It will exit when stdin is closed (host process exited) or on communication error.
+    /// Messages are read from stdin on a separate thread and handled one at a
+    /// time, in arrival order. Calls cancelled by the engine before the
+    /// handler gets to them are skipped. It will exit when stdin is closed
+    /// (host process exited) or on communication error.
     fn run_plugin_loop(&mut self) {
-        loop {
-            match read_message() {
-                Ok(message) => {
-                    let response = match message.command {
-                        EngineCommand::Hello { protocol_version } => {
-                            self.on_hello(&protocol_version)
-                        }
-                        EngineCommand::Preview {
-                            path,
-                            available_width,
-                        } => self.on_preview(&path, available_width),
-                        EngineCommand::PreviewPopup {
-                            path,
-                            available_width,
-                        } => self.on_preview_popup(&path, available_width),
-                    };
-
-                    if send_message(&response).is_err() {
-                        // Failed to send response, host probably disconnected
-                        break;
-                    }
-                }
-                Err(e) => {
-                    // Check if the error is a clean shutdown (UnexpectedEof)
-                    if let Some(io_err) = e.downcast_ref::<io::Error>() {
-                        if io_err.kind() == io::ErrorKind::UnexpectedEof {
-                            break;
-                        }
-                    }
+        let inbox = Arc::new(Inbox::default());
+        spawn_reader(inbox.clone());
+        handle_calls(self, &inbox);
+    }
 
-                    let error_msg = format!("Invalid command received: {}", e);
-                    eprintln!("{}", error_msg);
+    /// Like [`run`](Self::run), but handle up to `workers` calls at once
+    ///
+    /// Every worker thread handles calls with its own clone of the handler, so
+    /// a slow call such as a large preview does not hold up the others.
+    /// Responses are sent as calls finish, calls cancelled before a worker
+    /// gets to them are skipped.
+    fn run_concurrent(self, workers: usize)
+    where
+        Self: Clone + Send + Sized + 'static,
+    {
+        if !self.parse_args() {
+            return; // Exit if help was shown
+        }
+        let metadata = self.metadata();
+        eprintln!("Starting {} v{}", metadata.name, metadata.version);
 
-                    // Try to send the error back to the engine
-                    let error_response = PluginResponse::Error { message: error_msg };
-                    if send_message(&error_response).is_err() {
-                        eprintln!("Failed to send error response to engine");
-                        std::process::exit(-2);
-                    }
-                }
-            }
+        let inbox = Arc::new(Inbox::default());
+        spawn_reader(inbox.clone());
+        let threads: Vec<_> = (1..workers)
+            .map(|_| {
+                let mut handler = self.clone();
+                let inbox = inbox.clone();
+                std::thread::spawn(move || handle_calls(&mut handler, &inbox))
+            })
+            .collect();
+        let mut handler = self;
+        handle_calls(&mut handler, &inbox);
+        for thread in threads {
+            let _ = thread.join();
         }
     }
 
@@ -311,6 +501,23 @@ pub trait PluginHandler {
             } else {
                 println!("  No preview support");
             }
+            for command in &metadata.capabilities.commands {
+                println!("  Command: {}", command.name);
+                println!("    File Pattern: {}", command.file_pattern);
+                if let Some(shortcut) = &command.shortcut {
+                    println!("    Shortcut: {}", shortcut);
+                }
+            }
+            for column in &metadata.capabilities.columns {
+                println!("  Column: {}", column.name);
+                println!("    File Pattern: {}", column.file_pattern);
+            }
+            if let Some(vfs) = &metadata.capabilities.vfs {
+                println!("  Filesystem: {}://", vfs.scheme);
+                if vfs.read_only {
+                    println!("    Read-only");
+                }
+            }
             println!();
             println!("To install this plugin:");
             println!("  1. Copy the plugin binary into the plugins directory under kiorg's config directory.");
@@ -334,6 +541,164 @@ pub trait PluginHandler {
     }
 }
 
+fn vfs_unsupported(operation: &str, path: &str) -> PluginResponse {
+    PluginResponse::Error {
+        message: format!("{} is not supported: {}", operation, path),
+    }
+}
+
+/// Calls waiting for the handler, in arrival order
+#[derive(Debug, Default)]
+struct CallQueue {
+    calls: VecDeque<EngineMessage>,
+}
+
+impl CallQueue {
+    /// Queue a call, or drop the queued call a cancellation targets
+    fn push(&mut self, message: EngineMessage) {
+        match message.command {
+            EngineCommand::Cancel { id } => self.calls.retain(|call| call.id != id),
+            _ => self.calls.push_back(message),
+        }
+    }
+
+    fn pop(&mut self) -> Option<EngineMessage> {
+        self.calls.pop_front()
+    }
+}
+
+/// Calls read from stdin, waiting for a handler
+#[derive(Default)]
+struct Inbox {
+    state: Mutex<InboxState>,
+    arrived: Condvar,
+    /// Set when the engine said hello with protocol 0.x
+    legacy: AtomicBool,
+}
+
+#[derive(Default)]
+struct InboxState {
+    queue: CallQueue,
+    /// Stdin was closed, no more calls arrive
+    closed: bool,
+}
+
+impl Inbox {
+    fn lock(&self) -> std::sync::MutexGuard<'_, InboxState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn push(&self, message: EngineMessage) {
+        self.lock().queue.push(message);
+        self.arrived.notify_one();
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.arrived.notify_all();
+    }
+
+    /// The next call to handle, `None` once stdin is closed and no call is left
+    fn next(&self) -> Option<EngineMessage> {
+        let mut state = self.lock();
+        loop {
+            if let Some(message) = state.queue.pop() {
+                return Some(message);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self.arrived.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Send the response to call `id`, bare to engines speaking protocol 0.x
+    fn respond(
+        &self,
+        id: CallId,
+        response: PluginResponse,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.legacy.load(Ordering::Relaxed) {
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            return send_message_to_writer(&mut handle, &response);
+        }
+        send_message(&PluginMessage { id, response })
+    }
+}
+
+/// Read messages from stdin into the inbox until stdin is closed
+fn spawn_reader(inbox: Arc<Inbox>) {
+    std::thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut handle = stdin.lock();
+        loop {
+            match read_message_from_reader(&mut handle) {
+                Ok(message) => inbox.push(message),
+                // Stdin closed, host process exited
+                Err(e) if e.downcast_ref::<io::Error>().is_some() => break,
+                Err(e) => {
+                    let error_msg = format!("Invalid command received: {}", e);
+                    eprintln!("{}", error_msg);
+
+                    // Try to send the error back to the engine, the call it
+                    // belongs to is unknown
+                    let error_response = PluginResponse::Error { message: error_msg };
+                    if inbox.respond(CallId(Uuid::nil()), error_response).is_err() {
+                        eprintln!("Failed to send error response to engine");
+                        std::process::exit(-2);
+                    }
+                }
+            }
+        }
+        inbox.close();
+    });
+}
+
+/// Handle calls from the inbox until stdin is closed or the engine goes away
+fn handle_calls<H: PluginHandler + ?Sized>(handler: &mut H, inbox: &Inbox) {
+    while let Some(message) = inbox.next() {
+        let response = match message.command {
+            EngineCommand::Hello { protocol_version } => {
+                inbox
+                    .legacy
+                    .store(is_legacy_protocol(&protocol_version), Ordering::Relaxed);
+                handler.on_hello(&protocol_version)
+            }
+            EngineCommand::Preview {
+                path,
+                available_width,
+            } => handler.on_preview(&path, available_width),
+            EngineCommand::PreviewPopup {
+                path,
+                available_width,
+            } => handler.on_preview_popup(&path, available_width),
+            EngineCommand::Execute {
+                command,
+                paths,
+                cwd,
+            } => handler.on_execute(&command, &paths, &cwd),
+            EngineCommand::Columns { paths } => handler.on_columns(&paths),
+            EngineCommand::VfsList { path } => handler.on_vfs_list(&path),
+            EngineCommand::VfsStat { path } => handler.on_vfs_stat(&path),
+            EngineCommand::VfsRead { path, offset, len } => handler.on_vfs_read(&path, offset, len),
+            EngineCommand::VfsWrite { path, data, append } => {
+                handler.on_vfs_write(&path, &data, append)
+            }
+            EngineCommand::VfsCreateDir { path } => handler.on_vfs_create_dir(&path),
+            EngineCommand::VfsRename { from, to } => handler.on_vfs_rename(&from, &to),
+            EngineCommand::VfsRemove { path } => handler.on_vfs_remove(&path),
+            // Consumed by the queue
+            EngineCommand::Cancel { .. } => continue,
+        };
+
+        if inbox.respond(message.id, response).is_err() {
+            // Failed to send response, host probably disconnected
+            break;
+        }
+    }
+}
+
 /// Read a MessagePack message from stdin
 pub fn read_message() -> Result<EngineMessage, Box<dyn std::error::Error>> {
     let stdin = io::stdin();
@@ -345,22 +710,27 @@ pub fn read_message() -> Result<EngineMessage, Box<dyn std::error::Error>> {
 pub fn read_message_from_reader<R: Read, T: serde::de::DeserializeOwned>(
     reader: &mut R,
 ) -> Result<T, Box<dyn std::error::Error>> {
+    let msg_buf = read_frame_from_reader(reader)?;
+    let message: T = rmp_serde::from_slice(&msg_buf)?;
+    Ok(message)
+}
+
+/// Read the length prefixed bytes of a message without decoding them
+pub fn read_frame_from_reader<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
     let mut len_buf = [0u8; 4];
     reader.read_exact(&mut len_buf)?;
     let msg_len = u32::from_be_bytes(len_buf) as usize;
 
     let mut msg_buf = vec![0u8; msg_len];
     reader.read_exact(&mut msg_buf)?;
-
-    let message: T = rmp_serde::from_slice(&msg_buf)?;
-    Ok(message)
+    Ok(msg_buf)
 }
 
 /// Send a MessagePack message to stdout
-pub fn send_message(response: &PluginResponse) -> Result<(), Box<dyn std::error::Error>> {
+pub fn send_message(message: &PluginMessage) -> Result<(), Box<dyn std::error::Error>> {
     let stdout = io::stdout();
     let mut handle = stdout.lock();
-    send_message_to_writer(&mut handle, response)
+    send_message_to_writer(&mut handle, message)
 }
 
 /// Send a MessagePack message to any writer
@@ -460,9 +830,218 @@ mod tests {
         assert_eq!(bytes, expected, "PluginResponse bytes mismatch");
     }
 
+    #[test]
+    fn test_execute_round_trip() {
+        let msg = EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::Execute {
+                command: "Stage".to_string(),
+                paths: vec!["/repo/a.txt".to_string(), "/repo/b.txt".to_string()],
+                cwd: "/repo".to_string(),
+            },
+        };
+        let mut bytes = Vec::new();
+        send_message_to_writer(&mut bytes, &msg).unwrap();
+        let decoded: EngineMessage = read_message_from_reader(&mut bytes.as_slice()).unwrap();
+        match decoded.command {
+            EngineCommand::Execute {
+                command,
+                paths,
+                cwd,
+            } => {
+                assert_eq!(command, "Stage");
+                assert_eq!(paths.len(), 2);
+                assert_eq!(cwd, "/repo");
+            }
+            other => panic!("Unexpected command: {:?}", other),
+        }
+
+        let resp = PluginResponse::Execute {
+            refresh: true,
+            notification: None,
+            navigate: None,
+        };
+        let bytes = rmp_serde::to_vec_named(&resp).unwrap();
+        match rmp_serde::from_slice(&bytes).unwrap() {
+            PluginResponse::Execute {
+                refresh,
+                notification,
+                navigate,
+            } => {
+                assert!(refresh);
+                assert!(notification.is_none() && navigate.is_none());
+            }
+            other => panic!("Unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_columns_round_trip() {
+        let msg = EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::Columns {
+                paths: vec!["/photos/a.png".to_string()],
+            },
+        };
+        let mut bytes = Vec::new();
+        send_message_to_writer(&mut bytes, &msg).unwrap();
+        let decoded: EngineMessage = read_message_from_reader(&mut bytes.as_slice()).unwrap();
+        match decoded.command {
+            EngineCommand::Columns { paths } => assert_eq!(paths, vec!["/photos/a.png"]),
+            other => panic!("Unexpected command: {:?}", other),
+        }
+
+        let resp = PluginResponse::Columns {
+            rows: vec![vec!["640x480".to_string()]],
+        };
+        let bytes = rmp_serde::to_vec_named(&resp).unwrap();
+        match rmp_serde::from_slice(&bytes).unwrap() {
+            PluginResponse::Columns { rows } => assert_eq!(rows, vec![vec!["640x480"]]),
+            other => panic!("Unexpected response: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_vfs_round_trip() {
+        let msg = EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::VfsWrite {
+                path: "demo://local/a.txt".to_string(),
+                data: b"hello".to_vec(),
+                append: true,
+            },
+        };
+        let mut bytes = Vec::new();
+        send_message_to_writer(&mut bytes, &msg).unwrap();
+        let decoded: EngineMessage = read_message_from_reader(&mut bytes.as_slice()).unwrap();
+        match decoded.command {
+            EngineCommand::VfsWrite { path, data, append } => {
+                assert_eq!(path, "demo://local/a.txt");
+                assert_eq!(data, b"hello");
+                assert!(append);
+            }
+            other => panic!("Unexpected command: {:?}", other),
+        }
+
+        let info = FileInfo {
+            name: "a.txt".to_string(),
+            size: 5,
+            modified: 1_700_000_000,
+            ..Default::default()
+        };
+        let resp = PluginResponse::VfsEntries {
+            entries: vec![info.clone()],
+        };
+        let bytes = rmp_serde::to_vec_named(&resp).unwrap();
+        match rmp_serde::from_slice(&bytes).unwrap() {
+            PluginResponse::VfsEntries { entries } => assert_eq!(entries, vec![info]),
+            other => panic!("Unexpected response: {:?}", other),
+        }
+
+        let bytes = rmp_serde::to_vec_named(&PluginResponse::VfsDone).unwrap();
+        assert!(matches!(
+            rmp_serde::from_slice::<PluginResponse>(&bytes).unwrap(),
+            PluginResponse::VfsDone
+        ));
+    }
+
+    #[test]
+    fn test_plugin_message_round_trip() {
+        let id = CallId::new();
+        let msg = PluginMessage {
+            id,
+            response: PluginResponse::VfsDone,
+        };
+        let mut bytes = Vec::new();
+        send_message_to_writer(&mut bytes, &msg).unwrap();
+        let decoded: PluginMessage = read_message_from_reader(&mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded.id, id);
+        assert!(matches!(decoded.response, PluginResponse::VfsDone));
+
+        let msg = EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::Cancel { id },
+        };
+        let mut bytes = Vec::new();
+        send_message_to_writer(&mut bytes, &msg).unwrap();
+        let decoded: EngineMessage = read_message_from_reader(&mut bytes.as_slice()).unwrap();
+        match decoded.command {
+            EngineCommand::Cancel { id: cancelled } => assert_eq!(cancelled, id),
+            other => panic!("Unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_compatibility_with_legacy_engines() {
+        assert!(check_compatibility("1.2.0"));
+        assert!(check_compatibility("0.0.1"));
+        assert!(!check_compatibility("2.0.0"));
+        assert!(is_legacy_protocol("0.0.1"));
+        assert!(!is_legacy_protocol(PROTOCOL_VERSION));
+    }
+
+    #[test]
+    fn test_inbox_drains_before_closing() {
+        let inbox = Inbox::default();
+        let call = EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::VfsList {
+                path: "demo://local".to_string(),
+            },
+        };
+        let cancelled = EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::VfsStat {
+                path: "demo://local/a".to_string(),
+            },
+        };
+        let cancelled_id = cancelled.id;
+        inbox.push(call.clone());
+        inbox.push(cancelled);
+        inbox.push(EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::Cancel { id: cancelled_id },
+        });
+        inbox.close();
+
+        assert_eq!(inbox.next().map(|message| message.id), Some(call.id));
+        assert!(inbox.next().is_none());
+    }
+
+    #[test]
+    fn test_cancel_drops_queued_call() {
+        let preview = |path: &str| EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::Preview {
+                path: path.to_string(),
+                available_width: 100.0,
+            },
+        };
+        let (a, b, c) = (preview("a"), preview("b"), preview("c"));
+        let cancelled = b.id;
+
+        let mut queue = CallQueue::default();
+        queue.push(a.clone());
+        queue.push(b);
+        queue.push(c.clone());
+        queue.push(EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::Cancel { id: cancelled },
+        });
+        // Cancelling a call that is not queued is a no-op
+        queue.push(EngineMessage {
+            id: CallId::new(),
+            command: EngineCommand::Cancel { id: CallId::new() },
+        });
+
+        assert_eq!(queue.pop().map(|call| call.id), Some(a.id));
+        assert_eq!(queue.pop().map(|call| call.id), Some(c.id));
+        assert!(queue.pop().is_none());
+    }
+
     #[test]
     fn test_plugin_hello_response_serialization() {
-        let caps = PluginCapabilities { preview: None };
+        let caps = PluginCapabilities::default();
         let msg = PluginMetadata {
             name: "Test Plugin".to_string(),
             version: "1.0.0".to_string(),
@@ -513,7 +1092,7 @@ mod tests {
 
     #[test]
     fn test_plugin_version_incompatible_response_serialization() {
-        let caps = PluginCapabilities { preview: None };
+        let caps = PluginCapabilities::default();
         let meta = PluginMetadata {
             name: "Test Plugin".to_string(),
             version: "1.0.0".to_string(),
@@ -213,6 +213,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 preview: Some(PreviewCapability {
                     file_pattern: r"(?i)\.(heif|heic)$".to_string(),
                 }),
+                ..Default::default()
             },
         },
     }